@@ -1,16 +1,444 @@
 use serde::{Deserialize, Serialize};
+use std::env;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AppConfig {
     pub database_url: String,
+    pub server_host: String,
     pub server_port: u16,
+    pub database_max_connections: u32,
+    pub run_migrations: bool,
+    pub jwt: JwtConfig,
+    pub otp: OtpConfig,
+    pub login: LoginConfig,
+    pub notification: NotificationConfig,
+    pub social: SocialConfig,
+    pub checkout: CheckoutConfig,
+    pub payment: PaymentConfig,
+    pub media: MediaConfig,
+}
+
+/// Signing and verification settings for access tokens.
+///
+/// Key material is either inline (an HS256 secret, or a PEM document) or a
+/// path to a PEM file. `previous_keys` stay valid for verification only, so
+/// tokens signed before a rotation keep working until they expire.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct JwtConfig {
+    /// `HS256`, `RS256` or `EdDSA`.
+    pub algorithm: String,
+    pub key_id: String,
+    /// HS256 secret, or the private key PEM for RS256/EdDSA.
+    pub signing_key: Option<String>,
+    /// Public key PEM for RS256/EdDSA; published through the JWKS endpoint.
+    pub verification_key: Option<String>,
+    pub previous_keys: Vec<JwtKeyConfig>,
+    pub issuer: String,
+    pub audience: String,
+    pub access_token_ttl_seconds: i64,
+    pub refresh_token_ttl_days: i64,
+    /// JWKS endpoint of the auth service, for services that only verify tokens.
+    pub jwks_url: Option<String>,
+}
+
+/// A retired key that is still accepted when verifying tokens.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct JwtKeyConfig {
+    pub key_id: String,
+    pub algorithm: String,
+    /// HS256 secret, or the public key PEM for RS256/EdDSA.
+    pub key: String,
+}
+
+/// One-time verification codes sent to an email address or phone number.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OtpConfig {
+    /// Server-side key mixed into the stored code hashes. A random key is
+    /// generated at startup when unset, which only works for a single instance.
+    pub secret: Option<String>,
+    pub code_ttl_seconds: i64,
+    /// Wrong guesses allowed before a code is burned.
+    pub max_attempts: i32,
+    pub resend_interval_seconds: i64,
+    pub max_sends_per_hour: i64,
+    /// File the local stand-in sender appends outgoing codes to.
+    pub outbox_path: Option<String>,
+}
+
+/// Password sign-in protection: after `max_failed_attempts` wrong passwords
+/// in a row the account is locked for `lockout_seconds`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LoginConfig {
+    pub max_failed_attempts: i32,
+    pub lockout_seconds: i64,
+}
+
+/// External providers notifications are delivered through. A channel whose
+/// credentials are unset is disabled and its notifications are marked failed.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NotificationConfig {
+    /// Base URL of a Kavenegar-compatible SMS API.
+    pub sms_api_url: String,
+    pub sms_api_key: Option<String>,
+    /// Sender line number.
+    pub sms_sender: Option<String>,
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub email_from: String,
+}
+
+/// OAuth2/OIDC clients for social login. A provider is enabled only when its
+/// client id (and, for GitHub, the client secret) is set.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SocialConfig {
+    pub google_client_id: Option<String>,
+    pub linkedin_client_id: Option<String>,
+    pub meta_app_id: Option<String>,
+    pub github_client_id: Option<String>,
+    pub github_client_secret: Option<String>,
+    pub github_api_url: String,
+    /// How long provider signing keys are cached before they are refetched.
+    pub jwks_cache_ttl_seconds: u64,
+}
+
+/// How order totals are computed from the line items. Amounts are whole rials.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CheckoutConfig {
+    /// Value added tax charged on the subtotal, e.g. `0.10` for 10%.
+    pub tax_rate: f64,
+    pub shipping_fee: i64,
+    /// Subtotal from which shipping is free.
+    pub free_shipping_threshold: Option<i64>,
+    /// How long an unpaid order holds its stock before it is cancelled.
+    pub reservation_ttl_seconds: u64,
+    /// How long a price lock keeps the prices it quoted for the shopper's
+    /// order.
+    pub price_lock_ttl_seconds: u64,
+}
+
+/// The internet payment gateway (IPG) orders are paid through. `mock` runs a
+/// Zarinpal-compatible gateway inside the service that approves every
+/// payment; `zarinpal` needs a merchant id.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PaymentConfig {
+    pub gateway: String,
+    pub zarinpal_api_url: String,
+    pub zarinpal_merchant_id: Option<String>,
+    /// Public URL of `/api/payments/callback` the gateway sends the payer back to.
+    pub callback_url: String,
+    /// Page the payer is redirected to once the callback was handled; the
+    /// callback answers with JSON when unset.
+    pub return_url: Option<String>,
+    /// Shared secret gateway webhooks are signed with; the webhook endpoint
+    /// is disabled when unset.
+    pub webhook_secret: Option<String>,
+}
+
+/// Where uploaded images are kept and how they are checked. `local` keeps
+/// them under `local_root` and serves them from `public_url`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MediaConfig {
+    pub storage: String,
+    pub local_root: String,
+    /// URL stored files are served from, without a trailing slash.
+    pub public_url: String,
+    pub max_upload_bytes: usize,
+    /// Longest edge of each thumbnail made from an upload, in pixels.
+    pub thumbnail_sizes: Vec<u32>,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             database_url: "postgresql://localhost/pema".to_string(),
+            server_host: "127.0.0.1".to_string(),
             server_port: 8080,
+            database_max_connections: 5,
+            run_migrations: true,
+            jwt: JwtConfig::default(),
+            otp: OtpConfig::default(),
+            login: LoginConfig::default(),
+            notification: NotificationConfig::default(),
+            social: SocialConfig::default(),
+            checkout: CheckoutConfig::default(),
+            payment: PaymentConfig::default(),
+            media: MediaConfig::default(),
+        }
+    }
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: "HS256".to_string(),
+            key_id: "default".to_string(),
+            signing_key: None,
+            verification_key: None,
+            previous_keys: Vec::new(),
+            issuer: "pema-auth".to_string(),
+            audience: "pema-platform".to_string(),
+            access_token_ttl_seconds: 24 * 60 * 60,
+            refresh_token_ttl_days: 30,
+            jwks_url: None,
+        }
+    }
+}
+
+impl Default for OtpConfig {
+    fn default() -> Self {
+        Self {
+            secret: None,
+            code_ttl_seconds: 2 * 60,
+            max_attempts: 5,
+            resend_interval_seconds: 60,
+            max_sends_per_hour: 5,
+            outbox_path: None,
+        }
+    }
+}
+
+impl Default for LoginConfig {
+    fn default() -> Self {
+        Self {
+            max_failed_attempts: 5,
+            lockout_seconds: 15 * 60,
+        }
+    }
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            sms_api_url: "https://api.kavenegar.com/v1".to_string(),
+            sms_api_key: None,
+            sms_sender: None,
+            smtp_host: None,
+            smtp_port: 587,
+            smtp_username: None,
+            smtp_password: None,
+            email_from: "PEMA <no-reply@pema.ir>".to_string(),
+        }
+    }
+}
+
+impl Default for SocialConfig {
+    fn default() -> Self {
+        Self {
+            google_client_id: None,
+            linkedin_client_id: None,
+            meta_app_id: None,
+            github_client_id: None,
+            github_client_secret: None,
+            github_api_url: "https://api.github.com".to_string(),
+            jwks_cache_ttl_seconds: 60 * 60,
+        }
+    }
+}
+
+impl Default for CheckoutConfig {
+    fn default() -> Self {
+        Self {
+            tax_rate: 0.10,
+            shipping_fee: 500_000,
+            free_shipping_threshold: None,
+            reservation_ttl_seconds: 30 * 60,
+            price_lock_ttl_seconds: 15 * 60,
+        }
+    }
+}
+
+impl Default for PaymentConfig {
+    fn default() -> Self {
+        Self {
+            gateway: "mock".to_string(),
+            zarinpal_api_url: "https://payment.zarinpal.com".to_string(),
+            zarinpal_merchant_id: None,
+            callback_url: "http://127.0.0.1:8081/api/payments/callback".to_string(),
+            return_url: None,
+            webhook_secret: None,
         }
     }
 }
+
+impl Default for MediaConfig {
+    fn default() -> Self {
+        Self {
+            storage: "local".to_string(),
+            local_root: "media".to_string(),
+            public_url: "http://127.0.0.1:8081/media".to_string(),
+            max_upload_bytes: 10 * 1024 * 1024,
+            thumbnail_sizes: vec![160, 480, 1024],
+        }
+    }
+}
+
+impl AppConfig {
+    /// Builds the configuration from environment variables, falling back to
+    /// the defaults for anything that is unset or unparsable.
+    pub fn from_env() -> Self {
+        Self::default().with_env()
+    }
+
+    /// Overrides the fields of `self` with any values set in the environment.
+    /// Lets a service pick its own defaults (e.g. a different port) while
+    /// still honouring the shared variables.
+    pub fn with_env(self) -> Self {
+        Self {
+            database_url: env::var("DATABASE_URL").unwrap_or(self.database_url),
+            server_host: env::var("SERVER_HOST").unwrap_or(self.server_host),
+            server_port: env_parse("SERVER_PORT").unwrap_or(self.server_port),
+            database_max_connections: env_parse("DATABASE_MAX_CONNECTIONS")
+                .unwrap_or(self.database_max_connections),
+            run_migrations: env_parse("RUN_MIGRATIONS").unwrap_or(self.run_migrations),
+            jwt: self.jwt.with_env(),
+            otp: self.otp.with_env(),
+            login: self.login.with_env(),
+            notification: self.notification.with_env(),
+            social: self.social.with_env(),
+            checkout: self.checkout.with_env(),
+            payment: self.payment.with_env(),
+            media: self.media.with_env(),
+        }
+    }
+}
+
+impl JwtConfig {
+    /// Reads the `JWT_*` variables. `JWT_PREVIOUS_KEYS` is a comma separated
+    /// list of `key_id:algorithm:key` entries; malformed entries are skipped.
+    pub fn with_env(self) -> Self {
+        let previous_keys = match env::var("JWT_PREVIOUS_KEYS") {
+            Ok(value) => value.split(',').filter_map(parse_previous_key).collect(),
+            Err(_) => self.previous_keys,
+        };
+
+        Self {
+            algorithm: env::var("JWT_ALGORITHM").unwrap_or(self.algorithm),
+            key_id: env::var("JWT_KEY_ID").unwrap_or(self.key_id),
+            signing_key: env::var("JWT_SIGNING_KEY").ok().or(self.signing_key),
+            verification_key: env::var("JWT_VERIFICATION_KEY").ok().or(self.verification_key),
+            previous_keys,
+            issuer: env::var("JWT_ISSUER").unwrap_or(self.issuer),
+            audience: env::var("JWT_AUDIENCE").unwrap_or(self.audience),
+            access_token_ttl_seconds: env_parse("JWT_ACCESS_TOKEN_TTL_SECONDS")
+                .unwrap_or(self.access_token_ttl_seconds),
+            refresh_token_ttl_days: env_parse("JWT_REFRESH_TOKEN_TTL_DAYS")
+                .unwrap_or(self.refresh_token_ttl_days),
+            jwks_url: env::var("JWT_JWKS_URL").ok().or(self.jwks_url),
+        }
+    }
+}
+
+impl OtpConfig {
+    pub fn with_env(self) -> Self {
+        Self {
+            secret: env::var("OTP_SECRET").ok().or(self.secret),
+            code_ttl_seconds: env_parse("OTP_CODE_TTL_SECONDS").unwrap_or(self.code_ttl_seconds),
+            max_attempts: env_parse("OTP_MAX_ATTEMPTS").unwrap_or(self.max_attempts),
+            resend_interval_seconds: env_parse("OTP_RESEND_INTERVAL_SECONDS")
+                .unwrap_or(self.resend_interval_seconds),
+            max_sends_per_hour: env_parse("OTP_MAX_SENDS_PER_HOUR").unwrap_or(self.max_sends_per_hour),
+            outbox_path: env::var("OTP_OUTBOX_PATH").ok().or(self.outbox_path),
+        }
+    }
+}
+
+impl LoginConfig {
+    pub fn with_env(self) -> Self {
+        Self {
+            max_failed_attempts: env_parse("LOGIN_MAX_FAILED_ATTEMPTS").unwrap_or(self.max_failed_attempts),
+            lockout_seconds: env_parse("LOGIN_LOCKOUT_SECONDS").unwrap_or(self.lockout_seconds),
+        }
+    }
+}
+
+impl NotificationConfig {
+    pub fn with_env(self) -> Self {
+        Self {
+            sms_api_url: env::var("SMS_API_URL").unwrap_or(self.sms_api_url),
+            sms_api_key: env::var("SMS_API_KEY").ok().or(self.sms_api_key),
+            sms_sender: env::var("SMS_SENDER").ok().or(self.sms_sender),
+            smtp_host: env::var("SMTP_HOST").ok().or(self.smtp_host),
+            smtp_port: env_parse("SMTP_PORT").unwrap_or(self.smtp_port),
+            smtp_username: env::var("SMTP_USERNAME").ok().or(self.smtp_username),
+            smtp_password: env::var("SMTP_PASSWORD").ok().or(self.smtp_password),
+            email_from: env::var("EMAIL_FROM").unwrap_or(self.email_from),
+        }
+    }
+}
+
+impl SocialConfig {
+    pub fn with_env(self) -> Self {
+        Self {
+            google_client_id: env::var("GOOGLE_CLIENT_ID").ok().or(self.google_client_id),
+            linkedin_client_id: env::var("LINKEDIN_CLIENT_ID").ok().or(self.linkedin_client_id),
+            meta_app_id: env::var("META_APP_ID").ok().or(self.meta_app_id),
+            github_client_id: env::var("GITHUB_CLIENT_ID").ok().or(self.github_client_id),
+            github_client_secret: env::var("GITHUB_CLIENT_SECRET").ok().or(self.github_client_secret),
+            github_api_url: env::var("GITHUB_API_URL").unwrap_or(self.github_api_url),
+            jwks_cache_ttl_seconds: env_parse("SOCIAL_JWKS_CACHE_TTL_SECONDS").unwrap_or(self.jwks_cache_ttl_seconds),
+        }
+    }
+}
+
+impl CheckoutConfig {
+    pub fn with_env(self) -> Self {
+        Self {
+            tax_rate: env_parse("TAX_RATE").unwrap_or(self.tax_rate),
+            shipping_fee: env_parse("SHIPPING_FEE").unwrap_or(self.shipping_fee),
+            free_shipping_threshold: env_parse("FREE_SHIPPING_THRESHOLD").or(self.free_shipping_threshold),
+            reservation_ttl_seconds: env_parse("RESERVATION_TTL_SECONDS").unwrap_or(self.reservation_ttl_seconds),
+            price_lock_ttl_seconds: env_parse("PRICE_LOCK_TTL_SECONDS").unwrap_or(self.price_lock_ttl_seconds),
+        }
+    }
+}
+
+impl PaymentConfig {
+    pub fn with_env(self) -> Self {
+        Self {
+            gateway: env::var("PAYMENT_GATEWAY").unwrap_or(self.gateway),
+            zarinpal_api_url: env::var("ZARINPAL_API_URL").unwrap_or(self.zarinpal_api_url),
+            zarinpal_merchant_id: env::var("ZARINPAL_MERCHANT_ID").ok().or(self.zarinpal_merchant_id),
+            callback_url: env::var("PAYMENT_CALLBACK_URL").unwrap_or(self.callback_url),
+            return_url: env::var("PAYMENT_RETURN_URL").ok().or(self.return_url),
+            webhook_secret: env::var("PAYMENT_WEBHOOK_SECRET").ok().or(self.webhook_secret),
+        }
+    }
+}
+
+impl MediaConfig {
+    /// `MEDIA_THUMBNAIL_SIZES` is a comma separated list of pixel sizes;
+    /// the defaults stay when any of them does not parse.
+    pub fn with_env(self) -> Self {
+        let thumbnail_sizes = env::var("MEDIA_THUMBNAIL_SIZES")
+            .ok()
+            .and_then(|value| value.split(',').map(|size| size.trim().parse().ok()).collect::<Option<Vec<u32>>>())
+            .unwrap_or(self.thumbnail_sizes);
+
+        Self {
+            storage: env::var("MEDIA_STORAGE").unwrap_or(self.storage),
+            local_root: env::var("MEDIA_ROOT").unwrap_or(self.local_root),
+            public_url: env::var("MEDIA_PUBLIC_URL").unwrap_or(self.public_url),
+            max_upload_bytes: env_parse("MEDIA_MAX_UPLOAD_BYTES").unwrap_or(self.max_upload_bytes),
+            thumbnail_sizes,
+        }
+    }
+}
+
+fn parse_previous_key(entry: &str) -> Option<JwtKeyConfig> {
+    let mut parts = entry.trim().splitn(3, ':');
+    let key_id = parts.next().filter(|s| !s.is_empty())?;
+    let algorithm = parts.next().filter(|s| !s.is_empty())?;
+    let key = parts.next().filter(|s| !s.is_empty())?;
+
+    Some(JwtKeyConfig {
+        key_id: key_id.to_string(),
+        algorithm: algorithm.to_string(),
+        key: key.to_string(),
+    })
+}
+
+fn env_parse<T: std::str::FromStr>(key: &str) -> Option<T> {
+    env::var(key).ok().and_then(|value| value.parse().ok())
+}
@@ -1 +1,3 @@
 pub mod config;
+
+pub use config::{AppConfig, CheckoutConfig, JwtConfig, JwtKeyConfig, LoginConfig, MediaConfig, NotificationConfig, OtpConfig, PaymentConfig, SocialConfig};
@@ -0,0 +1,11 @@
+CREATE TABLE payments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES orders (id),
+    amount DOUBLE PRECISION NOT NULL CHECK (amount > 0),
+    status TEXT NOT NULL DEFAULT 'pending',
+    transaction_id TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_payments_order_id ON payments (order_id);
@@ -0,0 +1,5 @@
+ALTER TABLE users
+    ADD COLUMN role TEXT NOT NULL DEFAULT 'customer'
+        CHECK (role IN ('admin', 'vendor', 'customer', 'support'));
+
+CREATE INDEX idx_users_role ON users (role);
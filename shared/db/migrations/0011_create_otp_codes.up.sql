@@ -0,0 +1,14 @@
+-- One-time verification codes, keyed by the normalized email address or
+-- phone number they were sent to. Codes are stored as keyed hashes; rows are
+-- kept after use so the send history can drive per-target rate limiting.
+CREATE TABLE otp_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    target TEXT NOT NULL,
+    code_hash TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0 CHECK (attempts >= 0),
+    expires_at TIMESTAMPTZ NOT NULL,
+    consumed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_otp_codes_target_created_at ON otp_codes (target, created_at DESC);
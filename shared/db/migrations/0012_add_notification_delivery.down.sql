@@ -0,0 +1,8 @@
+DROP INDEX IF EXISTS idx_notifications_delivery_status;
+
+ALTER TABLE notifications
+    DROP COLUMN sent_at,
+    DROP COLUMN provider_message_id,
+    DROP COLUMN delivery_error,
+    DROP COLUMN delivery_status,
+    DROP COLUMN channel;
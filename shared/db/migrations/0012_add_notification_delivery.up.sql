@@ -0,0 +1,13 @@
+ALTER TABLE notifications
+    ADD COLUMN channel TEXT NOT NULL DEFAULT 'in_app'
+        CHECK (channel IN ('in_app', 'sms', 'email')),
+    ADD COLUMN delivery_status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (delivery_status IN ('pending', 'sent', 'delivered', 'failed')),
+    ADD COLUMN delivery_error TEXT,
+    ADD COLUMN provider_message_id TEXT,
+    ADD COLUMN sent_at TIMESTAMPTZ;
+
+-- Everything stored before delivery tracking existed was shown in-app only.
+UPDATE notifications SET delivery_status = 'delivered';
+
+CREATE INDEX idx_notifications_delivery_status ON notifications (delivery_status);
@@ -0,0 +1,14 @@
+-- External accounts (Google, GitHub, LinkedIn, Meta) a user signs in with.
+-- `subject` is the provider's stable user id; a user may link several
+-- providers, but each provider account belongs to exactly one user.
+CREATE TABLE linked_identities (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    provider TEXT NOT NULL CHECK (provider IN ('google', 'github', 'linkedin', 'meta')),
+    subject TEXT NOT NULL,
+    email TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_login_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (provider, subject),
+    UNIQUE (user_id, provider)
+);
@@ -0,0 +1,11 @@
+-- Consecutive wrong passwords; reaching the configured limit sets
+-- `locked_until` and starts the count again.
+ALTER TABLE users
+    ADD COLUMN failed_login_attempts INTEGER NOT NULL DEFAULT 0 CHECK (failed_login_attempts >= 0),
+    ADD COLUMN locked_until TIMESTAMPTZ;
+
+-- Users sign in by phone number, which is matched in its normalized `+98`
+-- form. Numbers that are not Iranian mobiles are left as they are.
+UPDATE users
+SET phone = '+98' || right(regexp_replace(phone, '\D', '', 'g'), 10)
+WHERE regexp_replace(phone, '\D', '', 'g') ~ '^(0|98)?9[0-9]{9}$';
@@ -0,0 +1,10 @@
+ALTER TABLE order_items
+    DROP COLUMN line_total,
+    DROP COLUMN product_name;
+
+ALTER TABLE orders
+    DROP COLUMN notes,
+    DROP COLUMN shipping_address,
+    DROP COLUMN shipping_amount,
+    DROP COLUMN tax_amount,
+    DROP COLUMN subtotal;
@@ -0,0 +1,20 @@
+-- Order totals are computed on the server from the line items:
+-- total_amount = subtotal + tax_amount + shipping_amount.
+ALTER TABLE orders
+    ADD COLUMN subtotal DOUBLE PRECISION NOT NULL DEFAULT 0 CHECK (subtotal >= 0),
+    ADD COLUMN tax_amount DOUBLE PRECISION NOT NULL DEFAULT 0 CHECK (tax_amount >= 0),
+    ADD COLUMN shipping_amount DOUBLE PRECISION NOT NULL DEFAULT 0 CHECK (shipping_amount >= 0),
+    ADD COLUMN shipping_address TEXT,
+    ADD COLUMN notes TEXT;
+
+-- Orders created before line items were recorded keep their total as the subtotal.
+UPDATE orders SET subtotal = total_amount;
+
+-- `product_name` keeps the line readable after the product is renamed or removed.
+ALTER TABLE order_items
+    ADD COLUMN product_name TEXT NOT NULL DEFAULT '',
+    ADD COLUMN line_total DOUBLE PRECISION NOT NULL DEFAULT 0 CHECK (line_total >= 0);
+
+UPDATE order_items
+SET line_total = quantity * unit_price,
+    product_name = COALESCE((SELECT name FROM products WHERE products.id = order_items.product_id), '');
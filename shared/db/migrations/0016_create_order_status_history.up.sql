@@ -0,0 +1,27 @@
+-- Free-form statuses from before the state machine: anything unknown is
+-- treated as still awaiting payment.
+UPDATE orders SET status = 'delivered' WHERE status = 'completed';
+UPDATE orders SET status = 'pending'
+WHERE status NOT IN ('pending', 'paid', 'processing', 'shipped', 'delivered', 'cancelled', 'refunded');
+
+ALTER TABLE orders
+    ADD CONSTRAINT orders_status_check
+        CHECK (status IN ('pending', 'paid', 'processing', 'shipped', 'delivered', 'cancelled', 'refunded'));
+
+-- Append-only log of status changes. `changed_by` is NULL for changes made
+-- by the system (e.g. payment callbacks).
+CREATE TABLE order_status_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES orders (id) ON DELETE CASCADE,
+    from_status TEXT,
+    to_status TEXT NOT NULL,
+    changed_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_order_status_history_order_id ON order_status_history (order_id, created_at);
+
+-- Existing orders start their history in their current status.
+INSERT INTO order_status_history (order_id, from_status, to_status, changed_by, created_at)
+SELECT id, NULL, status, NULL, created_at FROM orders;
@@ -0,0 +1,21 @@
+DROP TRIGGER stock_movements_append_only ON stock_movements;
+DROP FUNCTION reject_stock_movement_change();
+DROP TABLE stock_movements;
+DROP TABLE stock_transfers;
+
+DROP INDEX idx_inventory_items_warehouse_id;
+ALTER TABLE inventory_items DROP CONSTRAINT inventory_items_product_warehouse_key;
+ALTER TABLE inventory_items ADD COLUMN location TEXT;
+UPDATE inventory_items SET location = warehouses.code
+FROM warehouses WHERE warehouses.id = inventory_items.warehouse_id;
+ALTER TABLE inventory_items
+    ALTER COLUMN location SET NOT NULL,
+    DROP COLUMN warehouse_id;
+
+UPDATE stock_reservations SET status = 'committed' WHERE status = 'fulfilled';
+ALTER TABLE stock_reservations DROP CONSTRAINT stock_reservations_status_check;
+ALTER TABLE stock_reservations
+    ADD CONSTRAINT stock_reservations_status_check
+        CHECK (status IN ('active', 'committed', 'released'));
+
+DROP TABLE warehouses;
@@ -0,0 +1,126 @@
+-- Stock is kept per warehouse and every change is booked in the
+-- append-only stock_movements ledger. inventory_items.quantity is the
+-- running total of a product's movements in one warehouse, and
+-- products.stock is what can still be sold: the stock in all warehouses
+-- minus what open orders have reserved.
+CREATE TABLE warehouses (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    address TEXT,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_warehouses_default ON warehouses (is_default) WHERE is_default;
+
+INSERT INTO warehouses (code, name, is_default) VALUES ('main', 'انبار اصلی', TRUE);
+INSERT INTO warehouses (code, name)
+SELECT DISTINCT location, location FROM inventory_items WHERE location <> 'main';
+
+-- Reserved stock leaves the warehouse when the order ships.
+ALTER TABLE stock_reservations DROP CONSTRAINT stock_reservations_status_check;
+ALTER TABLE stock_reservations
+    ADD CONSTRAINT stock_reservations_status_check
+        CHECK (status IN ('active', 'committed', 'fulfilled', 'released'));
+
+UPDATE stock_reservations SET status = 'fulfilled'
+WHERE status = 'committed'
+  AND order_id IN (SELECT id FROM orders WHERE status IN ('shipped', 'delivered'));
+
+-- One row per product and warehouse; rows for the same location are merged.
+ALTER TABLE inventory_items ADD COLUMN warehouse_id UUID REFERENCES warehouses (id);
+UPDATE inventory_items SET warehouse_id = warehouses.id
+FROM warehouses WHERE warehouses.code = inventory_items.location;
+
+CREATE TEMPORARY TABLE merged_inventory_items AS
+SELECT product_id, warehouse_id, SUM(quantity)::INTEGER AS quantity,
+       MIN(created_at) AS created_at, MAX(updated_at) AS updated_at
+FROM inventory_items
+GROUP BY product_id, warehouse_id;
+
+DELETE FROM inventory_items;
+ALTER TABLE inventory_items
+    DROP COLUMN location,
+    ALTER COLUMN warehouse_id SET NOT NULL,
+    ADD CONSTRAINT inventory_items_product_warehouse_key UNIQUE (product_id, warehouse_id);
+
+INSERT INTO inventory_items (product_id, warehouse_id, quantity, created_at, updated_at)
+SELECT product_id, warehouse_id, quantity, created_at, updated_at FROM merged_inventory_items;
+
+DROP TABLE merged_inventory_items;
+
+CREATE INDEX idx_inventory_items_warehouse_id ON inventory_items (warehouse_id);
+
+-- Stock that products.stock and open reservations account for but no
+-- warehouse holds is placed in the default warehouse.
+INSERT INTO inventory_items (product_id, warehouse_id, quantity)
+SELECT products.id,
+       (SELECT id FROM warehouses WHERE is_default),
+       products.stock + COALESCE(reserved.quantity, 0) - COALESCE(on_hand.quantity, 0)
+FROM products
+LEFT JOIN (
+    SELECT product_id, SUM(quantity) AS quantity FROM stock_reservations
+    WHERE status IN ('active', 'committed') GROUP BY product_id
+) reserved ON reserved.product_id = products.id
+LEFT JOIN (
+    SELECT product_id, SUM(quantity) AS quantity FROM inventory_items GROUP BY product_id
+) on_hand ON on_hand.product_id = products.id
+WHERE products.stock + COALESCE(reserved.quantity, 0) > COALESCE(on_hand.quantity, 0)
+ON CONFLICT (product_id, warehouse_id)
+DO UPDATE SET quantity = inventory_items.quantity + EXCLUDED.quantity;
+
+UPDATE products SET stock = on_hand.quantity - COALESCE(reserved.quantity, 0)
+FROM (
+    SELECT product_id, SUM(quantity) AS quantity FROM inventory_items GROUP BY product_id
+) on_hand
+LEFT JOIN (
+    SELECT product_id, SUM(quantity) AS quantity FROM stock_reservations
+    WHERE status IN ('active', 'committed') GROUP BY product_id
+) reserved ON reserved.product_id = on_hand.product_id
+WHERE products.id = on_hand.product_id;
+
+CREATE TABLE stock_transfers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL REFERENCES products (id),
+    from_warehouse_id UUID NOT NULL REFERENCES warehouses (id),
+    to_warehouse_id UUID NOT NULL REFERENCES warehouses (id),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    note TEXT,
+    created_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (from_warehouse_id <> to_warehouse_id)
+);
+
+-- `reference_id` is the order (sale, return) or transfer behind a movement.
+CREATE TABLE stock_movements (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL REFERENCES products (id),
+    warehouse_id UUID NOT NULL REFERENCES warehouses (id),
+    movement_type TEXT NOT NULL
+        CHECK (movement_type IN ('receipt', 'sale', 'return', 'adjustment', 'transfer')),
+    quantity INTEGER NOT NULL CHECK (quantity <> 0),
+    balance_after INTEGER NOT NULL CHECK (balance_after >= 0),
+    reference_id UUID,
+    note TEXT,
+    created_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_stock_movements_product_warehouse ON stock_movements (product_id, warehouse_id, created_at);
+CREATE INDEX idx_stock_movements_reference_id ON stock_movements (reference_id);
+
+CREATE FUNCTION reject_stock_movement_change() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'stock_movements is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER stock_movements_append_only
+    BEFORE UPDATE OR DELETE ON stock_movements
+    FOR EACH ROW EXECUTE FUNCTION reject_stock_movement_change();
+
+INSERT INTO stock_movements (product_id, warehouse_id, movement_type, quantity, balance_after, note)
+SELECT product_id, warehouse_id, 'adjustment', quantity, quantity, 'Opening balance'
+FROM inventory_items WHERE quantity > 0;
@@ -0,0 +1,9 @@
+DROP INDEX idx_stock_movements_sales;
+
+ALTER TABLE inventory_items
+    DROP COLUMN low_stock_alerted_at,
+    DROP COLUMN reorder_quantity,
+    DROP COLUMN reorder_point;
+
+DROP INDEX idx_vendors_user_id;
+ALTER TABLE vendors DROP COLUMN user_id;
@@ -0,0 +1,14 @@
+-- The account that manages a vendor's catalog and receives its stock alerts.
+ALTER TABLE vendors ADD COLUMN user_id UUID REFERENCES users (id) ON DELETE SET NULL;
+CREATE INDEX idx_vendors_user_id ON vendors (user_id);
+
+-- A stock level at or below its reorder point is low. `low_stock_alerted_at`
+-- is set when the vendor was told and cleared once the level recovers, so
+-- each crossing is reported once.
+ALTER TABLE inventory_items
+    ADD COLUMN reorder_point INTEGER CHECK (reorder_point >= 0),
+    ADD COLUMN reorder_quantity INTEGER CHECK (reorder_quantity > 0),
+    ADD COLUMN low_stock_alerted_at TIMESTAMPTZ;
+
+CREATE INDEX idx_stock_movements_sales ON stock_movements (product_id, created_at)
+    WHERE movement_type = 'sale';
@@ -0,0 +1,11 @@
+ALTER TABLE payments DROP CONSTRAINT payments_gateway_transaction_id_key;
+ALTER TABLE payments ADD CONSTRAINT payments_transaction_id_key UNIQUE (transaction_id);
+
+ALTER TABLE payments
+    DROP CONSTRAINT payments_status_check,
+    DROP COLUMN paid_at,
+    DROP COLUMN failure_reason,
+    DROP COLUMN card_pan,
+    DROP COLUMN reference_id,
+    DROP COLUMN redirect_url,
+    DROP COLUMN gateway;
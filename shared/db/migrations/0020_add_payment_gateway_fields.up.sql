@@ -0,0 +1,16 @@
+-- Payments made before gateways existed were never confirmed.
+UPDATE payments SET status = 'failed' WHERE status NOT IN ('pending', 'paid', 'failed', 'refunded');
+
+ALTER TABLE payments
+    ADD COLUMN gateway TEXT NOT NULL DEFAULT 'manual',
+    ADD COLUMN redirect_url TEXT,
+    ADD COLUMN reference_id TEXT,
+    ADD COLUMN card_pan TEXT,
+    ADD COLUMN failure_reason TEXT,
+    ADD COLUMN paid_at TIMESTAMPTZ,
+    ADD CONSTRAINT payments_status_check CHECK (status IN ('pending', 'paid', 'failed', 'refunded'));
+ALTER TABLE payments ALTER COLUMN gateway DROP DEFAULT;
+
+-- The transaction id is the gateway's authority, unique per gateway.
+ALTER TABLE payments DROP CONSTRAINT payments_transaction_id_key;
+ALTER TABLE payments ADD CONSTRAINT payments_gateway_transaction_id_key UNIQUE (gateway, transaction_id);
@@ -0,0 +1,30 @@
+-- Responses to requests sent with an `Idempotency-Key` header, replayed
+-- when the client retries with the same key. `status_code` is NULL while
+-- the first request is still being handled.
+CREATE TABLE idempotency_keys (
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    scope TEXT NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    request_hash TEXT NOT NULL,
+    status_code SMALLINT,
+    response_body TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ,
+    PRIMARY KEY (user_id, scope, idempotency_key)
+);
+
+CREATE INDEX idx_idempotency_keys_created_at ON idempotency_keys (created_at);
+
+-- Gateway webhook events that were handled; a redelivered event is
+-- recognised by its id and not applied again.
+CREATE TABLE payment_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    gateway TEXT NOT NULL,
+    event_id TEXT NOT NULL,
+    event_type TEXT NOT NULL CHECK (event_type IN ('payment.paid', 'payment.failed')),
+    payment_id UUID NOT NULL REFERENCES payments (id) ON DELETE CASCADE,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (gateway, event_id)
+);
+
+CREATE INDEX idx_payment_events_payment_id ON payment_events (payment_id);
@@ -0,0 +1,8 @@
+DROP TABLE refund_items;
+DROP TABLE refunds;
+
+UPDATE payments SET status = 'paid' WHERE status = 'partially_refunded';
+ALTER TABLE payments DROP CONSTRAINT payments_status_check;
+ALTER TABLE payments
+    DROP COLUMN refunded_amount,
+    ADD CONSTRAINT payments_status_check CHECK (status IN ('pending', 'paid', 'failed', 'refunded'));
@@ -0,0 +1,34 @@
+ALTER TABLE payments DROP CONSTRAINT payments_status_check;
+ALTER TABLE payments
+    ADD COLUMN refunded_amount FLOAT8 NOT NULL DEFAULT 0 CHECK (refunded_amount >= 0),
+    ADD CONSTRAINT payments_status_check
+        CHECK (status IN ('pending', 'paid', 'partially_refunded', 'failed', 'refunded'));
+
+CREATE TABLE refunds (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    payment_id UUID NOT NULL REFERENCES payments (id) ON DELETE CASCADE,
+    order_id UUID NOT NULL REFERENCES orders (id),
+    amount FLOAT8 NOT NULL CHECK (amount > 0),
+    status TEXT NOT NULL CHECK (status IN ('succeeded', 'failed')),
+    reason TEXT,
+    restock BOOLEAN NOT NULL,
+    gateway_refund_id TEXT,
+    failure_reason TEXT,
+    created_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_refunds_payment_id ON refunds (payment_id);
+CREATE INDEX idx_refunds_order_id ON refunds (order_id);
+
+-- Only the items of succeeded refunds count as refunded.
+CREATE TABLE refund_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    refund_id UUID NOT NULL REFERENCES refunds (id) ON DELETE CASCADE,
+    order_item_id UUID NOT NULL REFERENCES order_items (id) ON DELETE CASCADE,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    amount FLOAT8 NOT NULL CHECK (amount >= 0),
+    UNIQUE (refund_id, order_item_id)
+);
+
+CREATE INDEX idx_refund_items_order_item_id ON refund_items (order_item_id);
@@ -0,0 +1,18 @@
+ALTER TABLE refund_items ALTER COLUMN amount TYPE DOUBLE PRECISION;
+ALTER TABLE refunds ALTER COLUMN amount TYPE DOUBLE PRECISION;
+
+ALTER TABLE payments
+    ALTER COLUMN amount TYPE DOUBLE PRECISION,
+    ALTER COLUMN refunded_amount TYPE DOUBLE PRECISION;
+
+ALTER TABLE order_items
+    ALTER COLUMN unit_price TYPE DOUBLE PRECISION,
+    ALTER COLUMN line_total TYPE DOUBLE PRECISION;
+
+ALTER TABLE orders
+    ALTER COLUMN subtotal TYPE DOUBLE PRECISION,
+    ALTER COLUMN tax_amount TYPE DOUBLE PRECISION,
+    ALTER COLUMN shipping_amount TYPE DOUBLE PRECISION,
+    ALTER COLUMN total_amount TYPE DOUBLE PRECISION;
+
+ALTER TABLE products ALTER COLUMN price TYPE DOUBLE PRECISION;
@@ -0,0 +1,19 @@
+-- Amounts are whole rials. NUMERIC keeps sums exact where floats drift.
+ALTER TABLE products ALTER COLUMN price TYPE NUMERIC(20, 0) USING ROUND(price);
+
+ALTER TABLE orders
+    ALTER COLUMN subtotal TYPE NUMERIC(20, 0) USING ROUND(subtotal),
+    ALTER COLUMN tax_amount TYPE NUMERIC(20, 0) USING ROUND(tax_amount),
+    ALTER COLUMN shipping_amount TYPE NUMERIC(20, 0) USING ROUND(shipping_amount),
+    ALTER COLUMN total_amount TYPE NUMERIC(20, 0) USING ROUND(total_amount);
+
+ALTER TABLE order_items
+    ALTER COLUMN unit_price TYPE NUMERIC(20, 0) USING ROUND(unit_price),
+    ALTER COLUMN line_total TYPE NUMERIC(20, 0) USING ROUND(line_total);
+
+ALTER TABLE payments
+    ALTER COLUMN amount TYPE NUMERIC(20, 0) USING ROUND(amount),
+    ALTER COLUMN refunded_amount TYPE NUMERIC(20, 0) USING ROUND(refunded_amount);
+
+ALTER TABLE refunds ALTER COLUMN amount TYPE NUMERIC(20, 0) USING ROUND(amount);
+ALTER TABLE refund_items ALTER COLUMN amount TYPE NUMERIC(20, 0) USING ROUND(amount);
@@ -0,0 +1,15 @@
+-- Rials one unit of a foreign currency is worth. The rial and the toman
+-- have fixed rates and no row.
+CREATE TABLE exchange_rates (
+    currency TEXT PRIMARY KEY CHECK (currency IN ('USD', 'EUR', 'AED')),
+    rials_per_unit BIGINT NOT NULL CHECK (rials_per_unit > 0),
+    source TEXT NOT NULL DEFAULT 'manual' CHECK (source IN ('manual')),
+    updated_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Amounts stay in rials; the currency the shopper saw and its rate at
+-- checkout are kept with the order.
+ALTER TABLE orders
+    ADD COLUMN currency TEXT NOT NULL DEFAULT 'IRR' CHECK (currency IN ('IRR', 'IRT', 'USD', 'EUR', 'AED')),
+    ADD COLUMN exchange_rate BIGINT NOT NULL DEFAULT 1 CHECK (exchange_rate > 0);
@@ -0,0 +1,8 @@
+-- The backend writes the search vector itself from normalized words, so
+-- Persian text is indexed the same whatever the database encoding and
+-- locale. NULL marks products it has not indexed yet; it fills those in on
+-- startup.
+ALTER TABLE products ADD COLUMN search_vector TSVECTOR;
+
+CREATE INDEX idx_products_search_vector ON products USING GIN (search_vector);
+CREATE INDEX idx_products_price ON products (price);
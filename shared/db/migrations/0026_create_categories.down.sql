@@ -0,0 +1,10 @@
+ALTER TABLE products ADD COLUMN category TEXT;
+UPDATE products SET category = categories.name_en
+FROM categories
+WHERE categories.id = products.category_id;
+ALTER TABLE products ALTER COLUMN category SET NOT NULL;
+ALTER TABLE products DROP COLUMN category_id;
+CREATE INDEX idx_products_category ON products (category);
+UPDATE products SET search_vector = NULL;
+
+DROP TABLE categories;
@@ -0,0 +1,44 @@
+CREATE TABLE categories (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    parent_id UUID REFERENCES categories (id),
+    slug TEXT NOT NULL UNIQUE CHECK (slug ~ '^[a-z0-9]+(-[a-z0-9]+)*$'),
+    name_fa TEXT NOT NULL,
+    name_en TEXT NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    attributes JSONB NOT NULL DEFAULT '[]' CHECK (jsonb_typeof(attributes) = 'array'),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (parent_id <> id)
+);
+
+CREATE INDEX idx_categories_parent_id ON categories (parent_id);
+
+-- Every category name products were listed under becomes a top-level
+-- category. Names without ASCII letters or digits get a slug from their
+-- hash; admins can rename them afterwards.
+CREATE FUNCTION category_slug(name TEXT) RETURNS TEXT
+LANGUAGE sql IMMUTABLE AS $$
+    SELECT COALESCE(
+        NULLIF(trim(BOTH '-' FROM regexp_replace(lower(name), '[^a-z0-9]+', '-', 'g')), ''),
+        'category-' || left(md5(name), 8)
+    )
+$$;
+
+INSERT INTO categories (slug, name_fa, name_en)
+SELECT category_slug(category), MIN(category), MIN(category)
+FROM products
+GROUP BY category_slug(category);
+
+ALTER TABLE products ADD COLUMN category_id UUID REFERENCES categories (id);
+UPDATE products SET category_id = categories.id
+FROM categories
+WHERE categories.slug = category_slug(products.category);
+ALTER TABLE products ALTER COLUMN category_id SET NOT NULL;
+ALTER TABLE products DROP COLUMN category;
+CREATE INDEX idx_products_category_id ON products (category_id);
+
+DROP FUNCTION category_slug(TEXT);
+
+-- Category names are indexed from the categories now; the backend
+-- reindexes these on startup.
+UPDATE products SET search_vector = NULL;
@@ -0,0 +1,41 @@
+-- Variants of the same product are merged back into it.
+ALTER TABLE order_items DROP COLUMN variant_id, DROP COLUMN sku;
+
+WITH merged AS (
+    SELECT order_id, product_id, SUM(quantity) AS quantity
+    FROM stock_reservations GROUP BY order_id, product_id HAVING COUNT(*) > 1
+)
+UPDATE stock_reservations r SET quantity = merged.quantity
+FROM merged
+WHERE merged.order_id = r.order_id AND merged.product_id = r.product_id;
+DELETE FROM stock_reservations r
+USING stock_reservations other
+WHERE other.order_id = r.order_id AND other.product_id = r.product_id AND other.id < r.id;
+ALTER TABLE stock_reservations
+    DROP CONSTRAINT stock_reservations_order_id_variant_id_key,
+    DROP COLUMN variant_id,
+    ADD CONSTRAINT stock_reservations_order_id_product_id_key UNIQUE (order_id, product_id);
+
+ALTER TABLE stock_transfers DROP COLUMN variant_id;
+
+DROP INDEX idx_stock_movements_variant_warehouse;
+ALTER TABLE stock_movements DROP COLUMN variant_id;
+
+CREATE TEMPORARY TABLE merged_inventory_items AS
+SELECT product_id, warehouse_id, SUM(quantity)::INTEGER AS quantity,
+       SUM(reorder_point)::INTEGER AS reorder_point, SUM(reorder_quantity)::INTEGER AS reorder_quantity,
+       MIN(created_at) AS created_at, MAX(updated_at) AS updated_at
+FROM inventory_items
+GROUP BY product_id, warehouse_id;
+DELETE FROM inventory_items;
+ALTER TABLE inventory_items
+    DROP CONSTRAINT inventory_items_variant_warehouse_key,
+    DROP COLUMN variant_id,
+    ADD CONSTRAINT inventory_items_product_warehouse_key UNIQUE (product_id, warehouse_id);
+INSERT INTO inventory_items (product_id, warehouse_id, quantity, reorder_point, reorder_quantity, created_at, updated_at)
+SELECT product_id, warehouse_id, quantity, reorder_point, reorder_quantity, created_at, updated_at
+FROM merged_inventory_items;
+DROP TABLE merged_inventory_items;
+
+DROP TABLE product_variants;
+UPDATE products SET search_vector = NULL;
@@ -0,0 +1,67 @@
+-- What is sold is a variant of a product; products.price and products.stock
+-- become the lowest variant price and the variants' stock combined.
+CREATE TABLE product_variants (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL REFERENCES products (id) ON DELETE CASCADE,
+    sku TEXT NOT NULL UNIQUE CHECK (sku ~ '^[A-Za-z0-9._-]{1,64}$'),
+    weight_grams NUMERIC(12, 3) CHECK (weight_grams > 0),
+    purity INTEGER CHECK (purity BETWEEN 1 AND 1000),
+    size TEXT,
+    color TEXT,
+    attributes JSONB NOT NULL DEFAULT '{}' CHECK (jsonb_typeof(attributes) = 'object'),
+    price NUMERIC(20, 0) NOT NULL CHECK (price >= 0),
+    stock INTEGER NOT NULL DEFAULT 0 CHECK (stock >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (id, product_id)
+);
+
+CREATE INDEX idx_product_variants_product_id ON product_variants (product_id);
+
+-- Every existing product becomes a single variant.
+INSERT INTO product_variants (product_id, sku, price, stock, created_at, updated_at)
+SELECT id, 'P-' || upper(replace(id::TEXT, '-', '')), price, stock, created_at, updated_at
+FROM products;
+
+-- Stock levels, the ledger, reservations and order lines point at the
+-- variant; product_id stays alongside it and must be the variant's product.
+ALTER TABLE inventory_items ADD COLUMN variant_id UUID;
+UPDATE inventory_items SET variant_id = v.id FROM product_variants v WHERE v.product_id = inventory_items.product_id;
+ALTER TABLE inventory_items ALTER COLUMN variant_id SET NOT NULL;
+ALTER TABLE inventory_items
+    ADD CONSTRAINT inventory_items_variant_fkey FOREIGN KEY (variant_id, product_id) REFERENCES product_variants (id, product_id) ON DELETE CASCADE,
+    DROP CONSTRAINT inventory_items_product_warehouse_key,
+    ADD CONSTRAINT inventory_items_variant_warehouse_key UNIQUE (variant_id, warehouse_id);
+
+ALTER TABLE stock_movements ADD COLUMN variant_id UUID;
+-- The ledger is append-only; the backfill is the one sanctioned rewrite.
+ALTER TABLE stock_movements DISABLE TRIGGER stock_movements_append_only;
+UPDATE stock_movements SET variant_id = v.id FROM product_variants v WHERE v.product_id = stock_movements.product_id;
+ALTER TABLE stock_movements ENABLE TRIGGER stock_movements_append_only;
+ALTER TABLE stock_movements ALTER COLUMN variant_id SET NOT NULL;
+ALTER TABLE stock_movements
+    ADD CONSTRAINT stock_movements_variant_fkey FOREIGN KEY (variant_id, product_id) REFERENCES product_variants (id, product_id);
+CREATE INDEX idx_stock_movements_variant_warehouse ON stock_movements (variant_id, warehouse_id, created_at);
+
+ALTER TABLE stock_transfers ADD COLUMN variant_id UUID;
+UPDATE stock_transfers SET variant_id = v.id FROM product_variants v WHERE v.product_id = stock_transfers.product_id;
+ALTER TABLE stock_transfers ALTER COLUMN variant_id SET NOT NULL;
+ALTER TABLE stock_transfers
+    ADD CONSTRAINT stock_transfers_variant_fkey FOREIGN KEY (variant_id, product_id) REFERENCES product_variants (id, product_id);
+
+ALTER TABLE stock_reservations ADD COLUMN variant_id UUID;
+UPDATE stock_reservations SET variant_id = v.id FROM product_variants v WHERE v.product_id = stock_reservations.product_id;
+ALTER TABLE stock_reservations ALTER COLUMN variant_id SET NOT NULL;
+ALTER TABLE stock_reservations
+    ADD CONSTRAINT stock_reservations_variant_fkey FOREIGN KEY (variant_id, product_id) REFERENCES product_variants (id, product_id),
+    DROP CONSTRAINT stock_reservations_order_id_product_id_key,
+    ADD CONSTRAINT stock_reservations_order_id_variant_id_key UNIQUE (order_id, variant_id);
+
+ALTER TABLE order_items ADD COLUMN variant_id UUID, ADD COLUMN sku TEXT;
+UPDATE order_items SET variant_id = v.id, sku = v.sku FROM product_variants v WHERE v.product_id = order_items.product_id;
+ALTER TABLE order_items ALTER COLUMN variant_id SET NOT NULL, ALTER COLUMN sku SET NOT NULL;
+ALTER TABLE order_items
+    ADD CONSTRAINT order_items_variant_fkey FOREIGN KEY (variant_id, product_id) REFERENCES product_variants (id, product_id);
+
+-- SKUs are searchable; the backend reindexes these on startup.
+UPDATE products SET search_vector = NULL;
@@ -0,0 +1,5 @@
+DROP TABLE price_lock_items;
+DROP TABLE price_locks;
+DROP TABLE price_history;
+DROP TABLE product_pricing_rules;
+DROP TABLE metal_rates;
@@ -0,0 +1,53 @@
+-- Rials a gram of the pure metal costs, set by admins.
+CREATE TABLE metal_rates (
+    metal TEXT PRIMARY KEY CHECK (metal IN ('silver')),
+    rials_per_gram NUMERIC(20, 0) NOT NULL CHECK (rials_per_gram > 0),
+    updated_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- A product with a rule has its variants priced from their metal content:
+-- weight × purity × rate, plus the making charge, plus the margin.
+CREATE TABLE product_pricing_rules (
+    product_id UUID PRIMARY KEY REFERENCES products (id) ON DELETE CASCADE,
+    metal TEXT NOT NULL CHECK (metal IN ('silver')),
+    making_charge NUMERIC(20, 0) NOT NULL CHECK (making_charge >= 0),
+    margin_basis_points INTEGER NOT NULL DEFAULT 0 CHECK (margin_basis_points BETWEEN 0 AND 10000),
+    updated_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_product_pricing_rules_metal ON product_pricing_rules (metal);
+
+CREATE TABLE price_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL,
+    variant_id UUID NOT NULL,
+    old_price NUMERIC(20, 0) NOT NULL,
+    new_price NUMERIC(20, 0) NOT NULL,
+    reason TEXT NOT NULL CHECK (reason IN ('manual', 'pricing_rule', 'metal_rate')),
+    metal_rate NUMERIC(20, 0),
+    changed_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (variant_id, product_id) REFERENCES product_variants (id, product_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_price_history_product_id ON price_history (product_id, created_at);
+
+-- Prices held for a shopper during checkout; `order_id` is set once an
+-- order used the lock.
+CREATE TABLE price_locks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    order_id UUID UNIQUE REFERENCES orders (id) ON DELETE SET NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE price_lock_items (
+    price_lock_id UUID NOT NULL REFERENCES price_locks (id) ON DELETE CASCADE,
+    variant_id UUID NOT NULL REFERENCES product_variants (id) ON DELETE CASCADE,
+    unit_price NUMERIC(20, 0) NOT NULL CHECK (unit_price >= 0),
+    PRIMARY KEY (price_lock_id, variant_id)
+);
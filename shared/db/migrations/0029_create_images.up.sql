@@ -0,0 +1,23 @@
+-- Uploaded images of products and vendors. The files live in the media
+-- store under `storage_key`; `thumbnails` lists the smaller copies made on
+-- upload.
+CREATE TABLE images (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID REFERENCES products (id) ON DELETE CASCADE,
+    vendor_id UUID REFERENCES vendors (id) ON DELETE CASCADE,
+    storage_key TEXT NOT NULL UNIQUE,
+    url TEXT NOT NULL,
+    content_type TEXT NOT NULL CHECK (content_type IN ('image/jpeg', 'image/png', 'image/webp')),
+    byte_size BIGINT NOT NULL CHECK (byte_size > 0),
+    width INTEGER NOT NULL CHECK (width > 0),
+    height INTEGER NOT NULL CHECK (height > 0),
+    thumbnails JSONB NOT NULL DEFAULT '[]' CHECK (jsonb_typeof(thumbnails) = 'array'),
+    alt_text TEXT,
+    position INTEGER NOT NULL DEFAULT 0,
+    uploaded_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((product_id IS NULL) <> (vendor_id IS NULL))
+);
+
+CREATE INDEX idx_images_product_id ON images (product_id, position) WHERE product_id IS NOT NULL;
+CREATE INDEX idx_images_vendor_id ON images (vendor_id, position) WHERE vendor_id IS NOT NULL;
@@ -0,0 +1,4 @@
+UPDATE refunds SET status = 'failed' WHERE status = 'pending';
+ALTER TABLE refunds DROP CONSTRAINT refunds_status_check;
+ALTER TABLE refunds
+    ADD CONSTRAINT refunds_status_check CHECK (status IN ('succeeded', 'failed'));
@@ -0,0 +1,4 @@
+-- A refund whose gateway call timed out stays pending until it is resolved.
+ALTER TABLE refunds DROP CONSTRAINT refunds_status_check;
+ALTER TABLE refunds
+    ADD CONSTRAINT refunds_status_check CHECK (status IN ('pending', 'succeeded', 'failed'));
@@ -0,0 +1,37 @@
+use sqlx::migrate::MigrateError;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+#[derive(Debug)]
+pub enum DbError {
+    Database(sqlx::Error),
+    Migration(MigrateError),
+    SchemaMismatch(Vec<String>),
+    InvalidCommand(String),
+}
+
+impl Display for DbError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            DbError::Database(err) => write!(f, "Database error: {}", err),
+            DbError::Migration(err) => write!(f, "Migration error: {}", err),
+            DbError::SchemaMismatch(problems) => {
+                write!(f, "Database schema does not match the models: {}", problems.join("; "))
+            }
+            DbError::InvalidCommand(msg) => write!(f, "Invalid migrate command: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
+impl From<sqlx::Error> for DbError {
+    fn from(err: sqlx::Error) -> Self {
+        DbError::Database(err)
+    }
+}
+
+impl From<MigrateError> for DbError {
+    fn from(err: MigrateError) -> Self {
+        DbError::Migration(err)
+    }
+}
@@ -0,0 +1,7 @@
+pub mod error;
+pub mod migrate;
+pub mod schema;
+
+pub use error::DbError;
+pub use migrate::{run_command, run_migrations, MIGRATOR};
+pub use schema::verify_schema;
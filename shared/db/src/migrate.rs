@@ -0,0 +1,82 @@
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::PgPool;
+use crate::{error::DbError, schema::verify_schema};
+
+/// Every migration under `shared/db/migrations`, embedded at compile time so
+/// the backends can bring a fresh database up to date without extra files.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Applies all pending up migrations.
+pub async fn run_migrations(pool: &PgPool) -> Result<(), DbError> {
+    MIGRATOR.run(pool).await?;
+    log::info!("Database migrations are up to date");
+    Ok(())
+}
+
+/// Reverts every applied migration newer than `target`. Without a target only
+/// the most recently applied migration is reverted.
+pub async fn revert_migrations(pool: &PgPool, target: Option<i64>) -> Result<i64, DbError> {
+    let target = match target {
+        Some(target) => target,
+        None => {
+            let applied = applied_versions(pool).await?;
+            applied.iter().rev().nth(1).copied().unwrap_or(0)
+        }
+    };
+
+    MIGRATOR.undo(pool, target).await?;
+    log::info!("Reverted database migrations down to version {}", target);
+    Ok(target)
+}
+
+/// Versions of the migrations recorded as applied, oldest first.
+pub async fn applied_versions(pool: &PgPool) -> Result<Vec<i64>, DbError> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+
+    let mut versions: Vec<i64> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+    versions.sort_unstable();
+
+    Ok(versions)
+}
+
+/// Handles the arguments following a `migrate` subcommand:
+///
+/// * `migrate` / `migrate up` – apply pending migrations
+/// * `migrate down [version]` – revert the latest migration, or down to `version`
+/// * `migrate status` – list migrations and whether they are applied
+/// * `migrate verify` – check the models against the live schema
+pub async fn run_command(pool: &PgPool, args: &[String]) -> Result<(), DbError> {
+    match args.first().map(String::as_str) {
+        None | Some("up") => run_migrations(pool).await,
+        Some("down") => {
+            let target = args
+                .get(1)
+                .map(|version| {
+                    version
+                        .parse::<i64>()
+                        .map_err(|_| DbError::InvalidCommand(format!("`{}` is not a migration version", version)))
+                })
+                .transpose()?;
+            revert_migrations(pool, target).await.map(|_| ())
+        }
+        Some("status") => {
+            let applied = applied_versions(pool).await?;
+            for migration in MIGRATOR.iter().filter(|m| !m.migration_type.is_down_migration()) {
+                let state = if applied.contains(&migration.version) { "applied" } else { "pending" };
+                println!("{:04} {:<40} {}", migration.version, migration.description, state);
+            }
+            Ok(())
+        }
+        Some("verify") => verify_schema(pool).await,
+        Some(other) => Err(DbError::InvalidCommand(format!(
+            "unknown subcommand `{}`, expected one of: up, down [version], status, verify",
+            other
+        ))),
+    }
+}
@@ -0,0 +1,92 @@
+use models::{
+    category::Category,
+    exchange_rate::ExchangeRate,
+    identity::LinkedIdentity,
+    media::Image,
+    inventory::{InventoryItem, StockMovement, StockReservation, StockTransfer},
+    notification::Notification,
+    order::{Order, OrderItem, OrderStatusChange},
+    payment::{Payment, Refund, RefundItem},
+    pricing::{MetalRate, PriceChange, PriceLock, PriceLockItem, PricingRule},
+    product::{Product, ProductVariant},
+    schema::Table,
+    user::User,
+    vendor::Vendor,
+    warehouse::Warehouse,
+};
+use sqlx::{Column as _, Executor, PgPool, TypeInfo};
+use crate::error::DbError;
+
+/// Checks that every table the backends read with `SELECT *` has the columns,
+/// types and nullability the corresponding model's `FromRow` impl expects.
+pub async fn verify_schema(pool: &PgPool) -> Result<(), DbError> {
+    let mut problems = Vec::new();
+
+    check_table::<User>(pool, &mut problems).await?;
+    check_table::<LinkedIdentity>(pool, &mut problems).await?;
+    check_table::<Vendor>(pool, &mut problems).await?;
+    check_table::<Category>(pool, &mut problems).await?;
+    check_table::<Product>(pool, &mut problems).await?;
+    check_table::<ProductVariant>(pool, &mut problems).await?;
+    check_table::<Image>(pool, &mut problems).await?;
+    check_table::<Warehouse>(pool, &mut problems).await?;
+    check_table::<InventoryItem>(pool, &mut problems).await?;
+    check_table::<StockMovement>(pool, &mut problems).await?;
+    check_table::<StockTransfer>(pool, &mut problems).await?;
+    check_table::<StockReservation>(pool, &mut problems).await?;
+    check_table::<Order>(pool, &mut problems).await?;
+    check_table::<OrderItem>(pool, &mut problems).await?;
+    check_table::<OrderStatusChange>(pool, &mut problems).await?;
+    check_table::<Payment>(pool, &mut problems).await?;
+    check_table::<Refund>(pool, &mut problems).await?;
+    check_table::<RefundItem>(pool, &mut problems).await?;
+    check_table::<ExchangeRate>(pool, &mut problems).await?;
+    check_table::<MetalRate>(pool, &mut problems).await?;
+    check_table::<PricingRule>(pool, &mut problems).await?;
+    check_table::<PriceChange>(pool, &mut problems).await?;
+    check_table::<PriceLock>(pool, &mut problems).await?;
+    check_table::<PriceLockItem>(pool, &mut problems).await?;
+    check_table::<Notification>(pool, &mut problems).await?;
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(DbError::SchemaMismatch(problems))
+    }
+}
+
+async fn check_table<T: Table>(pool: &PgPool, problems: &mut Vec<String>) -> Result<(), DbError> {
+    let query = format!("SELECT * FROM {}", T::TABLE);
+    let describe = match pool.describe(&query).await {
+        Ok(describe) => describe,
+        Err(sqlx::Error::Database(err)) => {
+            problems.push(format!("table `{}` cannot be read: {}", T::TABLE, err.message()));
+            return Ok(());
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    for expected in T::COLUMNS {
+        let Some(index) = describe.columns().iter().position(|c| c.name() == expected.name) else {
+            problems.push(format!("`{}.{}` is missing", T::TABLE, expected.name));
+            continue;
+        };
+
+        let actual_type = describe.column(index).type_info().name();
+        if actual_type != expected.sql_type {
+            problems.push(format!(
+                "`{}.{}` is {} but the model expects {}",
+                T::TABLE, expected.name, actual_type, expected.sql_type
+            ));
+        }
+
+        if describe.nullable(index) == Some(true) && !expected.nullable {
+            problems.push(format!(
+                "`{}.{}` is nullable but the model field is not an Option",
+                T::TABLE, expected.name
+            ));
+        }
+    }
+
+    Ok(())
+}
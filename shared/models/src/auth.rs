@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use uuid::Uuid;
+
+use crate::{role::{Permission, Role}, user::User};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginRequest {
+    /// Email address, username or phone number.
+    #[serde(alias = "email_or_phone")]
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterRequest {
+    pub email: String,
+    pub password: String,
+    pub name: String,
+    pub phone: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthResponse {
+    pub user: User,
+    pub token: String,
+    pub refresh_token: String,
+    pub expires_in: u64,
+}
+
+/// Where a verification code is sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmailOrPhone {
+    Email(String),
+    Phone(String),
+}
+
+impl EmailOrPhone {
+    /// Classifies user input without validating it: anything with an `@` is an email.
+    pub fn parse(input: &str) -> Self {
+        let input = input.trim().to_string();
+        if input.contains('@') {
+            EmailOrPhone::Email(input)
+        } else {
+            EmailOrPhone::Phone(input)
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            EmailOrPhone::Email(email) => email,
+            EmailOrPhone::Phone(phone) => phone,
+        }
+    }
+
+    /// Canonical spelling used as a storage and rate limiting key: lowercase
+    /// emails, and Iranian mobile numbers in `+98` form whichever way they
+    /// were typed (`0912...`, `912...`, `+98912...`).
+    pub fn normalized(&self) -> String {
+        match self {
+            EmailOrPhone::Email(email) => email.trim().to_lowercase(),
+            EmailOrPhone::Phone(phone) => {
+                let digits: String = phone.chars().filter(char::is_ascii_digit).collect();
+                let national = digits
+                    .strip_prefix("98")
+                    .filter(|rest| rest.len() == 10)
+                    .or_else(|| digits.strip_prefix('0'))
+                    .unwrap_or(&digits);
+                format!("+98{}", national)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtpRequest {
+    pub email_or_phone: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtpVerifyRequest {
+    pub email_or_phone: String,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+/// Access token claims, issued by the auth backend and verified by every service.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String, // Subject (user ID)
+    pub exp: usize,  // Expiration time
+    pub iat: usize,  // Issued at
+    pub iss: String, // Issuer
+    pub aud: String, // Audience
+    pub role: Role,
+    pub permissions: Vec<Permission>,
+}
+
+impl Claims {
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+}
+
+/// The user a verified access token was issued to. Every backend wraps it
+/// in its own request extractor, which verifies the token with that
+/// backend's keys; handlers then call [`require`](Self::require) or
+/// [`require_owner_or`](Self::require_owner_or) before touching protected
+/// data.
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub user_id: Uuid,
+    pub claims: Claims,
+}
+
+impl Caller {
+    pub fn from_claims(claims: Claims) -> Result<Self, AccessError> {
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AccessError::InvalidSubject)?;
+        Ok(Self { user_id, claims })
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.claims.has_permission(permission)
+    }
+
+    pub fn require(&self, permission: Permission) -> Result<(), AccessError> {
+        if self.has_permission(permission) {
+            Ok(())
+        } else {
+            Err(AccessError::MissingPermission(permission))
+        }
+    }
+
+    /// Lets the owner of a resource through, and everyone else only with `permission`.
+    pub fn require_owner_or(&self, owner_id: Uuid, permission: Permission) -> Result<(), AccessError> {
+        if self.user_id == owner_id {
+            Ok(())
+        } else {
+            self.require(permission)
+        }
+    }
+}
+
+/// Why a request was turned away before reaching protected data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessError {
+    MissingToken,
+    /// The token's subject is not a user id.
+    InvalidSubject,
+    MissingPermission(Permission),
+}
+
+impl Display for AccessError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            AccessError::MissingToken => f.write_str("Missing bearer token"),
+            AccessError::InvalidSubject => f.write_str("Invalid token subject"),
+            AccessError::MissingPermission(permission) => write!(f, "Missing permission `{}`", permission),
+        }
+    }
+}
+
+/// The token of an `Authorization: Bearer <jwt>` header value.
+pub fn bearer_token(authorization: &str) -> Option<&str> {
+    authorization
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phone_spellings_share_a_key() {
+        for phone in ["09123456789", "+989123456789", "9123456789", "0912 345 6789"] {
+            assert_eq!(EmailOrPhone::Phone(phone.to_string()).normalized(), "+989123456789");
+        }
+        assert_eq!(EmailOrPhone::Email(" Ali@Example.com".to_string()).normalized(), "ali@example.com");
+    }
+
+    #[test]
+    fn test_callers_need_the_permission_or_ownership() {
+        let owner = Uuid::new_v4();
+        let caller = Caller::from_claims(Claims {
+            sub: owner.to_string(),
+            exp: 0,
+            iat: 0,
+            iss: "pema-auth".to_string(),
+            aud: "pema-platform".to_string(),
+            role: Role::Customer,
+            permissions: Role::Customer.permissions().to_vec(),
+        })
+        .unwrap();
+
+        assert_eq!(caller.require(Permission::OrdersCreate), Ok(()));
+        assert_eq!(caller.require(Permission::OrdersRead), Err(AccessError::MissingPermission(Permission::OrdersRead)));
+        assert_eq!(caller.require_owner_or(owner, Permission::OrdersRead), Ok(()));
+        assert!(caller.require_owner_or(Uuid::new_v4(), Permission::OrdersRead).is_err());
+
+        let anonymous = Claims { sub: "nobody".to_string(), ..caller.claims.clone() };
+        assert!(matches!(Caller::from_claims(anonymous), Err(AccessError::InvalidSubject)));
+    }
+
+    #[test]
+    fn test_reads_bearer_tokens() {
+        assert_eq!(bearer_token("Bearer abc.def"), Some("abc.def"));
+        assert_eq!(bearer_token("Bearer  "), None);
+        assert_eq!(bearer_token("Basic abc"), None);
+    }
+}
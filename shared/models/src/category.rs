@@ -0,0 +1,391 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use crate::schema::{Column, Table};
+
+/// A node of the product category tree. Top-level categories have no
+/// parent; siblings are listed by `position`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Category {
+    pub id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub slug: String,
+    pub name_fa: String,
+    pub name_en: String,
+    pub position: i32,
+    /// What products of the category are described by, e.g. a ring's size.
+    #[cfg_attr(feature = "sqlx", sqlx(json))]
+    pub attributes: Vec<CategoryAttribute>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Table for Category {
+    const TABLE: &'static str = "categories";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::nullable("parent_id", "UUID"),
+        Column::new("slug", "TEXT"),
+        Column::new("name_fa", "TEXT"),
+        Column::new("name_en", "TEXT"),
+        Column::new("position", "INT4"),
+        Column::new("attributes", "JSONB"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+        Column::new("updated_at", "TIMESTAMPTZ"),
+    ];
+}
+
+/// Slugs are lowercase ASCII words joined by dashes, e.g. `silver-rings`.
+pub fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug.split('-').all(|word| !word.is_empty() && word.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttributeKind {
+    #[default]
+    Text,
+    Number,
+    Boolean,
+    /// One of the attribute's `options`.
+    Choice,
+}
+
+impl AttributeKind {
+    pub const ALL: [AttributeKind; 4] = [AttributeKind::Text, AttributeKind::Number, AttributeKind::Boolean, AttributeKind::Choice];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttributeKind::Text => "text",
+            AttributeKind::Number => "number",
+            AttributeKind::Boolean => "boolean",
+            AttributeKind::Choice => "choice",
+        }
+    }
+}
+
+impl Display for AttributeKind {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AttributeKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        AttributeKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == value)
+            .ok_or_else(|| format!("Unknown attribute kind `{}`", value))
+    }
+}
+
+/// One field of a category's attribute schema.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CategoryAttribute {
+    pub key: String,
+    pub name_fa: String,
+    pub name_en: String,
+    #[serde(default)]
+    pub kind: AttributeKind,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+    /// Unit numbers are given in, e.g. `g` for weights.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+}
+
+impl CategoryAttribute {
+    /// Checks that keys are unique slugs and that choices have options.
+    pub fn validate_schema(attributes: &[CategoryAttribute]) -> Result<(), String> {
+        let mut keys = HashSet::new();
+        for attribute in attributes {
+            if attribute.key.contains('-') || !is_valid_slug(&attribute.key.replace('_', "-")) {
+                return Err(format!("Attribute key `{}` must be lowercase letters, digits and underscores", attribute.key));
+            }
+            if !keys.insert(attribute.key.as_str()) {
+                return Err(format!("Attribute `{}` is listed twice", attribute.key));
+            }
+            if attribute.name_fa.trim().is_empty() || attribute.name_en.trim().is_empty() {
+                return Err(format!("Attribute `{}` needs a Persian and an English name", attribute.key));
+            }
+            match attribute.kind {
+                AttributeKind::Choice if attribute.options.is_empty() => {
+                    return Err(format!("Attribute `{}` needs options to choose from", attribute.key));
+                }
+                AttributeKind::Choice => {}
+                _ if !attribute.options.is_empty() => {
+                    return Err(format!("Only choice attributes have options, `{}` is {}", attribute.key, attribute.kind));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks values given for a category's `attributes`: every key is in
+    /// the schema, each value has the attribute's kind, choices are one of
+    /// the options and required attributes are present.
+    pub fn validate_values(attributes: &[CategoryAttribute], values: &BTreeMap<String, AttributeValue>) -> Result<(), String> {
+        if let Some(key) = values.keys().find(|key| !attributes.iter().any(|attribute| &attribute.key == *key)) {
+            return Err(format!("The category has no attribute `{}`", key));
+        }
+
+        for attribute in attributes {
+            let valid = match (attribute.kind, values.get(&attribute.key)) {
+                (_, None) if attribute.required => return Err(format!("Attribute `{}` is required", attribute.key)),
+                (_, None) => true,
+                (AttributeKind::Text, Some(AttributeValue::Text(_))) => true,
+                (AttributeKind::Number, Some(AttributeValue::Number(_))) => true,
+                (AttributeKind::Boolean, Some(AttributeValue::Boolean(_))) => true,
+                (AttributeKind::Choice, Some(AttributeValue::Text(choice))) => {
+                    if !attribute.options.contains(choice) {
+                        return Err(format!("`{}` is not an option of `{}`", choice, attribute.key));
+                    }
+                    true
+                }
+                _ => false,
+            };
+            if !valid {
+                return Err(format!("Attribute `{}` takes a {} value", attribute.key, attribute.kind));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A value of a category attribute, as a plain JSON string, number or
+/// boolean. Choices are the chosen option's text.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    Boolean(bool),
+    Number(f64),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CreateCategory {
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
+    pub slug: String,
+    pub name_fa: String,
+    pub name_en: String,
+    #[serde(default)]
+    pub position: i32,
+    #[serde(default)]
+    pub attributes: Vec<CategoryAttribute>,
+}
+
+/// `parent_id` moves the category under another one, or to the top level
+/// when it is `null`; leaving it out keeps the category where it is.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct UpdateCategory {
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<Option<Uuid>>,
+    pub slug: Option<String>,
+    pub name_fa: Option<String>,
+    pub name_en: Option<String>,
+    pub position: Option<i32>,
+    pub attributes: Option<Vec<CategoryAttribute>>,
+}
+
+/// Tells a missing field (`None`) from an explicit `null` (`Some(None)`).
+pub(crate) fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// A category with its subcategories, as served by `categories/tree`. It
+/// serializes as the category with a `children` list.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CategoryNode {
+    #[serde(flatten)]
+    pub category: Category,
+    pub children: Vec<CategoryNode>,
+}
+
+impl CategoryNode {
+    /// Arranges categories into trees, siblings ordered by position and
+    /// then English name. Categories whose parent is not in the list are
+    /// left out.
+    pub fn build(mut categories: Vec<Category>) -> Vec<CategoryNode> {
+        categories.sort_by(|a, b| a.position.cmp(&b.position).then_with(|| a.name_en.cmp(&b.name_en)));
+
+        fn children_of(parent_id: Option<Uuid>, categories: &[Category]) -> Vec<CategoryNode> {
+            categories
+                .iter()
+                .filter(|category| category.parent_id == parent_id)
+                .map(|category| CategoryNode {
+                    category: category.clone(),
+                    children: children_of(Some(category.id), categories),
+                })
+                .collect()
+        }
+
+        children_of(None, &categories)
+    }
+
+    /// The node of `id` among `nodes` and their descendants.
+    pub fn find(nodes: &[CategoryNode], id: Uuid) -> Option<&CategoryNode> {
+        nodes.iter().find_map(|node| {
+            if node.category.id == id {
+                Some(node)
+            } else {
+                CategoryNode::find(&node.children, id)
+            }
+        })
+    }
+
+    /// The ids of this category and all categories below it.
+    pub fn ids(&self) -> Vec<Uuid> {
+        std::iter::once(self.category.id)
+            .chain(self.children.iter().flat_map(CategoryNode::ids))
+            .collect()
+    }
+
+    /// Every node below `nodes` in display order, with its depth (0 at the
+    /// top level), for rendering the tree as an indented list.
+    pub fn flatten(nodes: &[CategoryNode]) -> Vec<(usize, &Category)> {
+        fn walk<'a>(nodes: &'a [CategoryNode], depth: usize, out: &mut Vec<(usize, &'a Category)>) {
+            for node in nodes {
+                out.push((depth, &node.category));
+                walk(&node.children, depth + 1, out);
+            }
+        }
+
+        let mut out = Vec::new();
+        walk(nodes, 0, &mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(id: u128, parent: Option<u128>, slug: &str, position: i32) -> Category {
+        let now = Utc::now();
+        Category {
+            id: Uuid::from_u128(id),
+            parent_id: parent.map(Uuid::from_u128),
+            slug: slug.to_string(),
+            name_fa: slug.to_string(),
+            name_en: slug.to_string(),
+            position,
+            attributes: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_tree_is_built_in_order() {
+        let tree = CategoryNode::build(vec![
+            category(3, Some(1), "rings", 1),
+            category(2, None, "watches", 1),
+            category(4, Some(1), "necklaces", 0),
+            category(1, None, "jewelry", 0),
+            category(5, Some(9), "orphan", 0),
+        ]);
+
+        let flat: Vec<(usize, &str)> = CategoryNode::flatten(&tree).into_iter().map(|(depth, c)| (depth, c.slug.as_str())).collect();
+        assert_eq!(flat, vec![(0, "jewelry"), (1, "necklaces"), (1, "rings"), (0, "watches")]);
+
+        let jewelry = CategoryNode::find(&tree, Uuid::from_u128(1)).unwrap();
+        assert_eq!(jewelry.ids(), vec![Uuid::from_u128(1), Uuid::from_u128(4), Uuid::from_u128(3)]);
+        assert!(CategoryNode::find(&tree, Uuid::from_u128(5)).is_none());
+
+        let value = serde_json::to_value(&tree[0]).unwrap();
+        assert_eq!(value["slug"], "jewelry");
+        assert_eq!(value["children"][1]["slug"], "rings");
+    }
+
+    #[test]
+    fn test_attribute_schemas_are_validated() {
+        let size = CategoryAttribute {
+            key: "ring_size".to_string(),
+            name_fa: "سایز".to_string(),
+            name_en: "Size".to_string(),
+            kind: AttributeKind::Choice,
+            required: true,
+            options: vec!["52".to_string(), "54".to_string()],
+            unit: None,
+        };
+        let weight = CategoryAttribute {
+            key: "weight".to_string(),
+            name_fa: "وزن".to_string(),
+            name_en: "Weight".to_string(),
+            kind: AttributeKind::Number,
+            required: false,
+            options: Vec::new(),
+            unit: Some("g".to_string()),
+        };
+
+        assert_eq!(CategoryAttribute::validate_schema(&[size.clone(), weight.clone()]), Ok(()));
+        assert!(CategoryAttribute::validate_schema(&[weight.clone(), weight.clone()]).is_err());
+        assert!(CategoryAttribute::validate_schema(&[CategoryAttribute { options: Vec::new(), ..size.clone() }]).is_err());
+        assert!(CategoryAttribute::validate_schema(&[CategoryAttribute { options: vec!["1".to_string()], ..weight.clone() }]).is_err());
+        assert!(CategoryAttribute::validate_schema(&[CategoryAttribute { key: "Ring Size".to_string(), ..size }]).is_err());
+
+        assert!(is_valid_slug("silver-rings-925"));
+        assert!(!is_valid_slug("Silver"));
+        assert!(!is_valid_slug("rings-"));
+        assert!(!is_valid_slug("انگشتر"));
+    }
+
+    #[test]
+    fn test_attribute_values_follow_the_schema() {
+        let schema = vec![
+            CategoryAttribute {
+                key: "ring_size".to_string(),
+                name_fa: "سایز".to_string(),
+                name_en: "Size".to_string(),
+                kind: AttributeKind::Choice,
+                required: true,
+                options: vec!["52".to_string(), "54".to_string()],
+                unit: None,
+            },
+            CategoryAttribute {
+                key: "stone".to_string(),
+                name_fa: "سنگ".to_string(),
+                name_en: "Stone".to_string(),
+                kind: AttributeKind::Boolean,
+                required: false,
+                options: Vec::new(),
+                unit: None,
+            },
+        ];
+        let values = |json: &str| serde_json::from_str::<BTreeMap<String, AttributeValue>>(json).unwrap();
+
+        assert_eq!(CategoryAttribute::validate_values(&schema, &values(r#"{"ring_size": "54", "stone": true}"#)), Ok(()));
+        assert_eq!(CategoryAttribute::validate_values(&schema, &values(r#"{"ring_size": "52"}"#)), Ok(()));
+        assert!(CategoryAttribute::validate_values(&schema, &values(r#"{"stone": false}"#)).is_err());
+        assert!(CategoryAttribute::validate_values(&schema, &values(r#"{"ring_size": "60"}"#)).is_err());
+        assert!(CategoryAttribute::validate_values(&schema, &values(r#"{"ring_size": 54}"#)).is_err());
+        assert!(CategoryAttribute::validate_values(&schema, &values(r#"{"ring_size": "54", "colour": "red"}"#)).is_err());
+        assert_eq!(values(r#"{"stone": true}"#)["stone"], AttributeValue::Boolean(true));
+    }
+
+    #[test]
+    fn test_update_tells_null_parent_from_missing() {
+        let moved: UpdateCategory = serde_json::from_str(r#"{"parent_id": null}"#).unwrap();
+        assert_eq!(moved.parent_id, Some(None));
+        let unchanged: UpdateCategory = serde_json::from_str("{}").unwrap();
+        assert_eq!(unchanged.parent_id, None);
+    }
+}
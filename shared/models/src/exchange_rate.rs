@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use crate::money::Currency;
+use crate::schema::{text_enum, Column, Table};
+
+/// Where an exchange rate came from. Rates are entered by admins for now;
+/// a feed would be another source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+pub enum RateSource {
+    #[default]
+    #[serde(rename = "manual")]
+    Manual,
+}
+
+impl RateSource {
+    pub const ALL: [RateSource; 1] = [RateSource::Manual];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateSource::Manual => "manual",
+        }
+    }
+}
+
+impl Display for RateSource {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RateSource {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        RateSource::ALL
+            .into_iter()
+            .find(|source| source.as_str() == value)
+            .ok_or_else(|| format!("Unknown rate source `{}`", value))
+    }
+}
+
+text_enum!(RateSource);
+
+/// What one unit of a foreign currency is worth in rials. The rial and the
+/// toman have fixed rates and no row.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct ExchangeRate {
+    pub currency: Currency,
+    pub rials_per_unit: i64,
+    pub source: RateSource,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Table for ExchangeRate {
+    const TABLE: &'static str = "exchange_rates";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("currency", "TEXT"),
+        Column::new("rials_per_unit", "INT8"),
+        Column::new("source", "TEXT"),
+        Column::nullable("updated_by", "UUID"),
+        Column::new("updated_at", "TIMESTAMPTZ"),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct UpdateExchangeRate {
+    pub rials_per_unit: i64,
+}
+
+/// `?currency=` on listings, to show prices in another currency at the
+/// current rate.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct CurrencyQuery {
+    #[serde(default)]
+    pub currency: Option<Currency>,
+}
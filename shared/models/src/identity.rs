@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use crate::schema::{text_enum, Column, Table};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SocialProvider {
+    Google,
+    Github,
+    Linkedin,
+    Meta,
+}
+
+impl SocialProvider {
+    pub const ALL: [SocialProvider; 4] = [
+        SocialProvider::Google,
+        SocialProvider::Github,
+        SocialProvider::Linkedin,
+        SocialProvider::Meta,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SocialProvider::Google => "google",
+            SocialProvider::Github => "github",
+            SocialProvider::Linkedin => "linkedin",
+            SocialProvider::Meta => "meta",
+        }
+    }
+}
+
+impl Display for SocialProvider {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SocialProvider {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        SocialProvider::ALL
+            .into_iter()
+            .find(|provider| provider.as_str() == value)
+            .ok_or_else(|| format!("Unknown social provider `{}`", value))
+    }
+}
+
+text_enum!(SocialProvider);
+
+/// A provider account linked to a user.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct LinkedIdentity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub provider: SocialProvider,
+    /// The provider's stable id for the account.
+    pub subject: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: DateTime<Utc>,
+}
+
+impl Table for LinkedIdentity {
+    const TABLE: &'static str = "linked_identities";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("user_id", "UUID"),
+        Column::new("provider", "TEXT"),
+        Column::new("subject", "TEXT"),
+        Column::nullable("email", "TEXT"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+        Column::new("last_login_at", "TIMESTAMPTZ"),
+    ];
+}
+
+/// Proof of a provider sign-in. Google, LinkedIn and Meta send the OIDC
+/// `id_token`; GitHub has no ID tokens and sends its OAuth `access_token`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SocialLoginRequest {
+    pub provider: SocialProvider,
+    #[serde(default)]
+    pub id_token: Option<String>,
+    #[serde(default)]
+    pub access_token: Option<String>,
+}
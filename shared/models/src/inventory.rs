@@ -1,28 +1,330 @@
 use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
 
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use crate::schema::{text_enum, Column, Table};
 
+/// Stock of one product variant in one warehouse. `quantity` is the
+/// running total of the variant's `stock_movements` there and is only changed together
+/// with a movement.
+///
+/// The level is low once `quantity` drops to `reorder_point`; the vendor is
+/// then asked to restock `reorder_quantity` units.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
 pub struct InventoryItem {
     pub id: Uuid,
     pub product_id: Uuid,
+    pub variant_id: Uuid,
+    pub warehouse_id: Uuid,
     pub quantity: i32,
-    pub location: String,
+    pub reorder_point: Option<i32>,
+    pub reorder_quantity: Option<i32>,
+    /// When the vendor was last told the level is low; cleared once it is
+    /// back above the reorder point.
+    pub low_stock_alerted_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl Table for InventoryItem {
+    const TABLE: &'static str = "inventory_items";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("product_id", "UUID"),
+        Column::new("variant_id", "UUID"),
+        Column::new("warehouse_id", "UUID"),
+        Column::new("quantity", "INT4"),
+        Column::nullable("reorder_point", "INT4"),
+        Column::nullable("reorder_quantity", "INT4"),
+        Column::nullable("low_stock_alerted_at", "TIMESTAMPTZ"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+        Column::new("updated_at", "TIMESTAMPTZ"),
+    ];
+}
+
+impl InventoryItem {
+    pub fn is_low(&self) -> bool {
+        self.reorder_point.is_some_and(|point| self.quantity <= point)
+    }
+}
+
+/// Books `quantity` units into a warehouse as a receipt.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct CreateInventoryItem {
-    pub product_id: Uuid,
+    pub variant_id: Uuid,
+    pub warehouse_id: Uuid,
     pub quantity: i32,
-    pub location: String,
+    #[serde(default)]
+    pub note: Option<String>,
 }
 
+/// Sets the counted quantity; the difference is booked as an adjustment.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct UpdateInventoryItem {
     pub quantity: Option<i32>,
-    pub location: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub reorder_point: Option<i32>,
+    #[serde(default)]
+    pub reorder_quantity: Option<i32>,
 }
 
+/// Narrows stock level and movement listings.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct InventoryFilter {
+    pub product_id: Option<Uuid>,
+    pub variant_id: Option<Uuid>,
+    pub warehouse_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MovementType {
+    /// Goods received from a supplier.
+    Receipt,
+    /// Goods shipped to a customer.
+    Sale,
+    /// Goods a customer sent back.
+    Return,
+    /// A correction after a count, damage or loss.
+    Adjustment,
+    /// One side of a move between warehouses.
+    Transfer,
+}
+
+impl MovementType {
+    pub const ALL: [MovementType; 5] = [
+        MovementType::Receipt,
+        MovementType::Sale,
+        MovementType::Return,
+        MovementType::Adjustment,
+        MovementType::Transfer,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MovementType::Receipt => "receipt",
+            MovementType::Sale => "sale",
+            MovementType::Return => "return",
+            MovementType::Adjustment => "adjustment",
+            MovementType::Transfer => "transfer",
+        }
+    }
+}
+
+impl Display for MovementType {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MovementType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        MovementType::ALL
+            .into_iter()
+            .find(|movement_type| movement_type.as_str() == value)
+            .ok_or_else(|| format!("Unknown movement type `{}`", value))
+    }
+}
+
+text_enum!(MovementType);
+
+/// An entry of the append-only stock ledger. `quantity` is signed: positive
+/// entries add stock to the warehouse, negative ones take it away.
+/// `reference_id` points at the order or transfer that caused the movement.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct StockMovement {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Uuid,
+    pub warehouse_id: Uuid,
+    pub movement_type: MovementType,
+    pub quantity: i32,
+    pub balance_after: i32,
+    pub reference_id: Option<Uuid>,
+    pub note: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Table for StockMovement {
+    const TABLE: &'static str = "stock_movements";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("product_id", "UUID"),
+        Column::new("variant_id", "UUID"),
+        Column::new("warehouse_id", "UUID"),
+        Column::new("movement_type", "TEXT"),
+        Column::new("quantity", "INT4"),
+        Column::new("balance_after", "INT4"),
+        Column::nullable("reference_id", "UUID"),
+        Column::nullable("note", "TEXT"),
+        Column::nullable("created_by", "UUID"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+    ];
+}
+
+/// A manual ledger entry. Sales and transfers are recorded by shipping
+/// orders and by [`CreateStockTransfer`] and cannot be entered directly.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CreateStockMovement {
+    pub variant_id: Uuid,
+    pub warehouse_id: Uuid,
+    pub movement_type: MovementType,
+    pub quantity: i32,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct StockTransfer {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Uuid,
+    pub from_warehouse_id: Uuid,
+    pub to_warehouse_id: Uuid,
+    pub quantity: i32,
+    pub note: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Table for StockTransfer {
+    const TABLE: &'static str = "stock_transfers";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("product_id", "UUID"),
+        Column::new("variant_id", "UUID"),
+        Column::new("from_warehouse_id", "UUID"),
+        Column::new("to_warehouse_id", "UUID"),
+        Column::new("quantity", "INT4"),
+        Column::nullable("note", "TEXT"),
+        Column::nullable("created_by", "UUID"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CreateStockTransfer {
+    pub variant_id: Uuid,
+    pub from_warehouse_id: Uuid,
+    pub to_warehouse_id: Uuid,
+    pub quantity: i32,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// A stored quantity that does not match the one derived from the ledger.
+/// `warehouse_id` is empty for a variant's sellable `stock`, which should
+/// equal its stock in all warehouses minus what open orders have reserved,
+/// and `variant_id` too for a product's `stock`, the sum of its variants'.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct StockDiscrepancy {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub warehouse_id: Option<Uuid>,
+    pub recorded: i64,
+    pub expected: i64,
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReservationStatus {
+    /// Held for an unpaid order until `expires_at`.
+    #[default]
+    Active,
+    /// The order was paid; the stock is sold.
+    Committed,
+    /// The order shipped and the sale was booked against a warehouse.
+    Fulfilled,
+    /// Returned to stock.
+    Released,
+}
+
+impl ReservationStatus {
+    pub const ALL: [ReservationStatus; 4] = [
+        ReservationStatus::Active,
+        ReservationStatus::Committed,
+        ReservationStatus::Fulfilled,
+        ReservationStatus::Released,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReservationStatus::Active => "active",
+            ReservationStatus::Committed => "committed",
+            ReservationStatus::Fulfilled => "fulfilled",
+            ReservationStatus::Released => "released",
+        }
+    }
+}
+
+impl Display for ReservationStatus {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ReservationStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        ReservationStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == value)
+            .ok_or_else(|| format!("Unknown reservation status `{}`", value))
+    }
+}
+
+text_enum!(ReservationStatus);
+
+/// Stock taken off a variant's sellable stock for one line of an order.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct StockReservation {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Uuid,
+    pub quantity: i32,
+    pub status: ReservationStatus,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Table for StockReservation {
+    const TABLE: &'static str = "stock_reservations";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("order_id", "UUID"),
+        Column::new("product_id", "UUID"),
+        Column::new("variant_id", "UUID"),
+        Column::new("quantity", "INT4"),
+        Column::new("status", "TEXT"),
+        Column::new("expires_at", "TIMESTAMPTZ"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+        Column::new("updated_at", "TIMESTAMPTZ"),
+    ];
+}
+
+/// A variant an order asked for more of than is in stock.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct StockShortage {
+    pub product_id: Uuid,
+    pub variant_id: Uuid,
+    pub product_name: String,
+    pub sku: String,
+    pub requested: i32,
+    pub available: i32,
+}
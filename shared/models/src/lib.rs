@@ -1,13 +1,29 @@
+pub mod auth;
+pub mod identity;
+pub mod role;
 pub mod user;
 pub mod pagination;
+pub mod persian;
+pub mod money;
+pub mod weight;
+pub mod schema;
 
 
 pub mod product;
 
 
+pub mod media;
+
+
+pub mod category;
+
+
 pub mod inventory;
 
 
+pub mod warehouse;
+
+
 pub mod order;
 
 
@@ -17,5 +33,11 @@ pub mod notification;
 pub mod payment;
 
 
+pub mod exchange_rate;
+
+
+pub mod pricing;
+
+
 pub mod vendor;
 
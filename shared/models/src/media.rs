@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use crate::category::double_option;
+use crate::schema::{text_enum, Column, Table};
+
+/// Image formats uploads are accepted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum ImageContentType {
+    #[serde(rename = "image/jpeg")]
+    Jpeg,
+    #[serde(rename = "image/png")]
+    Png,
+    #[serde(rename = "image/webp")]
+    Webp,
+}
+
+impl ImageContentType {
+    pub const ALL: [ImageContentType; 3] = [ImageContentType::Jpeg, ImageContentType::Png, ImageContentType::Webp];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageContentType::Jpeg => "image/jpeg",
+            ImageContentType::Png => "image/png",
+            ImageContentType::Webp => "image/webp",
+        }
+    }
+
+    /// File extension stored files of this type get.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageContentType::Jpeg => "jpg",
+            ImageContentType::Png => "png",
+            ImageContentType::Webp => "webp",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "jpg" | "jpeg" => Some(ImageContentType::Jpeg),
+            "png" => Some(ImageContentType::Png),
+            "webp" => Some(ImageContentType::Webp),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ImageContentType {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ImageContentType {
+    type Err = String;
+
+    /// Takes a `Content-Type` header value; parameters are ignored.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let essence = value.split(';').next().unwrap_or_default().trim();
+        ImageContentType::ALL
+            .into_iter()
+            .find(|content_type| content_type.as_str().eq_ignore_ascii_case(essence))
+            .ok_or_else(|| format!("`{}` is not an accepted image type; use JPEG, PNG or WebP", essence))
+    }
+}
+
+text_enum!(ImageContentType);
+
+/// A smaller copy of an image whose longest edge is at most `size` pixels.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Thumbnail {
+    pub size: u32,
+    pub width: u32,
+    pub height: u32,
+    pub content_type: ImageContentType,
+    pub storage_key: String,
+    pub url: String,
+}
+
+/// An uploaded image of a product or a vendor, with its thumbnails. Images
+/// are listed by `position`; a vendor's first image is its logo.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Image {
+    pub id: Uuid,
+    pub product_id: Option<Uuid>,
+    pub vendor_id: Option<Uuid>,
+    pub storage_key: String,
+    pub url: String,
+    pub content_type: ImageContentType,
+    pub byte_size: i64,
+    pub width: i32,
+    pub height: i32,
+    #[cfg_attr(feature = "sqlx", sqlx(json))]
+    pub thumbnails: Vec<Thumbnail>,
+    pub alt_text: Option<String>,
+    pub position: i32,
+    pub uploaded_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Image {
+    /// Storage keys of the image and its thumbnails.
+    pub fn storage_keys(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.storage_key.as_str()).chain(self.thumbnails.iter().map(|thumbnail| thumbnail.storage_key.as_str()))
+    }
+}
+
+impl Table for Image {
+    const TABLE: &'static str = "images";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::nullable("product_id", "UUID"),
+        Column::nullable("vendor_id", "UUID"),
+        Column::new("storage_key", "TEXT"),
+        Column::new("url", "TEXT"),
+        Column::new("content_type", "TEXT"),
+        Column::new("byte_size", "INT8"),
+        Column::new("width", "INT4"),
+        Column::new("height", "INT4"),
+        Column::new("thumbnails", "JSONB"),
+        Column::nullable("alt_text", "TEXT"),
+        Column::new("position", "INT4"),
+        Column::nullable("uploaded_by", "UUID"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+    ];
+}
+
+/// What an image belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageOwner {
+    Product(Uuid),
+    Vendor(Uuid),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct UpdateImage {
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub alt_text: Option<Option<String>>,
+    #[serde(default)]
+    pub position: Option<i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_content_types_come_from_headers_and_extensions() {
+        assert_eq!("image/jpeg".parse(), Ok(ImageContentType::Jpeg));
+        assert_eq!("IMAGE/PNG; charset=binary".parse(), Ok(ImageContentType::Png));
+        assert!("image/svg+xml".parse::<ImageContentType>().is_err());
+        assert!("text/html".parse::<ImageContentType>().is_err());
+
+        assert_eq!(ImageContentType::from_extension("JPEG"), Some(ImageContentType::Jpeg));
+        assert_eq!(ImageContentType::from_extension("gif"), None);
+        assert_eq!(ImageContentType::Webp.extension(), "webp");
+    }
+}
@@ -0,0 +1,459 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use crate::schema::text_enum;
+
+/// Currency an amount is quoted in. Prices are usually quoted in tomans,
+/// which have no ISO code; `IRT` is the one Iranian gateways use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+pub enum Currency {
+    #[default]
+    #[serde(rename = "IRR")]
+    Irr,
+    /// Toman, ten rials.
+    #[serde(rename = "IRT")]
+    Irt,
+    #[serde(rename = "USD")]
+    Usd,
+    #[serde(rename = "EUR")]
+    Eur,
+    #[serde(rename = "AED")]
+    Aed,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 5] = [Currency::Irr, Currency::Irt, Currency::Usd, Currency::Eur, Currency::Aed];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Currency::Irr => "IRR",
+            Currency::Irt => "IRT",
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+            Currency::Aed => "AED",
+        }
+    }
+
+    /// Decimal places of an amount in this currency; the rial is the minor
+    /// unit of the toman as well.
+    pub const fn decimals(&self) -> u32 {
+        match self {
+            Currency::Irr => 0,
+            Currency::Irt => 1,
+            Currency::Usd | Currency::Eur | Currency::Aed => 2,
+        }
+    }
+
+    /// Rials one unit is worth for the rial and the toman; other currencies
+    /// are converted at an exchange rate.
+    pub const fn fixed_rials_per_unit(&self) -> Option<i64> {
+        match self {
+            Currency::Irr => Some(1),
+            Currency::Irt => Some(10),
+            Currency::Usd | Currency::Eur | Currency::Aed => None,
+        }
+    }
+
+    /// Whether the minor unit is the rial, so amounts are stored as they are.
+    pub const fn is_rial_based(&self) -> bool {
+        self.fixed_rials_per_unit().is_some()
+    }
+}
+
+impl Display for Currency {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Currency {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Currency::ALL
+            .into_iter()
+            .find(|currency| currency.as_str().eq_ignore_ascii_case(value))
+            .ok_or_else(|| format!("Unknown currency `{}`", value))
+    }
+}
+
+text_enum!(Currency);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoneyError {
+    CurrencyMismatch(Currency, Currency),
+    Overflow,
+    InvalidAmount(String),
+}
+
+impl Display for MoneyError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            MoneyError::CurrencyMismatch(left, right) => write!(f, "Cannot combine amounts in {} and {}", left, right),
+            MoneyError::Overflow => f.write_str("The amount is too large"),
+            MoneyError::InvalidAmount(amount) => write!(f, "`{}` is not a valid amount", amount),
+        }
+    }
+}
+
+impl std::error::Error for MoneyError {}
+
+/// An exact amount of money: a whole number of the currency's minor unit
+/// (the rial for rials and tomans, cents for dollars), tagged with the
+/// currency. Moving an amount between rials and tomans never rounds; other
+/// currencies go through [`Money::checked_convert`].
+///
+/// Rial amounts are plain JSON numbers, as all amounts were before this
+/// type; other currencies are `{"minor_units": 45000005, "currency": "IRT"}`.
+/// In Postgres an amount is a `NUMERIC` number of rials.
+///
+/// There are no operators: arithmetic is checked and fails on overflow or
+/// mixed currencies instead of wrapping or rounding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Money {
+    minor_units: i64,
+    currency: Currency,
+}
+
+impl Money {
+    pub const fn new(minor_units: i64, currency: Currency) -> Self {
+        Self { minor_units, currency }
+    }
+
+    pub const fn rials(amount: i64) -> Self {
+        Self::new(amount, Currency::Irr)
+    }
+
+    pub const fn zero(currency: Currency) -> Self {
+        Self::new(0, currency)
+    }
+
+    pub fn tomans(amount: i64) -> Result<Self, MoneyError> {
+        let minor_units = amount.checked_mul(10).ok_or(MoneyError::Overflow)?;
+        Ok(Self::new(minor_units, Currency::Irt))
+    }
+
+    pub const fn minor_units(&self) -> i64 {
+        self.minor_units
+    }
+
+    pub const fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// The same amount quoted in `currency`, which has to share the minor
+    /// unit (i.e. rials and tomans).
+    pub fn in_currency(self, currency: Currency) -> Result<Self, MoneyError> {
+        if self.currency != currency && !(self.currency.is_rial_based() && currency.is_rial_based()) {
+            return Err(MoneyError::CurrencyMismatch(self.currency, currency));
+        }
+        Ok(Self::new(self.minor_units, currency))
+    }
+
+    /// The amount in `currency`, given the rials one unit of each currency
+    /// is worth. Rounded half away from zero to the target's minor unit.
+    pub fn checked_convert(self, currency: Currency, from_rials_per_unit: i64, to_rials_per_unit: i64) -> Result<Self, MoneyError> {
+        let numerator = from_rials_per_unit.checked_mul(10i64.pow(currency.decimals())).ok_or(MoneyError::Overflow)?;
+        let denominator = to_rials_per_unit.checked_mul(10i64.pow(self.currency.decimals())).ok_or(MoneyError::Overflow)?;
+        Self::new(self.minor_units, currency).checked_mul_ratio(numerator, denominator)
+    }
+
+    pub const fn is_zero(&self) -> bool {
+        self.minor_units == 0
+    }
+
+    pub const fn is_positive(&self) -> bool {
+        self.minor_units > 0
+    }
+
+    pub const fn is_negative(&self) -> bool {
+        self.minor_units < 0
+    }
+
+    fn same_currency(&self, other: &Money) -> Result<(), MoneyError> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(MoneyError::CurrencyMismatch(self.currency, other.currency))
+        }
+    }
+
+    pub fn checked_add(self, other: Money) -> Result<Self, MoneyError> {
+        self.same_currency(&other)?;
+        let minor_units = self.minor_units.checked_add(other.minor_units).ok_or(MoneyError::Overflow)?;
+        Ok(Self::new(minor_units, self.currency))
+    }
+
+    pub fn checked_sub(self, other: Money) -> Result<Self, MoneyError> {
+        self.same_currency(&other)?;
+        let minor_units = self.minor_units.checked_sub(other.minor_units).ok_or(MoneyError::Overflow)?;
+        Ok(Self::new(minor_units, self.currency))
+    }
+
+    pub fn checked_mul(self, factor: i64) -> Result<Self, MoneyError> {
+        let minor_units = self.minor_units.checked_mul(factor).ok_or(MoneyError::Overflow)?;
+        Ok(Self::new(minor_units, self.currency))
+    }
+
+    /// `self × numerator / denominator`, rounded half away from zero to
+    /// whole rials, e.g. a line's share of the order's tax.
+    pub fn checked_mul_ratio(self, numerator: i64, denominator: i64) -> Result<Self, MoneyError> {
+        if denominator == 0 {
+            return Err(MoneyError::InvalidAmount(format!("{} / 0", numerator)));
+        }
+        let product = self.minor_units as i128 * numerator as i128;
+        let (quotient, remainder) = (product / denominator as i128, product % denominator as i128);
+        let rounded = if remainder.abs() * 2 >= (denominator as i128).abs() {
+            quotient + if (product < 0) == (denominator < 0) { 1 } else { -1 }
+        } else {
+            quotient
+        };
+        let minor_units = i64::try_from(rounded).map_err(|_| MoneyError::Overflow)?;
+        Ok(Self::new(minor_units, self.currency))
+    }
+
+    /// `self × rate` for a configured rate such as a tax rate of `0.09`,
+    /// rounded to whole rials. The rate is taken to six decimal places.
+    pub fn checked_mul_rate(self, rate: f64) -> Result<Self, MoneyError> {
+        if !rate.is_finite() {
+            return Err(MoneyError::InvalidAmount(rate.to_string()));
+        }
+        self.checked_mul_ratio((rate * 1_000_000.0).round() as i64, 1_000_000)
+    }
+
+    pub fn checked_sum<I: IntoIterator<Item = Money>>(currency: Currency, amounts: I) -> Result<Self, MoneyError> {
+        amounts.into_iter().try_fold(Self::zero(currency), Money::checked_add)
+    }
+
+    /// Parses a decimal amount in `currency`, e.g. `4500000.5` tomans.
+    /// Digits beyond a rial are rejected rather than rounded.
+    pub fn parse(amount: &str, currency: Currency) -> Result<Self, MoneyError> {
+        let invalid = || MoneyError::InvalidAmount(amount.to_string());
+        let text = amount.trim();
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let fraction = fraction.trim_end_matches('0');
+        let digits_ok = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if whole.is_empty() || !digits_ok(whole) || !digits_ok(fraction) || fraction.len() > currency.decimals() as usize {
+            return Err(invalid());
+        }
+
+        let scale = 10i64.pow(currency.decimals());
+        let padded = format!("{:0<width$}", fraction, width = currency.decimals() as usize);
+        let fraction: i64 = if padded.is_empty() { 0 } else { padded.parse().map_err(|_| invalid())? };
+        let minor_units = whole
+            .parse::<i64>()
+            .map_err(|_| MoneyError::Overflow)?
+            .checked_mul(scale)
+            .and_then(|whole| whole.checked_add(fraction))
+            .ok_or(MoneyError::Overflow)?;
+
+        Ok(Self::new(if negative { -minor_units } else { minor_units }, currency))
+    }
+
+    /// The amount in its currency as a decimal number, e.g. `4500000.5`.
+    pub fn to_decimal_string(&self) -> String {
+        let decimals = self.currency.decimals();
+        if decimals == 0 {
+            return self.minor_units.to_string();
+        }
+
+        let scale = 10u64.pow(decimals);
+        let units = self.minor_units.unsigned_abs();
+        let sign = if self.minor_units < 0 { "-" } else { "" };
+        let (whole, fraction) = (units / scale, units % scale);
+        if fraction == 0 {
+            format!("{}{}", sign, whole)
+        } else {
+            let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+            format!("{}{}.{}", sign, whole, fraction.trim_end_matches('0'))
+        }
+    }
+}
+
+impl PartialOrd for Money {
+    /// Amounts in different currencies are not compared.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self.currency == other.currency).then(|| self.minor_units.cmp(&other.minor_units))
+    }
+}
+
+impl Display for Money {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{} {}", self.to_decimal_string(), self.currency)
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        if self.currency == Currency::Irr {
+            return serializer.serialize_i64(self.minor_units);
+        }
+        let mut state = serializer.serialize_struct("Money", 2)?;
+        state.serialize_field("minor_units", &self.minor_units)?;
+        state.serialize_field("currency", &self.currency)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+struct MoneyVisitor;
+
+impl<'de> de::Visitor<'de> for MoneyVisitor {
+    type Value = Money;
+
+    fn expecting(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str("a whole number of rials or {\"minor_units\", \"currency\"}")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Money, E> {
+        Ok(Money::rials(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Money, E> {
+        i64::try_from(value).map(Money::rials).map_err(|_| E::custom(MoneyError::Overflow))
+    }
+
+    /// Clients used to send amounts as floats; whole ones are still taken.
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Money, E> {
+        const MAX_EXACT: f64 = 9_007_199_254_740_992.0;
+        if value.fract() != 0.0 || !value.is_finite() || value.abs() > MAX_EXACT {
+            return Err(E::custom(MoneyError::InvalidAmount(value.to_string())));
+        }
+        Ok(Money::rials(value as i64))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Money, E> {
+        Money::parse(value, Currency::Irr).map_err(E::custom)
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Money, A::Error> {
+        let (mut minor_units, mut currency) = (None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "minor_units" => minor_units = Some(map.next_value::<i64>()?),
+                "currency" => currency = Some(map.next_value::<Currency>()?),
+                _ => {
+                    map.next_value::<de::IgnoredAny>()?;
+                }
+            }
+        }
+        let minor_units = minor_units.ok_or_else(|| de::Error::missing_field("minor_units"))?;
+        Ok(Money::new(minor_units, currency.unwrap_or_default()))
+    }
+}
+
+#[cfg(feature = "sqlx")]
+mod sql {
+    use super::Money;
+    use rust_decimal::Decimal;
+    use sqlx::encode::IsNull;
+    use sqlx::error::BoxDynError;
+    use sqlx::postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef};
+    use sqlx::{Decode, Encode, Postgres, Type};
+
+    impl Type<Postgres> for Money {
+        fn type_info() -> PgTypeInfo {
+            <Decimal as Type<Postgres>>::type_info()
+        }
+
+        fn compatible(ty: &PgTypeInfo) -> bool {
+            <Decimal as Type<Postgres>>::compatible(ty)
+        }
+    }
+
+    impl PgHasArrayType for Money {
+        fn array_type_info() -> PgTypeInfo {
+            <Decimal as PgHasArrayType>::array_type_info()
+        }
+    }
+
+    /// Only rials and tomans are stored; anything else is converted first.
+    impl Encode<'_, Postgres> for Money {
+        fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+            if !self.currency.is_rial_based() {
+                return Err(format!("{} has to be converted to rials before it is stored", self).into());
+            }
+            <Decimal as Encode<Postgres>>::encode_by_ref(&Decimal::from(self.minor_units), buf)
+        }
+    }
+
+    /// Columns hold rials; a fraction of a rial is an error, not rounded.
+    impl<'r> Decode<'r, Postgres> for Money {
+        fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+            let amount = <Decimal as Decode<Postgres>>::decode(value)?;
+            if !amount.fract().is_zero() {
+                return Err(format!("{} is not a whole number of rials", amount).into());
+            }
+            Ok(Money::rials(i64::try_from(amount)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serde_keeps_plain_rial_numbers() {
+        let price: Money = serde_json::from_str("45000000").unwrap();
+        assert_eq!(price, Money::rials(45_000_000));
+        assert_eq!(serde_json::to_string(&price).unwrap(), "45000000");
+        assert_eq!(serde_json::from_str::<Money>("1600000.0").unwrap(), Money::rials(1_600_000));
+        assert_eq!(serde_json::from_str::<Money>("\"250000\"").unwrap(), Money::rials(250_000));
+        assert!(serde_json::from_str::<Money>("0.5").is_err());
+
+        let tomans = Money::parse("4500000.5", Currency::Irt).unwrap();
+        let json = serde_json::to_string(&tomans).unwrap();
+        assert_eq!(json, r#"{"minor_units":45000005,"currency":"IRT"}"#);
+        assert_eq!(serde_json::from_str::<Money>(&json).unwrap(), tomans);
+    }
+
+    #[test]
+    fn test_arithmetic_is_checked() {
+        let price = Money::rials(1_000_000);
+        assert_eq!(price.checked_mul(3).unwrap().checked_add(Money::rials(5)).unwrap(), Money::rials(3_000_005));
+        assert_eq!(price.checked_mul_rate(0.09).unwrap(), Money::rials(90_000));
+        assert_eq!(Money::rials(5).checked_mul_ratio(1, 2).unwrap(), Money::rials(3));
+        assert_eq!(Money::rials(-5).checked_mul_ratio(1, 2).unwrap(), Money::rials(-3));
+        assert_eq!(Money::rials(i64::MAX).checked_add(Money::rials(1)), Err(MoneyError::Overflow));
+        assert_eq!(
+            price.checked_add(Money::tomans(1).unwrap()),
+            Err(MoneyError::CurrencyMismatch(Currency::Irr, Currency::Irt))
+        );
+        assert_eq!(price.partial_cmp(&price.in_currency(Currency::Irt).unwrap()), None);
+        assert!(price.in_currency(Currency::Usd).is_err());
+        assert!(Money::rials(2) > Money::rials(1));
+    }
+
+    #[test]
+    fn test_conversion_rounds_to_the_minor_unit() {
+        let rate = 1_023_456;
+        let price = Money::rials(45_000_000);
+        assert_eq!(price.checked_convert(Currency::Usd, 1, rate).unwrap(), Money::new(4_397, Currency::Usd));
+        assert_eq!(Money::new(4_397, Currency::Usd).checked_convert(Currency::Irr, rate, 1).unwrap(), Money::rials(45_001_360));
+        assert_eq!(price.checked_convert(Currency::Irt, 1, 10).unwrap(), Money::tomans(4_500_000).unwrap());
+    }
+
+    #[test]
+    fn test_decimal_strings() {
+        assert_eq!(Money::tomans(4_500_000).unwrap().to_string(), "4500000 IRT");
+        assert_eq!(Money::new(-45_000_005, Currency::Irt).to_decimal_string(), "-4500000.5");
+        assert_eq!(Money::new(1_250, Currency::Usd).to_string(), "12.5 USD");
+        assert_eq!(Money::parse("12.50", Currency::Irt).unwrap(), Money::new(125, Currency::Irt));
+        assert!(Money::parse("12.25", Currency::Irt).is_err());
+        assert!(Money::parse("1e5", Currency::Irr).is_err());
+    }
+}
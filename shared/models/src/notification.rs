@@ -1,24 +1,235 @@
 use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
 
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use crate::persian::{format_amount, to_persian_digits};
+use crate::schema::{text_enum, Column, Table};
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
 pub struct Notification {
     pub id: Uuid,
     pub user_id: Uuid,
     pub message: String,
     pub notification_type: String,
     pub is_read: bool,
+    pub channel: DeliveryChannel,
+    pub delivery_status: DeliveryStatus,
+    pub delivery_error: Option<String>,
+    pub provider_message_id: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl Table for Notification {
+    const TABLE: &'static str = "notifications";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("user_id", "UUID"),
+        Column::new("message", "TEXT"),
+        Column::new("notification_type", "TEXT"),
+        Column::new("is_read", "BOOL"),
+        Column::new("channel", "TEXT"),
+        Column::new("delivery_status", "TEXT"),
+        Column::nullable("delivery_error", "TEXT"),
+        Column::nullable("provider_message_id", "TEXT"),
+        Column::nullable("sent_at", "TIMESTAMPTZ"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+        Column::new("updated_at", "TIMESTAMPTZ"),
+    ];
+}
+
+/// How a notification reaches the user. In-app notifications are the stored
+/// row itself; the others are also pushed through an external provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryChannel {
+    #[default]
+    InApp,
+    Sms,
+    Email,
+}
+
+impl DeliveryChannel {
+    pub const ALL: [DeliveryChannel; 3] = [DeliveryChannel::InApp, DeliveryChannel::Sms, DeliveryChannel::Email];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryChannel::InApp => "in_app",
+            DeliveryChannel::Sms => "sms",
+            DeliveryChannel::Email => "email",
+        }
+    }
+}
+
+impl Display for DeliveryChannel {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DeliveryChannel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        DeliveryChannel::ALL
+            .into_iter()
+            .find(|channel| channel.as_str() == value)
+            .ok_or_else(|| format!("Unknown delivery channel `{}`", value))
+    }
+}
+
+text_enum!(DeliveryChannel);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Stored, not yet handed to a provider.
+    #[default]
+    Pending,
+    /// Accepted by the provider.
+    Sent,
+    /// Confirmed to have reached the user.
+    Delivered,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub const ALL: [DeliveryStatus; 4] = [
+        DeliveryStatus::Pending,
+        DeliveryStatus::Sent,
+        DeliveryStatus::Delivered,
+        DeliveryStatus::Failed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Sent => "sent",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+impl Display for DeliveryStatus {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DeliveryStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        DeliveryStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == value)
+            .ok_or_else(|| format!("Unknown delivery status `{}`", value))
+    }
+}
+
+text_enum!(DeliveryStatus);
+
+/// Predefined Persian messages. Amounts are in rials.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "template", rename_all = "snake_case")]
+pub enum NotificationTemplate {
+    Welcome { name: String },
+    VerificationCode { code: String },
+    OrderPlaced { order_id: Uuid, total_amount: i64 },
+    OrderStatusChanged { order_id: Uuid, status: String },
+    PaymentReceived { order_id: Uuid, amount: i64 },
+    LowStock {
+        product_name: String,
+        quantity: i32,
+        #[serde(default)]
+        reorder_quantity: Option<i32>,
+    },
+}
+
+impl NotificationTemplate {
+    /// The `notification_type` stored for messages rendered from this template.
+    pub fn notification_type(&self) -> &'static str {
+        match self {
+            NotificationTemplate::Welcome { .. } => "welcome",
+            NotificationTemplate::VerificationCode { .. } => "verification_code",
+            NotificationTemplate::OrderPlaced { .. } => "order_placed",
+            NotificationTemplate::OrderStatusChanged { .. } => "order_status_changed",
+            NotificationTemplate::PaymentReceived { .. } => "payment_received",
+            NotificationTemplate::LowStock { .. } => "low_stock",
+        }
+    }
+
+    /// Subject line for channels that have one (email).
+    pub fn subject(&self) -> String {
+        match self {
+            NotificationTemplate::Welcome { .. } => "به پما خوش آمدید".to_string(),
+            NotificationTemplate::VerificationCode { .. } => "کد تایید پما".to_string(),
+            NotificationTemplate::OrderPlaced { order_id, .. } => format!("ثبت سفارش {}", short_id(order_id)),
+            NotificationTemplate::OrderStatusChanged { order_id, .. } => format!("وضعیت سفارش {}", short_id(order_id)),
+            NotificationTemplate::PaymentReceived { order_id, .. } => format!("پرداخت سفارش {}", short_id(order_id)),
+            NotificationTemplate::LowStock { .. } => "هشدار موجودی انبار".to_string(),
+        }
+    }
+
+    pub fn render(&self) -> String {
+        match self {
+            NotificationTemplate::Welcome { name } => {
+                format!("{} عزیز، به پما خوش آمدید.", name)
+            }
+            NotificationTemplate::VerificationCode { code } => {
+                format!("کد تایید شما در پما: {}\nاین کد را در اختیار دیگران قرار ندهید.", to_persian_digits(code))
+            }
+            NotificationTemplate::OrderPlaced { order_id, total_amount } => format!(
+                "سفارش شما با شماره {} ثبت شد.\nمبلغ کل: {} ریال",
+                short_id(order_id),
+                format_amount(*total_amount)
+            ),
+            NotificationTemplate::OrderStatusChanged { order_id, status } => {
+                format!("وضعیت سفارش {} به «{}» تغییر کرد.", short_id(order_id), status)
+            }
+            NotificationTemplate::PaymentReceived { order_id, amount } => format!(
+                "پرداخت {} ریال برای سفارش {} با موفقیت انجام شد.",
+                format_amount(*amount),
+                short_id(order_id)
+            ),
+            NotificationTemplate::LowStock { product_name, quantity, reorder_quantity } => {
+                let mut message = format!(
+                    "موجودی «{}» به {} عدد رسیده است. لطفاً برای تامین مجدد اقدام کنید.",
+                    product_name,
+                    to_persian_digits(&quantity.to_string())
+                );
+                if let Some(reorder_quantity) = reorder_quantity {
+                    message.push_str(&format!("\nمقدار پیشنهادی سفارش: {} عدد", to_persian_digits(&reorder_quantity.to_string())));
+                }
+                message
+            }
+        }
+    }
+}
+
+/// First block of the UUID, which is what users are shown as an order number.
+fn short_id(id: &Uuid) -> String {
+    id.simple().to_string()[..8].to_uppercase()
+}
+
+/// Either `message` or `template` must be given; a template overrides both
+/// `message` and `notification_type`.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct CreateNotification {
     pub user_id: Uuid,
+    #[serde(default)]
     pub message: String,
+    #[serde(default)]
     pub notification_type: String,
+    #[serde(default)]
+    pub channel: DeliveryChannel,
+    #[serde(default, flatten)]
+    pub template: Option<NotificationTemplate>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -28,3 +239,33 @@ pub struct UpdateNotification {
     pub is_read: Option<bool>,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_notification_accepts_a_template() {
+        let user_id = Uuid::new_v4();
+        let request: CreateNotification = serde_json::from_value(serde_json::json!({
+            "user_id": user_id,
+            "channel": "sms",
+            "template": "verification_code",
+            "code": "0427",
+        }))
+        .unwrap();
+
+        assert_eq!(request.channel, DeliveryChannel::Sms);
+        let template = request.template.unwrap();
+        assert_eq!(template.notification_type(), "verification_code");
+        assert!(template.render().contains("۰۴۲۷"));
+
+        let plain: CreateNotification = serde_json::from_value(serde_json::json!({
+            "user_id": user_id,
+            "message": "سلام",
+            "notification_type": "info",
+        }))
+        .unwrap();
+        assert_eq!(plain.channel, DeliveryChannel::InApp);
+        assert_eq!(plain.template, None);
+    }
+}
@@ -1,27 +1,282 @@
 use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
 
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use crate::money::{Currency, Money, MoneyError};
+use crate::schema::{text_enum, Column, Table};
 
+/// Where an order is in its lifecycle. The happy path is
+/// pending → paid → processing → shipped → delivered; an order can be
+/// cancelled before it ships and refunded once it has been paid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderStatus {
+    #[default]
+    Pending,
+    Paid,
+    Processing,
+    Shipped,
+    Delivered,
+    Cancelled,
+    Refunded,
+}
+
+impl OrderStatus {
+    pub const ALL: [OrderStatus; 7] = [
+        OrderStatus::Pending,
+        OrderStatus::Paid,
+        OrderStatus::Processing,
+        OrderStatus::Shipped,
+        OrderStatus::Delivered,
+        OrderStatus::Cancelled,
+        OrderStatus::Refunded,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Paid => "paid",
+            OrderStatus::Processing => "processing",
+            OrderStatus::Shipped => "shipped",
+            OrderStatus::Delivered => "delivered",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Refunded => "refunded",
+        }
+    }
+
+    /// Persian name shown to users.
+    pub fn label(&self) -> &'static str {
+        match self {
+            OrderStatus::Pending => "در انتظار پرداخت",
+            OrderStatus::Paid => "پرداخت شده",
+            OrderStatus::Processing => "در حال پردازش",
+            OrderStatus::Shipped => "ارسال شده",
+            OrderStatus::Delivered => "تحویل داده شده",
+            OrderStatus::Cancelled => "لغو شده",
+            OrderStatus::Refunded => "بازپرداخت شده",
+        }
+    }
+
+    /// The statuses an order in this status may move to.
+    pub fn next_statuses(&self) -> &'static [OrderStatus] {
+        match self {
+            OrderStatus::Pending => &[OrderStatus::Paid, OrderStatus::Cancelled],
+            OrderStatus::Paid => &[OrderStatus::Processing, OrderStatus::Cancelled, OrderStatus::Refunded],
+            OrderStatus::Processing => &[OrderStatus::Shipped, OrderStatus::Cancelled, OrderStatus::Refunded],
+            OrderStatus::Shipped => &[OrderStatus::Delivered, OrderStatus::Refunded],
+            OrderStatus::Delivered => &[OrderStatus::Refunded],
+            OrderStatus::Cancelled | OrderStatus::Refunded => &[],
+        }
+    }
+
+    pub fn can_transition_to(&self, next: OrderStatus) -> bool {
+        self.next_statuses().contains(&next)
+    }
+
+    pub fn is_final(&self) -> bool {
+        self.next_statuses().is_empty()
+    }
+}
+
+impl Display for OrderStatus {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OrderStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        OrderStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == value)
+            .ok_or_else(|| format!("Unknown order status `{}`", value))
+    }
+}
+
+text_enum!(OrderStatus);
+
+/// `total_amount` is always `subtotal + tax_amount + shipping_amount`, all
+/// computed on the server from the order's items. Amounts are rials;
+/// `currency` is what the shopper saw prices in and `exchange_rate` the
+/// rials one unit of it was worth at checkout.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
 pub struct Order {
     pub id: Uuid,
     pub user_id: Uuid,
-    pub status: String,
-    pub total_amount: f64,
+    pub status: OrderStatus,
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub shipping_amount: Money,
+    pub total_amount: Money,
+    pub currency: Currency,
+    pub exchange_rate: i64,
+    pub shipping_address: Option<String>,
+    pub notes: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl Table for Order {
+    const TABLE: &'static str = "orders";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("user_id", "UUID"),
+        Column::new("status", "TEXT"),
+        Column::new("subtotal", "NUMERIC"),
+        Column::new("tax_amount", "NUMERIC"),
+        Column::new("shipping_amount", "NUMERIC"),
+        Column::new("total_amount", "NUMERIC"),
+        Column::new("currency", "TEXT"),
+        Column::new("exchange_rate", "INT8"),
+        Column::nullable("shipping_address", "TEXT"),
+        Column::nullable("notes", "TEXT"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+        Column::new("updated_at", "TIMESTAMPTZ"),
+    ];
+}
+
+impl Order {
+    /// The total in the currency the order was placed in, at its rate.
+    pub fn total_in_currency(&self) -> Result<Money, MoneyError> {
+        self.total_amount.checked_convert(self.currency, 1, self.exchange_rate)
+    }
+}
+
+/// A line of an order, priced when the order was placed. The product's
+/// name and the variant's SKU are kept as they were then.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct OrderItem {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Uuid,
+    pub product_name: String,
+    pub sku: String,
+    pub quantity: i32,
+    pub unit_price: Money,
+    pub line_total: Money,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Table for OrderItem {
+    const TABLE: &'static str = "order_items";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("order_id", "UUID"),
+        Column::new("product_id", "UUID"),
+        Column::new("variant_id", "UUID"),
+        Column::new("product_name", "TEXT"),
+        Column::new("sku", "TEXT"),
+        Column::new("quantity", "INT4"),
+        Column::new("unit_price", "NUMERIC"),
+        Column::new("line_total", "NUMERIC"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+    ];
+}
+
+/// One status change of an order. The first entry of every order has no
+/// `from_status`; `changed_by` is empty for changes made by the system.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct OrderStatusChange {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub from_status: Option<OrderStatus>,
+    pub to_status: OrderStatus,
+    pub changed_by: Option<Uuid>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Table for OrderStatusChange {
+    const TABLE: &'static str = "order_status_history";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("order_id", "UUID"),
+        Column::nullable("from_status", "TEXT"),
+        Column::new("to_status", "TEXT"),
+        Column::nullable("changed_by", "UUID"),
+        Column::nullable("note", "TEXT"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+    ];
+}
+
+/// Prices and totals sent by the client are only checked against the
+/// server's; an order whose client figures disagree is rejected. They are
+/// in `currency`, converted at the current rate. Variants held by
+/// `price_lock_id` are charged at their locked price.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct CreateOrder {
     pub user_id: Uuid,
-    pub total_amount: f64,
+    pub items: Vec<CreateOrderItem>,
+    #[serde(default)]
+    pub shipping_address: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub total_amount: Option<Money>,
+    #[serde(default)]
+    pub currency: Currency,
+    #[serde(default)]
+    pub price_lock_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CreateOrderItem {
+    pub variant_id: Uuid,
+    pub quantity: i32,
+    /// Unit price the client displayed.
+    #[serde(default)]
+    pub price: Option<Money>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct UpdateOrder {
-    pub status: Option<String>,
-    pub total_amount: Option<f64>,
+    #[serde(default)]
+    pub status: Option<OrderStatus>,
+    #[serde(default)]
+    pub shipping_address: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+/// Moves an order to `status`; `note` is kept in the status history.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct UpdateOrderStatus {
+    pub status: OrderStatus,
+    #[serde(default)]
+    pub note: Option<String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CancelOrder {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_transitions() {
+        for status in OrderStatus::ALL {
+            assert_eq!(status.as_str().parse::<OrderStatus>(), Ok(status));
+            assert!(!status.can_transition_to(status), "{} loops to itself", status);
+        }
+
+        assert!(OrderStatus::Pending.can_transition_to(OrderStatus::Paid));
+        assert!(!OrderStatus::Pending.can_transition_to(OrderStatus::Shipped));
+        assert!(!OrderStatus::Pending.can_transition_to(OrderStatus::Refunded));
+        assert!(!OrderStatus::Shipped.can_transition_to(OrderStatus::Cancelled));
+        assert!(!OrderStatus::Delivered.can_transition_to(OrderStatus::Processing));
+        assert!(OrderStatus::Cancelled.is_final());
+        assert!(OrderStatus::Refunded.is_final());
+    }
+}
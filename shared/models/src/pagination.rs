@@ -11,6 +11,22 @@ pub struct Pagination {
 fn default_page() -> u32 { 1 }
 fn default_limit() -> u32 { 10 }
 
+impl Pagination {
+    /// Rows to skip for the requested page. Pages start at 1 and hold at
+    /// least one item.
+    pub fn offset(&self) -> Result<i64, String> {
+        if self.page == 0 {
+            return Err("page must be at least 1".to_string());
+        }
+        if self.limit == 0 {
+            return Err("limit must be at least 1".to_string());
+        }
+        (self.page as i64 - 1)
+            .checked_mul(self.limit as i64)
+            .ok_or_else(|| "page is out of range".to_string())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaginatedResponse<T> {
     pub items: Vec<T>,
@@ -20,3 +36,16 @@ pub struct PaginatedResponse<T> {
     pub limit: u32,
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_rejects_empty_pages() {
+        assert_eq!(Pagination { page: 3, limit: 10 }.offset(), Ok(20));
+        assert!(Pagination { page: u32::MAX, limit: u32::MAX }.offset().is_err());
+        assert!(Pagination { page: 0, limit: 10 }.offset().is_err());
+        assert!(Pagination { page: 1, limit: 0 }.offset().is_err());
+    }
+}
@@ -1,27 +1,348 @@
 use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
 
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use crate::money::Money;
+use crate::schema::{text_enum, Column, Table};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaymentStatus {
+    /// Sent to the gateway; the payer has not come back yet.
+    #[default]
+    Pending,
+    /// Verified with the gateway.
+    Paid,
+    /// Some of the amount was given back to the payer.
+    #[serde(rename = "partially_refunded")]
+    PartiallyRefunded,
+    /// Cancelled by the payer or rejected by the gateway.
+    Failed,
+    Refunded,
+}
+
+impl PaymentStatus {
+    pub const ALL: [PaymentStatus; 5] = [
+        PaymentStatus::Pending,
+        PaymentStatus::Paid,
+        PaymentStatus::PartiallyRefunded,
+        PaymentStatus::Failed,
+        PaymentStatus::Refunded,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentStatus::Pending => "pending",
+            PaymentStatus::Paid => "paid",
+            PaymentStatus::PartiallyRefunded => "partially_refunded",
+            PaymentStatus::Failed => "failed",
+            PaymentStatus::Refunded => "refunded",
+        }
+    }
+
+    /// Whether the money was collected; refunds do not undo that.
+    pub fn is_settled(&self) -> bool {
+        matches!(self, PaymentStatus::Paid | PaymentStatus::PartiallyRefunded | PaymentStatus::Refunded)
+    }
+}
+
+impl Display for PaymentStatus {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PaymentStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        PaymentStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == value)
+            .ok_or_else(|| format!("Unknown payment status `{}`", value))
+    }
+}
+
+text_enum!(PaymentStatus);
+
+/// One attempt to pay for an order through a gateway. `transaction_id` is
+/// the gateway's id for the attempt (the IPG "authority") and
+/// `reference_id` the bank reference it returns once the payment is verified.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
 pub struct Payment {
     pub id: Uuid,
     pub order_id: Uuid,
-    pub amount: f64,
-    pub status: String,
+    pub amount: Money,
+    pub status: PaymentStatus,
+    pub gateway: String,
     pub transaction_id: String,
+    /// Gateway page the payer has to be sent to.
+    pub redirect_url: Option<String>,
+    pub reference_id: Option<String>,
+    /// Masked card number reported by the gateway.
+    pub card_pan: Option<String>,
+    pub failure_reason: Option<String>,
+    pub paid_at: Option<DateTime<Utc>>,
+    /// Sum of the payment's successful refunds.
+    pub refunded_amount: Money,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl Table for Payment {
+    const TABLE: &'static str = "payments";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("order_id", "UUID"),
+        Column::new("amount", "NUMERIC"),
+        Column::new("status", "TEXT"),
+        Column::new("gateway", "TEXT"),
+        Column::new("transaction_id", "TEXT"),
+        Column::nullable("redirect_url", "TEXT"),
+        Column::nullable("reference_id", "TEXT"),
+        Column::nullable("card_pan", "TEXT"),
+        Column::nullable("failure_reason", "TEXT"),
+        Column::nullable("paid_at", "TIMESTAMPTZ"),
+        Column::new("refunded_amount", "NUMERIC"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+        Column::new("updated_at", "TIMESTAMPTZ"),
+    ];
+}
+
+impl Payment {
+    /// What can still be given back to the payer.
+    pub fn refundable_amount(&self) -> Money {
+        match self.amount.checked_sub(self.refunded_amount) {
+            Ok(left) if self.status.is_settled() && left.is_positive() => left,
+            _ => Money::zero(self.amount.currency()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct CreatePayment {
     pub order_id: Uuid,
-    pub amount: f64,
+    pub amount: Money,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-pub struct PaymentStatus {
+pub struct PaymentStatusResponse {
+    pub status: PaymentStatus,
+    pub reference_id: Option<String>,
+}
+
+/// Query string the gateway sends the payer back with, e.g.
+/// `?Authority=A000...&Status=OK`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PaymentCallback {
+    #[serde(rename = "Authority")]
+    pub authority: String,
+    #[serde(rename = "Status")]
     pub status: String,
 }
 
+impl PaymentCallback {
+    /// Whether the payer completed the payment; it still has to be verified.
+    pub fn is_ok(&self) -> bool {
+        self.status.eq_ignore_ascii_case("OK")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum PaymentEventType {
+    /// The payer completed the payment; it still has to be verified.
+    #[serde(rename = "payment.paid")]
+    Paid,
+    #[serde(rename = "payment.failed")]
+    Failed,
+}
+
+impl PaymentEventType {
+    pub const ALL: [PaymentEventType; 2] = [PaymentEventType::Paid, PaymentEventType::Failed];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentEventType::Paid => "payment.paid",
+            PaymentEventType::Failed => "payment.failed",
+        }
+    }
+}
+
+impl Display for PaymentEventType {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PaymentEventType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        PaymentEventType::ALL
+            .into_iter()
+            .find(|event_type| event_type.as_str() == value)
+            .ok_or_else(|| format!("Unknown payment event type `{}`", value))
+    }
+}
+
+text_enum!(PaymentEventType);
+
+/// A signed notification a gateway posts to `/api/payments/webhook`.
+/// Gateways redeliver events until they are acknowledged, so `id` must stay
+/// the same across deliveries.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PaymentEvent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: PaymentEventType,
+    pub authority: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RefundStatus {
+    /// The gateway could not be reached, so whether the money was given
+    /// back is not known until the refund is resolved.
+    Pending,
+    Succeeded,
+    /// The gateway refused; nothing was given back.
+    Failed,
+}
+
+impl RefundStatus {
+    pub const ALL: [RefundStatus; 3] = [RefundStatus::Pending, RefundStatus::Succeeded, RefundStatus::Failed];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RefundStatus::Pending => "pending",
+            RefundStatus::Succeeded => "succeeded",
+            RefundStatus::Failed => "failed",
+        }
+    }
+}
+
+impl Display for RefundStatus {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RefundStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        RefundStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == value)
+            .ok_or_else(|| format!("Unknown refund status `{}`", value))
+    }
+}
+
+text_enum!(RefundStatus);
+
+/// Money given back from a payment through its gateway. Failed attempts
+/// are kept as well.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Refund {
+    pub id: Uuid,
+    pub payment_id: Uuid,
+    pub order_id: Uuid,
+    pub amount: Money,
+    pub status: RefundStatus,
+    pub reason: Option<String>,
+    /// Whether the refunded items were put back into stock.
+    pub restock: bool,
+    pub gateway_refund_id: Option<String>,
+    pub failure_reason: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Table for Refund {
+    const TABLE: &'static str = "refunds";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("payment_id", "UUID"),
+        Column::new("order_id", "UUID"),
+        Column::new("amount", "NUMERIC"),
+        Column::new("status", "TEXT"),
+        Column::nullable("reason", "TEXT"),
+        Column::new("restock", "BOOL"),
+        Column::nullable("gateway_refund_id", "TEXT"),
+        Column::nullable("failure_reason", "TEXT"),
+        Column::nullable("created_by", "UUID"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+    ];
+}
+
+/// The part of a refund that pays back units of one order line.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct RefundItem {
+    pub id: Uuid,
+    pub refund_id: Uuid,
+    pub order_item_id: Uuid,
+    pub quantity: i32,
+    pub amount: Money,
+}
+
+impl Table for RefundItem {
+    const TABLE: &'static str = "refund_items";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("refund_id", "UUID"),
+        Column::new("order_item_id", "UUID"),
+        Column::new("quantity", "INT4"),
+        Column::new("amount", "NUMERIC"),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RefundWithItems {
+    #[serde(flatten)]
+    pub refund: Refund,
+    pub items: Vec<RefundItem>,
+}
+
+/// Refunds order lines of a payment. Without `items` and `amount` everything
+/// that is left is refunded, which needs the order to have been paid in a
+/// single payment. Lines are refunded at their price plus their share of
+/// the tax unless `amount` says otherwise; an `amount` alone
+/// refunds money without returning any items.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CreateRefund {
+    #[serde(default)]
+    pub items: Vec<CreateRefundItem>,
+    #[serde(default)]
+    pub amount: Option<Money>,
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Put the refunded items back into stock.
+    #[serde(default = "default_restock")]
+    pub restock: bool,
+}
+
+fn default_restock() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CreateRefundItem {
+    pub order_item_id: Uuid,
+    pub quantity: i32,
+}
+
+/// Settles a pending refund as succeeded or failed once the gateway's
+/// records show whether the money was given back.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ResolveRefund {
+    pub status: RefundStatus,
+    #[serde(default)]
+    pub gateway_refund_id: Option<String>,
+    #[serde(default)]
+    pub failure_reason: Option<String>,
+}
@@ -0,0 +1,115 @@
+use crate::money::{Currency, Money};
+
+/// Replaces ASCII digits with Persian ones (`۰`–`۹`), leaving everything else as is.
+pub fn to_persian_digits(text: &str) -> String {
+    text.chars()
+        .map(|c| match c.to_digit(10) {
+            Some(digit) => char::from_u32('۰' as u32 + digit).unwrap_or(c),
+            None => c,
+        })
+        .collect()
+}
+
+/// Formats a whole amount with Persian digits and the Persian thousands
+/// separator, e.g. `1250000` becomes `۱٬۲۵۰٬۰۰۰`.
+pub fn format_amount(amount: i64) -> String {
+    let digits = amount.unsigned_abs().to_string();
+    let mut grouped = String::with_capacity(digits.len() * 2);
+
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push('٬');
+        }
+        grouped.push(digit);
+    }
+
+    let sign = if amount < 0 { "-" } else { "" };
+    to_persian_digits(&format!("{}{}", sign, grouped))
+}
+
+/// Folds the ways the same Persian text gets typed together, for search:
+/// Arabic yeh, kaf and teh marbuta become Persian letters, hamza forms of
+/// alef plain alef, Persian and Arabic digits ASCII ones, and half-spaces
+/// (ZWNJ) spaces. Diacritics and kashida are dropped, letters lowercased
+/// and whitespace collapsed.
+pub fn normalize_search_text(text: &str) -> String {
+    let folded: String = text
+        .chars()
+        .filter_map(|c| match c {
+            'ي' | 'ى' => Some('ی'),
+            'ك' => Some('ک'),
+            'ة' | 'ۀ' => Some('ه'),
+            'أ' | 'إ' | 'ٱ' | 'آ' => Some('ا'),
+            '۰'..='۹' => char::from_u32(c as u32 - '۰' as u32 + '0' as u32),
+            '٠'..='٩' => char::from_u32(c as u32 - '٠' as u32 + '0' as u32),
+            '\u{200c}' | '\u{200d}' => Some(' '),
+            '\u{064b}'..='\u{065f}' | '\u{0670}' | '\u{0640}' => None,
+            _ => Some(c),
+        })
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The Persian name of a currency's unit.
+pub fn currency_unit(currency: Currency) -> &'static str {
+    match currency {
+        Currency::Irr => "ریال",
+        Currency::Irt => "تومان",
+        Currency::Usd => "دلار",
+        Currency::Eur => "یورو",
+        Currency::Aed => "درهم",
+    }
+}
+
+/// Formats an amount with Persian digits and its unit, e.g.
+/// `۴٬۵۰۰٬۰۰۰ تومان` or `۱۲٫۵ دلار`.
+pub fn format_money(money: &Money) -> String {
+    let decimal = money.to_decimal_string();
+    let unsigned = decimal.trim_start_matches('-');
+    let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    let mut formatted = format_amount(whole.parse().unwrap_or_default());
+    if !fraction.is_empty() {
+        formatted.push('٫');
+        formatted.push_str(&to_persian_digits(fraction));
+    }
+
+    let sign = if money.is_negative() { "-" } else { "" };
+    format!("{}{} {}", sign, formatted, currency_unit(money.currency()))
+}
+
+/// Formats a price for shoppers: rial amounts are shown in tomans, other
+/// currencies as they are.
+pub fn format_price(money: &Money) -> String {
+    format_money(&money.in_currency(Currency::Irt).unwrap_or(*money))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_persian_number_formatting() {
+        assert_eq!(to_persian_digits("کد 0912"), "کد ۰۹۱۲");
+        assert_eq!(format_amount(0), "۰");
+        assert_eq!(format_amount(999), "۹۹۹");
+        assert_eq!(format_amount(1250000), "۱٬۲۵۰٬۰۰۰");
+        assert_eq!(format_amount(-45000), "-۴۵٬۰۰۰");
+    }
+
+    #[test]
+    fn test_search_text_normalization() {
+        assert_eq!(normalize_search_text("علي كريمي"), normalize_search_text("علی کریمی"));
+        assert_eq!(normalize_search_text("گردن\u{200c}بند  نقره"), "گردن بند نقره");
+        assert_eq!(normalize_search_text("انگشتر ۹۲۵ مُدل"), "انگشتر 925 مدل");
+        assert_eq!(normalize_search_text("Silver ٩٢٥"), "silver 925");
+    }
+
+    #[test]
+    fn test_money_formatting() {
+        assert_eq!(format_money(&Money::rials(45_000_000)), "۴۵٬۰۰۰٬۰۰۰ ریال");
+        assert_eq!(format_price(&Money::rials(45_000_005)), "۴٬۵۰۰٬۰۰۰٫۵ تومان");
+        assert_eq!(format_price(&Money::new(-1_250, Currency::Usd)), "-۱۲٫۵ دلار");
+    }
+}
@@ -0,0 +1,280 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use crate::money::{Money, MoneyError};
+use crate::schema::{text_enum, Column, Table};
+use crate::weight::Weight;
+
+/// A precious metal products can be priced by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+pub enum Metal {
+    #[default]
+    #[serde(rename = "silver")]
+    Silver,
+}
+
+impl Metal {
+    pub const ALL: [Metal; 1] = [Metal::Silver];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Metal::Silver => "silver",
+        }
+    }
+}
+
+impl Display for Metal {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Metal {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Metal::ALL
+            .into_iter()
+            .find(|metal| metal.as_str() == value)
+            .ok_or_else(|| format!("Unknown metal `{}`", value))
+    }
+}
+
+text_enum!(Metal);
+
+/// What a gram of the pure metal costs, in rials. Products priced by the
+/// metal are repriced whenever an admin changes it.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct MetalRate {
+    pub metal: Metal,
+    pub rials_per_gram: Money,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Table for MetalRate {
+    const TABLE: &'static str = "metal_rates";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("metal", "TEXT"),
+        Column::new("rials_per_gram", "NUMERIC"),
+        Column::nullable("updated_by", "UUID"),
+        Column::new("updated_at", "TIMESTAMPTZ"),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct UpdateMetalRate {
+    pub rials_per_gram: Money,
+}
+
+/// Prices every variant of a product from the metal in it: its weight
+/// times its purity times the metal rate, plus a making charge per piece,
+/// plus a margin on both. Variants of such a product need a weight, and
+/// their prices cannot be set by hand.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct PricingRule {
+    pub product_id: Uuid,
+    pub metal: Metal,
+    pub making_charge: Money,
+    /// Margin in hundredths of a percent, e.g. `1500` for 15%.
+    pub margin_basis_points: i32,
+    pub updated_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PricingRule {
+    /// The price of a piece at `rate` per gram of pure metal. A piece
+    /// without a purity is taken to be pure. Rounded to whole rials.
+    pub fn price(&self, weight: Weight, purity: Option<i32>, rate: Money) -> Result<Money, MoneyError> {
+        let fine_milligrams = weight
+            .milligrams()
+            .checked_mul(purity.unwrap_or(1000) as i64)
+            .ok_or(MoneyError::Overflow)?;
+        let metal = rate.checked_mul_ratio(fine_milligrams, 1_000_000)?;
+        let cost = metal.checked_add(self.making_charge)?;
+        cost.checked_mul_ratio(10_000 + self.margin_basis_points as i64, 10_000)
+    }
+}
+
+impl Table for PricingRule {
+    const TABLE: &'static str = "product_pricing_rules";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("product_id", "UUID"),
+        Column::new("metal", "TEXT"),
+        Column::new("making_charge", "NUMERIC"),
+        Column::new("margin_basis_points", "INT4"),
+        Column::nullable("updated_by", "UUID"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+        Column::new("updated_at", "TIMESTAMPTZ"),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SetPricingRule {
+    #[serde(default)]
+    pub metal: Metal,
+    pub making_charge: Money,
+    #[serde(default)]
+    pub margin_basis_points: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceChangeReason {
+    /// Set by hand on the variant.
+    Manual,
+    /// The product's pricing rule was set or the variant's weight changed.
+    PricingRule,
+    /// The metal rate changed.
+    MetalRate,
+}
+
+impl PriceChangeReason {
+    pub const ALL: [PriceChangeReason; 3] = [PriceChangeReason::Manual, PriceChangeReason::PricingRule, PriceChangeReason::MetalRate];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PriceChangeReason::Manual => "manual",
+            PriceChangeReason::PricingRule => "pricing_rule",
+            PriceChangeReason::MetalRate => "metal_rate",
+        }
+    }
+}
+
+impl Display for PriceChangeReason {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PriceChangeReason {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        PriceChangeReason::ALL
+            .into_iter()
+            .find(|reason| reason.as_str() == value)
+            .ok_or_else(|| format!("Unknown price change reason `{}`", value))
+    }
+}
+
+text_enum!(PriceChangeReason);
+
+/// One change of a variant's price. `metal_rate` is the rate a rule-based
+/// price was computed at.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct PriceChange {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Uuid,
+    pub old_price: Money,
+    pub new_price: Money,
+    pub reason: PriceChangeReason,
+    pub metal_rate: Option<Money>,
+    pub changed_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Table for PriceChange {
+    const TABLE: &'static str = "price_history";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("product_id", "UUID"),
+        Column::new("variant_id", "UUID"),
+        Column::new("old_price", "NUMERIC"),
+        Column::new("new_price", "NUMERIC"),
+        Column::new("reason", "TEXT"),
+        Column::nullable("metal_rate", "NUMERIC"),
+        Column::nullable("changed_by", "UUID"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+    ];
+}
+
+/// Holds the current prices of some variants for a shopper while they
+/// check out, so a metal rate change does not move the price under them.
+/// An order placed with the lock before it expires gets the locked prices;
+/// a lock is used by one order only.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct PriceLock {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// The order the lock was used for.
+    pub order_id: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Table for PriceLock {
+    const TABLE: &'static str = "price_locks";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("user_id", "UUID"),
+        Column::nullable("order_id", "UUID"),
+        Column::new("expires_at", "TIMESTAMPTZ"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct PriceLockItem {
+    pub price_lock_id: Uuid,
+    pub variant_id: Uuid,
+    pub unit_price: Money,
+}
+
+impl Table for PriceLockItem {
+    const TABLE: &'static str = "price_lock_items";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("price_lock_id", "UUID"),
+        Column::new("variant_id", "UUID"),
+        Column::new("unit_price", "NUMERIC"),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PriceLockWithItems {
+    #[serde(flatten)]
+    pub lock: PriceLock,
+    pub items: Vec<PriceLockItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CreatePriceLock {
+    pub variant_ids: Vec<Uuid>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pricing_rule_prices_the_fine_metal() {
+        let rule = PricingRule {
+            product_id: Uuid::nil(),
+            metal: Metal::Silver,
+            making_charge: Money::rials(2_000_000),
+            margin_basis_points: 1_500,
+            updated_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let rate = Money::rials(1_000_000);
+
+        // 10 g of 925 silver: 9.25 g of fine silver, 9,250,000 + 2,000,000, plus 15%.
+        assert_eq!(rule.price(Weight::grams(10), Some(925), rate), Ok(Money::rials(12_937_500)));
+        assert_eq!(rule.price(Weight::from_milligrams(4_250), None, rate), Ok(Money::rials(7_187_500)));
+        assert_eq!(
+            PricingRule { margin_basis_points: 0, ..rule }.price(Weight::from_milligrams(1), Some(925), rate),
+            Ok(Money::rials(2_000_925))
+        );
+    }
+}
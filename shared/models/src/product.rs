@@ -1,34 +1,293 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use crate::category::{double_option, AttributeValue};
+use crate::money::Money;
+use crate::pagination::PaginatedResponse;
+use crate::schema::{Column, Table};
+use crate::weight::Weight;
 
+/// What is sold is one of a product's variants; `price` is the lowest
+/// price among them and `stock` their sellable stock combined, both kept up
+/// to date as the variants change.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
 pub struct Product {
     pub id: Uuid,
     pub name: String,
     pub description: Option<String>,
-    pub price: f64,
+    pub price: Money,
     pub stock: i32,
-    pub category: String,
+    pub category_id: Uuid,
     pub vendor_id: Uuid,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl Table for Product {
+    const TABLE: &'static str = "products";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("name", "TEXT"),
+        Column::nullable("description", "TEXT"),
+        Column::new("price", "NUMERIC"),
+        Column::new("stock", "INT4"),
+        Column::new("category_id", "UUID"),
+        Column::new("vendor_id", "UUID"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+        Column::new("updated_at", "TIMESTAMPTZ"),
+    ];
+}
+
+/// The product's first variant is given alongside its own fields, e.g.
+/// `price` and `stock`; more are added under `products/{id}/variants`.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct CreateProduct {
     pub name: String,
     pub description: Option<String>,
-    pub price: f64,
-    pub stock: i32,
+    pub category_id: Uuid,
+    pub vendor_id: Uuid,
+    #[serde(flatten)]
+    pub variant: CreateProductVariant,
 }
 
+/// `price` and `stock` can only be set here on products with a single
+/// variant; otherwise each variant is updated on its own.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct UpdateProduct {
     pub name: Option<String>,
     pub description: Option<String>,
-    pub price: Option<f64>,
+    pub price: Option<Money>,
+    pub stock: Option<i32>,
+    pub category_id: Option<Uuid>,
+}
+
+/// One sellable version of a product, e.g. a ring in one size and purity,
+/// with its own SKU, price and stock. `stock` is sellable stock like
+/// [`Product::stock`]; the warehouses' stock levels are per variant.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct ProductVariant {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub sku: String,
+    pub weight_grams: Option<Weight>,
+    /// Fineness in parts per thousand, e.g. 925 for sterling silver.
+    pub purity: Option<i32>,
+    pub size: Option<String>,
+    pub color: Option<String>,
+    /// Values of the attributes the product's category defines, checked
+    /// against its schema when the variant is saved.
+    #[cfg_attr(feature = "sqlx", sqlx(json))]
+    pub attributes: BTreeMap<String, AttributeValue>,
+    pub price: Money,
+    pub stock: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Table for ProductVariant {
+    const TABLE: &'static str = "product_variants";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("product_id", "UUID"),
+        Column::new("sku", "TEXT"),
+        Column::nullable("weight_grams", "NUMERIC"),
+        Column::nullable("purity", "INT4"),
+        Column::nullable("size", "TEXT"),
+        Column::nullable("color", "TEXT"),
+        Column::new("attributes", "JSONB"),
+        Column::new("price", "NUMERIC"),
+        Column::new("stock", "INT4"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+        Column::new("updated_at", "TIMESTAMPTZ"),
+    ];
+}
+
+/// SKUs are letters, digits, dots, dashes and underscores, e.g. `RNG-925-54`.
+pub fn is_valid_sku(sku: &str) -> bool {
+    !sku.is_empty() && sku.len() <= 64 && sku.bytes().all(|b| b.is_ascii_alphanumeric() || b"-_.".contains(&b))
+}
+
+/// Without a SKU one is made up from the variant's id.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CreateProductVariant {
+    #[serde(default)]
+    pub sku: Option<String>,
+    #[serde(default)]
+    pub weight_grams: Option<Weight>,
+    #[serde(default)]
+    pub purity: Option<i32>,
+    #[serde(default)]
+    pub size: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub attributes: BTreeMap<String, AttributeValue>,
+    pub price: Money,
+    #[serde(default)]
+    pub stock: i32,
+}
+
+/// Optional attributes are cleared with an explicit `null`; leaving them
+/// out keeps them. `attributes` replaces all attribute values at once.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct UpdateProductVariant {
+    pub sku: Option<String>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub weight_grams: Option<Option<Weight>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub purity: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub size: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub color: Option<Option<String>>,
+    pub attributes: Option<BTreeMap<String, AttributeValue>>,
+    pub price: Option<Money>,
     pub stock: Option<i32>,
 }
 
+/// A product that is running out, as listed by `products/low-stock`. It
+/// serializes as the product with the reorder figures alongside.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LowStockProduct {
+    #[serde(flatten)]
+    pub product: Product,
+    /// Units on hand across all warehouses, including reserved ones.
+    pub on_hand: i64,
+    /// Sum of the reorder points of the product's stock levels.
+    pub reorder_point: Option<i64>,
+    pub reorder_quantity: Option<i64>,
+    /// Average units shipped per day over the sales window.
+    pub daily_sales: f64,
+    /// How many days the sellable stock lasts at `daily_sales`; empty for
+    /// products that did not sell.
+    pub days_of_cover: Option<f64>,
+}
+
+impl LowStockProduct {
+    pub fn days_of_cover(stock: i32, daily_sales: f64) -> Option<f64> {
+        (daily_sales > 0.0).then(|| (stock.max(0) as f64 / daily_sales * 10.0).round() / 10.0)
+    }
+}
+
+/// `threshold` also lists products whose sellable stock is at or below it,
+/// whether or not they have reorder points.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct LowStockFilter {
+    pub threshold: Option<i32>,
+    pub vendor_id: Option<Uuid>,
+}
+
+/// Filters of `GET /products`. `search` matches words of the name,
+/// category and description by prefix, however Arabic letters, half-spaces
+/// and digits were typed. `category` is a category slug and also matches
+/// its subcategories; prices are rials.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ProductSearch {
+    pub search: Option<String>,
+    pub category: Option<String>,
+    pub vendor_id: Option<Uuid>,
+    pub min_price: Option<Money>,
+    pub max_price: Option<Money>,
+    pub in_stock: Option<bool>,
+}
+
+/// A page of search results, best matches first, with counts of what each
+/// filter would leave. Each facet applies all the other filters but its own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductSearchResult {
+    #[serde(flatten)]
+    pub page: PaginatedResponse<Product>,
+    pub facets: ProductFacets,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProductFacets {
+    pub categories: Vec<CategoryFacet>,
+    pub price_ranges: Vec<PriceRangeFacet>,
+    pub stock: StockFacet,
+}
+
+/// Products listed directly in a category, not counting subcategories.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryFacet {
+    pub category_id: Uuid,
+    pub slug: String,
+    pub name_fa: String,
+    pub name_en: String,
+    pub count: i64,
+}
+
+/// Products priced from `min` up to but excluding `max`; a missing bound
+/// leaves the range open.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceRangeFacet {
+    pub min: Option<Money>,
+    pub max: Option<Money>,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StockFacet {
+    pub in_stock: i64,
+    pub out_of_stock: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_low_stock_product_reads_as_a_product() {
+        let now = Utc::now();
+        let product = Product {
+            id: Uuid::new_v4(),
+            name: "انگشتر نقره".to_string(),
+            description: None,
+            price: Money::rials(2_500_000),
+            stock: 3,
+            category_id: Uuid::new_v4(),
+            vendor_id: Uuid::new_v4(),
+            created_at: now,
+            updated_at: now,
+        };
+        let low_stock = LowStockProduct {
+            product: product.clone(),
+            on_hand: 5,
+            reorder_point: Some(4),
+            reorder_quantity: Some(20),
+            daily_sales: 1.5,
+            days_of_cover: LowStockProduct::days_of_cover(product.stock, 1.5),
+        };
+
+        let value = serde_json::to_value(&low_stock).unwrap();
+        assert_eq!(value["days_of_cover"], 2.0);
+        assert_eq!(serde_json::from_value::<Product>(value).unwrap(), product);
+        assert_eq!(LowStockProduct::days_of_cover(3, 0.0), None);
+        assert_eq!(LowStockProduct::days_of_cover(-1, 2.0), Some(0.0));
+    }
+
+    #[test]
+    fn test_create_product_takes_its_first_variant_inline() {
+        let product: CreateProduct = serde_json::from_str(&format!(
+            r#"{{"name": "انگشتر", "description": null, "price": 2500000, "stock": 3, "category_id": "{id}", "vendor_id": "{id}",
+                "sku": "RNG-925-54", "weight_grams": 4.25, "purity": 925, "attributes": {{"ring_size": "54"}}}}"#,
+            id = Uuid::nil()
+        ))
+        .unwrap();
+
+        assert_eq!(product.variant.price, Money::rials(2_500_000));
+        assert_eq!(product.variant.stock, 3);
+        assert_eq!(product.variant.sku.as_deref(), Some("RNG-925-54"));
+        assert_eq!(product.variant.weight_grams, Some(Weight::from_milligrams(4_250)));
+        assert_eq!(product.variant.attributes["ring_size"], AttributeValue::Text("54".to_string()));
+
+        let unchanged: UpdateProductVariant = serde_json::from_str("{}").unwrap();
+        assert_eq!(unchanged.size, None);
+        let cleared: UpdateProductVariant = serde_json::from_str(r#"{"size": null}"#).unwrap();
+        assert_eq!(cleared.size, Some(None));
+    }
+}
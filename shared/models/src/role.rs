@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use crate::schema::text_enum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    Vendor,
+    #[default]
+    Customer,
+    Support,
+}
+
+impl Role {
+    pub const ALL: [Role; 4] = [Role::Admin, Role::Vendor, Role::Customer, Role::Support];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Vendor => "vendor",
+            Role::Customer => "customer",
+            Role::Support => "support",
+        }
+    }
+
+    /// Permissions granted to the role. Access to a user's own resources
+    /// (profile, orders, notifications, a vendor's products and their stock)
+    /// is checked by ownership instead.
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+
+        match self {
+            Role::Admin => &Permission::ALL,
+            Role::Vendor => &[InventoryRead],
+            Role::Customer => &[OrdersCreate, PaymentsCreate],
+            Role::Support => &[
+                UsersRead,
+                InventoryRead,
+                OrdersCreate,
+                OrdersRead,
+                OrdersWrite,
+                PaymentsRead,
+                NotificationsRead,
+                NotificationsWrite,
+            ],
+        }
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Role::ALL
+            .into_iter()
+            .find(|role| role.as_str() == value)
+            .ok_or_else(|| format!("Unknown role `{}`", value))
+    }
+}
+
+text_enum!(Role);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Permission {
+    #[serde(rename = "users:read")]
+    UsersRead,
+    #[serde(rename = "users:write")]
+    UsersWrite,
+    #[serde(rename = "users:delete")]
+    UsersDelete,
+    #[serde(rename = "users:manage_roles")]
+    UsersManageRoles,
+    #[serde(rename = "vendors:write")]
+    VendorsWrite,
+    #[serde(rename = "products:write")]
+    ProductsWrite,
+    #[serde(rename = "pricing:write")]
+    PricingWrite,
+    #[serde(rename = "categories:write")]
+    CategoriesWrite,
+    #[serde(rename = "inventory:read")]
+    InventoryRead,
+    #[serde(rename = "inventory:write")]
+    InventoryWrite,
+    #[serde(rename = "orders:create")]
+    OrdersCreate,
+    #[serde(rename = "orders:read")]
+    OrdersRead,
+    #[serde(rename = "orders:write")]
+    OrdersWrite,
+    #[serde(rename = "orders:delete")]
+    OrdersDelete,
+    #[serde(rename = "payments:create")]
+    PaymentsCreate,
+    #[serde(rename = "payments:read")]
+    PaymentsRead,
+    #[serde(rename = "payments:refund")]
+    PaymentsRefund,
+    #[serde(rename = "notifications:read")]
+    NotificationsRead,
+    #[serde(rename = "notifications:write")]
+    NotificationsWrite,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 19] = [
+        Permission::UsersRead,
+        Permission::UsersWrite,
+        Permission::UsersDelete,
+        Permission::UsersManageRoles,
+        Permission::VendorsWrite,
+        Permission::ProductsWrite,
+        Permission::PricingWrite,
+        Permission::CategoriesWrite,
+        Permission::InventoryRead,
+        Permission::InventoryWrite,
+        Permission::OrdersCreate,
+        Permission::OrdersRead,
+        Permission::OrdersWrite,
+        Permission::OrdersDelete,
+        Permission::PaymentsCreate,
+        Permission::PaymentsRead,
+        Permission::PaymentsRefund,
+        Permission::NotificationsRead,
+        Permission::NotificationsWrite,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::UsersRead => "users:read",
+            Permission::UsersWrite => "users:write",
+            Permission::UsersDelete => "users:delete",
+            Permission::UsersManageRoles => "users:manage_roles",
+            Permission::VendorsWrite => "vendors:write",
+            Permission::ProductsWrite => "products:write",
+            Permission::PricingWrite => "pricing:write",
+            Permission::CategoriesWrite => "categories:write",
+            Permission::InventoryRead => "inventory:read",
+            Permission::InventoryWrite => "inventory:write",
+            Permission::OrdersCreate => "orders:create",
+            Permission::OrdersRead => "orders:read",
+            Permission::OrdersWrite => "orders:write",
+            Permission::OrdersDelete => "orders:delete",
+            Permission::PaymentsCreate => "payments:create",
+            Permission::PaymentsRead => "payments:read",
+            Permission::PaymentsRefund => "payments:refund",
+            Permission::NotificationsRead => "notifications:read",
+            Permission::NotificationsWrite => "notifications:write",
+        }
+    }
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Permission {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Permission::ALL
+            .into_iter()
+            .find(|permission| permission.as_str() == value)
+            .ok_or_else(|| format!("Unknown permission `{}`", value))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct UpdateUserRole {
+    pub role: Role,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_round_trip() {
+        for role in Role::ALL {
+            assert_eq!(role.as_str().parse::<Role>(), Ok(role));
+            assert_eq!(serde_json::to_value(role).unwrap(), role.as_str());
+        }
+        for permission in Permission::ALL {
+            assert_eq!(permission.as_str().parse::<Permission>(), Ok(permission));
+            assert_eq!(serde_json::to_value(permission).unwrap(), permission.as_str());
+        }
+    }
+
+    #[test]
+    fn test_only_admin_manages_roles() {
+        for role in Role::ALL {
+            let can_manage = role.permissions().contains(&Permission::UsersManageRoles);
+            assert_eq!(can_manage, role == Role::Admin, "{}", role);
+        }
+    }
+
+    #[test]
+    fn test_only_admin_writes_any_product() {
+        for role in Role::ALL {
+            for permission in [Permission::ProductsWrite, Permission::InventoryWrite] {
+                let can_write = role.permissions().contains(&permission);
+                assert_eq!(can_write, role == Role::Admin, "{} {}", role, permission);
+            }
+        }
+    }
+}
@@ -0,0 +1,390 @@
+/// A column a model expects to read from its table.
+///
+/// `sql_type` is the Postgres type name as reported by the server
+/// (e.g. `UUID`, `TEXT`, `INT4`), so the list can be compared against a
+/// live database without going through the Rust type system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    pub name: &'static str,
+    pub sql_type: &'static str,
+    pub nullable: bool,
+}
+
+impl Column {
+    pub const fn new(name: &'static str, sql_type: &'static str) -> Self {
+        Self { name, sql_type, nullable: false }
+    }
+
+    pub const fn nullable(name: &'static str, sql_type: &'static str) -> Self {
+        Self { name, sql_type, nullable: true }
+    }
+}
+
+/// Describes the table a row model is loaded from with `SELECT *`.
+///
+/// Keep `COLUMNS` in the same order and with the same names as the struct
+/// fields; the tests below and the backends' startup schema check rely on it.
+pub trait Table {
+    const TABLE: &'static str;
+    const COLUMNS: &'static [Column];
+}
+
+/// Maps an enum stored in a `TEXT` column through its `as_str`/`FromStr`
+/// pair, so the same spelling is used in the database and in JSON.
+macro_rules! text_enum {
+    ($name:ident) => {
+        #[cfg(feature = "sqlx")]
+        impl<DB: sqlx::Database> sqlx::Type<DB> for $name
+        where
+            str: sqlx::Type<DB>,
+        {
+            fn type_info() -> DB::TypeInfo {
+                <str as sqlx::Type<DB>>::type_info()
+            }
+
+            fn compatible(ty: &DB::TypeInfo) -> bool {
+                <str as sqlx::Type<DB>>::compatible(ty)
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for $name
+        where
+            &'r str: sqlx::Decode<'r, DB>,
+        {
+            fn decode(value: <DB as sqlx::Database>::ValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+                let value = <&'r str as sqlx::Decode<'r, DB>>::decode(value)?;
+                Ok(value.parse::<$name>()?)
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl<'q, DB: sqlx::Database> sqlx::Encode<'q, DB> for $name
+        where
+            &'q str: sqlx::Encode<'q, DB>,
+        {
+            fn encode_by_ref(
+                &self,
+                buf: &mut <DB as sqlx::Database>::ArgumentBuffer<'q>,
+            ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+                <&'q str as sqlx::Encode<'q, DB>>::encode_by_ref(&self.as_str(), buf)
+            }
+        }
+    };
+}
+
+pub(crate) use text_enum;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        category::Category,
+        identity::{LinkedIdentity, SocialProvider},
+        exchange_rate::{ExchangeRate, RateSource},
+        pricing::{Metal, MetalRate, PriceChange, PriceChangeReason, PriceLock, PriceLockItem, PricingRule},
+        money::{Currency, Money},
+        media::{Image, ImageContentType, Thumbnail},
+        inventory::{InventoryItem, MovementType, ReservationStatus, StockMovement, StockReservation, StockTransfer},
+        notification::{DeliveryChannel, DeliveryStatus, Notification},
+        order::{Order, OrderItem, OrderStatus, OrderStatusChange}, payment::{Payment, PaymentStatus, Refund, RefundItem, RefundStatus}, product::{Product, ProductVariant}, role::Role, user::User, vendor::Vendor,
+        warehouse::Warehouse,
+    };
+    use chrono::Utc;
+    use serde::Serialize;
+    use uuid::Uuid;
+
+    /// `hidden` lists columns whose fields are never serialized (e.g. secrets).
+    fn assert_columns_match<T: Table + Serialize>(row: &T, hidden: &[&'static str]) {
+        let value = serde_json::to_value(row).unwrap();
+        let mut fields: Vec<&str> = value.as_object().unwrap().keys().map(String::as_str).collect();
+        fields.extend_from_slice(hidden);
+        let mut columns: Vec<&str> = T::COLUMNS.iter().map(|c| c.name).collect();
+        fields.sort_unstable();
+        columns.sort_unstable();
+        assert_eq!(fields, columns, "column list for `{}` is out of sync with its model", T::TABLE);
+    }
+
+    #[test]
+    fn test_model_columns_match_fields() {
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+
+        assert_columns_match(&User {
+            id,
+            username: "user".to_string(),
+            email: "user@example.com".to_string(),
+            phone: None,
+            role: Role::Customer,
+            password_hash: "hash".to_string(),
+            created_at: now,
+            updated_at: now,
+        }, &["password_hash"]);
+        assert_columns_match(&LinkedIdentity {
+            id,
+            user_id: id,
+            provider: SocialProvider::Github,
+            subject: "583231".to_string(),
+            email: None,
+            created_at: now,
+            last_login_at: now,
+        }, &[]);
+        assert_columns_match(&Vendor {
+            id,
+            name: "vendor".to_string(),
+            contact_person: "contact".to_string(),
+            email: "vendor@example.com".to_string(),
+            phone: "09123456789".to_string(),
+            address: "Tehran".to_string(),
+            user_id: None,
+            created_at: now,
+            updated_at: now,
+        }, &[]);
+        assert_columns_match(&Product {
+            id,
+            name: "product".to_string(),
+            description: None,
+            price: Money::rials(1),
+            stock: 1,
+            category_id: id,
+            vendor_id: id,
+            created_at: now,
+            updated_at: now,
+        }, &[]);
+        assert_columns_match(&ProductVariant {
+            id,
+            product_id: id,
+            sku: "RNG-925-54".to_string(),
+            weight_grams: None,
+            purity: Some(925),
+            size: None,
+            color: None,
+            attributes: Default::default(),
+            price: Money::rials(1),
+            stock: 1,
+            created_at: now,
+            updated_at: now,
+        }, &[]);
+        assert_columns_match(&Image {
+            id,
+            product_id: Some(id),
+            vendor_id: None,
+            storage_key: "images/original.jpg".to_string(),
+            url: "/media/images/original.jpg".to_string(),
+            content_type: ImageContentType::Jpeg,
+            byte_size: 1,
+            width: 1,
+            height: 1,
+            thumbnails: vec![Thumbnail {
+                size: 160,
+                width: 1,
+                height: 1,
+                content_type: ImageContentType::Jpeg,
+                storage_key: "images/160.jpg".to_string(),
+                url: "/media/images/160.jpg".to_string(),
+            }],
+            alt_text: None,
+            position: 0,
+            uploaded_by: None,
+            created_at: now,
+        }, &[]);
+        assert_columns_match(&Category {
+            id,
+            parent_id: None,
+            slug: "rings".to_string(),
+            name_fa: "انگشتر".to_string(),
+            name_en: "Rings".to_string(),
+            position: 0,
+            attributes: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }, &[]);
+        assert_columns_match(&Warehouse {
+            id,
+            code: "main".to_string(),
+            name: "warehouse".to_string(),
+            address: None,
+            is_default: true,
+            created_at: now,
+            updated_at: now,
+        }, &[]);
+        assert_columns_match(&InventoryItem {
+            id,
+            product_id: id,
+            variant_id: id,
+            warehouse_id: id,
+            quantity: 1,
+            reorder_point: None,
+            reorder_quantity: None,
+            low_stock_alerted_at: None,
+            created_at: now,
+            updated_at: now,
+        }, &[]);
+        assert_columns_match(&StockMovement {
+            id,
+            product_id: id,
+            variant_id: id,
+            warehouse_id: id,
+            movement_type: MovementType::Receipt,
+            quantity: 1,
+            balance_after: 1,
+            reference_id: None,
+            note: None,
+            created_by: None,
+            created_at: now,
+        }, &[]);
+        assert_columns_match(&StockTransfer {
+            id,
+            product_id: id,
+            variant_id: id,
+            from_warehouse_id: id,
+            to_warehouse_id: id,
+            quantity: 1,
+            note: None,
+            created_by: None,
+            created_at: now,
+        }, &[]);
+        assert_columns_match(&StockReservation {
+            id,
+            order_id: id,
+            product_id: id,
+            variant_id: id,
+            quantity: 1,
+            status: ReservationStatus::Active,
+            expires_at: now,
+            created_at: now,
+            updated_at: now,
+        }, &[]);
+        assert_columns_match(&Order {
+            id,
+            user_id: id,
+            status: OrderStatus::Pending,
+            subtotal: Money::rials(1),
+            tax_amount: Money::rials(0),
+            shipping_amount: Money::rials(0),
+            total_amount: Money::rials(1),
+            currency: Currency::Irr,
+            exchange_rate: 1,
+            shipping_address: None,
+            notes: None,
+            created_at: now,
+            updated_at: now,
+        }, &[]);
+        assert_columns_match(&OrderItem {
+            id,
+            order_id: id,
+            product_id: id,
+            variant_id: id,
+            product_name: "product".to_string(),
+            sku: "RNG-925-54".to_string(),
+            quantity: 1,
+            unit_price: Money::rials(1),
+            line_total: Money::rials(1),
+            created_at: now,
+        }, &[]);
+        assert_columns_match(&OrderStatusChange {
+            id,
+            order_id: id,
+            from_status: None,
+            to_status: OrderStatus::Pending,
+            changed_by: Some(id),
+            note: None,
+            created_at: now,
+        }, &[]);
+        assert_columns_match(&Payment {
+            id,
+            order_id: id,
+            amount: Money::rials(1),
+            status: PaymentStatus::Pending,
+            gateway: "mock".to_string(),
+            transaction_id: "tx".to_string(),
+            redirect_url: None,
+            reference_id: None,
+            card_pan: None,
+            failure_reason: None,
+            paid_at: None,
+            refunded_amount: Money::rials(0),
+            created_at: now,
+            updated_at: now,
+        }, &[]);
+        assert_columns_match(&Refund {
+            id,
+            payment_id: id,
+            order_id: id,
+            amount: Money::rials(1),
+            status: RefundStatus::Succeeded,
+            reason: None,
+            restock: true,
+            gateway_refund_id: None,
+            failure_reason: None,
+            created_by: None,
+            created_at: now,
+        }, &[]);
+        assert_columns_match(&RefundItem {
+            id,
+            refund_id: id,
+            order_item_id: id,
+            quantity: 1,
+            amount: Money::rials(1),
+        }, &[]);
+        assert_columns_match(&ExchangeRate {
+            currency: Currency::Usd,
+            rials_per_unit: 1_000_000,
+            source: RateSource::Manual,
+            updated_by: None,
+            updated_at: now,
+        }, &[]);
+        assert_columns_match(&MetalRate {
+            metal: Metal::Silver,
+            rials_per_gram: Money::rials(1),
+            updated_by: None,
+            updated_at: now,
+        }, &[]);
+        assert_columns_match(&PricingRule {
+            product_id: id,
+            metal: Metal::Silver,
+            making_charge: Money::rials(1),
+            margin_basis_points: 1_500,
+            updated_by: None,
+            created_at: now,
+            updated_at: now,
+        }, &[]);
+        assert_columns_match(&PriceChange {
+            id,
+            product_id: id,
+            variant_id: id,
+            old_price: Money::rials(1),
+            new_price: Money::rials(2),
+            reason: PriceChangeReason::MetalRate,
+            metal_rate: Some(Money::rials(1)),
+            changed_by: None,
+            created_at: now,
+        }, &[]);
+        assert_columns_match(&PriceLock {
+            id,
+            user_id: id,
+            order_id: None,
+            expires_at: now,
+            created_at: now,
+        }, &[]);
+        assert_columns_match(&PriceLockItem {
+            price_lock_id: id,
+            variant_id: id,
+            unit_price: Money::rials(1),
+        }, &[]);
+        assert_columns_match(&Notification {
+            id,
+            user_id: id,
+            message: "message".to_string(),
+            notification_type: "info".to_string(),
+            is_read: false,
+            channel: DeliveryChannel::Sms,
+            delivery_status: DeliveryStatus::Sent,
+            delivery_error: None,
+            provider_message_id: Some("42".to_string()),
+            sent_at: Some(now),
+            created_at: now,
+            updated_at: now,
+        }, &[]);
+    }
+}
@@ -2,21 +2,42 @@ use serde::{Deserialize, Serialize};
 
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use crate::{role::Role, schema::{Column, Table}};
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
 pub struct User {
     pub id: Uuid,
     pub username: String,
     pub email: String,
+    pub phone: Option<String>,
+    pub role: Role,
+    #[serde(skip_serializing, default)]
     pub password_hash: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl Table for User {
+    const TABLE: &'static str = "users";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("username", "TEXT"),
+        Column::new("email", "TEXT"),
+        Column::nullable("phone", "TEXT"),
+        Column::new("role", "TEXT"),
+        Column::new("password_hash", "TEXT"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+        Column::new("updated_at", "TIMESTAMPTZ"),
+    ];
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct CreateUser {
     pub username: String,
     pub email: String,
+    #[serde(default)]
+    pub phone: Option<String>,
     pub password: String,
 }
 
@@ -24,5 +45,6 @@ pub struct CreateUser {
 pub struct UpdateUser {
     pub username: Option<String>,
     pub email: Option<String>,
+    pub phone: Option<String>,
 }
 
@@ -2,8 +2,10 @@ use serde::{Deserialize, Serialize};
 
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use crate::schema::{Column, Table};
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
 pub struct Vendor {
     pub id: Uuid,
     pub name: String,
@@ -11,10 +13,27 @@ pub struct Vendor {
     pub email: String,
     pub phone: String,
     pub address: String,
+    /// Account that manages the vendor and receives its stock alerts.
+    pub user_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl Table for Vendor {
+    const TABLE: &'static str = "vendors";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("name", "TEXT"),
+        Column::new("contact_person", "TEXT"),
+        Column::new("email", "TEXT"),
+        Column::new("phone", "TEXT"),
+        Column::new("address", "TEXT"),
+        Column::nullable("user_id", "UUID"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+        Column::new("updated_at", "TIMESTAMPTZ"),
+    ];
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct CreateVendor {
     pub name: String,
@@ -22,6 +41,8 @@ pub struct CreateVendor {
     pub email: String,
     pub phone: String,
     pub address: String,
+    #[serde(default)]
+    pub user_id: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -31,5 +52,7 @@ pub struct UpdateVendor {
     pub email: Option<String>,
     pub phone: Option<String>,
     pub address: Option<String>,
+    #[serde(default)]
+    pub user_id: Option<Uuid>,
 }
 
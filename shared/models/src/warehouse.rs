@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use crate::schema::{Column, Table};
+
+/// A place stock is kept. Stock edited directly on a product is booked
+/// against the default warehouse.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Warehouse {
+    pub id: Uuid,
+    pub code: String,
+    pub name: String,
+    pub address: Option<String>,
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Table for Warehouse {
+    const TABLE: &'static str = "warehouses";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("id", "UUID"),
+        Column::new("code", "TEXT"),
+        Column::new("name", "TEXT"),
+        Column::nullable("address", "TEXT"),
+        Column::new("is_default", "BOOL"),
+        Column::new("created_at", "TIMESTAMPTZ"),
+        Column::new("updated_at", "TIMESTAMPTZ"),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CreateWarehouse {
+    pub code: String,
+    pub name: String,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+/// Setting `is_default` moves the default away from the current default
+/// warehouse; it cannot be cleared directly.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct UpdateWarehouse {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub is_default: Option<bool>,
+}
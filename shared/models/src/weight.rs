@@ -0,0 +1,184 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+/// An exact weight, kept as a whole number of milligrams so that prices
+/// derived from it do not pick up float rounding.
+///
+/// In JSON a weight is a number of grams such as `4.25`; in Postgres it is
+/// a `NUMERIC` number of grams with up to three decimals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Weight {
+    milligrams: i64,
+}
+
+impl Weight {
+    pub const fn from_milligrams(milligrams: i64) -> Self {
+        Self { milligrams }
+    }
+
+    pub const fn grams(grams: i64) -> Self {
+        Self::from_milligrams(grams * 1000)
+    }
+
+    pub const fn milligrams(&self) -> i64 {
+        self.milligrams
+    }
+
+    pub const fn is_positive(&self) -> bool {
+        self.milligrams > 0
+    }
+
+    /// Parses a decimal number of grams, e.g. `31.1`. More than three
+    /// decimals are rejected rather than rounded.
+    pub fn parse(grams: &str) -> Result<Self, String> {
+        let invalid = || format!("`{}` is not a weight in grams", grams);
+        let text = grams.trim();
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let fraction = fraction.trim_end_matches('0');
+        let digits_ok = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if whole.is_empty() || !digits_ok(whole) || !digits_ok(fraction) || fraction.len() > 3 {
+            return Err(invalid());
+        }
+
+        let fraction: i64 = format!("{:0<3}", fraction).parse().map_err(|_| invalid())?;
+        let milligrams = whole
+            .parse::<i64>()
+            .ok()
+            .and_then(|whole| whole.checked_mul(1000))
+            .and_then(|whole| whole.checked_add(fraction))
+            .ok_or_else(invalid)?;
+
+        Ok(Self::from_milligrams(if negative { -milligrams } else { milligrams }))
+    }
+
+    /// The weight in grams as a decimal number, e.g. `4.25`.
+    pub fn to_decimal_string(&self) -> String {
+        let units = self.milligrams.unsigned_abs();
+        let sign = if self.milligrams < 0 { "-" } else { "" };
+        let (whole, fraction) = (units / 1000, units % 1000);
+        if fraction == 0 {
+            format!("{}{}", sign, whole)
+        } else {
+            format!("{}{}.{}", sign, whole, format!("{:03}", fraction).trim_end_matches('0'))
+        }
+    }
+}
+
+impl Display for Weight {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{} g", self.to_decimal_string())
+    }
+}
+
+impl FromStr for Weight {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Weight::parse(value)
+    }
+}
+
+impl Serialize for Weight {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.milligrams as f64 / 1000.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Weight {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(WeightVisitor)
+    }
+}
+
+struct WeightVisitor;
+
+impl de::Visitor<'_> for WeightVisitor {
+    type Value = Weight;
+
+    fn expecting(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str("a number of grams")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Weight, E> {
+        value.checked_mul(1000).map(Weight::from_milligrams).ok_or_else(|| E::custom("weight is too large"))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Weight, E> {
+        i64::try_from(value).map_err(|_| E::custom("weight is too large")).and_then(|value| self.visit_i64(value))
+    }
+
+    /// Goes through the shortest decimal form of the float, so `4.25`
+    /// is exactly 4250 mg.
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Weight, E> {
+        if !value.is_finite() {
+            return Err(E::custom(format!("`{}` is not a weight in grams", value)));
+        }
+        Weight::parse(&value.to_string()).map_err(E::custom)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Weight, E> {
+        Weight::parse(value).map_err(E::custom)
+    }
+}
+
+#[cfg(feature = "sqlx")]
+mod sql {
+    use super::Weight;
+    use rust_decimal::Decimal;
+    use sqlx::encode::IsNull;
+    use sqlx::error::BoxDynError;
+    use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
+    use sqlx::{Decode, Encode, Postgres, Type};
+
+    impl Type<Postgres> for Weight {
+        fn type_info() -> PgTypeInfo {
+            <Decimal as Type<Postgres>>::type_info()
+        }
+
+        fn compatible(ty: &PgTypeInfo) -> bool {
+            <Decimal as Type<Postgres>>::compatible(ty)
+        }
+    }
+
+    impl Encode<'_, Postgres> for Weight {
+        fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+            <Decimal as Encode<Postgres>>::encode_by_ref(&Decimal::new(self.milligrams, 3), buf)
+        }
+    }
+
+    impl<'r> Decode<'r, Postgres> for Weight {
+        fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+            let grams = <Decimal as Decode<Postgres>>::decode(value)?;
+            let milligrams = grams * Decimal::from(1000);
+            if !milligrams.fract().is_zero() {
+                return Err(format!("{} g is finer than a milligram", grams).into());
+            }
+            Ok(Weight::from_milligrams(i64::try_from(milligrams)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weights_are_exact_grams() {
+        assert_eq!(Weight::parse("4.25"), Ok(Weight::from_milligrams(4_250)));
+        assert_eq!(Weight::parse("31.100"), Ok(Weight::from_milligrams(31_100)));
+        assert_eq!(Weight::parse("12"), Ok(Weight::grams(12)));
+        assert!(Weight::parse("1.0005").is_err());
+        assert!(Weight::parse("g").is_err());
+
+        assert_eq!(Weight::from_milligrams(4_250).to_string(), "4.25 g");
+        assert_eq!(Weight::from_milligrams(500).to_decimal_string(), "0.5");
+        assert_eq!(serde_json::from_str::<Weight>("4.25").unwrap(), Weight::from_milligrams(4_250));
+        assert_eq!(serde_json::from_str::<Weight>("\"0.1\"").unwrap(), Weight::from_milligrams(100));
+        assert_eq!(serde_json::to_string(&Weight::from_milligrams(4_250)).unwrap(), "4.25");
+    }
+}
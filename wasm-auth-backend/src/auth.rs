@@ -0,0 +1,43 @@
+use actix_web::{dev::Payload, http::header, web, FromRequest, HttpRequest};
+use std::future::{ready, Ready};
+use std::ops::Deref;
+use models::auth::{bearer_token, AccessError, Caller};
+use crate::{error::ServiceError, jwt};
+
+/// The caller identified by a valid `Authorization: Bearer <jwt>` header.
+/// Add it as a handler argument to require authentication; the permission
+/// checks are those of [`Caller`].
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser(Caller);
+
+impl Deref for AuthenticatedUser {
+    type Target = Caller;
+
+    fn deref(&self) -> &Caller {
+        &self.0
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = ServiceError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(authenticate(req))
+    }
+}
+
+fn authenticate(req: &HttpRequest) -> Result<AuthenticatedUser, ServiceError> {
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(bearer_token)
+        .ok_or(AccessError::MissingToken)?;
+    let keys = req
+        .app_data::<web::Data<jwt::KeyRing>>()
+        .ok_or_else(|| ServiceError::InternalServerError("JWT key ring is not configured".to_string()))?;
+    let claims = keys.decode_token(token)?;
+
+    Ok(AuthenticatedUser(Caller::from_claims(claims)?))
+}
@@ -1,4 +1,5 @@
 use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use models::auth::AccessError;
 use serde::Serialize;
 use sqlx::Error as SqlxError;
 use std::fmt::{Display, Formatter, Result as FmtResult};
@@ -9,6 +10,8 @@ pub enum ServiceError {
     InternalServerError(String),
     BadRequest(String),
     Unauthorized(String),
+    Forbidden(String),
+    TooManyRequests(String),
     DuplicateEntry(String),
 }
 
@@ -29,6 +32,10 @@ impl ResponseError for ServiceError {
                 .json(serde_json::json!({ "message": msg })),
             ServiceError::Unauthorized(msg) => HttpResponse::build(StatusCode::UNAUTHORIZED)
                 .json(serde_json::json!({ "message": msg })),
+            ServiceError::Forbidden(msg) => HttpResponse::build(StatusCode::FORBIDDEN)
+                .json(serde_json::json!({ "message": msg })),
+            ServiceError::TooManyRequests(msg) => HttpResponse::build(StatusCode::TOO_MANY_REQUESTS)
+                .json(serde_json::json!({ "message": msg })),
             ServiceError::DuplicateEntry(msg) => HttpResponse::build(StatusCode::CONFLICT)
                 .json(serde_json::json!({ "message": msg })),
         }
@@ -43,6 +50,12 @@ impl From<SqlxError> for ServiceError {
             SqlxError::Database(db_err) if db_err.is_unique_violation() => {
                 ServiceError::DuplicateEntry("A record with this unique identifier already exists.".to_string())
             }
+            SqlxError::Database(db_err) if db_err.is_foreign_key_violation() => {
+                ServiceError::BadRequest("The referenced record does not exist or is still in use.".to_string())
+            }
+            SqlxError::Database(db_err) if db_err.is_check_violation() => {
+                ServiceError::BadRequest("The request violates a data constraint.".to_string())
+            }
             _ => ServiceError::InternalServerError("Database error occurred".to_string()),
         }
     }
@@ -55,6 +68,17 @@ impl From<argon2::Error> for ServiceError {
     }
 }
 
+impl From<AccessError> for ServiceError {
+    fn from(err: AccessError) -> Self {
+        match err {
+            AccessError::MissingToken | AccessError::InvalidSubject => ServiceError::Unauthorized(err.to_string()),
+            AccessError::MissingPermission(_) => ServiceError::Forbidden(err.to_string()),
+        }
+    }
+}
+
+/// For errors from decoding and validating tokens the client presented;
+/// signing errors are mapped where tokens are issued.
 impl From<jsonwebtoken::errors::Error> for ServiceError {
     fn from(err: jsonwebtoken::errors::Error) -> Self {
         log::error!("JWT error: {:?}", err);
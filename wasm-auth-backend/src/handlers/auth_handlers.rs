@@ -0,0 +1,87 @@
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use serde::Deserialize;
+use config::AppConfig;
+use models::{auth::{EmailOrPhone, LoginRequest, OtpRequest, OtpVerifyRequest, RefreshTokenRequest, RegisterRequest}, identity::{SocialLoginRequest, SocialProvider}};
+use crate::{auth::AuthenticatedUser, jwt::KeyRing, otp::OtpIssuer, service, social::SocialVerifier, error::ServiceError};
+
+pub async fn login(pool: web::Data<PgPool>, keys: web::Data<KeyRing>, config: web::Data<AppConfig>, request: web::Json<LoginRequest>) -> Result<HttpResponse, ServiceError> {
+    let response = service::login(&pool, &keys, &config.login, request.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+pub async fn register(pool: web::Data<PgPool>, keys: web::Data<KeyRing>, request: web::Json<RegisterRequest>) -> Result<HttpResponse, ServiceError> {
+    let response = service::register(&pool, &keys, request.into_inner()).await?;
+    Ok(HttpResponse::Created().json(response))
+}
+
+pub async fn social_login(pool: web::Data<PgPool>, keys: web::Data<KeyRing>, verifier: web::Data<SocialVerifier>, request: web::Json<SocialLoginRequest>) -> Result<HttpResponse, ServiceError> {
+    let response = service::social_login(&pool, &keys, &verifier, request.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+pub async fn get_linked_identities(pool: web::Data<PgPool>, user: AuthenticatedUser) -> Result<HttpResponse, ServiceError> {
+    let identities = service::get_linked_identities(&pool, user.user_id).await?;
+    Ok(HttpResponse::Ok().json(identities))
+}
+
+pub async fn link_identity(pool: web::Data<PgPool>, verifier: web::Data<SocialVerifier>, user: AuthenticatedUser, request: web::Json<SocialLoginRequest>) -> Result<HttpResponse, ServiceError> {
+    let identity = service::link_identity(&pool, &verifier, user.user_id, request.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(identity))
+}
+
+pub async fn unlink_identity(pool: web::Data<PgPool>, user: AuthenticatedUser, provider: web::Path<SocialProvider>) -> Result<HttpResponse, ServiceError> {
+    service::unlink_identity(&pool, user.user_id, provider.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn refresh_token(pool: web::Data<PgPool>, keys: web::Data<KeyRing>, request: web::Json<RefreshTokenRequest>) -> Result<HttpResponse, ServiceError> {
+    let response = service::refresh(&pool, &keys, request.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+pub async fn logout(pool: web::Data<PgPool>, user: AuthenticatedUser) -> Result<HttpResponse, ServiceError> {
+    service::logout(&pool, user.user_id).await?;
+    // The frontend deserializes the body as `()`, which expects JSON `null`.
+    Ok(HttpResponse::Ok().json(()))
+}
+
+pub async fn get_profile(pool: web::Data<PgPool>, user: AuthenticatedUser) -> Result<HttpResponse, ServiceError> {
+    let user = service::get_user_by_id(&pool, user.user_id).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
+
+pub async fn request_otp(pool: web::Data<PgPool>, issuer: web::Data<OtpIssuer>, request: web::Json<OtpRequest>) -> Result<HttpResponse, ServiceError> {
+    let target = EmailOrPhone::parse(&request.email_or_phone);
+    service::request_otp(&pool, &issuer, &target).await?;
+    Ok(HttpResponse::Accepted().json(()))
+}
+
+pub async fn verify_otp(pool: web::Data<PgPool>, issuer: web::Data<OtpIssuer>, request: web::Json<OtpVerifyRequest>) -> Result<HttpResponse, ServiceError> {
+    let target = EmailOrPhone::parse(&request.email_or_phone);
+    service::verify_otp(&pool, &issuer, &target, &request.code).await?;
+    Ok(HttpResponse::Ok().json(()))
+}
+
+pub async fn get_permissions(user: AuthenticatedUser) -> HttpResponse {
+    HttpResponse::Ok().json(&user.claims.permissions)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PermissionQuery {
+    pub permission: String,
+}
+
+/// Unknown permission names are simply not held, so they answer `false`.
+pub async fn check_permission(user: AuthenticatedUser, web::Query(query): web::Query<PermissionQuery>) -> HttpResponse {
+    let granted = query
+        .permission
+        .parse()
+        .map(|permission| user.has_permission(permission))
+        .unwrap_or(false);
+    HttpResponse::Ok().json(granted)
+}
+
+pub async fn jwks(keys: web::Data<KeyRing>) -> HttpResponse {
+    HttpResponse::Ok().json(keys.jwks())
+}
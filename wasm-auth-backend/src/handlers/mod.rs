@@ -1,3 +1,36 @@
+use actix_web::web;
+
+pub mod auth_handlers;
 pub mod health_handler;
 pub mod user_handlers;
 
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("/health", web::get().to(health_handler::health_check))
+        .route("/.well-known/jwks.json", web::get().to(auth_handlers::jwks))
+        .service(
+            web::scope("/api/auth")
+                .route("/login", web::post().to(auth_handlers::login))
+                .route("/register", web::post().to(auth_handlers::register))
+                .route("/social", web::post().to(auth_handlers::social_login))
+                .route("/identities", web::get().to(auth_handlers::get_linked_identities))
+                .route("/identities", web::post().to(auth_handlers::link_identity))
+                .route("/identities/{provider}", web::delete().to(auth_handlers::unlink_identity))
+                .route("/refresh", web::post().to(auth_handlers::refresh_token))
+                .route("/logout", web::post().to(auth_handlers::logout))
+                .route("/otp", web::post().to(auth_handlers::request_otp))
+                .route("/otp/verify", web::post().to(auth_handlers::verify_otp))
+                .route("/profile", web::get().to(auth_handlers::get_profile))
+                .route("/permissions", web::get().to(auth_handlers::get_permissions))
+                .route("/permissions/check", web::get().to(auth_handlers::check_permission)),
+        )
+        .service(
+            web::scope("/api/users")
+                .route("", web::post().to(user_handlers::create_user))
+                .route("", web::get().to(user_handlers::get_users))
+                .route("/roles", web::get().to(user_handlers::get_roles))
+                .route("/{id}", web::get().to(user_handlers::get_user_by_id))
+                .route("/{id}", web::put().to(user_handlers::update_user))
+                .route("/{id}", web::delete().to(user_handlers::delete_user))
+                .route("/{id}/role", web::put().to(user_handlers::update_user_role)),
+        );
+}
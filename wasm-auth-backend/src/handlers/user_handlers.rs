@@ -1,31 +1,47 @@
 use actix_web::{web, HttpResponse};
 use sqlx::PgPool;
 use uuid::Uuid;
-use models::{user::{CreateUser, UpdateUser}, pagination::Pagination};
-use crate::{service, error::ServiceError};
+use models::{role::{Permission, Role, UpdateUserRole}, user::{CreateUser, UpdateUser}, pagination::Pagination};
+use crate::{auth::AuthenticatedUser, service, error::ServiceError};
 
-pub async fn create_user(pool: web::Data<PgPool>, new_user: web::Json<CreateUser>) -> Result<HttpResponse, ServiceError> {
+pub async fn create_user(pool: web::Data<PgPool>, auth: AuthenticatedUser, new_user: web::Json<CreateUser>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::UsersWrite)?;
     let user = service::create_user(&pool, new_user.into_inner()).await?;
     Ok(HttpResponse::Created().json(user))
 }
 
-pub async fn get_users(pool: web::Data<PgPool>, web::Query(pagination): web::Query<Pagination>) -> Result<HttpResponse, ServiceError> {
+pub async fn get_users(pool: web::Data<PgPool>, auth: AuthenticatedUser, web::Query(pagination): web::Query<Pagination>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::UsersRead)?;
     let users = service::get_users(&pool, pagination).await?;
     Ok(HttpResponse::Ok().json(users))
 }
 
-pub async fn get_user_by_id(pool: web::Data<PgPool>, user_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
-    let user = service::get_user_by_id(&pool, user_id.into_inner()).await?;
+pub async fn get_user_by_id(pool: web::Data<PgPool>, auth: AuthenticatedUser, user_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let user_id = user_id.into_inner();
+    auth.require_owner_or(user_id, Permission::UsersRead)?;
+    let user = service::get_user_by_id(&pool, user_id).await?;
     Ok(HttpResponse::Ok().json(user))
 }
 
-pub async fn update_user(pool: web::Data<PgPool>, user_id: web::Path<Uuid>, updated_user: web::Json<UpdateUser>) -> Result<HttpResponse, ServiceError> {
-    let user = service::update_user(&pool, user_id.into_inner(), updated_user.into_inner()).await?;
+pub async fn update_user(pool: web::Data<PgPool>, auth: AuthenticatedUser, user_id: web::Path<Uuid>, updated_user: web::Json<UpdateUser>) -> Result<HttpResponse, ServiceError> {
+    let user_id = user_id.into_inner();
+    auth.require_owner_or(user_id, Permission::UsersWrite)?;
+    let user = service::update_user(&pool, user_id, updated_user.into_inner()).await?;
     Ok(HttpResponse::Ok().json(user))
 }
 
-pub async fn delete_user(pool: web::Data<PgPool>, user_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+pub async fn update_user_role(pool: web::Data<PgPool>, auth: AuthenticatedUser, user_id: web::Path<Uuid>, request: web::Json<UpdateUserRole>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::UsersManageRoles)?;
+    let user = service::update_user_role(&pool, user_id.into_inner(), request.into_inner().role).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
+
+pub async fn get_roles(_auth: AuthenticatedUser) -> HttpResponse {
+    HttpResponse::Ok().json(Role::ALL)
+}
+
+pub async fn delete_user(pool: web::Data<PgPool>, auth: AuthenticatedUser, user_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::UsersDelete)?;
     service::delete_user(&pool, user_id.into_inner()).await?;
     Ok(HttpResponse::NoContent().finish())
 }
-
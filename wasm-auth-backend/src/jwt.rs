@@ -1,38 +1,254 @@
-use jsonwebtoken::{encode, decode, Header, Algorithm, Validation, EncodingKey, DecodingKey};
-use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use jsonwebtoken::{encode, decode, decode_header, Header, Algorithm, Validation, EncodingKey, DecodingKey};
+use jsonwebtoken::errors::{Error as JwtError, ErrorKind};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, OctetKeyPairParameters,
+    OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rsa::{pkcs1::DecodeRsaPublicKey, pkcs8::DecodePublicKey, traits::PublicKeyParts, RsaPublicKey};
 use chrono::{Utc, Duration};
-use wasm_bindgen::prelude::*;
-
-// Our claims struct, it needs to derive `Serialize` and `Deserialize`
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Claims {
-    pub sub: String, // Subject (user ID)
-    pub exp: usize,  // Expiration time
-    pub iat: usize,  // Issued at
+use config::JwtConfig;
+use models::role::Role;
+
+pub use models::auth::Claims;
+
+struct VerificationKey {
+    algorithm: Algorithm,
+    decoding_key: DecodingKey,
+    jwk: Option<Jwk>,
 }
 
-const JWT_SECRET: &[u8] = b"supersecretjwtkey"; // TODO: Load from config
+/// The active signing key plus every key still accepted for verification,
+/// indexed by `kid`. Built once at startup from [`JwtConfig`].
+pub struct KeyRing {
+    key_id: String,
+    algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    verification_keys: HashMap<String, VerificationKey>,
+    issuer: String,
+    audience: String,
+    access_token_ttl: Duration,
+    refresh_token_ttl: Duration,
+}
 
-#[wasm_bindgen]
-pub fn generate_token(user_id: String) -> Result<String, JsValue> {
-    let now = Utc::now();
-    let expires_at = now + Duration::hours(24);
+impl KeyRing {
+    pub fn from_config(config: &JwtConfig) -> Result<Self, String> {
+        let algorithm = parse_algorithm(&config.algorithm)?;
+        let signing_key = config
+            .signing_key
+            .as_deref()
+            .ok_or("JWT_SIGNING_KEY must be set")?;
 
-    let claims = Claims {
-        sub: user_id,
-        iat: now.timestamp() as usize,
-        exp: expires_at.timestamp() as usize,
-    };
+        let encoding_key = match algorithm {
+            Algorithm::HS256 => EncodingKey::from_secret(signing_key.as_bytes()),
+            Algorithm::RS256 => EncodingKey::from_rsa_pem(&read_key_material(signing_key)?)
+                .map_err(|e| format!("Invalid RSA signing key: {}", e))?,
+            _ => EncodingKey::from_ed_pem(&read_key_material(signing_key)?)
+                .map_err(|e| format!("Invalid EdDSA signing key: {}", e))?,
+        };
+
+        let active_key = match algorithm {
+            Algorithm::HS256 => signing_key.to_string(),
+            _ => config
+                .verification_key
+                .clone()
+                .ok_or("JWT_VERIFICATION_KEY must be set for asymmetric algorithms")?,
+        };
+
+        let mut verification_keys = HashMap::new();
+        verification_keys.insert(config.key_id.clone(), verification_key(&config.key_id, algorithm, &active_key)?);
+
+        for previous in &config.previous_keys {
+            let algorithm = parse_algorithm(&previous.algorithm)?;
+            let key = verification_key(&previous.key_id, algorithm, &previous.key)?;
+            verification_keys.entry(previous.key_id.clone()).or_insert(key);
+        }
+
+        Ok(Self {
+            key_id: config.key_id.clone(),
+            algorithm,
+            encoding_key,
+            verification_keys,
+            issuer: config.issuer.clone(),
+            audience: config.audience.clone(),
+            access_token_ttl: Duration::seconds(config.access_token_ttl_seconds),
+            refresh_token_ttl: Duration::days(config.refresh_token_ttl_days),
+        })
+    }
+
+    pub fn access_token_ttl(&self) -> Duration {
+        self.access_token_ttl
+    }
+
+    pub fn refresh_token_ttl(&self) -> Duration {
+        self.refresh_token_ttl
+    }
+
+    /// The role's permissions are embedded in the token, so a role change
+    /// takes effect on the user's next refresh.
+    pub fn generate_token(&self, user_id: String, role: Role) -> Result<String, JwtError> {
+        let now = Utc::now();
+        let expires_at = now + self.access_token_ttl;
+
+        let claims = Claims {
+            sub: user_id,
+            iat: now.timestamp() as usize,
+            exp: expires_at.timestamp() as usize,
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+            role,
+            permissions: role.permissions().to_vec(),
+        };
+
+        let mut header = Header::new(self.algorithm);
+        header.kid = Some(self.key_id.clone());
+
+        encode(&header, &claims, &self.encoding_key)
+    }
+
+    /// Validates the signature with the key named by the token's `kid`
+    /// (the active key when there is none), then expiry, issuer and audience.
+    pub fn decode_token(&self, token: &str) -> Result<Claims, JwtError> {
+        let header = decode_header(token)?;
+        let key_id = header.kid.as_deref().unwrap_or(&self.key_id);
+        let key = self
+            .verification_keys
+            .get(key_id)
+            .ok_or_else(|| JwtError::from(ErrorKind::InvalidSignature))?;
+
+        let mut validation = Validation::new(key.algorithm);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+
+        decode::<Claims>(token, &key.decoding_key, &validation).map(|data| data.claims)
+    }
 
-    encode(&Header::default(), &claims, &EncodingKey::from_secret(JWT_SECRET))
-        .map_err(|e| JsValue::from_str(&format!("Failed to generate token: {}", e)))
+    /// Public keys for the JWKS endpoint. HS256 secrets are never published.
+    pub fn jwks(&self) -> JwkSet {
+        let mut keys: Vec<Jwk> = self
+            .verification_keys
+            .values()
+            .filter_map(|key| key.jwk.clone())
+            .collect();
+        keys.sort_by(|a, b| a.common.key_id.cmp(&b.common.key_id));
+
+        JwkSet { keys }
+    }
+}
+
+fn parse_algorithm(name: &str) -> Result<Algorithm, String> {
+    match Algorithm::from_str(name) {
+        Ok(algorithm @ (Algorithm::HS256 | Algorithm::RS256 | Algorithm::EdDSA)) => Ok(algorithm),
+        _ => Err(format!("Unsupported JWT algorithm `{}`, expected HS256, RS256 or EdDSA", name)),
+    }
+}
+
+/// Accepts an inline PEM document or a path to one.
+fn read_key_material(value: &str) -> Result<Vec<u8>, String> {
+    if value.trim_start().starts_with("-----BEGIN") {
+        Ok(value.as_bytes().to_vec())
+    } else {
+        std::fs::read(value).map_err(|e| format!("Failed to read key file `{}`: {}", value, e))
+    }
 }
 
-#[wasm_bindgen]
-pub fn validate_token(token: String) -> Result<String, JsValue> {
-    let validation = Validation::new(Algorithm::HS256);
-    decode::<Claims>(&token, &DecodingKey::from_secret(JWT_SECRET), &validation)
-        .map(|data| data.claims.sub)
-        .map_err(|e| JsValue::from_str(&format!("Failed to validate token: {}", e)))
+fn verification_key(key_id: &str, algorithm: Algorithm, key: &str) -> Result<VerificationKey, String> {
+    let (decoding_key, parameters) = match algorithm {
+        Algorithm::HS256 => (DecodingKey::from_secret(key.as_bytes()), None),
+        Algorithm::RS256 => {
+            let pem = read_key_material(key)?;
+            let pem = String::from_utf8_lossy(&pem);
+            let public_key = RsaPublicKey::from_public_key_pem(&pem)
+                .or_else(|_| RsaPublicKey::from_pkcs1_pem(&pem))
+                .map_err(|e| format!("Invalid RSA public key `{}`: {}", key_id, e))?;
+            let n = URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be());
+            let e = URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be());
+            let decoding_key = DecodingKey::from_rsa_components(&n, &e)
+                .map_err(|e| format!("Invalid RSA public key `{}`: {}", key_id, e))?;
+            (decoding_key, Some(AlgorithmParameters::RSA(RSAKeyParameters { key_type: RSAKeyType::RSA, n, e })))
+        }
+        _ => {
+            let pem = pem::parse(read_key_material(key)?)
+                .map_err(|e| format!("Invalid EdDSA public key `{}`: {}", key_id, e))?;
+            // An Ed25519 SubjectPublicKeyInfo is a fixed 12 byte prefix followed by the raw key.
+            let raw = pem
+                .contents
+                .strip_prefix(&[0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00][..])
+                .filter(|raw| raw.len() == 32)
+                .ok_or_else(|| format!("EdDSA public key `{}` is not an Ed25519 SubjectPublicKeyInfo", key_id))?;
+            let x = URL_SAFE_NO_PAD.encode(raw);
+            (
+                DecodingKey::from_ed_der(raw),
+                Some(AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x,
+                })),
+            )
+        }
+    };
+
+    let jwk = parameters.map(|algorithm_parameters| Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            algorithm: Some(algorithm),
+            key_id: Some(key_id.to_string()),
+            ..Default::default()
+        },
+        algorithm: algorithm_parameters,
+    });
+
+    Ok(VerificationKey { algorithm, decoding_key, jwk })
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::JwtKeyConfig;
+
+    fn hs256_config(key_id: &str, secret: &str) -> JwtConfig {
+        JwtConfig {
+            key_id: key_id.to_string(),
+            signing_key: Some(secret.to_string()),
+            ..JwtConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_rotated_key_still_validates_old_tokens() {
+        let old_ring = KeyRing::from_config(&hs256_config("2026-01", "old-secret")).unwrap();
+        let old_token = old_ring.generate_token("user-1".to_string(), Role::Customer).unwrap();
+
+        let mut rotated = hs256_config("2026-02", "new-secret");
+        let rotated_ring = KeyRing::from_config(&rotated).unwrap();
+        assert!(rotated_ring.decode_token(&old_token).is_err());
+
+        rotated.previous_keys.push(JwtKeyConfig {
+            key_id: "2026-01".to_string(),
+            algorithm: "HS256".to_string(),
+            key: "old-secret".to_string(),
+        });
+        let rotated_ring = KeyRing::from_config(&rotated).unwrap();
+        assert_eq!(rotated_ring.decode_token(&old_token).unwrap().sub, "user-1");
+
+        let new_token = rotated_ring.generate_token("user-2".to_string(), Role::Customer).unwrap();
+        assert_eq!(decode_header(&new_token).unwrap().kid.as_deref(), Some("2026-02"));
+        assert!(old_ring.decode_token(&new_token).is_err());
+        assert!(rotated_ring.jwks().keys.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_foreign_audience() {
+        let ring = KeyRing::from_config(&hs256_config("k1", "secret")).unwrap();
+        let other = KeyRing::from_config(&JwtConfig {
+            audience: "someone-else".to_string(),
+            ..hs256_config("k1", "secret")
+        })
+        .unwrap();
+
+        let token = other.generate_token("user-1".to_string(), Role::Customer).unwrap();
+        assert!(ring.decode_token(&token).is_err());
+    }
+}
@@ -1,14 +1,34 @@
 use wasm_bindgen::prelude::*;
+use config::JwtConfig;
+use models::role::Role;
 
-mod jwt;
+pub mod auth;
+pub mod error;
+pub mod handlers;
+pub mod jwt;
+pub mod otp;
+pub mod service;
+pub mod social;
 
-#[wasm_bindgen]
-pub fn generate_auth_token(user_id: String) -> Result<String, JsValue> {
-    jwt::generate_token(user_id)
+fn hs256_key_ring(secret: String) -> Result<jwt::KeyRing, JsValue> {
+    let config = JwtConfig {
+        signing_key: Some(secret),
+        ..JwtConfig::default()
+    };
+    jwt::KeyRing::from_config(&config).map_err(|e| JsValue::from_str(&e))
 }
 
 #[wasm_bindgen]
-pub fn validate_auth_token(token: String) -> Result<String, JsValue> {
-    jwt::validate_token(token)
+pub fn generate_auth_token(user_id: String, secret: String) -> Result<String, JsValue> {
+    hs256_key_ring(secret)?
+        .generate_token(user_id, Role::Customer)
+        .map_err(|e| JsValue::from_str(&format!("Failed to generate token: {}", e)))
 }
 
+#[wasm_bindgen]
+pub fn validate_auth_token(token: String, secret: String) -> Result<String, JsValue> {
+    hs256_key_ring(secret)?
+        .decode_token(&token)
+        .map(|claims| claims.sub)
+        .map_err(|e| JsValue::from_str(&format!("Failed to validate token: {}", e)))
+}
@@ -1 +1,86 @@
+use actix_web::{middleware::Logger, web, App, HttpServer};
+use config::AppConfig;
+use sqlx::postgres::PgPoolOptions;
+use wasm_auth_backend::{handlers, jwt::KeyRing, otp::OtpIssuer, service, social::SocialVerifier};
 
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    dotenvy::dotenv().ok();
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info"));
+
+    let config = AppConfig::from_env();
+    let keys = web::Data::new(KeyRing::from_config(&config.jwt).map_err(std::io::Error::other)?);
+    let otp = web::Data::new(OtpIssuer::from_config(&config.otp));
+    let social = web::Data::new(SocialVerifier::from_config(&config.social));
+
+    let pool = PgPoolOptions::new()
+        .max_connections(config.database_max_connections)
+        .connect(&config.database_url)
+        .await
+        .map_err(|e| std::io::Error::other(format!("Failed to connect to database: {}", e)))?;
+
+    // `<binary> migrate [up|down [version]|status|verify]` manages the schema and exits.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        let result = db::run_command(&pool, &args[1..]).await;
+        pool.close().await;
+        return result.map_err(std::io::Error::other);
+    }
+
+    // `<binary> set-role <email> <role>` grants a role, e.g. to bootstrap the first admin.
+    if args.first().map(String::as_str) == Some("set-role") {
+        let result = set_role(&pool, &args[1..]).await;
+        pool.close().await;
+        return result;
+    }
+
+    if config.run_migrations {
+        db::run_migrations(&pool).await.map_err(std::io::Error::other)?;
+    }
+    db::verify_schema(&pool).await.map_err(std::io::Error::other)?;
+
+    log::info!("Starting User Management Service on {}:{}", config.server_host, config.server_port);
+
+    let server_pool = pool.clone();
+    let server_config = web::Data::new(config.clone());
+
+    // actix-web stops accepting connections on SIGINT/SIGTERM and lets
+    // in-flight requests finish within the shutdown timeout.
+    HttpServer::new(move || {
+        App::new()
+            .wrap(Logger::default())
+            .app_data(web::Data::new(server_pool.clone()))
+            .app_data(server_config.clone())
+            .app_data(keys.clone())
+            .app_data(otp.clone())
+            .app_data(social.clone())
+            .configure(handlers::config)
+    })
+    .bind((config.server_host.as_str(), config.server_port))?
+    .shutdown_timeout(30)
+    .run()
+    .await?;
+
+    log::info!("User Management Service stopped, closing database pool");
+    pool.close().await;
+
+    Ok(())
+}
+
+async fn set_role(pool: &sqlx::PgPool, args: &[String]) -> std::io::Result<()> {
+    let [email, role] = args else {
+        return Err(std::io::Error::other("usage: set-role <email> <role>"));
+    };
+    let role = role.parse().map_err(std::io::Error::other)?;
+    let user = service::get_user_by_email(pool, email)
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))?
+        .ok_or_else(|| std::io::Error::other(format!("No user with email `{}`", email)))?;
+
+    service::update_user_role(pool, user.id, role)
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+    log::info!("Granted role `{}` to {}", role, email);
+
+    Ok(())
+}
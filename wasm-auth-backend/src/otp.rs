@@ -0,0 +1,157 @@
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use rand::{Rng, RngCore};
+use sha2::Sha256;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use config::OtpConfig;
+use models::auth::EmailOrPhone;
+
+/// Delivers a verification code to its target. Implementations plug in an
+/// SMS gateway or mail server; [`OutboxSender`] stands in for local testing.
+#[async_trait]
+pub trait OtpSender: Send + Sync {
+    async fn send(&self, target: &EmailOrPhone, code: &str) -> Result<(), String>;
+}
+
+/// Logs every code and, when configured, appends it to an outbox file so
+/// codes can be read back without a real SMS or email provider.
+pub struct OutboxSender {
+    outbox: Option<PathBuf>,
+}
+
+impl OutboxSender {
+    pub fn new(outbox: Option<PathBuf>) -> Self {
+        Self { outbox }
+    }
+}
+
+#[async_trait]
+impl OtpSender for OutboxSender {
+    async fn send(&self, target: &EmailOrPhone, code: &str) -> Result<(), String> {
+        log::info!("Verification code for {}: {}", target.as_str(), code);
+
+        if let Some(path) = &self.outbox {
+            let line = format!("{}\t{}\t{}\n", Utc::now().to_rfc3339(), target.normalized(), code);
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(line.as_bytes()))
+                .map_err(|e| format!("Failed to write OTP outbox `{}`: {}", path.display(), e))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Code generation, hashing and delivery settings, built once at startup
+/// from [`OtpConfig`]. The database side lives in `service::otp`.
+pub struct OtpIssuer {
+    secret: Vec<u8>,
+    sender: Arc<dyn OtpSender>,
+    code_ttl: Duration,
+    max_attempts: i32,
+    resend_interval: Duration,
+    max_sends_per_hour: i64,
+}
+
+impl OtpIssuer {
+    pub fn new(config: &OtpConfig, sender: Arc<dyn OtpSender>) -> Self {
+        let secret = match &config.secret {
+            Some(secret) => secret.as_bytes().to_vec(),
+            None => {
+                log::warn!("OTP_SECRET is not set, using a random key; codes will not survive a restart");
+                let mut secret = vec![0u8; 32];
+                rand::thread_rng().fill_bytes(&mut secret);
+                secret
+            }
+        };
+
+        Self {
+            secret,
+            sender,
+            code_ttl: Duration::seconds(config.code_ttl_seconds),
+            max_attempts: config.max_attempts,
+            resend_interval: Duration::seconds(config.resend_interval_seconds),
+            max_sends_per_hour: config.max_sends_per_hour,
+        }
+    }
+
+    /// An issuer delivering through [`OutboxSender`] to the configured outbox file.
+    pub fn from_config(config: &OtpConfig) -> Self {
+        let sender = OutboxSender::new(config.outbox_path.clone().map(PathBuf::from));
+        Self::new(config, Arc::new(sender))
+    }
+
+    pub fn code_ttl(&self) -> Duration {
+        self.code_ttl
+    }
+
+    pub fn max_attempts(&self) -> i32 {
+        self.max_attempts
+    }
+
+    pub fn resend_interval(&self) -> Duration {
+        self.resend_interval
+    }
+
+    pub fn max_sends_per_hour(&self) -> i64 {
+        self.max_sends_per_hour
+    }
+
+    pub fn sender(&self) -> &dyn OtpSender {
+        self.sender.as_ref()
+    }
+
+    /// A uniformly random 4-digit code, leading zeros included.
+    pub fn generate_code(&self) -> String {
+        format!("{:04}", rand::thread_rng().gen_range(0..10_000))
+    }
+
+    /// Keyed hash binding the code to its target, so a leaked table cannot be
+    /// brute-forced without the server secret.
+    pub fn hash_code(&self, target: &str, code: &str) -> String {
+        URL_SAFE_NO_PAD.encode(self.mac(target, code).finalize().into_bytes())
+    }
+
+    /// Constant-time comparison against a stored [`hash_code`](Self::hash_code).
+    pub fn verify_code(&self, target: &str, code: &str, stored_hash: &str) -> bool {
+        URL_SAFE_NO_PAD
+            .decode(stored_hash)
+            .is_ok_and(|expected| self.mac(target, code).verify_slice(&expected).is_ok())
+    }
+
+    fn mac(&self, target: &str, code: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(target.as_bytes());
+        mac.update(b":");
+        mac.update(code.as_bytes());
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_hash_is_bound_to_target() {
+        let issuer = OtpIssuer::from_config(&OtpConfig {
+            secret: Some("secret".to_string()),
+            ..OtpConfig::default()
+        });
+
+        let code = issuer.generate_code();
+        assert_eq!(code.len(), 4);
+        assert!(code.chars().all(|c| c.is_ascii_digit()));
+
+        let hash = issuer.hash_code("+989123456789", &code);
+        assert!(issuer.verify_code("+989123456789", &code, &hash));
+        assert!(!issuer.verify_code("+989120000000", &code, &hash));
+        assert!(!issuer.verify_code("+989123456789", "abcd", &hash));
+    }
+}
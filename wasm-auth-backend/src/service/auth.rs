@@ -0,0 +1,213 @@
+use sqlx::{FromRow, PgExecutor, PgPool};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use uuid::Uuid;
+use chrono::{DateTime, Duration, Utc};
+use config::LoginConfig;
+use models::{auth::{AuthResponse, LoginRequest, RefreshTokenRequest, RegisterRequest}, user::{CreateUser, User}};
+use crate::{error::ServiceError, jwt::KeyRing};
+use super::user::{available_username, create_user, find_user_by_login, get_user_by_id, username_taken, verify_password};
+
+#[derive(Debug, FromRow)]
+struct StoredRefreshToken {
+    id: Uuid,
+    user_id: Uuid,
+    family_id: Uuid,
+    expires_at: DateTime<Utc>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+pub async fn login(pool: &PgPool, keys: &KeyRing, policy: &LoginConfig, request: LoginRequest) -> Result<AuthResponse, ServiceError> {
+    let user = authenticate(pool, policy, &request.email, &request.password).await?;
+    issue_tokens(pool, keys, user).await
+}
+
+/// Checks the password of the user identified by email, username or phone
+/// number. Every wrong password counts towards a lockout; while the account
+/// is locked even the right password is refused.
+pub async fn authenticate(pool: &PgPool, policy: &LoginConfig, identifier: &str, password: &str) -> Result<User, ServiceError> {
+    let user = find_user_by_login(pool, identifier)
+        .await?
+        .ok_or_else(invalid_credentials)?;
+
+    let locked_until: Option<DateTime<Utc>> = sqlx::query_scalar("SELECT locked_until FROM users WHERE id = $1")
+        .bind(user.id)
+        .fetch_one(pool)
+        .await?;
+    if let Some(locked_until) = locked_until.filter(|until| *until > Utc::now()) {
+        return Err(account_locked(locked_until));
+    }
+
+    if !verify_password(&user.password_hash, password).await? {
+        let locked_until: Option<DateTime<Utc>> = sqlx::query_scalar(
+            "UPDATE users SET
+                failed_login_attempts = CASE WHEN failed_login_attempts + 1 >= $1 THEN 0 ELSE failed_login_attempts + 1 END,
+                locked_until = CASE WHEN failed_login_attempts + 1 >= $1 THEN $2 ELSE locked_until END
+             WHERE id = $3 RETURNING locked_until"
+        )
+        .bind(policy.max_failed_attempts)
+        .bind(Utc::now() + Duration::seconds(policy.lockout_seconds))
+        .bind(user.id)
+        .fetch_one(pool)
+        .await?;
+
+        return match locked_until.filter(|until| *until > Utc::now()) {
+            Some(locked_until) => {
+                log::warn!("Locked user {} after {} failed sign-ins", user.id, policy.max_failed_attempts);
+                Err(account_locked(locked_until))
+            }
+            None => Err(invalid_credentials()),
+        };
+    }
+
+    sqlx::query("UPDATE users SET failed_login_attempts = 0, locked_until = NULL WHERE id = $1")
+        .bind(user.id)
+        .execute(pool)
+        .await?;
+
+    Ok(user)
+}
+
+pub async fn register(pool: &PgPool, keys: &KeyRing, request: RegisterRequest) -> Result<AuthResponse, ServiceError> {
+    let email = request.email.trim().to_string();
+    let name = request.name.trim().to_string();
+
+    if name.is_empty() {
+        return Err(ServiceError::BadRequest("Name is required".to_string()));
+    }
+    if !email.contains('@') {
+        return Err(ServiceError::BadRequest("A valid email address is required".to_string()));
+    }
+    if request.password.len() < 8 {
+        return Err(ServiceError::BadRequest("Password must be at least 8 characters".to_string()));
+    }
+
+    // Another sign-up may take the generated username before the insert.
+    let user = loop {
+        let username = available_username(pool, &name).await?;
+        let created = create_user(pool, CreateUser {
+            username: username.clone(),
+            email: email.clone(),
+            phone: request.phone.clone(),
+            password: request.password.clone(),
+        })
+        .await;
+        match created {
+            Err(ServiceError::DuplicateEntry(_)) if username_taken(pool, &username).await? => continue,
+            created => break created?,
+        }
+    };
+
+    issue_tokens(pool, keys, user).await
+}
+
+/// Exchanges a refresh token for a new access/refresh token pair. The presented
+/// token is revoked; presenting an already rotated token revokes its whole
+/// family, since that means a copy of it has leaked.
+pub async fn refresh(pool: &PgPool, keys: &KeyRing, request: RefreshTokenRequest) -> Result<AuthResponse, ServiceError> {
+    let mut tx = pool.begin().await?;
+
+    let stored = sqlx::query_as::<_, StoredRefreshToken>(
+        "SELECT id, user_id, family_id, expires_at, revoked_at FROM refresh_tokens WHERE token_hash = $1 FOR UPDATE"
+    )
+    .bind(hash_refresh_token(&request.refresh_token))
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ServiceError::Unauthorized("Invalid refresh token".to_string()))?;
+
+    if stored.revoked_at.is_some() {
+        sqlx::query("UPDATE refresh_tokens SET revoked_at = $1 WHERE family_id = $2 AND revoked_at IS NULL")
+            .bind(Utc::now())
+            .bind(stored.family_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        log::warn!("Refresh token reuse detected for user {}, revoked family {}", stored.user_id, stored.family_id);
+        return Err(ServiceError::Unauthorized("Refresh token has been revoked".to_string()));
+    }
+
+    if stored.expires_at <= Utc::now() {
+        return Err(ServiceError::Unauthorized("Refresh token has expired".to_string()));
+    }
+
+    let (refresh_token, successor_id) = store_refresh_token(&mut *tx, keys, stored.user_id, stored.family_id).await?;
+
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = $1, replaced_by = $2 WHERE id = $3")
+        .bind(Utc::now())
+        .bind(successor_id)
+        .bind(stored.id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    let user = get_user_by_id(pool, stored.user_id).await?;
+    auth_response(keys, user, refresh_token)
+}
+
+/// Revokes every outstanding refresh token of the user, signing them out on all devices.
+pub async fn logout(pool: &PgPool, user_id: Uuid) -> Result<(), ServiceError> {
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = $1 WHERE user_id = $2 AND revoked_at IS NULL")
+        .bind(Utc::now())
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn issue_tokens(pool: &PgPool, keys: &KeyRing, user: User) -> Result<AuthResponse, ServiceError> {
+    let (refresh_token, _) = store_refresh_token(pool, keys, user.id, Uuid::new_v4()).await?;
+    auth_response(keys, user, refresh_token)
+}
+
+fn auth_response(keys: &KeyRing, user: User, refresh_token: String) -> Result<AuthResponse, ServiceError> {
+    // Signing only fails when the key is broken, which is the server's fault.
+    let token = keys.generate_token(user.id.to_string(), user.role).map_err(|err| {
+        log::error!("Failed to sign an access token: {:?}", err);
+        ServiceError::InternalServerError("Failed to issue the access token".to_string())
+    })?;
+
+    Ok(AuthResponse {
+        user,
+        token,
+        refresh_token,
+        expires_in: keys.access_token_ttl().num_seconds() as u64,
+    })
+}
+
+/// Generates a new opaque refresh token, stores its hash and returns the raw
+/// token together with the id of the stored row.
+async fn store_refresh_token<'e, E: PgExecutor<'e>>(executor: E, keys: &KeyRing, user_id: Uuid, family_id: Uuid) -> Result<(String, Uuid), ServiceError> {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let token = URL_SAFE_NO_PAD.encode(bytes);
+
+    let id: Uuid = sqlx::query_scalar(
+        "INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at, created_at) VALUES ($1, $2, $3, $4, $5) RETURNING id"
+    )
+    .bind(user_id)
+    .bind(family_id)
+    .bind(hash_refresh_token(&token))
+    .bind(Utc::now() + keys.refresh_token_ttl())
+    .bind(Utc::now())
+    .fetch_one(executor)
+    .await?;
+
+    Ok((token, id))
+}
+
+fn hash_refresh_token(token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
+
+fn invalid_credentials() -> ServiceError {
+    ServiceError::Unauthorized("Invalid email or password".to_string())
+}
+
+fn account_locked(locked_until: DateTime<Utc>) -> ServiceError {
+    let minutes = (locked_until - Utc::now()).num_minutes() + 1;
+    ServiceError::TooManyRequests(format!("Too many failed sign-ins; try again in {} minutes", minutes))
+}
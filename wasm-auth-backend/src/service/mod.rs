@@ -0,0 +1,9 @@
+mod auth;
+mod otp;
+mod social;
+mod user;
+
+pub use auth::*;
+pub use otp::*;
+pub use social::*;
+pub use user::*;
@@ -0,0 +1,136 @@
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+use chrono::{DateTime, Duration, Utc};
+use models::auth::EmailOrPhone;
+use crate::{error::ServiceError, otp::OtpIssuer};
+
+#[derive(Debug, FromRow)]
+struct StoredOtp {
+    id: Uuid,
+    code_hash: String,
+    attempts: i32,
+    expires_at: DateTime<Utc>,
+}
+
+/// Generates a code for `target`, stores its hash and hands it to the sender.
+/// Earlier codes for the same target stop working. Requests are limited per
+/// target to one per resend interval and a fixed number per hour.
+pub async fn request_otp(pool: &PgPool, issuer: &OtpIssuer, target: &EmailOrPhone) -> Result<(), ServiceError> {
+    let key = target_key(target)?;
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+
+    // Serializes concurrent requests for the same target so the limits hold.
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(&key)
+        .execute(&mut *tx)
+        .await?;
+
+    let (sent_last_hour, last_sent_at): (i64, Option<DateTime<Utc>>) = sqlx::query_as(
+        "SELECT COUNT(*), MAX(created_at) FROM otp_codes WHERE target = $1 AND created_at > $2"
+    )
+    .bind(&key)
+    .bind(now - Duration::hours(1))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if last_sent_at.is_some_and(|sent_at| sent_at + issuer.resend_interval() > now) {
+        return Err(ServiceError::TooManyRequests("Please wait before requesting another code".to_string()));
+    }
+    if sent_last_hour >= issuer.max_sends_per_hour() {
+        return Err(ServiceError::TooManyRequests("Too many codes requested, try again later".to_string()));
+    }
+
+    sqlx::query("UPDATE otp_codes SET consumed_at = $1 WHERE target = $2 AND consumed_at IS NULL")
+        .bind(now)
+        .bind(&key)
+        .execute(&mut *tx)
+        .await?;
+
+    let code = issuer.generate_code();
+    sqlx::query("INSERT INTO otp_codes (target, code_hash, expires_at, created_at) VALUES ($1, $2, $3, $4)")
+        .bind(&key)
+        .bind(issuer.hash_code(&key, &code))
+        .bind(now + issuer.code_ttl())
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+    // Only commit once the code is out, so failed deliveries do not count
+    // against the rate limit.
+    issuer.sender().send(target, &code).await.map_err(|e| {
+        log::error!("Failed to send verification code: {}", e);
+        ServiceError::InternalServerError("Failed to send verification code".to_string())
+    })?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Checks `code` against the latest code sent to `target` and leaves it
+/// usable, e.g. for a multi-step form that submits it again at the end.
+pub async fn verify_otp(pool: &PgPool, issuer: &OtpIssuer, target: &EmailOrPhone, code: &str) -> Result<(), ServiceError> {
+    check_otp(pool, issuer, target, code, false).await
+}
+
+/// Like [`verify_otp`], but the code cannot be used again afterwards.
+pub async fn consume_otp(pool: &PgPool, issuer: &OtpIssuer, target: &EmailOrPhone, code: &str) -> Result<(), ServiceError> {
+    check_otp(pool, issuer, target, code, true).await
+}
+
+async fn check_otp(pool: &PgPool, issuer: &OtpIssuer, target: &EmailOrPhone, code: &str, consume: bool) -> Result<(), ServiceError> {
+    let key = target_key(target)?;
+    let mut tx = pool.begin().await?;
+
+    let stored = sqlx::query_as::<_, StoredOtp>(
+        "SELECT id, code_hash, attempts, expires_at FROM otp_codes WHERE target = $1 AND consumed_at IS NULL ORDER BY created_at DESC LIMIT 1 FOR UPDATE"
+    )
+    .bind(&key)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ServiceError::BadRequest("No active verification code, please request a new one".to_string()))?;
+
+    if stored.expires_at <= Utc::now() {
+        return Err(ServiceError::BadRequest("Verification code has expired".to_string()));
+    }
+    if stored.attempts >= issuer.max_attempts() {
+        return Err(ServiceError::TooManyRequests("Too many incorrect attempts, please request a new code".to_string()));
+    }
+
+    if !issuer.verify_code(&key, code, &stored.code_hash) {
+        sqlx::query("UPDATE otp_codes SET attempts = attempts + 1 WHERE id = $1")
+            .bind(stored.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        return Err(ServiceError::BadRequest("Invalid verification code".to_string()));
+    }
+
+    if consume {
+        sqlx::query("UPDATE otp_codes SET consumed_at = $1 WHERE id = $2")
+            .bind(Utc::now())
+            .bind(stored.id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+fn target_key(target: &EmailOrPhone) -> Result<String, ServiceError> {
+    let key = target.normalized();
+    let valid = match target {
+        EmailOrPhone::Email(_) => key.contains('@'),
+        EmailOrPhone::Phone(_) => key.len() == 13 && key.starts_with("+989"),
+    };
+
+    if valid {
+        Ok(key)
+    } else {
+        Err(ServiceError::BadRequest("A valid email address or mobile number is required".to_string()))
+    }
+}
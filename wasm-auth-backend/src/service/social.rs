@@ -0,0 +1,151 @@
+use sqlx::PgPool;
+use rand::{distributions::Alphanumeric, Rng};
+use uuid::Uuid;
+use chrono::Utc;
+use models::{auth::AuthResponse, identity::{LinkedIdentity, SocialLoginRequest, SocialProvider}, user::{CreateUser, User}};
+use crate::{error::ServiceError, jwt::KeyRing, social::{SocialVerifier, VerifiedIdentity}};
+use super::auth::issue_tokens;
+use super::user::{available_username, create_user, get_user_by_email, get_user_by_id};
+
+/// Signs in with a provider account. An unknown account is linked to the
+/// user with the same email when the provider has verified that email, and
+/// otherwise gets a new user.
+pub async fn social_login(pool: &PgPool, keys: &KeyRing, verifier: &SocialVerifier, request: SocialLoginRequest) -> Result<AuthResponse, ServiceError> {
+    let identity = verifier.verify(&request).await?;
+
+    let user = match find_linked_identity(pool, identity.provider, &identity.subject).await? {
+        Some(linked) => {
+            touch_linked_identity(pool, linked.id, &identity).await?;
+            get_user_by_id(pool, linked.user_id).await?
+        }
+        None => {
+            let email = identity
+                .email
+                .as_deref()
+                .map(|email| email.trim().to_lowercase())
+                .ok_or_else(|| ServiceError::BadRequest(format!("{} did not share an email address", identity.provider)))?;
+
+            let user = match get_user_by_email(pool, &email).await? {
+                Some(user) if identity.email_verified => user,
+                Some(_) => {
+                    return Err(ServiceError::DuplicateEntry(format!(
+                        "An account with this email already exists; sign in and link {} from your profile",
+                        identity.provider
+                    )))
+                }
+                None => create_social_user(pool, &identity, email).await?,
+            };
+            insert_linked_identity(pool, user.id, &identity).await?;
+            user
+        }
+    };
+
+    issue_tokens(pool, keys, user).await
+}
+
+/// Links a provider account to a signed-in user.
+pub async fn link_identity(pool: &PgPool, verifier: &SocialVerifier, user_id: Uuid, request: SocialLoginRequest) -> Result<LinkedIdentity, ServiceError> {
+    let identity = verifier.verify(&request).await?;
+
+    match find_linked_identity(pool, identity.provider, &identity.subject).await? {
+        Some(linked) if linked.user_id == user_id => touch_linked_identity(pool, linked.id, &identity).await,
+        Some(_) => Err(ServiceError::DuplicateEntry(format!("This {} account is linked to another user", identity.provider))),
+        None => insert_linked_identity(pool, user_id, &identity).await,
+    }
+}
+
+pub async fn get_linked_identities(pool: &PgPool, user_id: Uuid) -> Result<Vec<LinkedIdentity>, ServiceError> {
+    let identities = sqlx::query_as::<_, LinkedIdentity>(
+        "SELECT * FROM linked_identities WHERE user_id = $1 ORDER BY created_at"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(identities)
+}
+
+/// Accounts created through social login have no known password, so the
+/// last linked provider cannot be removed.
+pub async fn unlink_identity(pool: &PgPool, user_id: Uuid, provider: SocialProvider) -> Result<(), ServiceError> {
+    let identities = get_linked_identities(pool, user_id).await?;
+    if !identities.iter().any(|identity| identity.provider == provider) {
+        return Err(ServiceError::NotFound(format!("No {} account is linked", provider)));
+    }
+    if identities.len() == 1 {
+        return Err(ServiceError::BadRequest("Link another provider before removing the last one".to_string()));
+    }
+
+    sqlx::query("DELETE FROM linked_identities WHERE user_id = $1 AND provider = $2")
+        .bind(user_id)
+        .bind(provider)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+async fn find_linked_identity(pool: &PgPool, provider: SocialProvider, subject: &str) -> Result<Option<LinkedIdentity>, ServiceError> {
+    let identity = sqlx::query_as::<_, LinkedIdentity>(
+        "SELECT * FROM linked_identities WHERE provider = $1 AND subject = $2"
+    )
+    .bind(provider)
+    .bind(subject)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(identity)
+}
+
+async fn insert_linked_identity(pool: &PgPool, user_id: Uuid, identity: &VerifiedIdentity) -> Result<LinkedIdentity, ServiceError> {
+    let linked = sqlx::query_as::<_, LinkedIdentity>(
+        "INSERT INTO linked_identities (user_id, provider, subject, email, created_at, last_login_at) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *"
+    )
+    .bind(user_id)
+    .bind(identity.provider)
+    .bind(&identity.subject)
+    .bind(&identity.email)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(linked)
+}
+
+async fn touch_linked_identity(pool: &PgPool, id: Uuid, identity: &VerifiedIdentity) -> Result<LinkedIdentity, ServiceError> {
+    let linked = sqlx::query_as::<_, LinkedIdentity>(
+        "UPDATE linked_identities SET email = COALESCE($1, email), last_login_at = $2 WHERE id = $3 RETURNING *"
+    )
+    .bind(&identity.email)
+    .bind(Utc::now())
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(linked)
+}
+
+/// The password is random and never shown, so the account can only be
+/// entered through its linked providers.
+async fn create_social_user(pool: &PgPool, identity: &VerifiedIdentity, email: String) -> Result<User, ServiceError> {
+    let base = identity
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| email.split('@').next().unwrap_or_default())
+        .to_string();
+
+    let username = available_username(pool, &base).await?;
+
+    let password: String = rand::thread_rng().sample_iter(&Alphanumeric).take(32).map(char::from).collect();
+
+    create_user(pool, CreateUser {
+        username,
+        email,
+        phone: None,
+        password,
+    })
+    .await
+}
@@ -0,0 +1,176 @@
+use sqlx::PgPool;
+use rand::{thread_rng, Rng};
+
+use uuid::Uuid;
+use chrono::Utc;
+use argon2::{Argon2, PasswordHasher, PasswordVerifier};
+use models::{auth::EmailOrPhone, role::Role, user::{User, CreateUser, UpdateUser}, pagination::{Pagination, PaginatedResponse}};
+use crate::error::ServiceError;
+
+pub async fn create_user(pool: &PgPool, new_user: CreateUser) -> Result<User, ServiceError> {
+    let mut rng = thread_rng();
+    let salt = argon2::password_hash::SaltString::generate(&mut rng);
+    let hashed_password = Argon2::default()
+        .hash_password(new_user.password.as_bytes(), &salt)
+        .map_err(|e| ServiceError::InternalServerError(format!("Failed to hash password: {}", e).to_string()))?
+        .to_string();
+
+    let user = sqlx::query_as::<_, User>(
+        "INSERT INTO users (username, email, phone, password_hash, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *"
+    )
+    .bind(new_user.username)
+    .bind(new_user.email)
+    .bind(new_user.phone.as_deref().map(normalize_phone))
+    .bind(hashed_password)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// Usernames are unique while names are not, so a taken name gets a random
+/// numeric suffix.
+pub(crate) async fn available_username(pool: &PgPool, name: &str) -> Result<String, ServiceError> {
+    let mut username = name.to_string();
+    while username_taken(pool, &username).await? {
+        username = format!("{}-{:04}", name, thread_rng().gen_range(0..10_000));
+    }
+
+    Ok(username)
+}
+
+pub(crate) async fn username_taken(pool: &PgPool, username: &str) -> Result<bool, ServiceError> {
+    let taken = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE username = $1)")
+        .bind(username)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(taken)
+}
+
+pub async fn get_users(pool: &PgPool, pagination: Pagination) -> Result<PaginatedResponse<User>, ServiceError> {
+    let offset = pagination.offset().map_err(ServiceError::BadRequest)?;
+
+    let total_items: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(pool)
+        .await?;
+
+    let users = sqlx::query_as::<_, User>(
+        "SELECT * FROM users ORDER BY created_at DESC LIMIT $1 OFFSET $2"
+    )
+    .bind(pagination.limit as i64)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let total_pages = (total_items as f64 / pagination.limit as f64).ceil() as u32;
+
+    Ok(PaginatedResponse {
+        items: users,
+        total_items: total_items as u32,
+        current_page: pagination.page,
+        total_pages,
+        limit: pagination.limit,
+    })
+}
+
+pub async fn get_user_by_id(pool: &PgPool, user_id: Uuid) -> Result<User, ServiceError> {
+    let user = sqlx::query_as::<_, User>(
+        "SELECT * FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// Emails are stored as typed, so they are matched in any case, preferring
+/// an exact match.
+pub async fn get_user_by_email(pool: &PgPool, email: &str) -> Result<Option<User>, ServiceError> {
+    let user = sqlx::query_as::<_, User>(
+        "SELECT * FROM users WHERE lower(email) = lower($1) ORDER BY email = $1 DESC LIMIT 1"
+    )
+    .bind(email)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// Finds the user a sign-in identifier refers to: an email address (any
+/// case), a username, or a phone number in any of its usual spellings.
+pub async fn find_user_by_login(pool: &PgPool, identifier: &str) -> Result<Option<User>, ServiceError> {
+    let identifier = identifier.trim();
+    let phone = match EmailOrPhone::parse(identifier) {
+        EmailOrPhone::Phone(phone) if phone.chars().filter(char::is_ascii_digit).count() >= 10 => Some(normalize_phone(&phone)),
+        _ => None,
+    };
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT * FROM users WHERE lower(email) = lower($1) OR username = $1 OR phone = $2
+         ORDER BY lower(email) = lower($1) DESC, username = $1 DESC LIMIT 1"
+    )
+    .bind(identifier)
+    .bind(phone)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
+
+pub async fn update_user(pool: &PgPool, user_id: Uuid, updated_user: UpdateUser) -> Result<User, ServiceError> {
+    let existing_user = get_user_by_id(pool, user_id).await?;
+
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET username = $1, email = $2, phone = $3, updated_at = $4 WHERE id = $5 RETURNING *"
+    )
+    .bind(updated_user.username.unwrap_or(existing_user.username))
+    .bind(updated_user.email.unwrap_or(existing_user.email))
+    .bind(updated_user.phone.as_deref().map(normalize_phone).or(existing_user.phone))
+    .bind(Utc::now())
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// Access tokens already issued keep the old role until they expire; the new
+/// one is picked up on the next refresh.
+pub async fn update_user_role(pool: &PgPool, user_id: Uuid, role: Role) -> Result<User, ServiceError> {
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET role = $1, updated_at = $2 WHERE id = $3 RETURNING *"
+    )
+    .bind(role)
+    .bind(Utc::now())
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(user)
+}
+
+pub async fn delete_user(pool: &PgPool, user_id: Uuid) -> Result<(), ServiceError> {
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Phone numbers are stored normalized so sign-in can match them however they are typed.
+fn normalize_phone(phone: &str) -> String {
+    EmailOrPhone::Phone(phone.to_string()).normalized()
+}
+
+pub async fn verify_password(hashed_password: &str, password: &str) -> Result<bool, ServiceError> {
+    let parsed_hash = argon2::password_hash::PasswordHash::new(hashed_password)
+        .map_err(|e| ServiceError::InternalServerError(format!("Failed to parse hash: {}", e)))?;
+
+    Ok(Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok())
+}
+
@@ -0,0 +1,380 @@
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use config::SocialConfig;
+use models::identity::{SocialLoginRequest, SocialProvider};
+use crate::error::ServiceError;
+
+const GOOGLE_ISSUERS: &[&str] = &["https://accounts.google.com", "accounts.google.com"];
+const GOOGLE_JWKS_URL: &str = "https://www.googleapis.com/oauth2/v3/certs";
+const LINKEDIN_ISSUERS: &[&str] = &["https://www.linkedin.com/oauth"];
+const LINKEDIN_JWKS_URL: &str = "https://www.linkedin.com/oauth/openid/jwks";
+const META_ISSUERS: &[&str] = &["https://www.facebook.com"];
+const META_JWKS_URL: &str = "https://limited.facebook.com/.well-known/oauth/openid/jwks/";
+
+/// A token signed with an unknown `kid` triggers a refetch at most this often,
+/// so rotated provider keys are picked up without letting forged tokens
+/// hammer the provider.
+const MIN_JWKS_REFETCH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// An account as vouched for by its provider.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedIdentity {
+    pub provider: SocialProvider,
+    pub subject: String,
+    pub email: Option<String>,
+    /// Whether the provider confirmed the user owns `email`.
+    pub email_verified: bool,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    #[serde(default)]
+    email: Option<String>,
+    /// A boolean for most providers, the string `"true"` for some.
+    #[serde(default)]
+    email_verified: Option<serde_json::Value>,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubTokenCheck {
+    user: GithubUser,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubUser {
+    id: i64,
+    login: String,
+    name: Option<String>,
+    email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+#[derive(Default)]
+struct KeyCache {
+    keys: HashMap<String, DecodingKey>,
+    fetched_at: Option<Instant>,
+}
+
+struct OidcProvider {
+    issuers: Vec<String>,
+    jwks_url: String,
+    client_id: String,
+    cache: RwLock<KeyCache>,
+}
+
+struct GithubApp {
+    api_url: String,
+    client_id: String,
+    client_secret: String,
+}
+
+/// Checks sign-in proofs with the provider that issued them. OIDC ID tokens
+/// are verified against the provider's published keys, cached for the
+/// configured TTL; GitHub access tokens are checked through GitHub's OAuth
+/// app API, which also proves the token was issued to this app.
+pub struct SocialVerifier {
+    client: reqwest::Client,
+    oidc: HashMap<SocialProvider, OidcProvider>,
+    github: Option<GithubApp>,
+    jwks_ttl: Duration,
+}
+
+impl SocialVerifier {
+    pub fn new(jwks_ttl: Duration) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            oidc: HashMap::new(),
+            github: None,
+            jwks_ttl,
+        }
+    }
+
+    pub fn from_config(config: &SocialConfig) -> Self {
+        let mut verifier = Self::new(Duration::from_secs(config.jwks_cache_ttl_seconds));
+
+        if let Some(client_id) = &config.google_client_id {
+            verifier = verifier.with_oidc_provider(SocialProvider::Google, GOOGLE_ISSUERS, GOOGLE_JWKS_URL, client_id);
+        }
+        if let Some(client_id) = &config.linkedin_client_id {
+            verifier = verifier.with_oidc_provider(SocialProvider::Linkedin, LINKEDIN_ISSUERS, LINKEDIN_JWKS_URL, client_id);
+        }
+        if let Some(app_id) = &config.meta_app_id {
+            verifier = verifier.with_oidc_provider(SocialProvider::Meta, META_ISSUERS, META_JWKS_URL, app_id);
+        }
+        if let (Some(client_id), Some(client_secret)) = (&config.github_client_id, &config.github_client_secret) {
+            verifier.github = Some(GithubApp {
+                api_url: config.github_api_url.trim_end_matches('/').to_string(),
+                client_id: client_id.clone(),
+                client_secret: client_secret.clone(),
+            });
+        }
+
+        verifier
+    }
+
+    /// Accepts ID tokens for `provider` issued by one of `issuers` to `client_id`.
+    pub fn with_oidc_provider(mut self, provider: SocialProvider, issuers: &[&str], jwks_url: &str, client_id: &str) -> Self {
+        self.oidc.insert(provider, OidcProvider {
+            issuers: issuers.iter().map(|issuer| issuer.to_string()).collect(),
+            jwks_url: jwks_url.to_string(),
+            client_id: client_id.to_string(),
+            cache: RwLock::new(KeyCache::default()),
+        });
+        self
+    }
+
+    pub fn is_enabled(&self, provider: SocialProvider) -> bool {
+        match provider {
+            SocialProvider::Github => self.github.is_some(),
+            _ => self.oidc.contains_key(&provider),
+        }
+    }
+
+    pub async fn verify(&self, request: &SocialLoginRequest) -> Result<VerifiedIdentity, ServiceError> {
+        let not_enabled = || ServiceError::BadRequest(format!("Sign-in with {} is not enabled", request.provider));
+
+        match request.provider {
+            SocialProvider::Github => {
+                let app = self.github.as_ref().ok_or_else(not_enabled)?;
+                let access_token = request
+                    .access_token
+                    .as_deref()
+                    .ok_or_else(|| ServiceError::BadRequest("`access_token` is required for github".to_string()))?;
+                self.verify_github(app, access_token).await
+            }
+            provider => {
+                let oidc = self.oidc.get(&provider).ok_or_else(not_enabled)?;
+                let id_token = request
+                    .id_token
+                    .as_deref()
+                    .ok_or_else(|| ServiceError::BadRequest(format!("`id_token` is required for {}", provider)))?;
+                self.verify_id_token(provider, oidc, id_token).await
+            }
+        }
+    }
+
+    async fn verify_id_token(&self, provider: SocialProvider, oidc: &OidcProvider, token: &str) -> Result<VerifiedIdentity, ServiceError> {
+        let header = decode_header(token).map_err(|_| invalid_token(provider))?;
+        if !matches!(header.alg, Algorithm::RS256 | Algorithm::ES256) {
+            return Err(invalid_token(provider));
+        }
+        let key_id = header.kid.ok_or_else(|| invalid_token(provider))?;
+        let key = self.signing_key(oidc, &key_id).await?.ok_or_else(|| invalid_token(provider))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&oidc.issuers);
+        validation.set_audience(&[&oidc.client_id]);
+
+        let claims = decode::<IdTokenClaims>(token, &key, &validation)
+            .map_err(|e| {
+                log::warn!("Rejected {} ID token: {}", provider, e);
+                invalid_token(provider)
+            })?
+            .claims;
+
+        let email_verified = match &claims.email_verified {
+            Some(serde_json::Value::Bool(verified)) => *verified,
+            Some(serde_json::Value::String(verified)) => verified == "true",
+            _ => false,
+        };
+
+        Ok(VerifiedIdentity {
+            provider,
+            subject: claims.sub,
+            email: claims.email,
+            email_verified,
+            name: claims.name,
+        })
+    }
+
+    /// Looks `key_id` up in the cached key set, refetching the set when it
+    /// has expired or, rate limited, when the key is unknown.
+    async fn signing_key(&self, oidc: &OidcProvider, key_id: &str) -> Result<Option<DecodingKey>, ServiceError> {
+        let refetch = {
+            let cache = oidc.cache.read().unwrap_or_else(|e| e.into_inner());
+            let age = cache.fetched_at.map(|fetched_at| fetched_at.elapsed());
+            match (cache.keys.get(key_id), age) {
+                (Some(key), Some(age)) if age < self.jwks_ttl => return Ok(Some(key.clone())),
+                (None, Some(age)) => age >= MIN_JWKS_REFETCH_INTERVAL,
+                _ => true,
+            }
+        };
+        if refetch {
+            let keys = self.fetch_jwks(&oidc.jwks_url).await?;
+            *oidc.cache.write().unwrap_or_else(|e| e.into_inner()) = KeyCache { keys, fetched_at: Some(Instant::now()) };
+        }
+
+        let cache = oidc.cache.read().unwrap_or_else(|e| e.into_inner());
+        Ok(cache.keys.get(key_id).cloned())
+    }
+
+    async fn fetch_jwks(&self, url: &str) -> Result<HashMap<String, DecodingKey>, ServiceError> {
+        let jwks: JwkSet = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| provider_unavailable(format!("Failed to fetch JWKS from `{}`: {}", url, e)))?
+            .json()
+            .await
+            .map_err(|e| provider_unavailable(format!("Invalid JWKS from `{}`: {}", url, e)))?;
+
+        // Keys this version of jsonwebtoken cannot use are skipped rather than
+        // failing the whole set.
+        Ok(jwks
+            .keys
+            .iter()
+            .filter_map(|jwk| Some((jwk.common.key_id.clone()?, DecodingKey::from_jwk(jwk).ok()?)))
+            .collect())
+    }
+
+    async fn verify_github(&self, app: &GithubApp, access_token: &str) -> Result<VerifiedIdentity, ServiceError> {
+        let response = self
+            .client
+            .post(format!("{}/applications/{}/token", app.api_url, app.client_id))
+            .basic_auth(&app.client_id, Some(&app.client_secret))
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header(reqwest::header::USER_AGENT, "pema-auth")
+            .json(&serde_json::json!({ "access_token": access_token }))
+            .send()
+            .await
+            .map_err(|e| provider_unavailable(format!("GitHub token check failed: {}", e)))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND || response.status() == reqwest::StatusCode::UNPROCESSABLE_ENTITY {
+            return Err(invalid_token(SocialProvider::Github));
+        }
+        let check: GithubTokenCheck = response
+            .error_for_status()
+            .map_err(|e| provider_unavailable(format!("GitHub token check failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| provider_unavailable(format!("Invalid GitHub token check response: {}", e)))?;
+
+        // The profile email is whatever the user made public; the emails
+        // endpoint (scope `user:email`) says which address is verified.
+        let verified_email = self.github_primary_email(app, access_token).await;
+        let (email, email_verified) = match verified_email {
+            Some(email) => (Some(email), true),
+            None => (check.user.email, false),
+        };
+
+        Ok(VerifiedIdentity {
+            provider: SocialProvider::Github,
+            subject: check.user.id.to_string(),
+            email,
+            email_verified,
+            name: check.user.name.or(Some(check.user.login)),
+        })
+    }
+
+    async fn github_primary_email(&self, app: &GithubApp, access_token: &str) -> Option<String> {
+        let emails: Vec<GithubEmail> = self
+            .client
+            .get(format!("{}/user/emails", app.api_url))
+            .bearer_auth(access_token)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header(reqwest::header::USER_AGENT, "pema-auth")
+            .send()
+            .await
+            .ok()?
+            .error_for_status()
+            .ok()?
+            .json()
+            .await
+            .ok()?;
+
+        emails
+            .into_iter()
+            .find(|email| email.primary && email.verified)
+            .map(|email| email.email)
+    }
+}
+
+fn invalid_token(provider: SocialProvider) -> ServiceError {
+    ServiceError::Unauthorized(format!("Invalid {} sign-in token", provider))
+}
+
+fn provider_unavailable(message: String) -> ServiceError {
+    log::error!("{}", message);
+    ServiceError::InternalServerError("The sign-in provider could not be reached".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use rsa::{pkcs8::DecodePrivateKey, traits::PublicKeyParts, RsaPrivateKey};
+
+    const TEST_KEY: &str = include_str!("testdata/oidc_test_key.pem");
+    const CLIENT_ID: &str = "pema-web.apps.googleusercontent.com";
+
+    /// A Google verifier whose key cache already holds the test key, so no
+    /// request is made to the (unreachable) JWKS URL.
+    fn verifier() -> SocialVerifier {
+        let verifier = SocialVerifier::new(Duration::from_secs(3600))
+            .with_oidc_provider(SocialProvider::Google, GOOGLE_ISSUERS, "http://127.0.0.1:9/certs", CLIENT_ID);
+
+        let private_key = RsaPrivateKey::from_pkcs8_pem(TEST_KEY).unwrap();
+        let n = URL_SAFE_NO_PAD.encode(private_key.n().to_bytes_be());
+        let e = URL_SAFE_NO_PAD.encode(private_key.e().to_bytes_be());
+        let mut cache = verifier.oidc[&SocialProvider::Google].cache.write().unwrap();
+        cache.keys.insert("test-key".to_string(), DecodingKey::from_rsa_components(&n, &e).unwrap());
+        cache.fetched_at = Some(Instant::now());
+        drop(cache);
+
+        verifier
+    }
+
+    fn id_token(audience: &str, kid: &str) -> SocialLoginRequest {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(kid.to_string());
+        let claims = serde_json::json!({
+            "iss": "https://accounts.google.com",
+            "aud": audience,
+            "sub": "109876543210",
+            "email": "sara@example.com",
+            "email_verified": true,
+            "name": "Sara",
+            "exp": chrono::Utc::now().timestamp() + 300,
+        });
+        let token = encode(&header, &claims, &EncodingKey::from_rsa_pem(TEST_KEY.as_bytes()).unwrap()).unwrap();
+
+        SocialLoginRequest { provider: SocialProvider::Google, id_token: Some(token), access_token: None }
+    }
+
+    #[actix_web::test]
+    async fn test_verifies_id_token_signature_and_audience() {
+        let verifier = verifier();
+
+        let identity = verifier.verify(&id_token(CLIENT_ID, "test-key")).await.unwrap();
+        assert_eq!(identity.subject, "109876543210");
+        assert_eq!(identity.email.as_deref(), Some("sara@example.com"));
+        assert!(identity.email_verified);
+
+        let foreign = verifier.verify(&id_token("another-app", "test-key")).await;
+        assert!(matches!(foreign, Err(ServiceError::Unauthorized(_))));
+
+        // Unknown keys are not refetched within a minute of the last fetch.
+        let unknown_key = verifier.verify(&id_token(CLIENT_ID, "rotated-key")).await;
+        assert!(matches!(unknown_key, Err(ServiceError::Unauthorized(_))));
+
+        let github = SocialLoginRequest { provider: SocialProvider::Github, id_token: None, access_token: Some("gho_x".to_string()) };
+        assert!(matches!(verifier.verify(&github).await, Err(ServiceError::BadRequest(_))));
+    }
+}
@@ -6,6 +6,8 @@ use models::{
     vendor::Vendor, 
     inventory::InventoryItem,
     notification::Notification,
+    money::Money,
+    persian::format_price,
     pagination::PaginatedResponse
 };
 use crate::components::{
@@ -38,7 +40,7 @@ pub struct DashboardStats {
     pub pending_orders: u32,
     pub low_stock_items: u32,
     pub unread_notifications: u32,
-    pub monthly_revenue: f64,
+    pub monthly_revenue: Money,
 }
 
 impl Default for DashboardStats {
@@ -51,7 +53,7 @@ impl Default for DashboardStats {
             pending_orders: 23,
             low_stock_items: 12,
             unread_notifications: 5,
-            monthly_revenue: 125000.0,
+            monthly_revenue: Money::rials(125_000),
         }
     }
 }
@@ -236,7 +238,7 @@ fn render_overview(stats: &DashboardStats) -> Html {
                         <div class="text-2xl">{"💰"}</div>
                         <div>
                             <h3 class="text-lg font-semibold text-green-800">{"درآمد ماهانه"}</h3>
-                            <p class="text-xl font-bold text-green-600">{format!("{:.0} تومان", stats.monthly_revenue)}</p>
+                            <p class="text-xl font-bold text-green-600">{format_price(&stats.monthly_revenue)}</p>
                         </div>
                     </div>
                 </div>
@@ -1,5 +1,6 @@
 use yew::prelude::*;
-use models::order::Order;
+use models::{order::{Order, OrderStatus}, persian::format_price};
+use crate::components::order_management::status_badge_class;
 use web_sys::HtmlSelectElement;
 
 #[derive(Properties, PartialEq)]
@@ -8,7 +9,7 @@ pub struct OrderItemProps {
     #[prop_or_default]
     pub on_view: Option<Callback<Order>>,
     #[prop_or_default]
-    pub on_update_status: Option<Callback<(Order, String)>>,
+    pub on_update_status: Option<Callback<(Order, OrderStatus)>>,
     #[prop_or_default]
     pub show_actions: bool,
 }
@@ -35,30 +36,25 @@ pub fn order_item(props: &OrderItemProps) -> Html {
         Callback::from(move |e: Event| {
             if let Some(callback) = &on_update_status {
                 if let Some(target) = e.target_dyn_into::<HtmlSelectElement>() {
-                    let new_status = target.value();
-                    callback.emit((order.clone(), new_status));
+                    if let Ok(new_status) = target.value().parse::<OrderStatus>() {
+                        callback.emit((order.clone(), new_status));
+                    }
                 }
             }
         })
     };
 
-    let (status_class, status_icon) = match order.status.as_str() {
-        "pending" => ("bg-yellow-100 text-yellow-800", "⏳"),
-        "processing" => ("bg-blue-100 text-blue-800", "🔄"),
-        "shipped" => ("bg-purple-100 text-purple-800", "🚚"),
-        "delivered" => ("bg-green-100 text-green-800", "✅"),
-        "cancelled" => ("bg-red-100 text-red-800", "❌"),
-        _ => ("bg-gray-100 text-gray-800", "❓"),
-    };
-
-    let status_text = match order.status.as_str() {
-        "pending" => "در انتظار",
-        "processing" => "در حال پردازش",
-        "shipped" => "ارسال شده",
-        "delivered" => "تحویل داده شده",
-        "cancelled" => "لغو شده",
-        _ => "نامشخص",
+    let status_class = status_badge_class(order.status);
+    let status_icon = match order.status {
+        OrderStatus::Pending => "⏳",
+        OrderStatus::Paid => "💳",
+        OrderStatus::Processing => "🔄",
+        OrderStatus::Shipped => "🚚",
+        OrderStatus::Delivered => "✅",
+        OrderStatus::Cancelled => "❌",
+        OrderStatus::Refunded => "↩️",
     };
+    let status_text = order.status.label();
 
     html! {
         <div class="bg-white rounded-lg shadow-sm hover:shadow-md transition-shadow duration-200 border border-gray-200 p-4">
@@ -83,7 +79,7 @@ pub fn order_item(props: &OrderItemProps) -> Html {
                 <div>
                     <p class="text-sm text-gray-500">{"مبلغ کل"}</p>
                     <p class="text-xl font-bold text-purple-600">
-                        {format!("{:.0} تومان", order.total_amount)}
+                        {format_price(&order.total_amount)}
                     </p>
                 </div>
                 <div>
@@ -111,17 +107,15 @@ pub fn order_item(props: &OrderItemProps) -> Html {
                         {"مشاهده جزئیات"}
                     </button>
                     
-                    if order.status != "delivered" && order.status != "cancelled" {
+                    if !order.status.is_final() {
                         <select 
                             onchange={handle_status_change}
                             class="flex-1 border border-gray-300 rounded-md text-sm py-2 px-3 focus:ring-2 focus:ring-purple-500 focus:border-transparent"
                         >
                             <option value="" disabled=true selected={true}>{"تغییر وضعیت"}</option>
-                            <option value="pending">{"در انتظار"}</option>
-                            <option value="processing">{"در حال پردازش"}</option>
-                            <option value="shipped">{"ارسال شده"}</option>
-                            <option value="delivered">{"تحویل داده شده"}</option>
-                            <option value="cancelled">{"لغو شده"}</option>
+                            {for order.status.next_statuses().iter().map(|status| html! {
+                                <option value={status.as_str()}>{status.label()}</option>
+                            })}
                         </select>
                     }
                 </div>
@@ -1,42 +1,19 @@
 use yew::prelude::*;
-use models::order::Order;
+use models::{money::{Currency, Money}, order::{Order, OrderStatus}, persian::format_price};
 use crate::components::{OrderItem, PaginationComponent};
-use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use web_sys::HtmlInputElement;
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub enum OrderStatus {
-    All,
-    Pending,
-    Processing,
-    Shipped,
-    Delivered,
-    Cancelled,
-}
-
-impl OrderStatus {
-    pub fn to_string(&self) -> String {
-        match self {
-            OrderStatus::All => "همه".to_string(),
-            OrderStatus::Pending => "در انتظار".to_string(),
-            OrderStatus::Processing => "در حال پردازش".to_string(),
-            OrderStatus::Shipped => "ارسال شده".to_string(),
-            OrderStatus::Delivered => "تحویل داده شده".to_string(),
-            OrderStatus::Cancelled => "لغو شده".to_string(),
-        }
-    }
-
-    pub fn to_badge_class(&self) -> &'static str {
-        match self {
-            OrderStatus::Pending => "bg-yellow-100 text-yellow-800",
-            OrderStatus::Processing => "bg-blue-100 text-blue-800",
-            OrderStatus::Shipped => "bg-purple-100 text-purple-800",
-            OrderStatus::Delivered => "bg-green-100 text-green-800",
-            OrderStatus::Cancelled => "bg-red-100 text-red-800",
-            OrderStatus::All => "bg-gray-100 text-gray-800",
-        }
+pub fn status_badge_class(status: OrderStatus) -> &'static str {
+    match status {
+        OrderStatus::Pending => "bg-yellow-100 text-yellow-800",
+        OrderStatus::Paid => "bg-teal-100 text-teal-800",
+        OrderStatus::Processing => "bg-blue-100 text-blue-800",
+        OrderStatus::Shipped => "bg-purple-100 text-purple-800",
+        OrderStatus::Delivered => "bg-green-100 text-green-800",
+        OrderStatus::Cancelled => "bg-red-100 text-red-800",
+        OrderStatus::Refunded => "bg-gray-100 text-gray-800",
     }
 }
 
@@ -45,7 +22,7 @@ pub struct OrderManagementProps {
     #[prop_or_default]
     pub orders: Vec<Order>,
     #[prop_or_default]
-    pub on_order_update: Option<Callback<(Order, String)>>,
+    pub on_order_update: Option<Callback<(Order, OrderStatus)>>,
     #[prop_or_default]
     pub on_order_delete: Option<Callback<Uuid>>,
 }
@@ -53,7 +30,8 @@ pub struct OrderManagementProps {
 #[function_component(OrderManagement)]
 pub fn order_management(props: &OrderManagementProps) -> Html {
     let search_term = use_state(|| String::new());
-    let selected_status = use_state(|| OrderStatus::All);
+    // `None` shows every status.
+    let selected_status = use_state(|| None::<OrderStatus>);
     let current_page = use_state(|| 1u32);
     let show_order_details = use_state(|| false);
     let selected_order = use_state(|| None::<Order>);
@@ -63,32 +41,60 @@ pub fn order_management(props: &OrderManagementProps) -> Html {
         Order {
             id: Uuid::new_v4(),
             user_id: Uuid::new_v4(),
-            total_amount: 2500000.0,
-            status: "pending".to_string(),
+            subtotal: Money::rials(2_500_000),
+            tax_amount: Money::rials(0),
+            shipping_amount: Money::rials(0),
+            total_amount: Money::rials(2_500_000),
+            currency: Currency::Irr,
+            exchange_rate: 1,
+            shipping_address: None,
+            notes: None,
+            status: OrderStatus::Pending,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         },
         Order {
             id: Uuid::new_v4(),
             user_id: Uuid::new_v4(),
-            total_amount: 1200000.0,
-            status: "processing".to_string(),
+            subtotal: Money::rials(1_200_000),
+            tax_amount: Money::rials(0),
+            shipping_amount: Money::rials(0),
+            total_amount: Money::rials(1_200_000),
+            currency: Currency::Irr,
+            exchange_rate: 1,
+            shipping_address: None,
+            notes: None,
+            status: OrderStatus::Processing,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         },
         Order {
             id: Uuid::new_v4(),
             user_id: Uuid::new_v4(),
-            total_amount: 850000.0,
-            status: "shipped".to_string(),
+            subtotal: Money::rials(850_000),
+            tax_amount: Money::rials(0),
+            shipping_amount: Money::rials(0),
+            total_amount: Money::rials(850_000),
+            currency: Currency::Irr,
+            exchange_rate: 1,
+            shipping_address: None,
+            notes: None,
+            status: OrderStatus::Shipped,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         },
         Order {
             id: Uuid::new_v4(),
             user_id: Uuid::new_v4(),
-            total_amount: 3200000.0,
-            status: "delivered".to_string(),
+            subtotal: Money::rials(3_200_000),
+            tax_amount: Money::rials(0),
+            shipping_amount: Money::rials(0),
+            total_amount: Money::rials(3_200_000),
+            currency: Currency::Irr,
+            exchange_rate: 1,
+            shipping_address: None,
+            notes: None,
+            status: OrderStatus::Delivered,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         },
@@ -111,7 +117,7 @@ pub fn order_management(props: &OrderManagementProps) -> Html {
 
     let handle_status_change = {
         let selected_status = selected_status.clone();
-        Callback::from(move |status: OrderStatus| {
+        Callback::from(move |status: Option<OrderStatus>| {
             selected_status.set(status);
         })
     };
@@ -145,7 +151,7 @@ pub fn order_management(props: &OrderManagementProps) -> Html {
     let status_counts = {
         let mut counts = std::collections::HashMap::new();
         for order in &orders {
-            *counts.entry(order.status.clone()).or_insert(0) += 1;
+            *counts.entry(order.status).or_insert(0) += 1;
         }
         counts
     };
@@ -156,16 +162,9 @@ pub fn order_management(props: &OrderManagementProps) -> Html {
         .filter(|order| {
             let matches_search = search_term.is_empty() || 
                 order.id.to_string().contains(&*search_term) ||
-                order.total_amount.to_string().contains(&*search_term);
+                order.total_amount.to_decimal_string().contains(&*search_term);
             
-            let matches_status = match *selected_status {
-                OrderStatus::All => true,
-                OrderStatus::Pending => order.status == "pending",
-                OrderStatus::Processing => order.status == "processing",
-                OrderStatus::Shipped => order.status == "shipped",
-                OrderStatus::Delivered => order.status == "delivered",
-                OrderStatus::Cancelled => order.status == "cancelled",
-            };
+            let matches_status = selected_status.is_none_or(|status| order.status == status);
 
             matches_search && matches_status
         })
@@ -190,29 +189,18 @@ pub fn order_management(props: &OrderManagementProps) -> Html {
             <div class="bg-white rounded-lg shadow-sm mb-6">
                 <div class="border-b border-gray-200">
                     <nav class="flex space-x-8 space-x-reverse px-6">
-                        {for [
-                            OrderStatus::All,
-                            OrderStatus::Pending,
-                            OrderStatus::Processing,
-                            OrderStatus::Shipped,
-                            OrderStatus::Delivered,
-                            OrderStatus::Cancelled,
-                        ].iter().map(|status| {
-                            let is_active = *selected_status == *status;
-                            let status_clone = status.clone();
+                        {for std::iter::once(None).chain(OrderStatus::ALL.map(Some)).map(|status| {
+                            let is_active = *selected_status == status;
+                            let status_clone = status;
                             let count = match status {
-                                OrderStatus::All => sample_orders.len(),
-                                OrderStatus::Pending => *status_counts.get("pending").unwrap_or(&0),
-                                OrderStatus::Processing => *status_counts.get("processing").unwrap_or(&0),
-                                OrderStatus::Shipped => *status_counts.get("shipped").unwrap_or(&0),
-                                OrderStatus::Delivered => *status_counts.get("delivered").unwrap_or(&0),
-                                OrderStatus::Cancelled => *status_counts.get("cancelled").unwrap_or(&0),
+                                None => sample_orders.len(),
+                                Some(status) => *status_counts.get(&status).unwrap_or(&0),
                             };
                             
                             let handle_click = {
                                 let handle_status_change = handle_status_change.clone();
                                 Callback::from(move |_| {
-                                    handle_status_change.emit(status_clone.clone());
+                                    handle_status_change.emit(status_clone);
                                 })
                             };
                             
@@ -225,7 +213,7 @@ pub fn order_management(props: &OrderManagementProps) -> Html {
                                         "py-4 px-1 border-b-2 border-transparent text-gray-500 hover:text-gray-700 flex items-center space-x-2 space-x-reverse"
                                     }}
                                 >
-                                    <span>{status.to_string()}</span>
+                                    <span>{status.map_or("همه", |status| status.label())}</span>
                                     <span class="bg-gray-100 text-gray-600 text-xs px-2 py-1 rounded-full">
                                         {count}
                                     </span>
@@ -278,15 +266,6 @@ pub fn order_management(props: &OrderManagementProps) -> Html {
                                 })
                             };
                             
-                            let status_from_string = match order.status.as_str() {
-                                "pending" => OrderStatus::Pending,
-                                "processing" => OrderStatus::Processing,
-                                "shipped" => OrderStatus::Shipped,
-                                "delivered" => OrderStatus::Delivered,
-                                "cancelled" => OrderStatus::Cancelled,
-                                _ => OrderStatus::All,
-                            };
-                            
                             html! {
                                 <div class="bg-white rounded-lg shadow-sm p-6 hover:shadow-md transition-shadow">
                                     <div class="flex items-center justify-between">
@@ -306,11 +285,11 @@ pub fn order_management(props: &OrderManagementProps) -> Html {
                                         </div>
                                         <div class="text-left">
                                             <p class="text-xl font-bold text-purple-600 mb-2">
-                                                {format!("{:.0} تومان", order.total_amount)}
+                                                {format_price(&order.total_amount)}
                                             </p>
                                             <span class={format!("inline-flex items-center px-2.5 py-0.5 rounded-full text-xs font-medium {}", 
-                                                status_from_string.to_badge_class())}>
-                                                {status_from_string.to_string()}
+                                                status_badge_class(order.status))}>
+                                                {order.status.label()}
                                             </span>
                                         </div>
                                         <div class="flex space-x-2 space-x-reverse">
@@ -399,11 +378,11 @@ pub fn order_details_modal(props: &OrderDetailsModalProps) -> Html {
                                 </div>
                                 <div class="flex justify-between">
                                     <span class="text-gray-600">{"وضعیت:"}</span>
-                                    <span class="font-medium">{&order.status}</span>
+                                    <span class="font-medium">{order.status.label()}</span>
                                 </div>
                                 <div class="flex justify-between">
                                     <span class="text-gray-600">{"مبلغ کل:"}</span>
-                                    <span class="font-bold text-purple-600">{format!("{:.0} تومان", order.total_amount)}</span>
+                                    <span class="font-bold text-purple-600">{format_price(&order.total_amount)}</span>
                                 </div>
                             </div>
                         </div>
@@ -1,5 +1,5 @@
 use yew::prelude::*;
-use models::product::Product;
+use models::{persian::format_price, product::Product};
 
 #[derive(Properties, PartialEq)]
 pub struct ProductCardProps {
@@ -80,7 +80,7 @@ pub fn product_card(props: &ProductCardProps) -> Html {
                 
                 <div class="flex justify-between items-center mb-3">
                     <div class="text-2xl font-bold text-purple-600">
-                        {format!("{:.0} تومان", product.price)}
+                        {format_price(&product.price)}
                     </div>
                     <div class="text-sm text-gray-500">
                         {"موجودی: "} {product.stock}
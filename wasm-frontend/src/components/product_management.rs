@@ -1,5 +1,5 @@
 use yew::prelude::*;
-use models::product::Product;
+use models::{category::{Category, CategoryNode}, money::{Currency, Money}, persian::format_price, product::Product};
 use crate::components::{ProductCard, PaginationComponent};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -14,20 +14,13 @@ pub enum ProductFilter {
     OutOfStock,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub enum ProductCategory {
-    All,
-    Electronics,
-    Clothing,
-    Books,
-    Home,
-    Sports,
-}
-
 #[derive(Properties, PartialEq)]
 pub struct ProductManagementProps {
     #[prop_or_default]
     pub products: Vec<Product>,
+    /// The category tree, as served by `categories/tree`.
+    #[prop_or_default]
+    pub categories: Vec<CategoryNode>,
     #[prop_or_default]
     pub on_product_update: Option<Callback<Product>>,
     #[prop_or_default]
@@ -36,11 +29,63 @@ pub struct ProductManagementProps {
     pub on_product_create: Option<Callback<Product>>,
 }
 
+/// Sample category tree for demonstration, with fixed ids so the selected
+/// category survives re-renders.
+fn sample_categories() -> Vec<CategoryNode> {
+    let category = |id: u128, parent: Option<u128>, slug: &str, name_fa: &str, name_en: &str| Category {
+        id: Uuid::from_u128(id),
+        parent_id: parent.map(Uuid::from_u128),
+        slug: slug.to_string(),
+        name_fa: name_fa.to_string(),
+        name_en: name_en.to_string(),
+        position: 0,
+        attributes: Vec::new(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+
+    CategoryNode::build(vec![
+        category(1, None, "electronics", "الکترونیک", "Electronics"),
+        category(11, Some(1), "mobile-phones", "گوشی موبایل", "Mobile phones"),
+        category(12, Some(1), "laptops", "لپ‌تاپ", "Laptops"),
+        category(13, Some(1), "audio", "صوتی", "Audio"),
+        category(2, None, "clothing", "پوشاک", "Clothing"),
+        category(3, None, "books", "کتاب", "Books"),
+        category(4, None, "home", "خانه و آشپزخانه", "Home"),
+        category(5, None, "sports", "ورزش", "Sports"),
+    ])
+}
+
+/// The icon of the top-level category a product is listed under.
+fn category_icon(categories: &[CategoryNode], category_id: Uuid) -> &'static str {
+    let root = categories.iter().find(|root| root.ids().contains(&category_id));
+    match root.map(|root| root.category.slug.as_str()) {
+        Some("electronics") => "📱",
+        Some("clothing") => "👕",
+        Some("books") => "📚",
+        Some("home") => "🏠",
+        Some("sports") => "⚽",
+        _ => "📦",
+    }
+}
+
+/// `<option>`s for every category, indented by depth.
+fn category_options(categories: &[CategoryNode], selected: Option<Uuid>) -> Html {
+    CategoryNode::flatten(categories)
+        .into_iter()
+        .map(|(depth, category)| html! {
+            <option value={category.id.to_string()} selected={selected == Some(category.id)}>
+                {format!("{}{}", "— ".repeat(depth), category.name_fa)}
+            </option>
+        })
+        .collect()
+}
+
 #[function_component(ProductManagement)]
 pub fn product_management(props: &ProductManagementProps) -> Html {
     let search_term = use_state(|| String::new());
     let selected_filter = use_state(|| ProductFilter::All);
-    let selected_category = use_state(|| ProductCategory::All);
+    let selected_category = use_state(|| None::<Uuid>);
     let current_page = use_state(|| 1u32);
     let show_create_modal = use_state(|| false);
     let show_edit_modal = use_state(|| false);
@@ -52,9 +97,9 @@ pub fn product_management(props: &ProductManagementProps) -> Html {
             id: Uuid::new_v4(),
             name: "گوشی هوشمند سامسونگ".to_string(),
             description: Some("گوشی هوشمند با کیفیت بالا".to_string()),
-            price: 15000000.0,
+            price: Money::rials(15_000_000),
             stock: 25,
-            category: "Electronics".to_string(),
+            category_id: Uuid::from_u128(11),
             vendor_id: Uuid::new_v4(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -63,9 +108,9 @@ pub fn product_management(props: &ProductManagementProps) -> Html {
             id: Uuid::new_v4(),
             name: "لپ‌تاپ ایسوس".to_string(),
             description: Some("لپ‌تاپ گیمینگ قدرتمند".to_string()),
-            price: 25000000.0,
+            price: Money::rials(25_000_000),
             stock: 12,
-            category: "Electronics".to_string(),
+            category_id: Uuid::from_u128(12),
             vendor_id: Uuid::new_v4(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -74,9 +119,9 @@ pub fn product_management(props: &ProductManagementProps) -> Html {
             id: Uuid::new_v4(),
             name: "تی‌شرت نخی".to_string(),
             description: Some("تی‌شرت راحت و با کیفیت".to_string()),
-            price: 150000.0,
+            price: Money::rials(150_000),
             stock: 50,
-            category: "Clothing".to_string(),
+            category_id: Uuid::from_u128(2),
             vendor_id: Uuid::new_v4(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -88,6 +133,11 @@ pub fn product_management(props: &ProductManagementProps) -> Html {
     } else {
         props.products.clone()
     };
+    let categories = if props.categories.is_empty() {
+        sample_categories()
+    } else {
+        props.categories.clone()
+    };
 
     let handle_search = {
         let search_term = search_term.clone();
@@ -107,7 +157,7 @@ pub fn product_management(props: &ProductManagementProps) -> Html {
 
     let handle_category_change = {
         let selected_category = selected_category.clone();
-        Callback::from(move |category: ProductCategory| {
+        Callback::from(move |category: Option<Uuid>| {
             selected_category.set(category);
         })
     };
@@ -146,6 +196,11 @@ pub fn product_management(props: &ProductManagementProps) -> Html {
         })
     };
 
+    // A category also lists the products of its subcategories
+    let selected_category_ids = selected_category
+        .and_then(|id| CategoryNode::find(&categories, id))
+        .map(CategoryNode::ids);
+
     // Filter products based on search and filters
     let filtered_products: Vec<Product> = products
         .into_iter()
@@ -161,14 +216,9 @@ pub fn product_management(props: &ProductManagementProps) -> Html {
                 ProductFilter::OutOfStock => product.stock == 0,
             };
 
-            let matches_category = match *selected_category {
-                ProductCategory::All => true,
-                ProductCategory::Electronics => product.category == "Electronics",
-                ProductCategory::Clothing => product.category == "Clothing",
-                ProductCategory::Books => product.category == "Books",
-                ProductCategory::Home => product.category == "Home",
-                ProductCategory::Sports => product.category == "Sports",
-            };
+            let matches_category = selected_category_ids
+                .as_ref()
+                .map_or(true, |ids| ids.contains(&product.category_id));
 
             matches_search && matches_filter && matches_category
         })
@@ -238,24 +288,12 @@ pub fn product_management(props: &ProductManagementProps) -> Html {
                             class="w-full px-4 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-purple-500 focus:border-transparent"
                             onchange={Callback::from(move |e: Event| {
                                 if let Some(select) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
-                                    let category = match select.value().as_str() {
-                                        "electronics" => ProductCategory::Electronics,
-                                        "clothing" => ProductCategory::Clothing,
-                                        "books" => ProductCategory::Books,
-                                        "home" => ProductCategory::Home,
-                                        "sports" => ProductCategory::Sports,
-                                        _ => ProductCategory::All,
-                                    };
-                                    handle_category_change.emit(category);
+                                    handle_category_change.emit(Uuid::parse_str(&select.value()).ok());
                                 }
                             })}
                         >
                             <option value="">{"همه دسته‌ها"}</option>
-                            <option value="electronics">{"الکترونیک"}</option>
-                            <option value="clothing">{"پوشاک"}</option>
-                            <option value="books">{"کتاب"}</option>
-                            <option value="home">{"خانه و آشپزخانه"}</option>
-                            <option value="sports">{"ورزش"}</option>
+                            {category_options(&categories, *selected_category)}
                         </select>
                     </div>
                 </div>
@@ -284,21 +322,14 @@ pub fn product_management(props: &ProductManagementProps) -> Html {
                                 <div class="bg-white rounded-lg shadow-sm overflow-hidden hover:shadow-md transition-shadow">
                                     <div class="p-4">
                                         <div class="text-4xl text-center mb-4">
-                                            {match product.category.as_str() {
-                                                "Electronics" => "📱",
-                                                "Clothing" => "👕",
-                                                "Books" => "📚",
-                                                "Home" => "🏠",
-                                                "Sports" => "⚽",
-                                                _ => "📦",
-                                            }}
+                                            {category_icon(&categories, product.category_id)}
                                         </div>
                                         <h3 class="font-semibold text-gray-900 mb-2 text-center">{&product.name}</h3>
                                         <p class="text-sm text-gray-600 mb-3 text-center line-clamp-2">
                                             {product.description.as_ref().unwrap_or(&"توضیحات موجود نیست".to_string())}
                                         </p>
                                         <div class="text-center mb-3">
-                                            <p class="text-lg font-bold text-purple-600">{format!("{:.0} تومان", product.price)}</p>
+                                            <p class="text-lg font-bold text-purple-600">{format_price(&product.price)}</p>
                                             <p class={format!("text-sm {}", 
                                                 if product.stock > 10 { "text-green-600" }
                                                 else if product.stock > 0 { "text-yellow-600" }
@@ -343,6 +374,7 @@ pub fn product_management(props: &ProductManagementProps) -> Html {
             if *show_create_modal {
                 <ProductModal 
                     product={None}
+                    categories={categories.clone()}
                     on_close={handle_close_modals.clone()}
                     on_save={Callback::from(|_| {})}
                 />
@@ -352,6 +384,7 @@ pub fn product_management(props: &ProductManagementProps) -> Html {
             if *show_edit_modal {
                 <ProductModal 
                     product={(*editing_product).clone()}
+                    categories={categories.clone()}
                     on_close={handle_close_modals.clone()}
                     on_save={Callback::from(|_| {})}
                 />
@@ -363,6 +396,8 @@ pub fn product_management(props: &ProductManagementProps) -> Html {
 #[derive(Properties, PartialEq)]
 pub struct ProductModalProps {
     pub product: Option<Product>,
+    #[prop_or_default]
+    pub categories: Vec<CategoryNode>,
     pub on_close: Callback<()>,
     pub on_save: Callback<Product>,
 }
@@ -371,9 +406,9 @@ pub struct ProductModalProps {
 pub fn product_modal(props: &ProductModalProps) -> Html {
     let name = use_state(|| props.product.as_ref().map(|p| p.name.clone()).unwrap_or_default());
     let description = use_state(|| props.product.as_ref().map(|p| p.description.clone()).unwrap_or_default());
-    let price = use_state(|| props.product.as_ref().map(|p| p.price.to_string()).unwrap_or_default());
+    let price = use_state(|| props.product.as_ref().map(|p| p.price.in_currency(Currency::Irt).unwrap_or(p.price).to_decimal_string()).unwrap_or_default());
     let stock = use_state(|| props.product.as_ref().map(|p| p.stock.to_string()).unwrap_or_default());
-    let category = use_state(|| props.product.as_ref().map(|p| p.category.clone()).unwrap_or_default());
+    let category_id = use_state(|| props.product.as_ref().map(|p| p.category_id));
 
     let is_edit = props.product.is_some();
     let title = if is_edit { "ویرایش محصول" } else { "افزودن محصول جدید" };
@@ -438,15 +473,10 @@ pub fn product_modal(props: &ProductModalProps) -> Html {
                         <div>
                             <label class="block text-sm font-medium text-gray-700 mb-2">{"دسته‌بندی"}</label>
                             <select 
-                                value={(*category).clone()}
                                 class="w-full px-3 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-purple-500 focus:border-transparent"
                             >
                                 <option value="">{"انتخاب دسته‌بندی"}</option>
-                                <option value="Electronics">{"الکترونیک"}</option>
-                                <option value="Clothing">{"پوشاک"}</option>
-                                <option value="Books">{"کتاب"}</option>
-                                <option value="Home">{"خانه و آشپزخانه"}</option>
-                                <option value="Sports">{"ورزش"}</option>
+                                {category_options(&props.categories, *category_id)}
                             </select>
                         </div>
 
@@ -3,7 +3,10 @@ use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use tera::{Tera, Context};
 use uuid::Uuid;
-use models::user::User;
+use config::AppConfig;
+use models::{auth::EmailOrPhone, identity::{SocialLoginRequest, SocialProvider}, money::Money};
+use wasm_auth_backend::{error::ServiceError, jwt::KeyRing, otp::OtpIssuer, service, social::SocialVerifier};
+use crate::{error::AppError, validation::Validator};
 
 #[derive(Deserialize)]
 pub struct AuthRequest {
@@ -27,22 +30,12 @@ pub struct RegisterRequest {
     pub username: String,
     pub email: String,
     pub phone: String,
+    pub password: String,
     pub code: String,
-}
-
-#[derive(Deserialize)]
-pub struct SocialLoginRequest {
-    pub provider: String,
-    pub access_token: String,
-    pub user_info: SocialUserInfo,
-}
-
-#[derive(Deserialize)]
-pub struct SocialUserInfo {
-    pub id: String,
-    pub email: Option<String>,
-    pub name: String,
-    pub avatar_url: Option<String>,
+    /// Where the code was sent; older clients omit it, in which case the
+    /// phone number (or else the email) is assumed.
+    #[serde(default)]
+    pub email_or_phone: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -71,199 +64,209 @@ pub async fn landing_page(tera: web::Data<Tera>) -> Result<HttpResponse> {
 
 pub async fn check_user(
     pool: web::Data<PgPool>,
+    otp: web::Data<OtpIssuer>,
     req: web::Json<AuthRequest>,
 ) -> Result<HttpResponse> {
-    // Check if user exists by email or phone
-    let user_exists = sqlx::query_scalar::<_, bool>(
-        "SELECT EXISTS(SELECT 1 FROM users WHERE email = $1 OR username = $1)"
-    )
-    .bind(&req.email_or_phone)
-    .fetch_one(pool.get_ref())
-    .await
-    .unwrap_or(false);
+    let target = Validator::validate_email_or_phone(req.email_or_phone.trim())?;
+
+    let user_exists = service::find_user_by_login(pool.get_ref(), &req.email_or_phone)
+        .await
+        .map(|user| user.is_some())
+        .unwrap_or(false);
 
-    let response = if user_exists {
-        AuthResponse {
+    if user_exists {
+        return Ok(HttpResponse::Ok().json(AuthResponse {
             exists: true,
             message: "کاربر موجود است. لطفاً رمز عبور خود را وارد کنید.".to_string(),
-        }
-    } else {
-        // Send verification code (simulate)
-        // In real implementation, integrate with notification service
-        AuthResponse {
-            exists: false,
-            message: "کد تایید به شماره/ایمیل شما ارسال شد.".to_string(),
-        }
-    };
+        }));
+    }
 
-    Ok(HttpResponse::Ok().json(response))
+    if let Err(err) = service::request_otp(pool.get_ref(), &otp, &target).await {
+        return Ok(otp_error_response(err));
+    }
+
+    Ok(HttpResponse::Ok().json(AuthResponse {
+        exists: false,
+        message: "کد تایید به شماره/ایمیل شما ارسال شد.".to_string(),
+    }))
 }
 
-pub async fn verify_code(req: web::Json<VerifyRequest>) -> Result<HttpResponse> {
-    // Simulate code verification (in real app, check against stored code)
-    // In production, this would verify against a code stored in Redis or database
-    if req.code == "1234" {
-        Ok(HttpResponse::Ok().json(serde_json::json!({
+pub async fn verify_code(
+    pool: web::Data<PgPool>,
+    otp: web::Data<OtpIssuer>,
+    req: web::Json<VerifyRequest>,
+) -> Result<HttpResponse> {
+    let target = Validator::validate_email_or_phone(req.email_or_phone.trim())?;
+    Validator::validate_verification_code(&req.code)?;
+
+    // The code stays valid so the registration step can submit it again.
+    match service::verify_otp(pool.get_ref(), &otp, &target, &req.code).await {
+        Ok(()) => Ok(HttpResponse::Ok().json(serde_json::json!({
             "success": true,
             "message": "کد تایید صحیح است."
-        })))
-    } else {
-        Ok(HttpResponse::BadRequest().json(serde_json::json!({
-            "success": false,
-            "message": "کد تایید نادرست است."
-        })))
+        }))),
+        Err(err) => Ok(otp_error_response(err)),
     }
 }
 
 pub async fn login(
     pool: web::Data<PgPool>,
+    keys: web::Data<KeyRing>,
+    config: web::Data<AppConfig>,
     req: web::Json<LoginRequest>,
 ) -> Result<HttpResponse> {
-    // Verify user credentials
-    let user = sqlx::query_as::<_, User>(
-        "SELECT * FROM users WHERE email = $1 OR username = $1"
-    )
-    .bind(&req.email_or_phone)
-    .fetch_optional(pool.get_ref())
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    let request = models::auth::LoginRequest {
+        email: req.email_or_phone.clone(),
+        password: req.password.clone(),
+    };
 
-    if let Some(user) = user {
-        // In real app, verify password hash using bcrypt
-        // For now, simulate successful login
-        let response = LoginResponse {
+    match service::login(pool.get_ref(), &keys, &config.login, request).await {
+        Ok(auth) => Ok(HttpResponse::Ok().json(LoginResponse {
             success: true,
             message: "ورود موفقیت‌آمیز بود.".to_string(),
-            user_id: Some(user.id),
-            token: Some(format!("token_{}", user.id)),
-        };
-        Ok(HttpResponse::Ok().json(response))
-    } else {
-        let response = LoginResponse {
-            success: false,
-            message: "نام کاربری یا رمز عبور نادرست است.".to_string(),
-            user_id: None,
-            token: None,
-        };
-        Ok(HttpResponse::Unauthorized().json(response))
+            user_id: Some(auth.user.id),
+            token: Some(auth.token),
+        })),
+        Err(err) => {
+            let (mut response, message) = match err {
+                ServiceError::TooManyRequests(_) => (
+                    HttpResponse::TooManyRequests(),
+                    "به دلیل تلاش‌های ناموفق متعدد، حساب شما موقتاً قفل شده است. لطفاً بعداً دوباره تلاش کنید.",
+                ),
+                ServiceError::Unauthorized(_) => (HttpResponse::Unauthorized(), "نام کاربری یا رمز عبور نادرست است."),
+                _ => (HttpResponse::InternalServerError(), "ورود با خطا مواجه شد."),
+            };
+            Ok(response.json(LoginResponse {
+                success: false,
+                message: message.to_string(),
+                user_id: None,
+                token: None,
+            }))
+        }
     }
 }
 
 pub async fn register(
     pool: web::Data<PgPool>,
+    keys: web::Data<KeyRing>,
+    otp: web::Data<OtpIssuer>,
     req: web::Json<RegisterRequest>,
 ) -> Result<HttpResponse> {
-    // Verify code first
-    if req.code != "1234" {
-        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-            "success": false,
-            "message": "کد تایید نادرست است."
-        })));
-    }
+    Validator::validate_verification_code(&req.code)?;
+    Validator::validate_password(&req.password)?;
+    let target = match req.email_or_phone.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(target) => Validator::validate_email_or_phone(target)?,
+        None if !req.phone.trim().is_empty() => EmailOrPhone::Phone(req.phone.trim().to_string()),
+        None => EmailOrPhone::Email(req.email.trim().to_string()),
+    };
 
-    // Check if user already exists
-    let existing_user = sqlx::query_scalar::<_, bool>(
-        "SELECT EXISTS(SELECT 1 FROM users WHERE email = $1 OR username = $2)"
-    )
-    .bind(&req.email)
-    .bind(&req.username)
-    .fetch_one(pool.get_ref())
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    // The code only proves ownership of `target`, so the account must be
+    // registered under that same contact.
+    let verified = match &target {
+        EmailOrPhone::Phone(_) => EmailOrPhone::Phone(req.phone.clone()).normalized() == target.normalized(),
+        EmailOrPhone::Email(_) => req.email.trim().to_lowercase() == target.normalized(),
+    };
+    if !verified {
+        return Err(AppError::ValidationError("کد تایید برای این ایمیل یا شماره تلفن ارسال نشده است".to_string()).into());
+    }
 
-    if existing_user {
-        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-            "success": false,
-            "message": "کاربر با این ایمیل یا نام کاربری قبلاً ثبت‌نام کرده است."
-        })));
+    // Verify code first
+    if let Err(err) = service::consume_otp(pool.get_ref(), &otp, &target, &req.code).await {
+        return Ok(otp_error_response(err));
     }
 
-    // Create new user
-    let user_id = Uuid::new_v4();
-    let user = sqlx::query_as::<_, User>(
-        "INSERT INTO users (id, username, email, password_hash, created_at, updated_at) 
-         VALUES ($1, $2, $3, $4, NOW(), NOW()) RETURNING *"
-    )
-    .bind(user_id)
-    .bind(&req.username)
-    .bind(&req.email)
-    .bind("temp_password_hash") // In real app, hash the password
-    .fetch_one(pool.get_ref())
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    let request = models::auth::RegisterRequest {
+        email: req.email.trim().to_string(),
+        password: req.password.clone(),
+        name: req.username.trim().to_string(),
+        phone: Some(req.phone.trim().to_string()).filter(|phone| !phone.is_empty()),
+    };
+
+    let auth = match service::register(pool.get_ref(), &keys, request).await {
+        Ok(auth) => auth,
+        Err(ServiceError::DuplicateEntry(_)) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "message": "کاربر با این ایمیل، شماره تلفن یا نام کاربری قبلاً ثبت‌نام کرده است."
+            })));
+        }
+        Err(err) => return Err(actix_web::error::ErrorInternalServerError(err)),
+    };
 
     let response = LoginResponse {
         success: true,
         message: "ثبت‌نام با موفقیت انجام شد.".to_string(),
-        user_id: Some(user.id),
-        token: Some(format!("token_{}", user.id)),
+        user_id: Some(auth.user.id),
+        token: Some(auth.token),
     };
 
     Ok(HttpResponse::Created().json(response))
 }
 
+/// The provider's token is verified by the auth service, which also
+/// issues the session token.
 pub async fn social_login(
     pool: web::Data<PgPool>,
+    keys: web::Data<KeyRing>,
+    verifier: web::Data<SocialVerifier>,
     req: web::Json<SocialLoginRequest>,
 ) -> Result<HttpResponse> {
-    // Verify the social login token (in real app, verify with the provider)
-    // For now, simulate successful verification
-    
-    let default_email = format!("{}@{}.com", req.user_info.id, req.provider);
-    let email = req.user_info.email.as_ref().unwrap_or(&default_email);
-    
-    // Check if user exists
-    let existing_user = sqlx::query_as::<_, User>(
-        "SELECT * FROM users WHERE email = $1"
-    )
-    .bind(email)
-    .fetch_optional(pool.get_ref())
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    let provider_name = match req.provider {
+        SocialProvider::Google => "گوگل",
+        SocialProvider::Github => "گیت‌هاب",
+        SocialProvider::Linkedin => "لینکدین",
+        SocialProvider::Meta => "متا",
+    };
 
-    let user = if let Some(user) = existing_user {
-        // User exists, update last login
-        sqlx::query(
-            "UPDATE users SET updated_at = NOW() WHERE id = $1"
-        )
-        .bind(user.id)
-        .execute(pool.get_ref())
-        .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
-        
-        user
-    } else {
-        // Create new user from social login
-        let user_id = Uuid::new_v4();
-        sqlx::query_as::<_, User>(
-            "INSERT INTO users (id, username, email, password_hash, created_at, updated_at) 
-             VALUES ($1, $2, $3, $4, NOW(), NOW()) RETURNING *"
-        )
-        .bind(user_id)
-        .bind(&req.user_info.name)
-        .bind(email)
-        .bind("social_login") // Mark as social login
-        .fetch_one(pool.get_ref())
-        .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?
+    let auth = match service::social_login(pool.get_ref(), &keys, &verifier, req.into_inner()).await {
+        Ok(auth) => auth,
+        Err(err) => {
+            let (mut response, message) = match err {
+                ServiceError::BadRequest(_) => (HttpResponse::BadRequest(), format!("ورود با {} در حال حاضر امکان‌پذیر نیست.", provider_name)),
+                ServiceError::Unauthorized(_) => (HttpResponse::Unauthorized(), format!("تایید هویت با {} ناموفق بود.", provider_name)),
+                ServiceError::DuplicateEntry(_) => (
+                    HttpResponse::Conflict(),
+                    format!("حسابی با این ایمیل وجود دارد. ابتدا وارد شوید و سپس حساب {} را متصل کنید.", provider_name),
+                ),
+                _ => (HttpResponse::InternalServerError(), "ورود با شبکه اجتماعی با خطا مواجه شد.".to_string()),
+            };
+            return Ok(response.json(LoginResponse {
+                success: false,
+                message,
+                user_id: None,
+                token: None,
+            }));
+        }
     };
 
     let response = LoginResponse {
         success: true,
-        message: format!("ورود با {} موفقیت‌آمیز بود.", match req.provider.as_str() {
-            "google" => "گوگل",
-            "github" => "گیت‌هاب",
-            "linkedin" => "لینکدین",
-            "meta" => "متا",
-            _ => "شبکه اجتماعی"
-        }),
-        user_id: Some(user.id),
-        token: Some(format!("token_{}_{}", req.provider, user.id)),
+        message: format!("ورود با {} موفقیت‌آمیز بود.", provider_name),
+        user_id: Some(auth.user.id),
+        token: Some(auth.token),
     };
 
     Ok(HttpResponse::Ok().json(response))
 }
 
+/// Translates OTP service failures into the `{success, message}` shape the landing page script expects.
+fn otp_error_response(err: ServiceError) -> HttpResponse {
+    match err {
+        ServiceError::TooManyRequests(_) => HttpResponse::TooManyRequests().json(serde_json::json!({
+            "success": false,
+            "message": "تعداد درخواست‌ها بیش از حد مجاز است. لطفاً کمی بعد دوباره تلاش کنید."
+        })),
+        ServiceError::BadRequest(_) => HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "message": "کد تایید نادرست یا منقضی شده است."
+        })),
+        _ => HttpResponse::InternalServerError().json(serde_json::json!({
+            "success": false,
+            "message": "ارسال یا بررسی کد تایید با خطا مواجه شد."
+        })),
+    }
+}
+
 pub async fn get_products() -> Result<HttpResponse> {
     // Call product-catalog service
     let client = reqwest::Client::new();
@@ -286,7 +289,7 @@ pub async fn get_products() -> Result<HttpResponse> {
                         "id": "550e8400-e29b-41d4-a716-446655440001",
                         "name": "لپ‌تاپ گیمینگ ASUS ROG",
                         "description": "لپ‌تاپ قدرتمند با پردازنده Intel Core i7 و کارت گرافیک RTX 4060",
-                        "price": 45000000,
+                        "price": Money::rials(45_000_000),
                         "stock_quantity": 5,
                         "category": "لپ‌تاپ",
                         "image_url": "/static/images/laptop.jpg"
@@ -295,7 +298,7 @@ pub async fn get_products() -> Result<HttpResponse> {
                         "id": "550e8400-e29b-41d4-a716-446655440002",
                         "name": "گوشی هوشمند Samsung Galaxy S24",
                         "description": "گوشی پرچمدار سامسونگ با دوربین 200 مگاپیکسل و نمایشگر Dynamic AMOLED",
-                        "price": 28000000,
+                        "price": Money::rials(28_000_000),
                         "stock_quantity": 12,
                         "category": "گوشی موبایل",
                         "image_url": "/static/images/phone.jpg"
@@ -304,7 +307,7 @@ pub async fn get_products() -> Result<HttpResponse> {
                         "id": "550e8400-e29b-41d4-a716-446655440003",
                         "name": "هدفون بی‌سیم Sony WH-1000XM5",
                         "description": "هدفون با کیفیت صدای Hi-Res و قابلیت حذف نویز فعال",
-                        "price": 8500000,
+                        "price": Money::rials(8_500_000),
                         "stock_quantity": 20,
                         "category": "صوتی",
                         "image_url": "/static/images/headphones.jpg"
@@ -313,7 +316,7 @@ pub async fn get_products() -> Result<HttpResponse> {
                         "id": "550e8400-e29b-41d4-a716-446655440004",
                         "name": "ساعت هوشمند Apple Watch Series 9",
                         "description": "ساعت هوشمند اپل با سنسور اکسیژن خون و GPS دقیق",
-                        "price": 15000000,
+                        "price": Money::rials(15_000_000),
                         "stock_quantity": 8,
                         "category": "پوشیدنی",
                         "image_url": "/static/images/watch.jpg"
@@ -322,7 +325,7 @@ pub async fn get_products() -> Result<HttpResponse> {
                         "id": "550e8400-e29b-41d4-a716-446655440005",
                         "name": "تبلت iPad Pro 12.9 اینچ",
                         "description": "تبلت حرفه‌ای اپل با تراشه M2 و نمایشگر Liquid Retina XDR",
-                        "price": 35000000,
+                        "price": Money::rials(35_000_000),
                         "stock_quantity": 3,
                         "category": "تبلت",
                         "image_url": "/static/images/tablet.jpg"
@@ -331,7 +334,7 @@ pub async fn get_products() -> Result<HttpResponse> {
                         "id": "550e8400-e29b-41d4-a716-446655440006",
                         "name": "کیبورد مکانیکی Logitech MX Keys",
                         "description": "کیبورد بی‌سیم با کلیدهای مکانیکی و نورپردازی هوشمند",
-                        "price": 3200000,
+                        "price": Money::rials(3_200_000),
                         "stock_quantity": 15,
                         "category": "جانبی",
                         "image_url": "/static/images/keyboard.jpg"
@@ -5,6 +5,13 @@ mod components;
 mod services;
 mod state;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod error;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod handlers;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod validation;
+
 use components::{LandingPage, AdminDashboard, DashboardStats};
 use services::{ApiService, AuthService};
 use services::auth::TokenStorage;
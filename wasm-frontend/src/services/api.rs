@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{Request, RequestInit, RequestMode, Response, Headers};
+use web_sys::{Blob, BlobPropertyBag, FormData, Request, RequestInit, RequestMode, Response, Headers};
 use js_sys::Promise;
 use std::collections::HashMap;
 
@@ -119,6 +119,61 @@ impl ApiService {
             code: None,
         })?;
 
+        self.send(request).await
+    }
+
+    /// Posts a file as `multipart/form-data` in the field `file`, with
+    /// `fields` as text fields. The browser sets the content type and its
+    /// boundary.
+    pub async fn upload<T>(&self, endpoint: &str, file: &[u8], content_type: &str, fields: &[(&str, &str)]) -> ApiResult<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let url = format!("{}/{}", self.base_url.trim_end_matches('/'), endpoint.trim_start_matches('/'));
+        let form_error = |_| ApiError {
+            message: "Failed to build upload form".to_string(),
+            code: None,
+        };
+
+        let blob_options = BlobPropertyBag::new();
+        blob_options.set_type(content_type);
+        let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(file));
+        let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &blob_options).map_err(form_error)?;
+        let form = FormData::new().map_err(form_error)?;
+        form.append_with_blob_and_filename("file", &blob, "upload").map_err(form_error)?;
+        for (name, value) in fields {
+            form.append_with_str(name, value).map_err(form_error)?;
+        }
+
+        let opts = RequestInit::new();
+        opts.set_method("POST");
+        opts.set_mode(RequestMode::Cors);
+
+        let headers = Headers::new().map_err(|_| ApiError {
+            message: "Failed to create headers".to_string(),
+            code: None,
+        })?;
+        if let Some(token) = &self.auth_token {
+            headers.set("Authorization", &format!("Bearer {}", token)).map_err(|_| ApiError {
+                message: "Failed to set authorization header".to_string(),
+                code: None,
+            })?;
+        }
+        opts.set_headers(&headers);
+        opts.set_body(&form);
+
+        let request = Request::new_with_str_and_init(&url, &opts).map_err(|_| ApiError {
+            message: "Failed to create request".to_string(),
+            code: None,
+        })?;
+
+        self.send(request).await
+    }
+
+    async fn send<T>(&self, request: Request) -> ApiResult<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
         let window = web_sys::window().ok_or_else(|| ApiError {
             message: "No global window object".to_string(),
             code: None,
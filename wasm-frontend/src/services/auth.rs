@@ -1,35 +1,9 @@
 use models::user::User;
+pub use models::auth::{AuthResponse, LoginRequest, RefreshTokenRequest, RegisterRequest};
 use super::api::{ApiService, ApiResult};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LoginRequest {
-    pub email: String,
-    pub password: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RegisterRequest {
-    pub email: String,
-    pub password: String,
-    pub name: String,
-    pub phone: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AuthResponse {
-    pub user: User,
-    pub token: String,
-    pub refresh_token: String,
-    pub expires_in: u64,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RefreshTokenRequest {
-    pub refresh_token: String,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChangePasswordRequest {
     pub current_password: String,
@@ -1,4 +1,7 @@
+use models::money::Money;
 use models::order::Order;
+use models::payment::{CreatePayment, Payment};
+pub use models::order::{CreateOrder, OrderItem, OrderStatus, OrderStatusChange, UpdateOrderStatus};
 use models::pagination::PaginatedResponse;
 use super::api::{ApiService, ApiResult};
 use uuid::Uuid;
@@ -9,7 +12,7 @@ use chrono::{DateTime, Utc};
 pub struct OrderQuery {
     pub page: Option<u32>,
     pub limit: Option<u32>,
-    pub status: Option<String>,
+    pub status: Option<OrderStatus>,
     pub user_id: Option<Uuid>,
     pub from_date: Option<DateTime<Utc>>,
     pub to_date: Option<DateTime<Utc>>,
@@ -42,8 +45,8 @@ impl OrderQuery {
         if let Some(limit) = self.limit {
             params.push(format!("limit={}", limit));
         }
-        if let Some(status) = &self.status {
-            params.push(format!("status={}", urlencoding::encode(status)));
+        if let Some(status) = self.status {
+            params.push(format!("status={}", status));
         }
         if let Some(user_id) = self.user_id {
             params.push(format!("user_id={}", user_id));
@@ -69,27 +72,6 @@ impl OrderQuery {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CreateOrder {
-    pub user_id: Uuid,
-    pub items: Vec<OrderItem>,
-    pub shipping_address: Option<String>,
-    pub notes: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct OrderItem {
-    pub product_id: Uuid,
-    pub quantity: i32,
-    pub price: f64,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UpdateOrderStatus {
-    pub status: String,
-    pub notes: Option<String>,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderStats {
     pub total_orders: u32,
@@ -98,8 +80,8 @@ pub struct OrderStats {
     pub shipped_orders: u32,
     pub delivered_orders: u32,
     pub cancelled_orders: u32,
-    pub total_revenue: f64,
-    pub average_order_value: f64,
+    pub total_revenue: Money,
+    pub average_order_value: Money,
 }
 
 pub struct OrderService {
@@ -135,6 +117,12 @@ impl OrderService {
         self.api.put(&endpoint, Some(status_update)).await
     }
 
+    /// Get the status changes of an order, oldest first
+    pub async fn get_order_history(&self, id: Uuid) -> ApiResult<Vec<OrderStatusChange>> {
+        let endpoint = format!("orders/{}/history", id);
+        self.api.get(&endpoint).await
+    }
+
     /// Cancel an order
     pub async fn cancel_order(&self, id: Uuid, reason: Option<String>) -> ApiResult<Order> {
         let endpoint = format!("orders/{}/cancel", id);
@@ -154,9 +142,9 @@ impl OrderService {
     }
 
     /// Get orders by status
-    pub async fn get_orders_by_status(&self, status: &str, page: Option<u32>, limit: Option<u32>) -> ApiResult<PaginatedResponse<Order>> {
+    pub async fn get_orders_by_status(&self, status: OrderStatus, page: Option<u32>, limit: Option<u32>) -> ApiResult<PaginatedResponse<Order>> {
         let query = OrderQuery {
-            status: Some(status.to_string()),
+            status: Some(status),
             page,
             limit,
             ..Default::default()
@@ -214,14 +202,16 @@ impl OrderService {
         self.api.put(&endpoint, Some(body)).await
     }
 
-    /// Process payment for an order
-    pub async fn process_payment(&self, order_id: Uuid, payment_method: String, payment_details: serde_json::Value) -> ApiResult<Order> {
-        let endpoint = format!("orders/{}/payment", order_id);
-        let body = serde_json::json!({
-            "payment_method": payment_method,
-            "payment_details": payment_details
-        });
-        self.api.post(&endpoint, Some(body)).await
+    /// Starts paying for an order. The user has to be sent to the returned
+    /// payment's `redirect_url` to pay at the gateway.
+    pub async fn process_payment(&self, order_id: Uuid, amount: Money) -> ApiResult<Payment> {
+        self.api.post("payments", Some(CreatePayment { order_id, amount })).await
+    }
+
+    /// Looks up a payment, e.g. after the gateway sent the user back.
+    pub async fn get_payment(&self, payment_id: Uuid) -> ApiResult<Payment> {
+        let endpoint = format!("payments/{}", payment_id);
+        self.api.get(&endpoint).await
     }
 }
 
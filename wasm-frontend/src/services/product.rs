@@ -1,4 +1,9 @@
-use models::product::{Product, CreateProduct, UpdateProduct};
+use models::category::{Category, CategoryNode, CreateCategory, UpdateCategory};
+use models::exchange_rate::{ExchangeRate, UpdateExchangeRate};
+use models::media::{Image, UpdateImage};
+use models::money::{Currency, Money};
+use models::pricing::{CreatePriceLock, Metal, MetalRate, PriceChange, PriceLockWithItems, PricingRule, SetPricingRule, UpdateMetalRate};
+use models::product::{Product, CreateProduct, CreateProductVariant, ProductSearchResult, ProductVariant, UpdateProduct, UpdateProductVariant};
 use models::pagination::PaginatedResponse;
 use super::api::{ApiService, ApiResult};
 use uuid::Uuid;
@@ -9,11 +14,15 @@ pub struct ProductQuery {
     pub page: Option<u32>,
     pub limit: Option<u32>,
     pub search: Option<String>,
+    /// Category slug; subcategories are included.
     pub category: Option<String>,
     pub vendor_id: Option<Uuid>,
-    pub min_price: Option<f64>,
-    pub max_price: Option<f64>,
+    /// Price bounds, in rials.
+    pub min_price: Option<Money>,
+    pub max_price: Option<Money>,
     pub in_stock: Option<bool>,
+    /// Currency to show prices in; rials when unset.
+    pub currency: Option<Currency>,
 }
 
 impl Default for ProductQuery {
@@ -27,6 +36,7 @@ impl Default for ProductQuery {
             min_price: None,
             max_price: None,
             in_stock: None,
+            currency: None,
         }
     }
 }
@@ -51,14 +61,17 @@ impl ProductQuery {
             params.push(format!("vendor_id={}", vendor_id));
         }
         if let Some(min_price) = self.min_price {
-            params.push(format!("min_price={}", min_price));
+            params.push(format!("min_price={}", min_price.minor_units()));
         }
         if let Some(max_price) = self.max_price {
-            params.push(format!("max_price={}", max_price));
+            params.push(format!("max_price={}", max_price.minor_units()));
         }
         if let Some(in_stock) = self.in_stock {
             params.push(format!("in_stock={}", in_stock));
         }
+        if let Some(currency) = self.currency {
+            params.push(format!("currency={}", currency));
+        }
 
         if params.is_empty() {
             String::new()
@@ -107,7 +120,7 @@ impl ProductService {
         self.api.delete(&endpoint).await
     }
 
-    /// Get products by category
+    /// Get products by category slug, including its subcategories
     pub async fn get_products_by_category(&self, category: &str, page: Option<u32>, limit: Option<u32>) -> ApiResult<PaginatedResponse<Product>> {
         let query = ProductQuery {
             category: Some(category.to_string()),
@@ -129,15 +142,66 @@ impl ProductService {
         self.get_products(Some(query)).await
     }
 
-    /// Search products
-    pub async fn search_products(&self, search_term: &str, page: Option<u32>, limit: Option<u32>) -> ApiResult<PaginatedResponse<Product>> {
+    /// Search products, best matches first, with the counts per category,
+    /// price range and stock of the matches
+    pub async fn search_products(&self, search_term: &str, page: Option<u32>, limit: Option<u32>) -> ApiResult<ProductSearchResult> {
         let query = ProductQuery {
             search: Some(search_term.to_string()),
             page,
             limit,
             ..Default::default()
         };
-        self.get_products(Some(query)).await
+        let endpoint = format!("products{}", query.to_query_string());
+        self.api.get(&endpoint).await
+    }
+
+    /// Get the variants of a product, each with its SKU, attributes, price and stock
+    pub async fn get_product_variants(&self, product_id: Uuid) -> ApiResult<Vec<ProductVariant>> {
+        let endpoint = format!("products/{}/variants", product_id);
+        self.api.get(&endpoint).await
+    }
+
+    /// Add a variant to a product
+    pub async fn create_product_variant(&self, product_id: Uuid, variant: CreateProductVariant) -> ApiResult<ProductVariant> {
+        let endpoint = format!("products/{}/variants", product_id);
+        self.api.post(&endpoint, Some(variant)).await
+    }
+
+    /// Update a variant's SKU, attributes, price or stock
+    pub async fn update_product_variant(&self, product_id: Uuid, variant_id: Uuid, variant: UpdateProductVariant) -> ApiResult<ProductVariant> {
+        let endpoint = format!("products/{}/variants/{}", product_id, variant_id);
+        self.api.put(&endpoint, Some(variant)).await
+    }
+
+    /// Delete a variant that was never stocked or ordered
+    pub async fn delete_product_variant(&self, product_id: Uuid, variant_id: Uuid) -> ApiResult<()> {
+        let endpoint = format!("products/{}/variants/{}", product_id, variant_id);
+        self.api.delete(&endpoint).await
+    }
+
+    /// Upload an image of a product; thumbnails are made from it
+    pub async fn upload_product_image(&self, product_id: Uuid, data: Vec<u8>, content_type: String, alt_text: Option<String>) -> ApiResult<Image> {
+        let endpoint = format!("products/{}/images", product_id);
+        let fields: Vec<(&str, &str)> = alt_text.as_deref().map(|alt_text| ("alt_text", alt_text)).into_iter().collect();
+        self.api.upload(&endpoint, &data, &content_type, &fields).await
+    }
+
+    /// Get a product's images in display order, each with its thumbnails
+    pub async fn get_product_images(&self, product_id: Uuid) -> ApiResult<Vec<Image>> {
+        let endpoint = format!("products/{}/images", product_id);
+        self.api.get(&endpoint).await
+    }
+
+    /// Update an image's description or position
+    pub async fn update_product_image(&self, product_id: Uuid, image_id: Uuid, image: UpdateImage) -> ApiResult<Image> {
+        let endpoint = format!("products/{}/images/{}", product_id, image_id);
+        self.api.put(&endpoint, Some(image)).await
+    }
+
+    /// Delete an image and its thumbnails
+    pub async fn delete_product_image(&self, product_id: Uuid, image_id: Uuid) -> ApiResult<()> {
+        let endpoint = format!("products/{}/images/{}", product_id, image_id);
+        self.api.delete(&endpoint).await
     }
 
     /// Get low stock products
@@ -146,11 +210,84 @@ impl ProductService {
         self.api.get(&endpoint).await
     }
 
-    /// Get product categories
+    /// Get the slugs of the categories products are listed in
     pub async fn get_categories(&self) -> ApiResult<Vec<String>> {
         self.api.get("products/categories").await
     }
 
+    /// Get the category tree to render category menus and filters from
+    pub async fn get_category_tree(&self) -> ApiResult<Vec<CategoryNode>> {
+        self.api.get("categories/tree").await
+    }
+
+    /// Create a category (admins only)
+    pub async fn create_category(&self, category: CreateCategory) -> ApiResult<Category> {
+        self.api.post("categories", Some(category)).await
+    }
+
+    /// Rename, move or reorder a category or change its attributes (admins only)
+    pub async fn update_category(&self, id: Uuid, category: UpdateCategory) -> ApiResult<Category> {
+        let endpoint = format!("categories/{}", id);
+        self.api.put(&endpoint, Some(category)).await
+    }
+
+    /// Delete an empty category (admins only)
+    pub async fn delete_category(&self, id: Uuid) -> ApiResult<()> {
+        let endpoint = format!("categories/{}", id);
+        self.api.delete(&endpoint).await
+    }
+
+    /// Current rates of the foreign currencies prices can be shown in
+    pub async fn get_exchange_rates(&self) -> ApiResult<Vec<ExchangeRate>> {
+        self.api.get("exchange-rates").await
+    }
+
+    /// Set a currency's rate by hand (admins only)
+    pub async fn update_exchange_rate(&self, currency: Currency, rials_per_unit: i64) -> ApiResult<ExchangeRate> {
+        let endpoint = format!("exchange-rates/{}", currency);
+        self.api.put(&endpoint, Some(UpdateExchangeRate { rials_per_unit })).await
+    }
+
+    /// Current rates of the metals products can be priced by
+    pub async fn get_metal_rates(&self) -> ApiResult<Vec<MetalRate>> {
+        self.api.get("metal-rates").await
+    }
+
+    /// Set a metal's rate per gram; products priced by it are repriced (admins only)
+    pub async fn update_metal_rate(&self, metal: Metal, rials_per_gram: Money) -> ApiResult<MetalRate> {
+        let endpoint = format!("metal-rates/{}", metal);
+        self.api.put(&endpoint, Some(UpdateMetalRate { rials_per_gram })).await
+    }
+
+    /// Get a product's pricing rule (admins only)
+    pub async fn get_pricing_rule(&self, product_id: Uuid) -> ApiResult<PricingRule> {
+        let endpoint = format!("products/{}/pricing-rule", product_id);
+        self.api.get(&endpoint).await
+    }
+
+    /// Price a product's variants by the metal rate (admins only)
+    pub async fn set_pricing_rule(&self, product_id: Uuid, rule: SetPricingRule) -> ApiResult<PricingRule> {
+        let endpoint = format!("products/{}/pricing-rule", product_id);
+        self.api.put(&endpoint, Some(rule)).await
+    }
+
+    /// Stop pricing a product by the metal rate (admins only)
+    pub async fn delete_pricing_rule(&self, product_id: Uuid) -> ApiResult<()> {
+        let endpoint = format!("products/{}/pricing-rule", product_id);
+        self.api.delete(&endpoint).await
+    }
+
+    /// Get the price changes of a product's variants (admins only)
+    pub async fn get_price_history(&self, product_id: Uuid) -> ApiResult<Vec<PriceChange>> {
+        let endpoint = format!("products/{}/price-history", product_id);
+        self.api.get(&endpoint).await
+    }
+
+    /// Hold the current prices of the variants while checking out
+    pub async fn create_price_lock(&self, variant_ids: Vec<Uuid>) -> ApiResult<PriceLockWithItems> {
+        self.api.post("price-locks", Some(CreatePriceLock { variant_ids })).await
+    }
+
     /// Update product stock
     pub async fn update_stock(&self, id: Uuid, stock: i32) -> ApiResult<Product> {
         let endpoint = format!("products/{}/stock", id);
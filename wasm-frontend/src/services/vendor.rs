@@ -1,3 +1,5 @@
+use models::media::{Image, UpdateImage};
+use models::money::Money;
 use models::vendor::Vendor;
 use models::pagination::PaginatedResponse;
 use super::api::{ApiService, ApiResult};
@@ -84,7 +86,7 @@ pub struct VendorStats {
     pub new_vendors_this_week: u32,
     pub new_vendors_this_month: u32,
     pub total_products: u32,
-    pub total_sales: f64,
+    pub total_sales: Money,
 }
 
 pub struct VendorService {
@@ -274,24 +276,45 @@ impl VendorService {
         self.api.put("vendors/bulk", Some(body)).await
     }
 
-    /// Upload vendor logo
-    pub async fn upload_logo(&self, vendor_id: Uuid, logo_data: Vec<u8>, content_type: String) -> ApiResult<String> {
-        // This would typically use a different endpoint for file uploads
-        // For now, we'll use a placeholder implementation
-        let endpoint = format!("vendors/{}/logo", vendor_id);
-        let body = serde_json::json!({
-            "logo_data": base64::encode(logo_data),
-            "content_type": content_type
-        });
-        self.api.put(&endpoint, Some(body)).await
+    /// Upload a logo and move it in front of the vendor's other images;
+    /// the first image is the logo
+    pub async fn upload_logo(&self, vendor_id: Uuid, logo_data: Vec<u8>, content_type: String) -> ApiResult<Image> {
+        let images = self.get_images(vendor_id).await?;
+        let endpoint = format!("vendors/{}/images", vendor_id);
+        let logo: Image = self.api.upload(&endpoint, &logo_data, &content_type, &[]).await?;
+        match images.first() {
+            Some(first) => {
+                let update = UpdateImage { position: Some(first.position - 1), ..Default::default() };
+                self.update_image(vendor_id, logo.id, update).await
+            }
+            None => Ok(logo),
+        }
+    }
+
+    /// Get a vendor's images in display order, each with its thumbnails
+    pub async fn get_images(&self, vendor_id: Uuid) -> ApiResult<Vec<Image>> {
+        let endpoint = format!("vendors/{}/images", vendor_id);
+        self.api.get(&endpoint).await
+    }
+
+    /// Update an image's description or position
+    pub async fn update_image(&self, vendor_id: Uuid, image_id: Uuid, image: UpdateImage) -> ApiResult<Image> {
+        let endpoint = format!("vendors/{}/images/{}", vendor_id, image_id);
+        self.api.put(&endpoint, Some(image)).await
+    }
+
+    /// Delete an image and its thumbnails
+    pub async fn delete_image(&self, vendor_id: Uuid, image_id: Uuid) -> ApiResult<()> {
+        let endpoint = format!("vendors/{}/images/{}", vendor_id, image_id);
+        self.api.delete(&endpoint).await
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VendorSalesStats {
-    pub total_sales: f64,
+    pub total_sales: Money,
     pub total_orders: u32,
-    pub average_order_value: f64,
+    pub average_order_value: Money,
     pub top_products: Vec<TopProduct>,
     pub sales_by_month: Vec<MonthlySales>,
 }
@@ -301,13 +324,13 @@ pub struct TopProduct {
     pub product_id: Uuid,
     pub product_name: String,
     pub sales_count: u32,
-    pub total_revenue: f64,
+    pub total_revenue: Money,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonthlySales {
     pub month: String,
-    pub sales: f64,
+    pub sales: Money,
     pub orders: u32,
 }
 
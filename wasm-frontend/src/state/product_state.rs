@@ -1,3 +1,4 @@
+use models::money::Money;
 use models::product::Product;
 use models::pagination::PaginatedResponse;
 use uuid::Uuid;
@@ -24,8 +25,8 @@ pub struct PaginationInfo {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ProductFilters {
-    pub min_price: Option<f64>,
-    pub max_price: Option<f64>,
+    pub min_price: Option<Money>,
+    pub max_price: Option<Money>,
     pub in_stock: Option<bool>,
     pub vendor_id: Option<Uuid>,
 }
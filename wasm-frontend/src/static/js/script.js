@@ -240,44 +240,60 @@ function showStep(stepNumber) {
 }
 
 // Social Login Functions
+// Providers hand the page an ID token (or, for GitHub, an OAuth access token);
+// the server verifies it with the provider before signing the user in.
+async function completeSocialLogin(provider, credentials) {
+    try {
+        const response = await fetch('/api/auth/social', {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({
+                provider: provider,
+                ...credentials
+            })
+        });
+
+        const data = await response.json();
+
+        if (data.success) {
+            showNotification(data.message, 'success');
+            closeLoginModal();
+            updateUIForLoggedInUser();
+        } else {
+            showNotification(data.message, 'error');
+        }
+    } catch (error) {
+        console.error('Error signing in with provider:', error);
+        showNotification('خطا در ورود با شبکه اجتماعی', 'error');
+    }
+}
+
 async function loginWithGoogle() {
+    // Requires the Google Identity Services script and `window.GOOGLE_CLIENT_ID`.
+    if (!window.google || !google.accounts || !window.GOOGLE_CLIENT_ID) {
+        showNotification('ورود با گوگل در حال حاضر در دسترس نیست.', 'error');
+        return;
+    }
     showNotification('در حال اتصال به گوگل...', 'info');
-    // Simulate OAuth flow
-    setTimeout(() => {
-        showNotification('ورود با گوگل موفقیت‌آمیز بود!', 'success');
-        closeLoginModal();
-        updateUIForLoggedInUser('کاربر گوگل');
-    }, 2000);
+    google.accounts.id.initialize({
+        client_id: window.GOOGLE_CLIENT_ID,
+        callback: (result) => completeSocialLogin('google', { id_token: result.credential })
+    });
+    google.accounts.id.prompt();
 }
 
 async function loginWithGithub() {
-    showNotification('در حال اتصال به گیت‌هاب...', 'info');
-    // Simulate OAuth flow
-    setTimeout(() => {
-        showNotification('ورود با گیت‌هاب موفقیت‌آمیز بود!', 'success');
-        closeLoginModal();
-        updateUIForLoggedInUser('کاربر گیت‌هاب');
-    }, 2000);
+    showNotification('ورود با گیت‌هاب در حال حاضر در دسترس نیست.', 'error');
 }
 
 async function loginWithLinkedin() {
-    showNotification('در حال اتصال به لینکدین...', 'info');
-    // Simulate OAuth flow
-    setTimeout(() => {
-        showNotification('ورود با لینکدین موفقیت‌آمیز بود!', 'success');
-        closeLoginModal();
-        updateUIForLoggedInUser('کاربر لینکدین');
-    }, 2000);
+    showNotification('ورود با لینکدین در حال حاضر در دسترس نیست.', 'error');
 }
 
 async function loginWithMeta() {
-    showNotification('در حال اتصال به متا...', 'info');
-    // Simulate OAuth flow
-    setTimeout(() => {
-        showNotification('ورود با متا موفقیت‌آمیز بود!', 'success');
-        closeLoginModal();
-        updateUIForLoggedInUser('کاربر متا');
-    }, 2000);
+    showNotification('ورود با متا در حال حاضر در دسترس نیست.', 'error');
 }
 
 // Authentication handlers
@@ -415,6 +431,7 @@ async function handleRegister(e) {
     const username = document.getElementById('username').value;
     const email = document.getElementById('email').value;
     const phone = document.getElementById('phone').value;
+    const password = document.getElementById('registerPassword').value;
     const code = document.getElementById('verificationCode').value;
     const submitBtn = e.target.querySelector('button[type="submit"]');
     
@@ -432,7 +449,9 @@ async function handleRegister(e) {
                 username: username,
                 email: email,
                 phone: phone,
-                code: code
+                password: password,
+                code: code,
+                email_or_phone: document.getElementById('emailOrPhone').value
             })
         });
         
@@ -1,6 +1,8 @@
 use regex::Regex;
 use crate::error::{AppError, AppResult};
 
+pub use models::auth::EmailOrPhone;
+
 pub struct Validator;
 
 impl Validator {
@@ -95,20 +97,6 @@ impl Validator {
     }
 }
 
-#[derive(Debug, Clone)]
-pub enum EmailOrPhone {
-    Email(String),
-    Phone(String),
-}
-
-impl EmailOrPhone {
-    pub fn as_str(&self) -> &str {
-        match self {
-            EmailOrPhone::Email(email) => email,
-            EmailOrPhone::Phone(phone) => phone,
-        }
-    }
-}
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,185 @@
+use actix_web::{dev::Payload, http::header, web, FromRequest, HttpRequest};
+use jsonwebtoken::errors::{Error as JwtError, ErrorKind};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::ops::Deref;
+use std::sync::RwLock;
+use config::JwtConfig;
+use models::auth::{bearer_token, AccessError, Caller, Claims};
+use crate::error::ServiceError;
+
+/// Verifies access tokens issued by the auth service.
+///
+/// HS256 secrets come straight from [`JwtConfig`]; asymmetric keys are
+/// fetched from the auth service's JWKS endpoint (`jwks_url`) and replaced
+/// on every [`refresh_jwks`](Self::refresh_jwks).
+pub struct TokenVerifier {
+    default_key_id: String,
+    issuer: String,
+    audience: String,
+    jwks_url: Option<String>,
+    shared_keys: HashMap<String, (Algorithm, DecodingKey)>,
+    remote_keys: RwLock<HashMap<String, (Algorithm, DecodingKey)>>,
+}
+
+impl TokenVerifier {
+    pub fn from_config(config: &JwtConfig) -> Result<Self, String> {
+        let mut shared_keys = HashMap::new();
+        if config.algorithm == "HS256" {
+            if let Some(secret) = &config.signing_key {
+                shared_keys.insert(config.key_id.clone(), (Algorithm::HS256, DecodingKey::from_secret(secret.as_bytes())));
+            }
+        }
+        for previous in config.previous_keys.iter().filter(|key| key.algorithm == "HS256") {
+            shared_keys
+                .entry(previous.key_id.clone())
+                .or_insert_with(|| (Algorithm::HS256, DecodingKey::from_secret(previous.key.as_bytes())));
+        }
+
+        if shared_keys.is_empty() && config.jwks_url.is_none() {
+            return Err("Either an HS256 JWT_SIGNING_KEY or JWT_JWKS_URL must be set".to_string());
+        }
+
+        Ok(Self {
+            default_key_id: config.key_id.clone(),
+            issuer: config.issuer.clone(),
+            audience: config.audience.clone(),
+            jwks_url: config.jwks_url.clone(),
+            shared_keys,
+            remote_keys: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Reloads the published keys and returns how many were loaded.
+    /// Does nothing when no JWKS endpoint is configured.
+    pub async fn refresh_jwks(&self) -> Result<usize, String> {
+        let Some(url) = &self.jwks_url else {
+            return Ok(0);
+        };
+
+        let jwks: JwkSet = reqwest::get(url)
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Failed to fetch JWKS from `{}`: {}", url, e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid JWKS from `{}`: {}", url, e))?;
+
+        let mut keys = HashMap::new();
+        for jwk in &jwks.keys {
+            let (Some(key_id), Some(algorithm)) = (&jwk.common.key_id, jwk.common.algorithm) else {
+                continue;
+            };
+            let key = DecodingKey::from_jwk(jwk).map_err(|e| format!("Invalid JWK `{}`: {}", key_id, e))?;
+            keys.insert(key_id.clone(), (algorithm, key));
+        }
+
+        let count = keys.len();
+        *self.remote_keys.write().unwrap_or_else(|e| e.into_inner()) = keys;
+
+        Ok(count)
+    }
+
+    pub fn decode_token(&self, token: &str) -> Result<Claims, JwtError> {
+        let header = decode_header(token)?;
+        let key_id = header.kid.as_deref().unwrap_or(&self.default_key_id);
+
+        let remote_keys = self.remote_keys.read().unwrap_or_else(|e| e.into_inner());
+        let (algorithm, key) = self
+            .shared_keys
+            .get(key_id)
+            .or_else(|| remote_keys.get(key_id))
+            .ok_or_else(|| JwtError::from(ErrorKind::InvalidSignature))?;
+
+        let mut validation = Validation::new(*algorithm);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+
+        decode::<Claims>(token, key, &validation).map(|data| data.claims)
+    }
+}
+
+/// The caller identified by a valid `Authorization: Bearer <jwt>` header.
+/// Add it as a handler argument to require authentication; the permission
+/// checks are those of [`Caller`].
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser(Caller);
+
+impl Deref for AuthenticatedUser {
+    type Target = Caller;
+
+    fn deref(&self) -> &Caller {
+        &self.0
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = ServiceError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(authenticate(req))
+    }
+}
+
+fn authenticate(req: &HttpRequest) -> Result<AuthenticatedUser, ServiceError> {
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(bearer_token)
+        .ok_or(AccessError::MissingToken)?;
+    let verifier = req
+        .app_data::<web::Data<TokenVerifier>>()
+        .ok_or_else(|| ServiceError::InternalServerError("Token verifier is not configured".to_string()))?;
+    let claims = verifier.decode_token(token)?;
+
+    Ok(AuthenticatedUser(Caller::from_claims(claims)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use models::role::{Permission, Role};
+    use uuid::Uuid;
+
+    fn claims(role: Role) -> Claims {
+        let now = chrono::Utc::now().timestamp() as usize;
+        Claims {
+            sub: Uuid::new_v4().to_string(),
+            exp: now + 60,
+            iat: now,
+            iss: "pema-auth".to_string(),
+            aud: "pema-platform".to_string(),
+            role,
+            permissions: role.permissions().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_verifies_shared_secret_tokens() {
+        let verifier = TokenVerifier::from_config(&JwtConfig {
+            signing_key: Some("secret".to_string()),
+            ..JwtConfig::default()
+        })
+        .unwrap();
+
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("default".to_string());
+        let token = encode(&header, &claims(Role::Vendor), &EncodingKey::from_secret(b"secret")).unwrap();
+        let decoded = verifier.decode_token(&token).unwrap();
+        assert_eq!(decoded.role, Role::Vendor);
+        assert!(decoded.has_permission(Permission::InventoryRead));
+
+        let forged = encode(&header, &claims(Role::Admin), &EncodingKey::from_secret(b"guess")).unwrap();
+        assert!(verifier.decode_token(&forged).is_err());
+    }
+
+    #[test]
+    fn test_requires_some_key_source() {
+        assert!(TokenVerifier::from_config(&JwtConfig::default()).is_err());
+    }
+}
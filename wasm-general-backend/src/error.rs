@@ -1,4 +1,5 @@
 use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use models::{auth::AccessError, inventory::StockShortage, money::MoneyError};
 use serde::Serialize;
 use sqlx::Error as SqlxError;
 use std::fmt::{Display, Formatter, Result as FmtResult};
@@ -8,7 +9,15 @@ pub enum ServiceError {
     NotFound(String),
     InternalServerError(String),
     BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
     DuplicateEntry(String),
+    /// A payment gateway or other upstream provider failed or refused.
+    BadGateway(String),
+    PayloadTooLarge(String),
+    UnsupportedMediaType(String),
+    /// An order asked for more than is in stock; lists every short variant.
+    InsufficientStock(Vec<StockShortage>),
 }
 
 impl Display for ServiceError {
@@ -26,8 +35,28 @@ impl ResponseError for ServiceError {
                 .json(serde_json::json!({ "message": msg })),
             ServiceError::BadRequest(msg) => HttpResponse::build(StatusCode::BAD_REQUEST)
                 .json(serde_json::json!({ "message": msg })),
+            ServiceError::Unauthorized(msg) => HttpResponse::build(StatusCode::UNAUTHORIZED)
+                .json(serde_json::json!({ "message": msg })),
+            ServiceError::Forbidden(msg) => HttpResponse::build(StatusCode::FORBIDDEN)
+                .json(serde_json::json!({ "message": msg })),
             ServiceError::DuplicateEntry(msg) => HttpResponse::build(StatusCode::CONFLICT)
                 .json(serde_json::json!({ "message": msg })),
+            ServiceError::BadGateway(msg) => HttpResponse::build(StatusCode::BAD_GATEWAY)
+                .json(serde_json::json!({ "message": msg })),
+            ServiceError::PayloadTooLarge(msg) => HttpResponse::build(StatusCode::PAYLOAD_TOO_LARGE)
+                .json(serde_json::json!({ "message": msg })),
+            ServiceError::UnsupportedMediaType(msg) => HttpResponse::build(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+                .json(serde_json::json!({ "message": msg })),
+            ServiceError::InsufficientStock(shortages) => {
+                let products: Vec<String> = shortages
+                    .iter()
+                    .map(|s| format!("{} {} (requested {}, available {})", s.product_name, s.sku, s.requested, s.available))
+                    .collect();
+                HttpResponse::build(StatusCode::CONFLICT).json(serde_json::json!({
+                    "message": format!("Not enough stock for {}", products.join(", ")),
+                    "shortages": shortages,
+                }))
+            }
         }
     }
 }
@@ -40,11 +69,39 @@ impl From<SqlxError> for ServiceError {
             SqlxError::Database(db_err) if db_err.is_unique_violation() => {
                 ServiceError::DuplicateEntry("A record with this unique identifier already exists.".to_string())
             }
+            SqlxError::Database(db_err) if db_err.is_foreign_key_violation() => {
+                ServiceError::BadRequest("The referenced record does not exist or is still in use.".to_string())
+            }
+            SqlxError::Database(db_err) if db_err.is_check_violation() => {
+                ServiceError::BadRequest("The request violates a data constraint.".to_string())
+            }
             _ => ServiceError::InternalServerError("Database error occurred".to_string()),
         }
     }
 }
 
+impl From<MoneyError> for ServiceError {
+    fn from(err: MoneyError) -> Self {
+        ServiceError::BadRequest(err.to_string())
+    }
+}
+
+impl From<AccessError> for ServiceError {
+    fn from(err: AccessError) -> Self {
+        match err {
+            AccessError::MissingToken | AccessError::InvalidSubject => ServiceError::Unauthorized(err.to_string()),
+            AccessError::MissingPermission(_) => ServiceError::Forbidden(err.to_string()),
+        }
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for ServiceError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        log::error!("JWT error: {:?}", err);
+        ServiceError::Unauthorized("Invalid or expired token".to_string())
+    }
+}
+
 impl From<std::env::VarError> for ServiceError {
     fn from(err: std::env::VarError) -> Self {
         log::error!("Environment variable error: {:?}", err);
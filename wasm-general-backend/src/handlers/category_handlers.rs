@@ -0,0 +1,38 @@
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+use models::{role::Permission, category::{CreateCategory, UpdateCategory}};
+use crate::{auth::AuthenticatedUser, service, error::ServiceError};
+
+pub async fn create_category(pool: web::Data<PgPool>, auth: AuthenticatedUser, new_category: web::Json<CreateCategory>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::CategoriesWrite)?;
+    let category = service::create_category(&pool, new_category.into_inner()).await?;
+    Ok(HttpResponse::Created().json(category))
+}
+
+pub async fn get_categories(pool: web::Data<PgPool>) -> Result<HttpResponse, ServiceError> {
+    let categories = service::get_categories(&pool).await?;
+    Ok(HttpResponse::Ok().json(categories))
+}
+
+pub async fn get_category_tree(pool: web::Data<PgPool>) -> Result<HttpResponse, ServiceError> {
+    let tree = service::get_category_tree(&pool).await?;
+    Ok(HttpResponse::Ok().json(tree))
+}
+
+pub async fn get_category_by_id(pool: web::Data<PgPool>, category_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let category = service::get_category_by_id(&pool, category_id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(category))
+}
+
+pub async fn update_category(pool: web::Data<PgPool>, auth: AuthenticatedUser, category_id: web::Path<Uuid>, updated_category: web::Json<UpdateCategory>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::CategoriesWrite)?;
+    let category = service::update_category(&pool, category_id.into_inner(), updated_category.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(category))
+}
+
+pub async fn delete_category(pool: web::Data<PgPool>, auth: AuthenticatedUser, category_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::CategoriesWrite)?;
+    service::delete_category(&pool, category_id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
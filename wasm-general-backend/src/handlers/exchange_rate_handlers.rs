@@ -0,0 +1,15 @@
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use models::{role::Permission, exchange_rate::UpdateExchangeRate, money::Currency};
+use crate::{auth::AuthenticatedUser, service, error::ServiceError};
+
+pub async fn get_exchange_rates(pool: web::Data<PgPool>) -> Result<HttpResponse, ServiceError> {
+    let rates = service::get_exchange_rates(&pool).await?;
+    Ok(HttpResponse::Ok().json(rates))
+}
+
+pub async fn update_exchange_rate(pool: web::Data<PgPool>, auth: AuthenticatedUser, currency: web::Path<Currency>, update: web::Json<UpdateExchangeRate>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::PricingWrite)?;
+    let rate = service::set_exchange_rate(&pool, currency.into_inner(), auth.user_id, update.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(rate))
+}
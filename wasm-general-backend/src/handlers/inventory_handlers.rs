@@ -1,31 +1,89 @@
 use actix_web::{web, HttpResponse};
 use sqlx::PgPool;
 use uuid::Uuid;
-use models::{inventory::{CreateInventoryItem, UpdateInventoryItem}, pagination::Pagination};
-use crate::{service, error::ServiceError};
+use models::{
+    role::Permission,
+    inventory::{CreateInventoryItem, UpdateInventoryItem, InventoryFilter, CreateStockMovement, CreateStockTransfer},
+    pagination::Pagination,
+};
+use crate::{auth::AuthenticatedUser, service, error::ServiceError};
 
-pub async fn create_inventory_item(pool: web::Data<PgPool>, new_item: web::Json<CreateInventoryItem>) -> Result<HttpResponse, ServiceError> {
-    let item = service::create_inventory_item(&pool, new_item.into_inner()).await?;
+/// A vendor's own account may manage the stock of its products; anyone
+/// else needs `inventory:write`.
+async fn authorize_product(pool: &PgPool, auth: &AuthenticatedUser, product_id: Uuid) -> Result<(), ServiceError> {
+    let product = service::get_product_by_id(pool, product_id).await?;
+    let vendor = service::get_vendor_by_id(pool, product.vendor_id).await?;
+    match vendor.user_id {
+        Some(user_id) => auth.require_owner_or(user_id, Permission::InventoryWrite)?,
+        None => auth.require(Permission::InventoryWrite)?,
+    }
+
+    Ok(())
+}
+
+async fn authorize_variant(pool: &PgPool, auth: &AuthenticatedUser, variant_id: Uuid) -> Result<(), ServiceError> {
+    let product = service::get_variant_product(pool, variant_id).await?;
+    authorize_product(pool, auth, product.id).await
+}
+
+pub async fn create_inventory_item(pool: web::Data<PgPool>, auth: AuthenticatedUser, new_item: web::Json<CreateInventoryItem>) -> Result<HttpResponse, ServiceError> {
+    authorize_variant(&pool, &auth, new_item.variant_id).await?;
+    let item = service::create_inventory_item(&pool, auth.user_id, new_item.into_inner()).await?;
     Ok(HttpResponse::Created().json(item))
 }
 
-pub async fn get_inventory_items(pool: web::Data<PgPool>, web::Query(pagination): web::Query<Pagination>) -> Result<HttpResponse, ServiceError> {
-    let items = service::get_inventory_items(&pool, pagination).await?;
+pub async fn get_inventory_items(pool: web::Data<PgPool>, auth: AuthenticatedUser, web::Query(filter): web::Query<InventoryFilter>, web::Query(pagination): web::Query<Pagination>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::InventoryRead)?;
+    let items = service::get_inventory_items(&pool, filter, pagination).await?;
     Ok(HttpResponse::Ok().json(items))
 }
 
-pub async fn get_inventory_item_by_id(pool: web::Data<PgPool>, item_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+pub async fn get_inventory_item_by_id(pool: web::Data<PgPool>, auth: AuthenticatedUser, item_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::InventoryRead)?;
     let item = service::get_inventory_item_by_id(&pool, item_id.into_inner()).await?;
     Ok(HttpResponse::Ok().json(item))
 }
 
-pub async fn update_inventory_item(pool: web::Data<PgPool>, item_id: web::Path<Uuid>, updated_item: web::Json<UpdateInventoryItem>) -> Result<HttpResponse, ServiceError> {
-    let item = service::update_inventory_item(&pool, item_id.into_inner(), updated_item.into_inner()).await?;
+pub async fn update_inventory_item(pool: web::Data<PgPool>, auth: AuthenticatedUser, item_id: web::Path<Uuid>, updated_item: web::Json<UpdateInventoryItem>) -> Result<HttpResponse, ServiceError> {
+    let item = service::get_inventory_item_by_id(&pool, item_id.into_inner()).await?;
+    authorize_product(&pool, &auth, item.product_id).await?;
+    let item = service::update_inventory_item(&pool, item.id, auth.user_id, updated_item.into_inner()).await?;
     Ok(HttpResponse::Ok().json(item))
 }
 
-pub async fn delete_inventory_item(pool: web::Data<PgPool>, item_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
-    service::delete_inventory_item(&pool, item_id.into_inner()).await?;
+pub async fn delete_inventory_item(pool: web::Data<PgPool>, auth: AuthenticatedUser, item_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let item = service::get_inventory_item_by_id(&pool, item_id.into_inner()).await?;
+    authorize_product(&pool, &auth, item.product_id).await?;
+    service::delete_inventory_item(&pool, item.id).await?;
     Ok(HttpResponse::NoContent().finish())
 }
 
+pub async fn create_stock_movement(pool: web::Data<PgPool>, auth: AuthenticatedUser, new_movement: web::Json<CreateStockMovement>) -> Result<HttpResponse, ServiceError> {
+    authorize_variant(&pool, &auth, new_movement.variant_id).await?;
+    let movement = service::create_stock_movement(&pool, auth.user_id, new_movement.into_inner()).await?;
+    Ok(HttpResponse::Created().json(movement))
+}
+
+pub async fn get_stock_movements(pool: web::Data<PgPool>, auth: AuthenticatedUser, web::Query(filter): web::Query<InventoryFilter>, web::Query(pagination): web::Query<Pagination>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::InventoryRead)?;
+    let movements = service::get_stock_movements(&pool, filter, pagination).await?;
+    Ok(HttpResponse::Ok().json(movements))
+}
+
+pub async fn create_stock_transfer(pool: web::Data<PgPool>, auth: AuthenticatedUser, new_transfer: web::Json<CreateStockTransfer>) -> Result<HttpResponse, ServiceError> {
+    authorize_variant(&pool, &auth, new_transfer.variant_id).await?;
+    let transfer = service::create_stock_transfer(&pool, auth.user_id, new_transfer.into_inner()).await?;
+    Ok(HttpResponse::Created().json(transfer))
+}
+
+pub async fn get_stock_transfers(pool: web::Data<PgPool>, auth: AuthenticatedUser, web::Query(filter): web::Query<InventoryFilter>, web::Query(pagination): web::Query<Pagination>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::InventoryRead)?;
+    let transfers = service::get_stock_transfers(&pool, filter, pagination).await?;
+    Ok(HttpResponse::Ok().json(transfers))
+}
+
+pub async fn audit_stock(pool: web::Data<PgPool>, auth: AuthenticatedUser) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::InventoryRead)?;
+    let discrepancies = service::audit_stock(&pool).await?;
+    Ok(HttpResponse::Ok().json(discrepancies))
+}
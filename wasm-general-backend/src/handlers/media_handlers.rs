@@ -0,0 +1,122 @@
+use actix_multipart::{form::{bytes::Bytes, text::Text, MultipartForm, MultipartFormConfig}, MultipartError};
+use actix_web::{error::PayloadError, http::header, web, HttpResponse};
+use config::{AppConfig, MediaConfig};
+use sqlx::PgPool;
+use uuid::Uuid;
+use models::{role::Permission, media::{ImageOwner, UpdateImage}};
+use crate::{auth::AuthenticatedUser, service::{self, ImageUpload, MediaStore}, error::ServiceError};
+
+/// An image upload: the file and an optional description of it.
+#[derive(MultipartForm)]
+pub struct ImageForm {
+    file: Bytes,
+    alt_text: Option<Text<String>>,
+}
+
+impl From<ImageForm> for ImageUpload {
+    fn from(form: ImageForm) -> Self {
+        ImageUpload {
+            bytes: form.file.data.to_vec(),
+            content_type: form.file.content_type.map(|content_type| content_type.to_string()),
+            alt_text: form.alt_text.map(Text::into_inner),
+        }
+    }
+}
+
+/// Lets forms through up to the upload limit, with some room for the other
+/// fields and part headers, and answers with the service's JSON errors.
+/// Requests that are not `multipart/form-data` are refused with 415 before
+/// the form is read.
+pub fn multipart_config(config: &MediaConfig) -> MultipartFormConfig {
+    let limit = config.max_upload_bytes + 64 * 1024;
+    let max_kb = config.max_upload_bytes / 1024;
+    MultipartFormConfig::default()
+        .total_limit(limit)
+        .memory_limit(limit)
+        .error_handler(move |e, _| {
+            match e {
+                MultipartError::Payload(PayloadError::Overflow) => ServiceError::PayloadTooLarge(format!("Images can be at most {} KB", max_kb)),
+                e => ServiceError::BadRequest(e.to_string()),
+            }
+            .into()
+        })
+}
+
+pub async fn upload_product_image(pool: web::Data<PgPool>, config: web::Data<AppConfig>, store: web::Data<dyn MediaStore>, auth: AuthenticatedUser, product_id: web::Path<Uuid>, MultipartForm(form): MultipartForm<ImageForm>) -> Result<HttpResponse, ServiceError> {
+    let product_id = product_id.into_inner();
+    authorize_product(&pool, &auth, product_id).await?;
+    let image = service::upload_image(&pool, &**store, &config.media, ImageOwner::Product(product_id), auth.user_id, form.into()).await?;
+    Ok(HttpResponse::Created().json(image))
+}
+
+pub async fn get_product_images(pool: web::Data<PgPool>, product_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let images = service::get_images(&pool, ImageOwner::Product(product_id.into_inner())).await?;
+    Ok(HttpResponse::Ok().json(images))
+}
+
+pub async fn update_product_image(pool: web::Data<PgPool>, auth: AuthenticatedUser, path: web::Path<(Uuid, Uuid)>, updated_image: web::Json<UpdateImage>) -> Result<HttpResponse, ServiceError> {
+    let (product_id, image_id) = path.into_inner();
+    authorize_product(&pool, &auth, product_id).await?;
+    let image = service::update_image(&pool, ImageOwner::Product(product_id), image_id, updated_image.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(image))
+}
+
+pub async fn delete_product_image(pool: web::Data<PgPool>, store: web::Data<dyn MediaStore>, auth: AuthenticatedUser, path: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, ServiceError> {
+    let (product_id, image_id) = path.into_inner();
+    authorize_product(&pool, &auth, product_id).await?;
+    service::delete_image(&pool, &**store, ImageOwner::Product(product_id), image_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// A vendor's own account may manage its images, e.g. to change its logo.
+async fn authorize_vendor(pool: &PgPool, auth: &AuthenticatedUser, vendor_id: Uuid) -> Result<(), ServiceError> {
+    let vendor = service::get_vendor_by_id(pool, vendor_id).await?;
+    match vendor.user_id {
+        Some(user_id) => auth.require_owner_or(user_id, Permission::VendorsWrite)?,
+        None => auth.require(Permission::VendorsWrite)?,
+    }
+
+    Ok(())
+}
+
+/// Product images belong to the product's vendor.
+async fn authorize_product(pool: &PgPool, auth: &AuthenticatedUser, product_id: Uuid) -> Result<(), ServiceError> {
+    let product = service::get_product_by_id(pool, product_id).await?;
+    authorize_vendor(pool, auth, product.vendor_id).await
+}
+
+pub async fn upload_vendor_image(pool: web::Data<PgPool>, config: web::Data<AppConfig>, store: web::Data<dyn MediaStore>, auth: AuthenticatedUser, vendor_id: web::Path<Uuid>, MultipartForm(form): MultipartForm<ImageForm>) -> Result<HttpResponse, ServiceError> {
+    let vendor_id = vendor_id.into_inner();
+    authorize_vendor(&pool, &auth, vendor_id).await?;
+    let image = service::upload_image(&pool, &**store, &config.media, ImageOwner::Vendor(vendor_id), auth.user_id, form.into()).await?;
+    Ok(HttpResponse::Created().json(image))
+}
+
+pub async fn get_vendor_images(pool: web::Data<PgPool>, vendor_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let images = service::get_images(&pool, ImageOwner::Vendor(vendor_id.into_inner())).await?;
+    Ok(HttpResponse::Ok().json(images))
+}
+
+pub async fn update_vendor_image(pool: web::Data<PgPool>, auth: AuthenticatedUser, path: web::Path<(Uuid, Uuid)>, updated_image: web::Json<UpdateImage>) -> Result<HttpResponse, ServiceError> {
+    let (vendor_id, image_id) = path.into_inner();
+    authorize_vendor(&pool, &auth, vendor_id).await?;
+    let image = service::update_image(&pool, ImageOwner::Vendor(vendor_id), image_id, updated_image.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(image))
+}
+
+pub async fn delete_vendor_image(pool: web::Data<PgPool>, store: web::Data<dyn MediaStore>, auth: AuthenticatedUser, path: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, ServiceError> {
+    let (vendor_id, image_id) = path.into_inner();
+    authorize_vendor(&pool, &auth, vendor_id).await?;
+    service::delete_image(&pool, &**store, ImageOwner::Vendor(vendor_id), image_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Stored files never change under a key, so clients may cache them for good.
+pub async fn get_media(store: web::Data<dyn MediaStore>, key: web::Path<String>) -> Result<HttpResponse, ServiceError> {
+    let (bytes, content_type) = service::get_media(&**store, &key).await?;
+    Ok(HttpResponse::Ok()
+        .content_type(content_type.as_str())
+        .insert_header((header::CACHE_CONTROL, "public, max-age=31536000, immutable"))
+        .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .body(bytes))
+}
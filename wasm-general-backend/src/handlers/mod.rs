@@ -1,2 +1,136 @@
+use actix_web::web;
+
+pub mod category_handlers;
+pub mod exchange_rate_handlers;
+pub mod health_handler;
+pub mod inventory_handlers;
+pub mod media_handlers;
+pub mod notification_handlers;
+pub mod order_handlers;
+pub mod payment_handlers;
+pub mod pricing_handlers;
+pub mod product_handlers;
 pub mod vendor_handlers;
+pub mod warehouse_handlers;
 
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("/health", web::get().to(health_handler::health_check))
+        .route("/media/{key:.*}", web::get().to(media_handlers::get_media))
+        .service(
+            web::scope("/api")
+                .service(
+                    web::scope("/vendors")
+                        .route("", web::post().to(vendor_handlers::create_vendor))
+                        .route("", web::get().to(vendor_handlers::get_vendors))
+                        .route("/{id}", web::get().to(vendor_handlers::get_vendor_by_id))
+                        .route("/{id}", web::put().to(vendor_handlers::update_vendor))
+                        .route("/{id}", web::delete().to(vendor_handlers::delete_vendor))
+                        .route("/{id}/images", web::post().to(media_handlers::upload_vendor_image))
+                        .route("/{id}/images", web::get().to(media_handlers::get_vendor_images))
+                        .route("/{id}/images/{image_id}", web::put().to(media_handlers::update_vendor_image))
+                        .route("/{id}/images/{image_id}", web::delete().to(media_handlers::delete_vendor_image)),
+                )
+                .service(
+                    web::scope("/products")
+                        .route("", web::post().to(product_handlers::create_product))
+                        .route("", web::get().to(product_handlers::get_products))
+                        .route("/low-stock", web::get().to(product_handlers::get_low_stock_products))
+                        .route("/categories", web::get().to(product_handlers::get_product_categories))
+                        .route("/{id}", web::get().to(product_handlers::get_product_by_id))
+                        .route("/{id}", web::put().to(product_handlers::update_product))
+                        .route("/{id}", web::delete().to(product_handlers::delete_product))
+                        .route("/{id}/variants", web::post().to(product_handlers::create_product_variant))
+                        .route("/{id}/variants", web::get().to(product_handlers::get_product_variants))
+                        .route("/{id}/variants/{variant_id}", web::get().to(product_handlers::get_product_variant_by_id))
+                        .route("/{id}/variants/{variant_id}", web::put().to(product_handlers::update_product_variant))
+                        .route("/{id}/variants/{variant_id}", web::delete().to(product_handlers::delete_product_variant))
+                        .route("/{id}/pricing-rule", web::get().to(pricing_handlers::get_pricing_rule))
+                        .route("/{id}/pricing-rule", web::put().to(pricing_handlers::set_pricing_rule))
+                        .route("/{id}/pricing-rule", web::delete().to(pricing_handlers::delete_pricing_rule))
+                        .route("/{id}/price-history", web::get().to(pricing_handlers::get_price_history))
+                        .route("/{id}/images", web::post().to(media_handlers::upload_product_image))
+                        .route("/{id}/images", web::get().to(media_handlers::get_product_images))
+                        .route("/{id}/images/{image_id}", web::put().to(media_handlers::update_product_image))
+                        .route("/{id}/images/{image_id}", web::delete().to(media_handlers::delete_product_image)),
+                )
+                .service(
+                    web::scope("/categories")
+                        .route("", web::post().to(category_handlers::create_category))
+                        .route("", web::get().to(category_handlers::get_categories))
+                        .route("/tree", web::get().to(category_handlers::get_category_tree))
+                        .route("/{id}", web::get().to(category_handlers::get_category_by_id))
+                        .route("/{id}", web::put().to(category_handlers::update_category))
+                        .route("/{id}", web::delete().to(category_handlers::delete_category)),
+                )
+                .service(
+                    web::scope("/inventory")
+                        .route("", web::post().to(inventory_handlers::create_inventory_item))
+                        .route("", web::get().to(inventory_handlers::get_inventory_items))
+                        .route("/movements", web::post().to(inventory_handlers::create_stock_movement))
+                        .route("/movements", web::get().to(inventory_handlers::get_stock_movements))
+                        .route("/transfers", web::post().to(inventory_handlers::create_stock_transfer))
+                        .route("/transfers", web::get().to(inventory_handlers::get_stock_transfers))
+                        .route("/audit", web::get().to(inventory_handlers::audit_stock))
+                        .route("/{id}", web::get().to(inventory_handlers::get_inventory_item_by_id))
+                        .route("/{id}", web::put().to(inventory_handlers::update_inventory_item))
+                        .route("/{id}", web::delete().to(inventory_handlers::delete_inventory_item)),
+                )
+                .service(
+                    web::scope("/warehouses")
+                        .route("", web::post().to(warehouse_handlers::create_warehouse))
+                        .route("", web::get().to(warehouse_handlers::get_warehouses))
+                        .route("/{id}", web::get().to(warehouse_handlers::get_warehouse_by_id))
+                        .route("/{id}", web::put().to(warehouse_handlers::update_warehouse))
+                        .route("/{id}", web::delete().to(warehouse_handlers::delete_warehouse)),
+                )
+                .service(
+                    web::scope("/orders")
+                        .route("", web::post().to(order_handlers::create_order))
+                        .route("", web::get().to(order_handlers::get_orders))
+                        .route("/{id}", web::get().to(order_handlers::get_order_by_id))
+                        .route("/{id}/items", web::get().to(order_handlers::get_order_items))
+                        .route("/{id}/status", web::put().to(order_handlers::update_order_status))
+                        .route("/{id}/cancel", web::put().to(order_handlers::cancel_order))
+                        .route("/{id}/history", web::get().to(order_handlers::get_order_status_history))
+                        .route("/{id}/reservations", web::get().to(order_handlers::get_order_reservations))
+                        .route("/{id}", web::put().to(order_handlers::update_order))
+                        .route("/{id}", web::delete().to(order_handlers::delete_order)),
+                )
+                .service(
+                    web::scope("/payments")
+                        .route("", web::post().to(payment_handlers::process_payment))
+                        .route("/callback", web::get().to(payment_handlers::payment_callback))
+                        .route("/webhook", web::post().to(payment_handlers::payment_webhook))
+                        .route("/{id}", web::get().to(payment_handlers::get_payment_by_id))
+                        .route("/{id}/status", web::get().to(payment_handlers::get_payment_status))
+                        .route("/{id}/refresh", web::post().to(payment_handlers::refresh_payment))
+                        .route("/{id}/refunds", web::post().to(payment_handlers::refund_payment))
+                        .route("/{id}/refunds", web::get().to(payment_handlers::get_payment_refunds))
+                        .route("/{id}/refunds/{refund_id}", web::put().to(payment_handlers::resolve_refund)),
+                )
+                .service(
+                    web::scope("/exchange-rates")
+                        .route("", web::get().to(exchange_rate_handlers::get_exchange_rates))
+                        .route("/{currency}", web::put().to(exchange_rate_handlers::update_exchange_rate)),
+                )
+                .service(
+                    web::scope("/metal-rates")
+                        .route("", web::get().to(pricing_handlers::get_metal_rates))
+                        .route("/{metal}", web::put().to(pricing_handlers::update_metal_rate)),
+                )
+                .service(
+                    web::scope("/price-locks")
+                        .route("", web::post().to(pricing_handlers::create_price_lock))
+                        .route("/{id}", web::get().to(pricing_handlers::get_price_lock)),
+                )
+                .service(
+                    web::scope("/notifications")
+                        .route("", web::post().to(notification_handlers::create_notification))
+                        .route("", web::get().to(notification_handlers::get_notifications))
+                        .route("/{id}", web::get().to(notification_handlers::get_notification_by_id))
+                        .route("/{id}", web::put().to(notification_handlers::update_notification))
+                        .route("/{id}", web::delete().to(notification_handlers::delete_notification))
+                        .route("/{id}/retry", web::post().to(notification_handlers::retry_notification)),
+                ),
+        );
+}
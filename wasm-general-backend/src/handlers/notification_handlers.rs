@@ -1,31 +1,47 @@
 use actix_web::{web, HttpResponse};
 use sqlx::PgPool;
 use uuid::Uuid;
-use models::{notification::{CreateNotification, UpdateNotification}, pagination::Pagination};
-use crate::{service, error::ServiceError};
+use models::{role::Permission, notification::{CreateNotification, UpdateNotification}, pagination::Pagination};
+use crate::{auth::AuthenticatedUser, service::{self, Notifier}, error::ServiceError};
 
-pub async fn create_notification(pool: web::Data<PgPool>, new_notification: web::Json<CreateNotification>) -> Result<HttpResponse, ServiceError> {
-    let notification = service::create_notification(&pool, new_notification.into_inner()).await?;
+pub async fn create_notification(pool: web::Data<PgPool>, notifier: web::Data<Notifier>, auth: AuthenticatedUser, new_notification: web::Json<CreateNotification>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::NotificationsWrite)?;
+    let notification = service::create_notification(&pool, &notifier, new_notification.into_inner()).await?;
     Ok(HttpResponse::Created().json(notification))
 }
 
-pub async fn get_notifications(pool: web::Data<PgPool>, web::Query(pagination): web::Query<Pagination>) -> Result<HttpResponse, ServiceError> {
-    let notifications = service::get_notifications(&pool, pagination).await?;
+/// Staff with `notifications:read` see every notification, everyone else only their own.
+pub async fn get_notifications(pool: web::Data<PgPool>, auth: AuthenticatedUser, web::Query(pagination): web::Query<Pagination>) -> Result<HttpResponse, ServiceError> {
+    let notifications = if auth.has_permission(Permission::NotificationsRead) {
+        service::get_notifications(&pool, pagination).await?
+    } else {
+        service::get_notifications_for_user(&pool, auth.user_id, pagination).await?
+    };
     Ok(HttpResponse::Ok().json(notifications))
 }
 
-pub async fn get_notification_by_id(pool: web::Data<PgPool>, notification_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+pub async fn get_notification_by_id(pool: web::Data<PgPool>, auth: AuthenticatedUser, notification_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
     let notification = service::get_notification_by_id(&pool, notification_id.into_inner()).await?;
+    auth.require_owner_or(notification.user_id, Permission::NotificationsRead)?;
     Ok(HttpResponse::Ok().json(notification))
 }
 
-pub async fn update_notification(pool: web::Data<PgPool>, notification_id: web::Path<Uuid>, updated_notification: web::Json<UpdateNotification>) -> Result<HttpResponse, ServiceError> {
-    let notification = service::update_notification(&pool, notification_id.into_inner(), updated_notification.into_inner()).await?;
+pub async fn update_notification(pool: web::Data<PgPool>, auth: AuthenticatedUser, notification_id: web::Path<Uuid>, updated_notification: web::Json<UpdateNotification>) -> Result<HttpResponse, ServiceError> {
+    let notification = service::get_notification_by_id(&pool, notification_id.into_inner()).await?;
+    auth.require_owner_or(notification.user_id, Permission::NotificationsWrite)?;
+    let notification = service::update_notification(&pool, notification.id, updated_notification.into_inner()).await?;
     Ok(HttpResponse::Ok().json(notification))
 }
 
-pub async fn delete_notification(pool: web::Data<PgPool>, notification_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
-    service::delete_notification(&pool, notification_id.into_inner()).await?;
-    Ok(HttpResponse::NoContent().finish())
+pub async fn retry_notification(pool: web::Data<PgPool>, notifier: web::Data<Notifier>, auth: AuthenticatedUser, notification_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::NotificationsWrite)?;
+    let notification = service::retry_notification(&pool, &notifier, notification_id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(notification))
 }
 
+pub async fn delete_notification(pool: web::Data<PgPool>, auth: AuthenticatedUser, notification_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let notification = service::get_notification_by_id(&pool, notification_id.into_inner()).await?;
+    auth.require_owner_or(notification.user_id, Permission::NotificationsWrite)?;
+    service::delete_notification(&pool, notification.id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,31 +1,84 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{http::StatusCode, web, HttpResponse};
+use config::AppConfig;
 use sqlx::PgPool;
 use uuid::Uuid;
-use models::{order::{CreateOrder, UpdateOrder}, pagination::Pagination};
-use crate::{service, error::ServiceError};
+use models::{role::Permission, order::{CancelOrder, CreateOrder, OrderStatus, UpdateOrder, UpdateOrderStatus}, pagination::Pagination};
+use crate::{auth::AuthenticatedUser, idempotency::{idempotent, IdempotencyKey}, service, error::ServiceError};
 
-pub async fn create_order(pool: web::Data<PgPool>, new_order: web::Json<CreateOrder>) -> Result<HttpResponse, ServiceError> {
-    let order = service::create_order(&pool, new_order.into_inner()).await?;
-    Ok(HttpResponse::Created().json(order))
+pub async fn create_order(pool: web::Data<PgPool>, config: web::Data<AppConfig>, auth: AuthenticatedUser, key: IdempotencyKey, new_order: web::Json<CreateOrder>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::OrdersCreate)?;
+    auth.require_owner_or(new_order.user_id, Permission::OrdersWrite)?;
+    idempotent(&pool, auth.user_id, key, "orders", StatusCode::CREATED, new_order.into_inner(), |new_order| {
+        service::create_order(&pool, &config.checkout, auth.user_id, new_order)
+    })
+    .await
 }
 
-pub async fn get_orders(pool: web::Data<PgPool>, web::Query(pagination): web::Query<Pagination>) -> Result<HttpResponse, ServiceError> {
-    let orders = service::get_orders(&pool, pagination).await?;
+/// Staff with `orders:read` see every order, everyone else only their own.
+pub async fn get_orders(pool: web::Data<PgPool>, auth: AuthenticatedUser, web::Query(pagination): web::Query<Pagination>) -> Result<HttpResponse, ServiceError> {
+    let orders = if auth.has_permission(Permission::OrdersRead) {
+        service::get_orders(&pool, pagination).await?
+    } else {
+        service::get_orders_for_user(&pool, auth.user_id, pagination).await?
+    };
     Ok(HttpResponse::Ok().json(orders))
 }
 
-pub async fn get_order_by_id(pool: web::Data<PgPool>, order_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+pub async fn get_order_by_id(pool: web::Data<PgPool>, auth: AuthenticatedUser, order_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
     let order = service::get_order_by_id(&pool, order_id.into_inner()).await?;
+    auth.require_owner_or(order.user_id, Permission::OrdersRead)?;
     Ok(HttpResponse::Ok().json(order))
 }
 
-pub async fn update_order(pool: web::Data<PgPool>, order_id: web::Path<Uuid>, updated_order: web::Json<UpdateOrder>) -> Result<HttpResponse, ServiceError> {
-    let order = service::update_order(&pool, order_id.into_inner(), updated_order.into_inner()).await?;
+pub async fn get_order_items(pool: web::Data<PgPool>, auth: AuthenticatedUser, order_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let order = service::get_order_by_id(&pool, order_id.into_inner()).await?;
+    auth.require_owner_or(order.user_id, Permission::OrdersRead)?;
+    let items = service::get_order_items(&pool, order.id).await?;
+    Ok(HttpResponse::Ok().json(items))
+}
+
+pub async fn update_order(pool: web::Data<PgPool>, auth: AuthenticatedUser, order_id: web::Path<Uuid>, updated_order: web::Json<UpdateOrder>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::OrdersWrite)?;
+    let order = service::update_order(&pool, order_id.into_inner(), auth.user_id, updated_order.into_inner()).await?;
     Ok(HttpResponse::Ok().json(order))
 }
 
-pub async fn delete_order(pool: web::Data<PgPool>, order_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+pub async fn update_order_status(pool: web::Data<PgPool>, auth: AuthenticatedUser, order_id: web::Path<Uuid>, update: web::Json<UpdateOrderStatus>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::OrdersWrite)?;
+    let update = update.into_inner();
+    let order = service::change_order_status(&pool, order_id.into_inner(), update.status, Some(auth.user_id), update.note).await?;
+    Ok(HttpResponse::Ok().json(order))
+}
+
+/// Customers may cancel their own orders until they are paid; after that a
+/// cancellation needs `orders:write`.
+pub async fn cancel_order(pool: web::Data<PgPool>, auth: AuthenticatedUser, order_id: web::Path<Uuid>, cancel: Option<web::Json<CancelOrder>>) -> Result<HttpResponse, ServiceError> {
+    let order = service::get_order_by_id(&pool, order_id.into_inner()).await?;
+    auth.require_owner_or(order.user_id, Permission::OrdersWrite)?;
+    if order.status != OrderStatus::Pending {
+        auth.require(Permission::OrdersWrite)?;
+    }
+    let reason = cancel.and_then(|cancel| cancel.into_inner().reason);
+    let order = service::change_order_status(&pool, order.id, OrderStatus::Cancelled, Some(auth.user_id), reason).await?;
+    Ok(HttpResponse::Ok().json(order))
+}
+
+pub async fn get_order_status_history(pool: web::Data<PgPool>, auth: AuthenticatedUser, order_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let order = service::get_order_by_id(&pool, order_id.into_inner()).await?;
+    auth.require_owner_or(order.user_id, Permission::OrdersRead)?;
+    let history = service::get_order_status_history(&pool, order.id).await?;
+    Ok(HttpResponse::Ok().json(history))
+}
+
+pub async fn get_order_reservations(pool: web::Data<PgPool>, auth: AuthenticatedUser, order_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let order = service::get_order_by_id(&pool, order_id.into_inner()).await?;
+    auth.require_owner_or(order.user_id, Permission::OrdersRead)?;
+    let reservations = service::get_order_reservations(&pool, order.id).await?;
+    Ok(HttpResponse::Ok().json(reservations))
+}
+
+pub async fn delete_order(pool: web::Data<PgPool>, auth: AuthenticatedUser, order_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::OrdersDelete)?;
     service::delete_order(&pool, order_id.into_inner()).await?;
     Ok(HttpResponse::NoContent().finish())
 }
-
@@ -1,16 +1,102 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{http::{header, StatusCode}, web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use config::AppConfig;
 use sqlx::PgPool;
 use uuid::Uuid;
-use models::payment::{CreatePayment};
-use crate::{service, error::ServiceError};
+use models::{role::Permission, payment::{CreatePayment, CreateRefund, PaymentCallback, PaymentEvent, ResolveRefund}};
+use crate::{auth::AuthenticatedUser, idempotency::{idempotent, IdempotencyKey}, service::{self, PaymentGateway}, error::ServiceError};
 
-pub async fn process_payment(pool: web::Data<PgPool>, new_payment: web::Json<CreatePayment>) -> Result<HttpResponse, ServiceError> {
-    let payment = service::process_payment(&pool, new_payment.into_inner()).await?;
-    Ok(HttpResponse::Created().json(payment))
+pub async fn process_payment(pool: web::Data<PgPool>, gateway: web::Data<dyn PaymentGateway>, config: web::Data<AppConfig>, auth: AuthenticatedUser, key: IdempotencyKey, new_payment: web::Json<CreatePayment>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::PaymentsCreate)?;
+    let order = service::get_order_by_id(&pool, new_payment.order_id).await?;
+    auth.require_owner_or(order.user_id, Permission::OrdersWrite)?;
+    idempotent(&pool, auth.user_id, key, "payments", StatusCode::CREATED, new_payment.into_inner(), |new_payment| {
+        service::process_payment(&pool, gateway.get_ref(), &config.payment, new_payment)
+    })
+    .await
 }
 
-pub async fn get_payment_status(pool: web::Data<PgPool>, payment_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
-    let payment = service::get_payment_status(&pool, payment_id.into_inner()).await?;
+/// The gateway sends the payer's browser here, so there is no token; the
+/// payment is verified with the gateway instead.
+pub async fn payment_callback(pool: web::Data<PgPool>, gateway: web::Data<dyn PaymentGateway>, config: web::Data<AppConfig>, web::Query(callback): web::Query<PaymentCallback>) -> Result<HttpResponse, ServiceError> {
+    let payment = service::handle_payment_callback(&pool, gateway.get_ref(), callback).await?;
+    match &config.payment.return_url {
+        Some(return_url) => {
+            let separator = if return_url.contains('?') { '&' } else { '?' };
+            let location = format!("{}{}payment_id={}&status={}", return_url, separator, payment.id, payment.status);
+            Ok(HttpResponse::Found().insert_header((header::LOCATION, location)).finish())
+        }
+        None => Ok(HttpResponse::Ok().json(payment)),
+    }
+}
+
+/// Gateways post signed events here; see
+/// [`verify_webhook_signature`](service::verify_webhook_signature).
+pub async fn payment_webhook(pool: web::Data<PgPool>, gateway: web::Data<dyn PaymentGateway>, config: web::Data<AppConfig>, req: HttpRequest, body: web::Bytes) -> Result<HttpResponse, ServiceError> {
+    let secret = config
+        .payment
+        .webhook_secret
+        .as_deref()
+        .ok_or_else(|| ServiceError::NotFound("Payment webhooks are not enabled".to_string()))?;
+    let header = |name: &str| req.headers().get(name).and_then(|value| value.to_str().ok()).unwrap_or_default();
+    service::verify_webhook_signature(
+        secret,
+        header(service::WEBHOOK_TIMESTAMP_HEADER),
+        header(service::WEBHOOK_SIGNATURE_HEADER),
+        &body,
+        Utc::now(),
+    )?;
+
+    let event: PaymentEvent = serde_json::from_slice(&body)
+        .map_err(|e| ServiceError::BadRequest(format!("Invalid payment event: {}", e)))?;
+    let payment = service::handle_payment_event(&pool, gateway.get_ref(), event).await?;
+    Ok(HttpResponse::Ok().json(payment))
+}
+
+pub async fn get_payment_by_id(pool: web::Data<PgPool>, auth: AuthenticatedUser, payment_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let payment = service::get_payment_by_id(&pool, payment_id.into_inner()).await?;
+    let order = service::get_order_by_id(&pool, payment.order_id).await?;
+    auth.require_owner_or(order.user_id, Permission::PaymentsRead)?;
+    Ok(HttpResponse::Ok().json(payment))
+}
+
+pub async fn refresh_payment(pool: web::Data<PgPool>, gateway: web::Data<dyn PaymentGateway>, auth: AuthenticatedUser, payment_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let payment = service::get_payment_by_id(&pool, payment_id.into_inner()).await?;
+    let order = service::get_order_by_id(&pool, payment.order_id).await?;
+    auth.require_owner_or(order.user_id, Permission::PaymentsRead)?;
+    let payment = service::refresh_payment(&pool, gateway.get_ref(), payment.id).await?;
     Ok(HttpResponse::Ok().json(payment))
 }
 
+pub async fn get_payment_status(pool: web::Data<PgPool>, auth: AuthenticatedUser, payment_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let payment = service::get_payment_by_id(&pool, payment_id.into_inner()).await?;
+    let order = service::get_order_by_id(&pool, payment.order_id).await?;
+    auth.require_owner_or(order.user_id, Permission::PaymentsRead)?;
+    let payment = service::get_payment_status(&pool, payment.id).await?;
+    Ok(HttpResponse::Ok().json(payment))
+}
+
+pub async fn refund_payment(pool: web::Data<PgPool>, gateway: web::Data<dyn PaymentGateway>, auth: AuthenticatedUser, key: IdempotencyKey, payment_id: web::Path<Uuid>, new_refund: web::Json<CreateRefund>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::PaymentsRefund)?;
+    let payment_id = payment_id.into_inner();
+    let scope = format!("payments/{}/refunds", payment_id);
+    idempotent(&pool, auth.user_id, key, &scope, StatusCode::CREATED, new_refund.into_inner(), |new_refund| {
+        service::refund_payment(&pool, gateway.get_ref(), payment_id, auth.user_id, new_refund)
+    })
+    .await
+}
+
+pub async fn resolve_refund(pool: web::Data<PgPool>, auth: AuthenticatedUser, path: web::Path<(Uuid, Uuid)>, resolution: web::Json<ResolveRefund>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::PaymentsRefund)?;
+    let (payment_id, refund_id) = path.into_inner();
+    let refund = service::resolve_refund(&pool, payment_id, refund_id, auth.user_id, resolution.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(refund))
+}
+
+pub async fn get_payment_refunds(pool: web::Data<PgPool>, auth: AuthenticatedUser, payment_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let payment = service::get_payment_by_id(&pool, payment_id.into_inner()).await?;
+    let order = service::get_order_by_id(&pool, payment.order_id).await?;
+    auth.require_owner_or(order.user_id, Permission::PaymentsRead)?;
+    let refunds = service::get_payment_refunds(&pool, payment.id).await?;
+    Ok(HttpResponse::Ok().json(refunds))
+}
@@ -0,0 +1,54 @@
+use actix_web::{web, HttpResponse};
+use config::AppConfig;
+use sqlx::PgPool;
+use uuid::Uuid;
+use models::{role::Permission, pricing::{CreatePriceLock, Metal, SetPricingRule, UpdateMetalRate}};
+use crate::{auth::AuthenticatedUser, service, error::ServiceError};
+
+pub async fn get_metal_rates(pool: web::Data<PgPool>) -> Result<HttpResponse, ServiceError> {
+    let rates = service::get_metal_rates(&pool).await?;
+    Ok(HttpResponse::Ok().json(rates))
+}
+
+pub async fn update_metal_rate(pool: web::Data<PgPool>, auth: AuthenticatedUser, metal: web::Path<Metal>, update: web::Json<UpdateMetalRate>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::PricingWrite)?;
+    let rate = service::set_metal_rate(&pool, metal.into_inner(), auth.user_id, update.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(rate))
+}
+
+/// Rules show the making charge and margin, so only pricing staff see them.
+pub async fn get_pricing_rule(pool: web::Data<PgPool>, auth: AuthenticatedUser, product_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::PricingWrite)?;
+    let rule = service::get_pricing_rule(&pool, product_id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(rule))
+}
+
+pub async fn set_pricing_rule(pool: web::Data<PgPool>, auth: AuthenticatedUser, product_id: web::Path<Uuid>, rule: web::Json<SetPricingRule>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::PricingWrite)?;
+    let rule = service::set_pricing_rule(&pool, product_id.into_inner(), auth.user_id, rule.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(rule))
+}
+
+pub async fn delete_pricing_rule(pool: web::Data<PgPool>, auth: AuthenticatedUser, product_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::PricingWrite)?;
+    service::delete_pricing_rule(&pool, product_id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn get_price_history(pool: web::Data<PgPool>, auth: AuthenticatedUser, product_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::PricingWrite)?;
+    let history = service::get_price_history(&pool, product_id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(history))
+}
+
+pub async fn create_price_lock(pool: web::Data<PgPool>, config: web::Data<AppConfig>, auth: AuthenticatedUser, new_lock: web::Json<CreatePriceLock>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::OrdersCreate)?;
+    let lock = service::create_price_lock(&pool, auth.user_id, config.checkout.price_lock_ttl_seconds, new_lock.into_inner()).await?;
+    Ok(HttpResponse::Created().json(lock))
+}
+
+pub async fn get_price_lock(pool: web::Data<PgPool>, auth: AuthenticatedUser, lock_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let lock = service::get_price_lock(&pool, lock_id.into_inner()).await?;
+    auth.require_owner_or(lock.lock.user_id, Permission::OrdersRead)?;
+    Ok(HttpResponse::Ok().json(lock))
+}
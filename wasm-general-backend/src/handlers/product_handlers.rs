@@ -1,31 +1,121 @@
 use actix_web::{web, HttpResponse};
 use sqlx::PgPool;
 use uuid::Uuid;
-use models::{product::{CreateProduct, UpdateProduct}, pagination::Pagination};
-use crate::{service, error::ServiceError};
+use models::{role::Permission, media::ImageOwner, exchange_rate::CurrencyQuery, product::{CreateProduct, CreateProductVariant, LowStockFilter, ProductSearch, UpdateProduct, UpdateProductVariant}, pagination::Pagination};
+use crate::{auth::AuthenticatedUser, service::{self, MediaStore}, error::ServiceError};
 
-pub async fn create_product(pool: web::Data<PgPool>, new_product: web::Json<CreateProduct>) -> Result<HttpResponse, ServiceError> {
-    let product = service::create_product(&pool, new_product.into_inner()).await?;
+/// A vendor's own account may manage its products; anyone else needs
+/// `products:write`.
+async fn authorize_vendor(pool: &PgPool, auth: &AuthenticatedUser, vendor_id: Uuid) -> Result<(), ServiceError> {
+    let vendor = service::get_vendor_by_id(pool, vendor_id).await?;
+    match vendor.user_id {
+        Some(user_id) => auth.require_owner_or(user_id, Permission::ProductsWrite)?,
+        None => auth.require(Permission::ProductsWrite)?,
+    }
+
+    Ok(())
+}
+
+async fn authorize_product(pool: &PgPool, auth: &AuthenticatedUser, product_id: Uuid) -> Result<(), ServiceError> {
+    let product = service::get_product_by_id(pool, product_id).await?;
+    authorize_vendor(pool, auth, product.vendor_id).await
+}
+
+pub async fn create_product(pool: web::Data<PgPool>, auth: AuthenticatedUser, new_product: web::Json<CreateProduct>) -> Result<HttpResponse, ServiceError> {
+    authorize_vendor(&pool, &auth, new_product.vendor_id).await?;
+    let product = service::create_product(&pool, auth.user_id, new_product.into_inner()).await?;
     Ok(HttpResponse::Created().json(product))
 }
 
-pub async fn get_products(pool: web::Data<PgPool>, web::Query(pagination): web::Query<Pagination>) -> Result<HttpResponse, ServiceError> {
-    let products = service::get_products(&pool, pagination).await?;
+pub async fn get_products(pool: web::Data<PgPool>, web::Query(pagination): web::Query<Pagination>, web::Query(search): web::Query<ProductSearch>, web::Query(display): web::Query<CurrencyQuery>) -> Result<HttpResponse, ServiceError> {
+    let mut products = service::get_products(&pool, pagination, search).await?;
+    if let Some(currency) = display.currency {
+        service::convert_product_prices(&pool, &mut products.page.items, currency).await?;
+    }
+    Ok(HttpResponse::Ok().json(products))
+}
+
+pub async fn get_product_categories(pool: web::Data<PgPool>) -> Result<HttpResponse, ServiceError> {
+    let categories = service::get_product_categories(&pool).await?;
+    Ok(HttpResponse::Ok().json(categories))
+}
+
+/// Without `inventory:write` only the caller's own vendor can be listed;
+/// `vendor_id` may be left out when they manage a single one.
+pub async fn get_low_stock_products(pool: web::Data<PgPool>, auth: AuthenticatedUser, web::Query(mut filter): web::Query<LowStockFilter>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::InventoryRead)?;
+    if !auth.has_permission(Permission::InventoryWrite) {
+        let own = service::get_user_vendor_ids(&pool, auth.user_id).await?;
+        filter.vendor_id = match (filter.vendor_id, own.as_slice()) {
+            (Some(vendor_id), _) if own.contains(&vendor_id) => Some(vendor_id),
+            (None, [vendor_id]) => Some(*vendor_id),
+            (None, [_, _, ..]) => return Err(ServiceError::BadRequest("vendor_id is required".to_string())),
+            _ => return Err(ServiceError::Forbidden("Only the stock of your own vendor can be listed".to_string())),
+        };
+    }
+    let products = service::get_low_stock_products(&pool, filter).await?;
     Ok(HttpResponse::Ok().json(products))
 }
 
-pub async fn get_product_by_id(pool: web::Data<PgPool>, product_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
-    let product = service::get_product_by_id(&pool, product_id.into_inner()).await?;
+pub async fn get_product_by_id(pool: web::Data<PgPool>, product_id: web::Path<Uuid>, web::Query(display): web::Query<CurrencyQuery>) -> Result<HttpResponse, ServiceError> {
+    let mut product = service::get_product_by_id(&pool, product_id.into_inner()).await?;
+    if let Some(currency) = display.currency {
+        service::convert_product_prices(&pool, std::slice::from_mut(&mut product), currency).await?;
+    }
     Ok(HttpResponse::Ok().json(product))
 }
 
-pub async fn update_product(pool: web::Data<PgPool>, product_id: web::Path<Uuid>, updated_product: web::Json<UpdateProduct>) -> Result<HttpResponse, ServiceError> {
-    let product = service::update_product(&pool, product_id.into_inner(), updated_product.into_inner()).await?;
+pub async fn update_product(pool: web::Data<PgPool>, auth: AuthenticatedUser, product_id: web::Path<Uuid>, updated_product: web::Json<UpdateProduct>) -> Result<HttpResponse, ServiceError> {
+    let product_id = product_id.into_inner();
+    authorize_product(&pool, &auth, product_id).await?;
+    let product = service::update_product(&pool, product_id, auth.user_id, updated_product.into_inner()).await?;
     Ok(HttpResponse::Ok().json(product))
 }
 
-pub async fn delete_product(pool: web::Data<PgPool>, product_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
-    service::delete_product(&pool, product_id.into_inner()).await?;
+/// The product's images go with it; their files are removed afterwards.
+pub async fn delete_product(pool: web::Data<PgPool>, store: web::Data<dyn MediaStore>, auth: AuthenticatedUser, product_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    let product_id = product_id.into_inner();
+    authorize_product(&pool, &auth, product_id).await?;
+    let images = service::get_images(&pool, ImageOwner::Product(product_id)).await?;
+    service::delete_product(&pool, product_id).await?;
+    service::remove_image_files(&**store, &images).await;
     Ok(HttpResponse::NoContent().finish())
 }
 
+pub async fn create_product_variant(pool: web::Data<PgPool>, auth: AuthenticatedUser, product_id: web::Path<Uuid>, new_variant: web::Json<CreateProductVariant>) -> Result<HttpResponse, ServiceError> {
+    let product_id = product_id.into_inner();
+    authorize_product(&pool, &auth, product_id).await?;
+    let variant = service::create_product_variant(&pool, product_id, auth.user_id, new_variant.into_inner()).await?;
+    Ok(HttpResponse::Created().json(variant))
+}
+
+pub async fn get_product_variants(pool: web::Data<PgPool>, product_id: web::Path<Uuid>, web::Query(display): web::Query<CurrencyQuery>) -> Result<HttpResponse, ServiceError> {
+    let mut variants = service::get_product_variants(&pool, product_id.into_inner()).await?;
+    if let Some(currency) = display.currency {
+        service::convert_variant_prices(&pool, &mut variants, currency).await?;
+    }
+    Ok(HttpResponse::Ok().json(variants))
+}
+
+pub async fn get_product_variant_by_id(pool: web::Data<PgPool>, path: web::Path<(Uuid, Uuid)>, web::Query(display): web::Query<CurrencyQuery>) -> Result<HttpResponse, ServiceError> {
+    let (product_id, variant_id) = path.into_inner();
+    let mut variant = service::get_product_variant_by_id(&pool, product_id, variant_id).await?;
+    if let Some(currency) = display.currency {
+        service::convert_variant_prices(&pool, std::slice::from_mut(&mut variant), currency).await?;
+    }
+    Ok(HttpResponse::Ok().json(variant))
+}
+
+pub async fn update_product_variant(pool: web::Data<PgPool>, auth: AuthenticatedUser, path: web::Path<(Uuid, Uuid)>, updated_variant: web::Json<UpdateProductVariant>) -> Result<HttpResponse, ServiceError> {
+    let (product_id, variant_id) = path.into_inner();
+    authorize_product(&pool, &auth, product_id).await?;
+    let variant = service::update_product_variant(&pool, product_id, variant_id, auth.user_id, updated_variant.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(variant))
+}
+
+pub async fn delete_product_variant(pool: web::Data<PgPool>, auth: AuthenticatedUser, path: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, ServiceError> {
+    let (product_id, variant_id) = path.into_inner();
+    authorize_product(&pool, &auth, product_id).await?;
+    service::delete_product_variant(&pool, product_id, variant_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
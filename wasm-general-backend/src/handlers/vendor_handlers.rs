@@ -1,10 +1,11 @@
 use actix_web::{web, HttpResponse};
 use sqlx::PgPool;
 use uuid::Uuid;
-use models::{vendor::{CreateVendor, UpdateVendor}, pagination::Pagination};
-use crate::{service, error::ServiceError};
+use models::{role::Permission, media::ImageOwner, vendor::{CreateVendor, UpdateVendor}, pagination::Pagination};
+use crate::{auth::AuthenticatedUser, service::{self, MediaStore}, error::ServiceError};
 
-pub async fn create_vendor(pool: web::Data<PgPool>, new_vendor: web::Json<CreateVendor>) -> Result<HttpResponse, ServiceError> {
+pub async fn create_vendor(pool: web::Data<PgPool>, auth: AuthenticatedUser, new_vendor: web::Json<CreateVendor>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::VendorsWrite)?;
     let vendor = service::create_vendor(&pool, new_vendor.into_inner()).await?;
     Ok(HttpResponse::Created().json(vendor))
 }
@@ -19,13 +20,19 @@ pub async fn get_vendor_by_id(pool: web::Data<PgPool>, vendor_id: web::Path<Uuid
     Ok(HttpResponse::Ok().json(vendor))
 }
 
-pub async fn update_vendor(pool: web::Data<PgPool>, vendor_id: web::Path<Uuid>, updated_vendor: web::Json<UpdateVendor>) -> Result<HttpResponse, ServiceError> {
+pub async fn update_vendor(pool: web::Data<PgPool>, auth: AuthenticatedUser, vendor_id: web::Path<Uuid>, updated_vendor: web::Json<UpdateVendor>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::VendorsWrite)?;
     let vendor = service::update_vendor(&pool, vendor_id.into_inner(), updated_vendor.into_inner()).await?;
     Ok(HttpResponse::Ok().json(vendor))
 }
 
-pub async fn delete_vendor(pool: web::Data<PgPool>, vendor_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
-    service::delete_vendor(&pool, vendor_id.into_inner()).await?;
+/// The vendor's images go with it; their files are removed afterwards.
+pub async fn delete_vendor(pool: web::Data<PgPool>, store: web::Data<dyn MediaStore>, auth: AuthenticatedUser, vendor_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::VendorsWrite)?;
+    let vendor_id = vendor_id.into_inner();
+    let images = service::get_images(&pool, ImageOwner::Vendor(vendor_id)).await?;
+    service::delete_vendor(&pool, vendor_id).await?;
+    service::remove_image_files(&**store, &images).await;
     Ok(HttpResponse::NoContent().finish())
 }
 
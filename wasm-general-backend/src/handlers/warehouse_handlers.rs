@@ -0,0 +1,35 @@
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+use models::{role::Permission, warehouse::{CreateWarehouse, UpdateWarehouse}, pagination::Pagination};
+use crate::{auth::AuthenticatedUser, service, error::ServiceError};
+
+pub async fn create_warehouse(pool: web::Data<PgPool>, auth: AuthenticatedUser, new_warehouse: web::Json<CreateWarehouse>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::InventoryWrite)?;
+    let warehouse = service::create_warehouse(&pool, new_warehouse.into_inner()).await?;
+    Ok(HttpResponse::Created().json(warehouse))
+}
+
+pub async fn get_warehouses(pool: web::Data<PgPool>, auth: AuthenticatedUser, web::Query(pagination): web::Query<Pagination>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::InventoryRead)?;
+    let warehouses = service::get_warehouses(&pool, pagination).await?;
+    Ok(HttpResponse::Ok().json(warehouses))
+}
+
+pub async fn get_warehouse_by_id(pool: web::Data<PgPool>, auth: AuthenticatedUser, warehouse_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::InventoryRead)?;
+    let warehouse = service::get_warehouse_by_id(&pool, warehouse_id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(warehouse))
+}
+
+pub async fn update_warehouse(pool: web::Data<PgPool>, auth: AuthenticatedUser, warehouse_id: web::Path<Uuid>, updated_warehouse: web::Json<UpdateWarehouse>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::InventoryWrite)?;
+    let warehouse = service::update_warehouse(&pool, warehouse_id.into_inner(), updated_warehouse.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(warehouse))
+}
+
+pub async fn delete_warehouse(pool: web::Data<PgPool>, auth: AuthenticatedUser, warehouse_id: web::Path<Uuid>) -> Result<HttpResponse, ServiceError> {
+    auth.require(Permission::InventoryWrite)?;
+    service::delete_warehouse(&pool, warehouse_id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,92 @@
+use actix_web::{dev::Payload, http::StatusCode, FromRequest, HttpRequest, HttpResponse};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::future::{ready, Future, Ready};
+use uuid::Uuid;
+use crate::{error::ServiceError, service::{self, StoredResponse}};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+/// Set on responses that were replayed for a repeated key.
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+const MAX_KEY_LENGTH: usize = 255;
+
+/// The optional `Idempotency-Key` header. A client that retries a request
+/// with the same key gets the first response back instead of the work being
+/// done twice; see [`idempotent`].
+#[derive(Debug, Clone, Default)]
+pub struct IdempotencyKey(pub Option<String>);
+
+impl FromRequest for IdempotencyKey {
+    type Error = ServiceError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(idempotency_key(req))
+    }
+}
+
+fn idempotency_key(req: &HttpRequest) -> Result<IdempotencyKey, ServiceError> {
+    let Some(value) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(IdempotencyKey(None));
+    };
+
+    let key = value.to_str().map(str::trim).unwrap_or_default();
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        return Err(ServiceError::BadRequest(format!(
+            "{} must be 1 to {} visible ASCII characters",
+            IDEMPOTENCY_KEY_HEADER, MAX_KEY_LENGTH
+        )));
+    }
+
+    Ok(IdempotencyKey(Some(key.to_string())))
+}
+
+/// Runs `handler` on `request` and answers with `status` and its result.
+///
+/// With a key, the response is stored under the user, `scope` and key, and
+/// replayed for retries with the same body for a day. Reusing a key for a
+/// different body is rejected, as is a retry while the first request is
+/// still running. A failed request stores nothing, so it can be retried.
+pub async fn idempotent<R, T, F, Fut>(pool: &PgPool, user_id: Uuid, key: IdempotencyKey, scope: &str, status: StatusCode, request: R, handler: F) -> Result<HttpResponse, ServiceError>
+where
+    R: Serialize,
+    T: Serialize,
+    F: FnOnce(R) -> Fut,
+    Fut: Future<Output = Result<T, ServiceError>>,
+{
+    let Some(key) = key.0 else {
+        return Ok(HttpResponse::build(status).json(handler(request).await?));
+    };
+
+    let request_hash = service::request_hash(&request)?;
+    if let Some(stored) = service::claim_idempotency_key(pool, user_id, scope, &key, &request_hash).await? {
+        let status = StatusCode::from_u16(stored.status_code as u16).unwrap_or(StatusCode::OK);
+        return Ok(HttpResponse::build(status)
+            .content_type("application/json")
+            .insert_header((IDEMPOTENT_REPLAYED_HEADER, "true"))
+            .body(stored.response_body));
+    }
+
+    let result = match handler(request).await {
+        Ok(result) => result,
+        Err(e) => {
+            if let Err(release_error) = service::release_idempotency_key(pool, user_id, scope, &key).await {
+                log::warn!("Failed to release idempotency key `{}`: {}", key, release_error);
+            }
+            return Err(e);
+        }
+    };
+
+    let stored = StoredResponse {
+        status_code: status.as_u16() as i16,
+        response_body: serde_json::to_string(&result)
+            .map_err(|e| ServiceError::InternalServerError(format!("Failed to serialize the response: {}", e)))?,
+    };
+    // The work is done; failing to store the response must not fail the
+    // request, the key then just blocks retries until it times out.
+    if let Err(e) = service::complete_idempotency_key(pool, user_id, scope, &key, &stored).await {
+        log::warn!("Failed to store the response for idempotency key `{}`: {}", key, e);
+    }
+
+    Ok(HttpResponse::build(status).content_type("application/json").body(stored.response_body))
+}
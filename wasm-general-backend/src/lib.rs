@@ -1,14 +1,22 @@
+pub mod auth;
+pub mod error;
+pub mod handlers;
+pub mod idempotency;
+pub mod service;
+
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub fn get_product_list() -> String {
     // In a real application, this would fetch data from a database or another service
     "[\"Product 1\", \"Product 2\", \"Product 3\"]".to_string()
 }
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub fn get_order_details(order_id: &str) -> String {
     // In a real application, this would fetch data from a database or another service
     format!("Details for order {}", order_id)
 }
-
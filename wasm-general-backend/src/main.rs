@@ -0,0 +1,137 @@
+use actix_web::{middleware::Logger, web, App, HttpServer};
+use config::AppConfig;
+use sqlx::postgres::PgPoolOptions;
+use std::time::Duration;
+use wasm_general_backend::{auth::TokenVerifier, handlers, service::{self, Notifier}};
+
+const JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const RESERVATION_EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+const LOW_STOCK_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const IDEMPOTENCY_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    dotenvy::dotenv().ok();
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info"));
+
+    // The landing page expects the catalog on 8081, next to the auth service on 8080.
+    let config = AppConfig {
+        server_port: 8081,
+        ..AppConfig::default()
+    }
+    .with_env();
+    let verifier = web::Data::new(TokenVerifier::from_config(&config.jwt).map_err(std::io::Error::other)?);
+    let notifier = web::Data::new(Notifier::from_config(&config.notification).map_err(std::io::Error::other)?);
+
+    let pool = PgPoolOptions::new()
+        .max_connections(config.database_max_connections)
+        .connect(&config.database_url)
+        .await
+        .map_err(|e| std::io::Error::other(format!("Failed to connect to database: {}", e)))?;
+
+    // `<binary> migrate [up|down [version]|status|verify]` manages the schema and exits.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        let result = db::run_command(&pool, &args[1..]).await;
+        pool.close().await;
+        return result.map_err(std::io::Error::other);
+    }
+
+    if config.run_migrations {
+        db::run_migrations(&pool).await.map_err(std::io::Error::other)?;
+    }
+    db::verify_schema(&pool).await.map_err(std::io::Error::other)?;
+    match service::index_unindexed_products(&pool).await {
+        Ok(0) => {}
+        Ok(count) => log::info!("Indexed {} products for search", count),
+        Err(e) => log::warn!("Failed to index products for search: {}", e),
+    }
+
+    let gateway = web::Data::from(service::gateway_from_config(&config.payment).await.map_err(std::io::Error::other)?);
+    let media = web::Data::from(service::media_store_from_config(&config.media).map_err(std::io::Error::other)?);
+    log::info!("Storing media in the {} store", media.name());
+
+    // The auth service may still be starting; keep retrying in the background
+    // so rotated keys are picked up without a restart.
+    let jwks_verifier = verifier.clone();
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(JWKS_REFRESH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = jwks_verifier.refresh_jwks().await {
+                log::warn!("{}", e);
+            }
+        }
+    });
+
+    // Unpaid orders give their stock back once their reservations run out.
+    let expiry_pool = pool.clone();
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(RESERVATION_EXPIRY_INTERVAL);
+        loop {
+            interval.tick().await;
+            match service::expire_reservations(&expiry_pool).await {
+                Ok(0) => {}
+                Ok(count) => log::info!("Cancelled {} unpaid orders with expired reservations", count),
+                Err(e) => log::warn!("Failed to expire stock reservations: {}", e),
+            }
+        }
+    });
+
+    // Vendors hear about stock levels that drop to their reorder point.
+    let low_stock_pool = pool.clone();
+    let low_stock_notifier = notifier.clone();
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(LOW_STOCK_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            match service::check_low_stock(&low_stock_pool, &low_stock_notifier).await {
+                Ok(0) => {}
+                Ok(count) => log::info!("Sent {} low-stock alerts", count),
+                Err(e) => log::warn!("Failed to check for low stock: {}", e),
+            }
+        }
+    });
+
+    // Stored responses to idempotent requests are only replayed for a day.
+    let idempotency_pool = pool.clone();
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(IDEMPOTENCY_PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = service::purge_idempotency_keys(&idempotency_pool).await {
+                log::warn!("Failed to purge expired idempotency keys: {}", e);
+            }
+        }
+    });
+
+    log::info!("Starting General Backend Service on {}:{}", config.server_host, config.server_port);
+
+    let server_pool = pool.clone();
+    let server_config = web::Data::new(config.clone());
+    let multipart_config = handlers::media_handlers::multipart_config(&config.media);
+
+    // actix-web stops accepting connections on SIGINT/SIGTERM and lets
+    // in-flight requests finish within the shutdown timeout.
+    HttpServer::new(move || {
+        App::new()
+            .wrap(Logger::default())
+            .app_data(web::Data::new(server_pool.clone()))
+            .app_data(server_config.clone())
+            .app_data(verifier.clone())
+            .app_data(notifier.clone())
+            .app_data(gateway.clone())
+            .app_data(media.clone())
+            .app_data(multipart_config.clone())
+            .configure(handlers::config)
+    })
+    .bind((config.server_host.as_str(), config.server_port))?
+    .shutdown_timeout(30)
+    .run()
+    .await?;
+
+    log::info!("General Backend Service stopped, closing database pool");
+    pool.close().await;
+
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use sqlx::{types::Json, PgConnection, PgPool};
+use uuid::Uuid;
+use chrono::Utc;
+use models::{
+    category::{is_valid_slug, Category, CategoryAttribute, CategoryNode, CreateCategory, UpdateCategory},
+    product::Product,
+};
+use crate::error::ServiceError;
+use super::product_search::index_product;
+
+pub async fn create_category(pool: &PgPool, new_category: CreateCategory) -> Result<Category, ServiceError> {
+    let slug = new_category.slug.trim().to_lowercase();
+    validate_category(&slug, &new_category.name_fa, &new_category.name_en, &new_category.attributes)?;
+    if let Some(parent_id) = new_category.parent_id {
+        if !category_exists(&mut *pool.acquire().await?, parent_id).await? {
+            return Err(parent_not_found());
+        }
+    }
+
+    let category = sqlx::query_as::<_, Category>(
+        "INSERT INTO categories (parent_id, slug, name_fa, name_en, position, attributes, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING *"
+    )
+    .bind(new_category.parent_id)
+    .bind(slug)
+    .bind(new_category.name_fa.trim())
+    .bind(new_category.name_en.trim())
+    .bind(new_category.position)
+    .bind(Json(new_category.attributes))
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(category)
+}
+
+/// All categories as a flat list, in display order.
+pub async fn get_categories(pool: &PgPool) -> Result<Vec<Category>, ServiceError> {
+    let categories = sqlx::query_as::<_, Category>(
+        "SELECT * FROM categories ORDER BY position, name_en"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(categories)
+}
+
+pub async fn get_category_tree(pool: &PgPool) -> Result<Vec<CategoryNode>, ServiceError> {
+    Ok(CategoryNode::build(get_categories(pool).await?))
+}
+
+pub async fn get_category_by_id(pool: &PgPool, category_id: Uuid) -> Result<Category, ServiceError> {
+    let category = sqlx::query_as::<_, Category>(
+        "SELECT * FROM categories WHERE id = $1"
+    )
+    .bind(category_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(category)
+}
+
+/// Products of the category and its subcategories are reindexed, as their
+/// search vectors hold the names of all categories above them.
+pub async fn update_category(pool: &PgPool, category_id: Uuid, updated_category: UpdateCategory) -> Result<Category, ServiceError> {
+    let existing_category = get_category_by_id(pool, category_id).await?;
+
+    let slug = updated_category.slug.map_or(existing_category.slug, |slug| slug.trim().to_lowercase());
+    let name_fa = updated_category.name_fa.unwrap_or(existing_category.name_fa);
+    let name_en = updated_category.name_en.unwrap_or(existing_category.name_en);
+    let attributes = updated_category.attributes.unwrap_or(existing_category.attributes);
+    validate_category(&slug, &name_fa, &name_en, &attributes)?;
+
+    let mut tx = pool.begin().await?;
+    let parent_id = match updated_category.parent_id {
+        Some(Some(parent_id)) => {
+            // Locks the subtree so a concurrent move cannot close a cycle.
+            let subtree = subtree_ids(&mut tx, category_id, true).await?;
+            if subtree.contains(&parent_id) {
+                return Err(ServiceError::BadRequest("A category cannot be moved under itself or its subcategories".to_string()));
+            }
+            if !category_exists(&mut tx, parent_id).await? {
+                return Err(parent_not_found());
+            }
+            Some(parent_id)
+        }
+        Some(None) => None,
+        None => existing_category.parent_id,
+    };
+
+    let category = sqlx::query_as::<_, Category>(
+        "UPDATE categories SET parent_id = $1, slug = $2, name_fa = $3, name_en = $4, position = $5, attributes = $6, updated_at = $7 WHERE id = $8 RETURNING *"
+    )
+    .bind(parent_id)
+    .bind(slug)
+    .bind(name_fa.trim())
+    .bind(name_en.trim())
+    .bind(updated_category.position.unwrap_or(existing_category.position))
+    .bind(Json(attributes))
+    .bind(Utc::now())
+    .bind(category_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let subtree = subtree_ids(&mut tx, category_id, false).await?;
+    let products = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE category_id = ANY($1)")
+        .bind(subtree)
+        .fetch_all(&mut *tx)
+        .await?;
+    for product in &products {
+        index_product(&mut tx, product).await?;
+    }
+    tx.commit().await?;
+
+    Ok(category)
+}
+
+/// Only empty categories can be deleted: without subcategories and without
+/// products.
+pub async fn delete_category(pool: &PgPool, category_id: Uuid) -> Result<(), ServiceError> {
+    get_category_by_id(pool, category_id).await?;
+
+    let (children, products): (i64, i64) = sqlx::query_as(
+        "SELECT (SELECT COUNT(*) FROM categories WHERE parent_id = $1), (SELECT COUNT(*) FROM products WHERE category_id = $1)"
+    )
+    .bind(category_id)
+    .fetch_one(pool)
+    .await?;
+    if children > 0 {
+        return Err(ServiceError::BadRequest("Move or delete the subcategories first".to_string()));
+    }
+    if products > 0 {
+        return Err(ServiceError::BadRequest(format!("{} products are still listed in this category", products)));
+    }
+
+    sqlx::query("DELETE FROM categories WHERE id = $1")
+        .bind(category_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// The category and every category below it, optionally locked for update.
+async fn subtree_ids(conn: &mut PgConnection, category_id: Uuid, lock: bool) -> Result<Vec<Uuid>, ServiceError> {
+    let ids = sqlx::query_scalar(&format!(
+        "WITH RECURSIVE subtree AS (
+            SELECT id FROM categories WHERE id = $1
+            UNION ALL
+            SELECT categories.id FROM categories JOIN subtree ON categories.parent_id = subtree.id
+        )
+        SELECT id FROM categories WHERE id IN (SELECT id FROM subtree){}",
+        if lock { " FOR UPDATE" } else { "" }
+    ))
+    .bind(category_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(ids)
+}
+
+/// The category and the categories above it, nearest first.
+pub(crate) async fn category_path(conn: &mut PgConnection, category_id: Uuid) -> Result<Vec<Category>, ServiceError> {
+    let path = sqlx::query_as::<_, Category>(
+        "WITH RECURSIVE path AS (
+            SELECT categories.*, 0 AS depth FROM categories WHERE id = $1
+            UNION ALL
+            SELECT categories.*, path.depth + 1 FROM categories JOIN path ON categories.id = path.parent_id
+        )
+        SELECT id, parent_id, slug, name_fa, name_en, position, attributes, created_at, updated_at FROM path ORDER BY depth"
+    )
+    .bind(category_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(path)
+}
+
+pub(crate) async fn category_exists(conn: &mut PgConnection, category_id: Uuid) -> Result<bool, ServiceError> {
+    let exists = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM categories WHERE id = $1)")
+        .bind(category_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok(exists)
+}
+
+/// The attribute schema products of the category are described by.
+pub(crate) async fn category_attributes(conn: &mut PgConnection, category_id: Uuid) -> Result<Vec<CategoryAttribute>, ServiceError> {
+    let Json(attributes) = sqlx::query_scalar::<_, Json<Vec<CategoryAttribute>>>("SELECT attributes FROM categories WHERE id = $1")
+        .bind(category_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok(attributes)
+}
+
+fn validate_category(slug: &str, name_fa: &str, name_en: &str, attributes: &[CategoryAttribute]) -> Result<(), ServiceError> {
+    if !is_valid_slug(slug) {
+        return Err(ServiceError::BadRequest("Category slugs are lowercase English words and digits joined by dashes".to_string()));
+    }
+    if name_fa.trim().is_empty() || name_en.trim().is_empty() {
+        return Err(ServiceError::BadRequest("Categories need a Persian and an English name".to_string()));
+    }
+
+    CategoryAttribute::validate_schema(attributes).map_err(ServiceError::BadRequest)
+}
+
+fn parent_not_found() -> ServiceError {
+    ServiceError::BadRequest("The parent category does not exist".to_string())
+}
@@ -0,0 +1,84 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use chrono::Utc;
+use models::{
+    exchange_rate::{ExchangeRate, RateSource, UpdateExchangeRate},
+    money::{Currency, Money},
+    product::{Product, ProductVariant},
+};
+use crate::error::ServiceError;
+
+pub async fn get_exchange_rates(pool: &PgPool) -> Result<Vec<ExchangeRate>, ServiceError> {
+    let rates = sqlx::query_as::<_, ExchangeRate>("SELECT * FROM exchange_rates ORDER BY currency")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rates)
+}
+
+/// Sets the rate of a foreign currency by hand. Prices and orders use the
+/// new rate from then on; orders already placed keep theirs.
+pub async fn set_exchange_rate(pool: &PgPool, currency: Currency, actor: Uuid, update: UpdateExchangeRate) -> Result<ExchangeRate, ServiceError> {
+    if currency.is_rial_based() {
+        return Err(ServiceError::BadRequest(format!("The rate of {} is fixed", currency)));
+    }
+    if update.rials_per_unit <= 0 {
+        return Err(ServiceError::BadRequest("An exchange rate must be positive".to_string()));
+    }
+
+    let rate = sqlx::query_as::<_, ExchangeRate>(
+        "INSERT INTO exchange_rates (currency, rials_per_unit, source, updated_by, updated_at) VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (currency) DO UPDATE
+        SET rials_per_unit = EXCLUDED.rials_per_unit, source = EXCLUDED.source,
+            updated_by = EXCLUDED.updated_by, updated_at = EXCLUDED.updated_at
+        RETURNING *"
+    )
+    .bind(currency)
+    .bind(update.rials_per_unit)
+    .bind(RateSource::Manual)
+    .bind(actor)
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(rate)
+}
+
+/// Rials one unit of `currency` is worth now.
+pub(crate) async fn rials_per_unit(conn: &mut PgConnection, currency: Currency) -> Result<i64, ServiceError> {
+    if let Some(rate) = currency.fixed_rials_per_unit() {
+        return Ok(rate);
+    }
+
+    sqlx::query_scalar("SELECT rials_per_unit FROM exchange_rates WHERE currency = $1")
+        .bind(currency)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| ServiceError::BadRequest(format!("There is no exchange rate for {} yet", currency)))
+}
+
+/// `amount` in rials at the current rate.
+pub(crate) async fn to_rials(conn: &mut PgConnection, amount: Money) -> Result<Money, ServiceError> {
+    let rate = rials_per_unit(conn, amount.currency()).await?;
+    Ok(amount.checked_convert(Currency::Irr, rate, 1)?)
+}
+
+/// Shows the products' prices in `currency` at the current rate.
+pub async fn convert_product_prices(pool: &PgPool, products: &mut [Product], currency: Currency) -> Result<(), ServiceError> {
+    let rate = rials_per_unit(&mut *pool.acquire().await?, currency).await?;
+    for product in products {
+        product.price = product.price.checked_convert(currency, 1, rate)?;
+    }
+
+    Ok(())
+}
+
+/// Shows the variants' prices in `currency` at the current rate.
+pub async fn convert_variant_prices(pool: &PgPool, variants: &mut [ProductVariant], currency: Currency) -> Result<(), ServiceError> {
+    let rate = rials_per_unit(&mut *pool.acquire().await?, currency).await?;
+    for variant in variants {
+        variant.price = variant.price.checked_convert(currency, 1, rate)?;
+    }
+
+    Ok(())
+}
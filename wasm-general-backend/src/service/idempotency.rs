@@ -0,0 +1,129 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use serde::Serialize;
+use crate::error::ServiceError;
+
+/// How long a response is replayed for a repeated key.
+const IDEMPOTENCY_KEY_TTL_HOURS: i64 = 24;
+/// A request that claimed a key but never finished (e.g. the service was
+/// restarted) stops blocking retries after this long.
+const IDEMPOTENCY_LOCK_TIMEOUT_MINUTES: i64 = 5;
+
+/// The response first given to an idempotent request.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct StoredResponse {
+    pub status_code: i16,
+    pub response_body: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct IdempotencyRecord {
+    request_hash: String,
+    status_code: Option<i16>,
+    response_body: Option<String>,
+}
+
+/// Fingerprint of a request body, so a key cannot be reused for a
+/// different request.
+pub fn request_hash<T: Serialize>(request: &T) -> Result<String, ServiceError> {
+    let body = serde_json::to_vec(request)
+        .map_err(|e| ServiceError::InternalServerError(format!("Failed to hash the request: {}", e)))?;
+    Ok(hex::encode(Sha256::digest(body)))
+}
+
+/// Claims `key` for a request. Returns `None` when the caller should go
+/// ahead and handle the request, or the stored response when it was
+/// already handled.
+pub async fn claim_idempotency_key(pool: &PgPool, user_id: Uuid, scope: &str, key: &str, request_hash: &str) -> Result<Option<StoredResponse>, ServiceError> {
+    let now = Utc::now();
+    let claimed = sqlx::query(
+        "INSERT INTO idempotency_keys (user_id, scope, idempotency_key, request_hash, created_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_id, scope, idempotency_key) DO UPDATE
+        SET request_hash = EXCLUDED.request_hash, status_code = NULL, response_body = NULL,
+            created_at = EXCLUDED.created_at, completed_at = NULL
+        WHERE idempotency_keys.created_at < $6
+           OR (idempotency_keys.completed_at IS NULL AND idempotency_keys.created_at < $7)"
+    )
+    .bind(user_id)
+    .bind(scope)
+    .bind(key)
+    .bind(request_hash)
+    .bind(now)
+    .bind(now - Duration::hours(IDEMPOTENCY_KEY_TTL_HOURS))
+    .bind(now - Duration::minutes(IDEMPOTENCY_LOCK_TIMEOUT_MINUTES))
+    .execute(pool)
+    .await?
+    .rows_affected();
+    if claimed == 1 {
+        return Ok(None);
+    }
+
+    let record = sqlx::query_as::<_, IdempotencyRecord>(
+        "SELECT request_hash, status_code, response_body FROM idempotency_keys
+        WHERE user_id = $1 AND scope = $2 AND idempotency_key = $3"
+    )
+    .bind(user_id)
+    .bind(scope)
+    .bind(key)
+    .fetch_optional(pool)
+    .await?;
+
+    match record {
+        Some(record) if record.request_hash != request_hash => Err(ServiceError::BadRequest(
+            "This Idempotency-Key was already used for a different request".to_string(),
+        )),
+        Some(IdempotencyRecord { status_code: Some(status_code), response_body: Some(response_body), .. }) => {
+            Ok(Some(StoredResponse { status_code, response_body }))
+        }
+        _ => Err(ServiceError::DuplicateEntry(
+            "A request with this Idempotency-Key is still being processed".to_string(),
+        )),
+    }
+}
+
+/// Stores the response to replay for a claimed key.
+pub async fn complete_idempotency_key(pool: &PgPool, user_id: Uuid, scope: &str, key: &str, response: &StoredResponse) -> Result<(), ServiceError> {
+    sqlx::query(
+        "UPDATE idempotency_keys SET status_code = $4, response_body = $5, completed_at = $6
+        WHERE user_id = $1 AND scope = $2 AND idempotency_key = $3"
+    )
+    .bind(user_id)
+    .bind(scope)
+    .bind(key)
+    .bind(response.status_code)
+    .bind(&response.response_body)
+    .bind(Utc::now())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Gives up a claimed key after the request failed, so it can be retried.
+pub async fn release_idempotency_key(pool: &PgPool, user_id: Uuid, scope: &str, key: &str) -> Result<(), ServiceError> {
+    sqlx::query(
+        "DELETE FROM idempotency_keys
+        WHERE user_id = $1 AND scope = $2 AND idempotency_key = $3 AND completed_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(scope)
+    .bind(key)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Deletes keys that are no longer replayed. Returns how many were deleted.
+pub async fn purge_idempotency_keys(pool: &PgPool) -> Result<u64, ServiceError> {
+    let deleted = sqlx::query("DELETE FROM idempotency_keys WHERE created_at < $1")
+        .bind(Utc::now() - Duration::hours(IDEMPOTENCY_KEY_TTL_HOURS))
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+    Ok(deleted)
+}
@@ -0,0 +1,477 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use chrono::Utc;
+use models::{
+    inventory::{
+        InventoryItem, CreateInventoryItem, UpdateInventoryItem, InventoryFilter, MovementType,
+        StockMovement, CreateStockMovement, StockTransfer, CreateStockTransfer, StockDiscrepancy,
+    },
+    pagination::{Pagination, PaginatedResponse},
+};
+use crate::error::ServiceError;
+use super::product_variant::lock_variant;
+
+/// A ledger entry about to be booked with [`record_movement`].
+pub(crate) struct NewMovement {
+    pub variant_id: Uuid,
+    pub warehouse_id: Uuid,
+    pub movement_type: MovementType,
+    pub quantity: i32,
+    pub reference_id: Option<Uuid>,
+    pub note: Option<String>,
+    pub created_by: Option<Uuid>,
+}
+
+/// Appends a movement to the ledger and applies it to the warehouse's stock
+/// level. Receipts, returns and adjustments also change the variant's and
+/// the product's sellable stock; see [`sellable_change`].
+///
+/// The product and variant rows are locked before the stock level so that
+/// concurrent movements of the same product cannot deadlock.
+pub(crate) async fn record_movement(conn: &mut PgConnection, movement: NewMovement) -> Result<StockMovement, ServiceError> {
+    if movement.quantity == 0 {
+        return Err(ServiceError::BadRequest("A stock movement needs a non-zero quantity".to_string()));
+    }
+
+    let (product, variant) = lock_variant(conn, movement.variant_id).await?;
+    let sellable = sellable_change(movement.movement_type, movement.quantity, variant.stock)?;
+
+    sqlx::query(
+        "INSERT INTO inventory_items (product_id, variant_id, warehouse_id, quantity, created_at, updated_at) VALUES ($1, $2, $3, 0, $4, $4) ON CONFLICT (variant_id, warehouse_id) DO NOTHING"
+    )
+    .bind(product.id)
+    .bind(variant.id)
+    .bind(movement.warehouse_id)
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+
+    let on_hand: i32 = sqlx::query_scalar(
+        "SELECT quantity FROM inventory_items WHERE variant_id = $1 AND warehouse_id = $2 FOR UPDATE"
+    )
+    .bind(variant.id)
+    .bind(movement.warehouse_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let balance = on_hand + movement.quantity;
+    if balance < 0 {
+        return Err(ServiceError::BadRequest(format!(
+            "The warehouse only holds {} units of {}",
+            on_hand, variant.sku
+        )));
+    }
+
+    sqlx::query("UPDATE inventory_items SET quantity = $1, updated_at = $2 WHERE variant_id = $3 AND warehouse_id = $4")
+        .bind(balance)
+        .bind(Utc::now())
+        .bind(variant.id)
+        .bind(movement.warehouse_id)
+        .execute(&mut *conn)
+        .await?;
+
+    if sellable != 0 {
+        sqlx::query("UPDATE product_variants SET stock = stock + $1, updated_at = $2 WHERE id = $3")
+            .bind(sellable)
+            .bind(Utc::now())
+            .bind(variant.id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("UPDATE products SET stock = stock + $1, updated_at = $2 WHERE id = $3")
+            .bind(sellable)
+            .bind(Utc::now())
+            .bind(product.id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let recorded = sqlx::query_as::<_, StockMovement>(
+        "INSERT INTO stock_movements (product_id, variant_id, warehouse_id, movement_type, quantity, balance_after, reference_id, note, created_by, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING *"
+    )
+    .bind(product.id)
+    .bind(variant.id)
+    .bind(movement.warehouse_id)
+    .bind(movement.movement_type)
+    .bind(movement.quantity)
+    .bind(balance)
+    .bind(movement.reference_id)
+    .bind(movement.note)
+    .bind(movement.created_by)
+    .bind(Utc::now())
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(recorded)
+}
+
+/// How a movement changes a variant's sellable stock, which must not go
+/// below zero. Sales were already taken off it when the order reserved
+/// them, and transfers only move units between warehouses, so neither
+/// changes it; reserved units may therefore still be transferred.
+fn sellable_change(movement_type: MovementType, quantity: i32, sellable: i32) -> Result<i32, ServiceError> {
+    let change = match movement_type {
+        MovementType::Sale | MovementType::Transfer => 0,
+        MovementType::Receipt | MovementType::Return | MovementType::Adjustment => quantity,
+    };
+    if sellable + change < 0 {
+        return Err(ServiceError::BadRequest(format!(
+            "Only {} units are not reserved by open orders",
+            sellable
+        )));
+    }
+
+    Ok(change)
+}
+
+/// Receives stock into a warehouse.
+pub async fn create_inventory_item(pool: &PgPool, actor: Uuid, new_item: CreateInventoryItem) -> Result<InventoryItem, ServiceError> {
+    if new_item.quantity <= 0 {
+        return Err(ServiceError::BadRequest("Received quantity must be positive".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+    record_movement(&mut tx, NewMovement {
+        variant_id: new_item.variant_id,
+        warehouse_id: new_item.warehouse_id,
+        movement_type: MovementType::Receipt,
+        quantity: new_item.quantity,
+        reference_id: None,
+        note: new_item.note,
+        created_by: Some(actor),
+    })
+    .await?;
+    let item = find_inventory_item(&mut tx, new_item.variant_id, new_item.warehouse_id).await?;
+    tx.commit().await?;
+
+    Ok(item)
+}
+
+pub async fn get_inventory_items(pool: &PgPool, filter: InventoryFilter, pagination: Pagination) -> Result<PaginatedResponse<InventoryItem>, ServiceError> {
+    let offset = pagination.offset().map_err(ServiceError::BadRequest)?;
+
+    let total_items: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM inventory_items WHERE ($1::uuid IS NULL OR product_id = $1) AND ($2::uuid IS NULL OR variant_id = $2) AND ($3::uuid IS NULL OR warehouse_id = $3)"
+    )
+    .bind(filter.product_id)
+    .bind(filter.variant_id)
+    .bind(filter.warehouse_id)
+    .fetch_one(pool)
+    .await?;
+
+    let items = sqlx::query_as::<_, InventoryItem>(
+        "SELECT * FROM inventory_items WHERE ($1::uuid IS NULL OR product_id = $1) AND ($2::uuid IS NULL OR variant_id = $2) AND ($3::uuid IS NULL OR warehouse_id = $3) ORDER BY created_at DESC LIMIT $4 OFFSET $5"
+    )
+    .bind(filter.product_id)
+    .bind(filter.variant_id)
+    .bind(filter.warehouse_id)
+    .bind(pagination.limit as i64)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let total_pages = (total_items as f64 / pagination.limit as f64).ceil() as u32;
+
+    Ok(PaginatedResponse {
+        items,
+        total_items: total_items as u32,
+        current_page: pagination.page,
+        total_pages,
+        limit: pagination.limit,
+    })
+}
+
+pub async fn get_inventory_item_by_id(pool: &PgPool, item_id: Uuid) -> Result<InventoryItem, ServiceError> {
+    let item = sqlx::query_as::<_, InventoryItem>(
+        "SELECT * FROM inventory_items WHERE id = $1"
+    )
+    .bind(item_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(item)
+}
+
+/// Books the difference to the counted quantity as an adjustment and sets
+/// the reorder point and quantity.
+pub async fn update_inventory_item(pool: &PgPool, item_id: Uuid, actor: Uuid, updated_item: UpdateInventoryItem) -> Result<InventoryItem, ServiceError> {
+    let existing_item = get_inventory_item_by_id(pool, item_id).await?;
+
+    if updated_item.quantity.is_some_and(|quantity| quantity < 0) {
+        return Err(ServiceError::BadRequest("Inventory quantity cannot be negative".to_string()));
+    }
+    if updated_item.reorder_point.is_some_and(|point| point < 0) {
+        return Err(ServiceError::BadRequest("Reorder point cannot be negative".to_string()));
+    }
+    if updated_item.reorder_quantity.is_some_and(|quantity| quantity <= 0) {
+        return Err(ServiceError::BadRequest("Reorder quantity must be positive".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+    // Lock in the same order as `record_movement` before reading the count.
+    lock_variant(&mut tx, existing_item.variant_id).await?;
+    let current = sqlx::query_as::<_, InventoryItem>(
+        "SELECT * FROM inventory_items WHERE id = $1 FOR UPDATE"
+    )
+    .bind(item_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if let Some(quantity) = updated_item.quantity.filter(|quantity| *quantity != current.quantity) {
+        record_movement(&mut tx, NewMovement {
+            variant_id: current.variant_id,
+            warehouse_id: current.warehouse_id,
+            movement_type: MovementType::Adjustment,
+            quantity: quantity - current.quantity,
+            reference_id: None,
+            note: updated_item.note,
+            created_by: Some(actor),
+        })
+        .await?;
+    }
+
+    let item = sqlx::query_as::<_, InventoryItem>(
+        "UPDATE inventory_items SET reorder_point = $1, reorder_quantity = $2, updated_at = $3 WHERE id = $4 RETURNING *"
+    )
+    .bind(updated_item.reorder_point.or(current.reorder_point))
+    .bind(updated_item.reorder_quantity.or(current.reorder_quantity))
+    .bind(Utc::now())
+    .bind(item_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(item)
+}
+
+/// Only empty stock levels can be removed; adjust the quantity to zero first.
+pub async fn delete_inventory_item(pool: &PgPool, item_id: Uuid) -> Result<(), ServiceError> {
+    let item = get_inventory_item_by_id(pool, item_id).await?;
+    if item.quantity != 0 {
+        return Err(ServiceError::BadRequest("Only empty stock levels can be deleted".to_string()));
+    }
+
+    sqlx::query("DELETE FROM inventory_items WHERE id = $1 AND quantity = 0")
+        .bind(item_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn create_stock_movement(pool: &PgPool, actor: Uuid, new_movement: CreateStockMovement) -> Result<StockMovement, ServiceError> {
+    match new_movement.movement_type {
+        MovementType::Sale | MovementType::Transfer => {
+            return Err(ServiceError::BadRequest(format!(
+                "Movements of type `{}` are recorded by orders and transfers",
+                new_movement.movement_type
+            )));
+        }
+        MovementType::Receipt | MovementType::Return if new_movement.quantity <= 0 => {
+            return Err(ServiceError::BadRequest(format!(
+                "A {} must add stock",
+                new_movement.movement_type
+            )));
+        }
+        _ => {}
+    }
+
+    let mut tx = pool.begin().await?;
+    let movement = record_movement(&mut tx, NewMovement {
+        variant_id: new_movement.variant_id,
+        warehouse_id: new_movement.warehouse_id,
+        movement_type: new_movement.movement_type,
+        quantity: new_movement.quantity,
+        reference_id: None,
+        note: new_movement.note,
+        created_by: Some(actor),
+    })
+    .await?;
+    tx.commit().await?;
+
+    Ok(movement)
+}
+
+pub async fn get_stock_movements(pool: &PgPool, filter: InventoryFilter, pagination: Pagination) -> Result<PaginatedResponse<StockMovement>, ServiceError> {
+    let offset = pagination.offset().map_err(ServiceError::BadRequest)?;
+
+    let total_items: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM stock_movements WHERE ($1::uuid IS NULL OR product_id = $1) AND ($2::uuid IS NULL OR variant_id = $2) AND ($3::uuid IS NULL OR warehouse_id = $3)"
+    )
+    .bind(filter.product_id)
+    .bind(filter.variant_id)
+    .bind(filter.warehouse_id)
+    .fetch_one(pool)
+    .await?;
+
+    let movements = sqlx::query_as::<_, StockMovement>(
+        "SELECT * FROM stock_movements WHERE ($1::uuid IS NULL OR product_id = $1) AND ($2::uuid IS NULL OR variant_id = $2) AND ($3::uuid IS NULL OR warehouse_id = $3) ORDER BY created_at DESC, id LIMIT $4 OFFSET $5"
+    )
+    .bind(filter.product_id)
+    .bind(filter.variant_id)
+    .bind(filter.warehouse_id)
+    .bind(pagination.limit as i64)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let total_pages = (total_items as f64 / pagination.limit as f64).ceil() as u32;
+
+    Ok(PaginatedResponse {
+        items: movements,
+        total_items: total_items as u32,
+        current_page: pagination.page,
+        total_pages,
+        limit: pagination.limit,
+    })
+}
+
+/// Moves stock between warehouses as a pair of transfer movements that
+/// reference the transfer.
+pub async fn create_stock_transfer(pool: &PgPool, actor: Uuid, new_transfer: CreateStockTransfer) -> Result<StockTransfer, ServiceError> {
+    if new_transfer.quantity <= 0 {
+        return Err(ServiceError::BadRequest("Transferred quantity must be positive".to_string()));
+    }
+    if new_transfer.from_warehouse_id == new_transfer.to_warehouse_id {
+        return Err(ServiceError::BadRequest("A transfer needs two different warehouses".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let transfer = sqlx::query_as::<_, StockTransfer>(
+        "INSERT INTO stock_transfers (product_id, variant_id, from_warehouse_id, to_warehouse_id, quantity, note, created_by, created_at)
+        SELECT product_id, id, $2, $3, $4, $5, $6, $7 FROM product_variants WHERE id = $1 RETURNING *"
+    )
+    .bind(new_transfer.variant_id)
+    .bind(new_transfer.from_warehouse_id)
+    .bind(new_transfer.to_warehouse_id)
+    .bind(new_transfer.quantity)
+    .bind(&new_transfer.note)
+    .bind(actor)
+    .bind(Utc::now())
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ServiceError::NotFound("Product variant not found".to_string()))?;
+
+    for (warehouse_id, quantity) in [
+        (transfer.from_warehouse_id, -transfer.quantity),
+        (transfer.to_warehouse_id, transfer.quantity),
+    ] {
+        record_movement(&mut tx, NewMovement {
+            variant_id: transfer.variant_id,
+            warehouse_id,
+            movement_type: MovementType::Transfer,
+            quantity,
+            reference_id: Some(transfer.id),
+            note: transfer.note.clone(),
+            created_by: Some(actor),
+        })
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(transfer)
+}
+
+pub async fn get_stock_transfers(pool: &PgPool, filter: InventoryFilter, pagination: Pagination) -> Result<PaginatedResponse<StockTransfer>, ServiceError> {
+    let offset = pagination.offset().map_err(ServiceError::BadRequest)?;
+
+    let total_items: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM stock_transfers WHERE ($1::uuid IS NULL OR product_id = $1) AND ($2::uuid IS NULL OR variant_id = $2) AND ($3::uuid IS NULL OR $3 IN (from_warehouse_id, to_warehouse_id))"
+    )
+    .bind(filter.product_id)
+    .bind(filter.variant_id)
+    .bind(filter.warehouse_id)
+    .fetch_one(pool)
+    .await?;
+
+    let transfers = sqlx::query_as::<_, StockTransfer>(
+        "SELECT * FROM stock_transfers WHERE ($1::uuid IS NULL OR product_id = $1) AND ($2::uuid IS NULL OR variant_id = $2) AND ($3::uuid IS NULL OR $3 IN (from_warehouse_id, to_warehouse_id)) ORDER BY created_at DESC LIMIT $4 OFFSET $5"
+    )
+    .bind(filter.product_id)
+    .bind(filter.variant_id)
+    .bind(filter.warehouse_id)
+    .bind(pagination.limit as i64)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let total_pages = (total_items as f64 / pagination.limit as f64).ceil() as u32;
+
+    Ok(PaginatedResponse {
+        items: transfers,
+        total_items: total_items as u32,
+        current_page: pagination.page,
+        total_pages,
+        limit: pagination.limit,
+    })
+}
+
+/// Recomputes every stock level from the ledger, every variant's sellable
+/// stock from its stock levels and open reservations and every product's
+/// stock from its variants, and returns the ones that disagree with what is
+/// stored.
+pub async fn audit_stock(pool: &PgPool) -> Result<Vec<StockDiscrepancy>, ServiceError> {
+    let discrepancies = sqlx::query_as::<_, StockDiscrepancy>(
+        "SELECT product_id, variant_id, warehouse_id, recorded, expected FROM (
+            SELECT COALESCE(i.product_id, m.product_id) AS product_id,
+                   COALESCE(i.variant_id, m.variant_id) AS variant_id,
+                   COALESCE(i.warehouse_id, m.warehouse_id) AS warehouse_id,
+                   COALESCE(i.quantity, 0)::BIGINT AS recorded,
+                   COALESCE(m.total, 0)::BIGINT AS expected
+            FROM inventory_items i
+            FULL JOIN (
+                SELECT product_id, variant_id, warehouse_id, SUM(quantity) AS total
+                FROM stock_movements GROUP BY product_id, variant_id, warehouse_id
+            ) m ON m.variant_id = i.variant_id AND m.warehouse_id = i.warehouse_id
+            UNION ALL
+            SELECT v.product_id, v.id, NULL, v.stock::BIGINT,
+                   (COALESCE(i.total, 0) - COALESCE(r.total, 0))::BIGINT
+            FROM product_variants v
+            LEFT JOIN (SELECT variant_id, SUM(quantity) AS total FROM inventory_items GROUP BY variant_id) i
+                ON i.variant_id = v.id
+            LEFT JOIN (
+                SELECT variant_id, SUM(quantity) AS total FROM stock_reservations
+                WHERE status IN ('active', 'committed') GROUP BY variant_id
+            ) r ON r.variant_id = v.id
+            UNION ALL
+            SELECT p.id, NULL, NULL, p.stock::BIGINT, COALESCE(v.total, 0)::BIGINT
+            FROM products p
+            LEFT JOIN (SELECT product_id, SUM(stock) AS total FROM product_variants GROUP BY product_id) v
+                ON v.product_id = p.id
+        ) levels
+        WHERE recorded <> expected
+        ORDER BY product_id, variant_id NULLS FIRST, warehouse_id NULLS FIRST"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(discrepancies)
+}
+
+async fn find_inventory_item(conn: &mut PgConnection, variant_id: Uuid, warehouse_id: Uuid) -> Result<InventoryItem, ServiceError> {
+    let item = sqlx::query_as::<_, InventoryItem>(
+        "SELECT * FROM inventory_items WHERE variant_id = $1 AND warehouse_id = $2"
+    )
+    .bind(variant_id)
+    .bind(warehouse_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserved_stock_can_be_transferred() {
+        // Ten units in stock, all reserved by open orders.
+        assert_eq!(sellable_change(MovementType::Transfer, -10, 0).unwrap(), 0);
+        assert_eq!(sellable_change(MovementType::Transfer, 10, 0).unwrap(), 0);
+        assert_eq!(sellable_change(MovementType::Sale, -10, 0).unwrap(), 0);
+        assert!(sellable_change(MovementType::Adjustment, -1, 0).is_err());
+
+        assert_eq!(sellable_change(MovementType::Adjustment, -3, 3).unwrap(), -3);
+        assert_eq!(sellable_change(MovementType::Receipt, 5, 0).unwrap(), 5);
+    }
+}
@@ -0,0 +1,145 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::{Duration, Utc};
+use models::{
+    notification::{CreateNotification, DeliveryChannel, NotificationTemplate},
+    product::{LowStockFilter, LowStockProduct, Product},
+};
+use crate::error::ServiceError;
+use super::notification::{create_notification, Notifier};
+
+/// Sales velocity is averaged over this many days of shipped orders.
+const SALES_WINDOW_DAYS: i64 = 30;
+
+#[derive(sqlx::FromRow)]
+struct LowStockRow {
+    #[sqlx(flatten)]
+    product: Product,
+    on_hand: i64,
+    reorder_point: Option<i64>,
+    reorder_quantity: Option<i64>,
+    sold: i64,
+}
+
+/// A stock level that has just dropped to its reorder point.
+#[derive(sqlx::FromRow)]
+struct LowStockCrossing {
+    id: Uuid,
+    product_name: String,
+    quantity: i32,
+    reorder_quantity: Option<i32>,
+    user_id: Uuid,
+}
+
+/// Products with a stock level at or below its reorder point, or with
+/// sellable stock at or below `threshold`, the ones running out soonest
+/// first.
+pub async fn get_low_stock_products(pool: &PgPool, filter: LowStockFilter) -> Result<Vec<LowStockProduct>, ServiceError> {
+    if filter.threshold.is_some_and(|threshold| threshold < 0) {
+        return Err(ServiceError::BadRequest("Threshold cannot be negative".to_string()));
+    }
+
+    let rows = sqlx::query_as::<_, LowStockRow>(
+        "SELECT p.*,
+                COALESCE(i.on_hand, 0) AS on_hand, i.reorder_point, i.reorder_quantity,
+                COALESCE(s.sold, 0) AS sold
+        FROM products p
+        LEFT JOIN (
+            SELECT product_id,
+                   SUM(quantity)::BIGINT AS on_hand,
+                   SUM(reorder_point)::BIGINT AS reorder_point,
+                   SUM(reorder_quantity)::BIGINT AS reorder_quantity,
+                   BOOL_OR(quantity <= reorder_point) AS is_low
+            FROM inventory_items GROUP BY product_id
+        ) i ON i.product_id = p.id
+        LEFT JOIN (
+            SELECT product_id, -SUM(quantity)::BIGINT AS sold
+            FROM stock_movements
+            WHERE movement_type = 'sale' AND created_at > $1
+            GROUP BY product_id
+        ) s ON s.product_id = p.id
+        WHERE ($2::uuid IS NULL OR p.vendor_id = $2)
+          AND (COALESCE(i.is_low, FALSE) OR p.stock <= $3)"
+    )
+    .bind(Utc::now() - Duration::days(SALES_WINDOW_DAYS))
+    .bind(filter.vendor_id)
+    .bind(filter.threshold)
+    .fetch_all(pool)
+    .await?;
+
+    let mut products: Vec<LowStockProduct> = rows
+        .into_iter()
+        .map(|row| {
+            let daily_sales = row.sold as f64 / SALES_WINDOW_DAYS as f64;
+            LowStockProduct {
+                days_of_cover: LowStockProduct::days_of_cover(row.product.stock, daily_sales),
+                product: row.product,
+                on_hand: row.on_hand,
+                reorder_point: row.reorder_point,
+                reorder_quantity: row.reorder_quantity,
+                daily_sales,
+            }
+        })
+        .collect();
+    products.sort_by(|a, b| {
+        let cover = |p: &LowStockProduct| p.days_of_cover.unwrap_or(f64::INFINITY);
+        cover(a).total_cmp(&cover(b)).then(a.product.stock.cmp(&b.product.stock))
+    });
+
+    Ok(products)
+}
+
+/// Tells vendors about stock levels that dropped to their reorder point
+/// since the last check, and re-arms the ones that have been restocked.
+/// Returns how many alerts were sent.
+pub async fn check_low_stock(pool: &PgPool, notifier: &Notifier) -> Result<u64, ServiceError> {
+    sqlx::query(
+        "UPDATE inventory_items SET low_stock_alerted_at = NULL
+        WHERE low_stock_alerted_at IS NOT NULL AND (reorder_point IS NULL OR quantity > reorder_point)"
+    )
+    .execute(pool)
+    .await?;
+
+    // Claiming a level before notifying keeps concurrent checks from
+    // alerting twice.
+    let crossings = sqlx::query_as::<_, LowStockCrossing>(
+        "WITH crossed AS (
+            UPDATE inventory_items SET low_stock_alerted_at = $1
+            WHERE low_stock_alerted_at IS NULL AND quantity <= reorder_point
+              AND product_id IN (
+                  SELECT p.id FROM products p JOIN vendors v ON v.id = p.vendor_id
+                  WHERE v.user_id IS NOT NULL
+              )
+            RETURNING id, product_id, variant_id, quantity, reorder_quantity
+        )
+        SELECT crossed.id, p.name || ' (' || pv.sku || ')' AS product_name, crossed.quantity, crossed.reorder_quantity, v.user_id
+        FROM crossed
+        JOIN products p ON p.id = crossed.product_id
+        JOIN product_variants pv ON pv.id = crossed.variant_id
+        JOIN vendors v ON v.id = p.vendor_id"
+    )
+    .bind(Utc::now())
+    .fetch_all(pool)
+    .await?;
+
+    let mut sent = 0;
+    for crossing in crossings {
+        let alert = CreateNotification {
+            user_id: crossing.user_id,
+            message: String::new(),
+            notification_type: String::new(),
+            channel: DeliveryChannel::InApp,
+            template: Some(NotificationTemplate::LowStock {
+                product_name: crossing.product_name,
+                quantity: crossing.quantity,
+                reorder_quantity: crossing.reorder_quantity,
+            }),
+        };
+        match create_notification(pool, notifier, alert).await {
+            Ok(_) => sent += 1,
+            Err(e) => log::warn!("Failed to send the low-stock alert for stock level {}: {}", crossing.id, e),
+        }
+    }
+
+    Ok(sent)
+}
@@ -0,0 +1,91 @@
+use actix_web::web;
+use async_trait::async_trait;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use super::store::{is_valid_storage_key, MediaStore};
+
+/// Keeps files in a directory on the local disk; the service serves them
+/// itself under `/media`.
+pub struct LocalMediaStore {
+    root: PathBuf,
+    public_url: String,
+}
+
+impl LocalMediaStore {
+    /// Creates `root` if it does not exist yet.
+    pub fn new(root: impl Into<PathBuf>, public_url: &str) -> Result<Self, String> {
+        let root = root.into();
+        fs::create_dir_all(&root).map_err(|e| format!("Failed to create media directory {}: {}", root.display(), e))?;
+        Ok(Self {
+            root,
+            public_url: public_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, String> {
+        if !is_valid_storage_key(key) {
+            return Err(format!("`{}` is not a valid storage key", key));
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl MediaStore for LocalMediaStore {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    /// Writes to a temporary file next to the target and renames it, so
+    /// the file is never served half written.
+    async fn put(&self, key: &str, bytes: Vec<u8>, _content_type: &str) -> Result<(), String> {
+        let path = self.path(key)?;
+        web::block(move || {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut partial = path.clone().into_os_string();
+            partial.push(".partial");
+            fs::write(&partial, bytes)?;
+            fs::rename(&partial, &path)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to store {}: {}", key, e))
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        let path = self.path(key)?;
+        match web::block(move || fs::read(path)).await.map_err(|e| e.to_string())? {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read {}: {}", key, e)),
+        }
+    }
+
+    /// Also removes the file's directory once it is empty.
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        let path = self.path(key)?;
+        let root = self.root.clone();
+        let removed = web::block(move || {
+            fs::remove_file(&path)?;
+            if let Some(parent) = path.parent().filter(|parent| *parent != root) {
+                // Fails while other files are left in it.
+                let _ = fs::remove_dir(parent);
+            }
+            Ok::<_, std::io::Error>(())
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+        match removed {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("Failed to delete {}: {}", key, e)),
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_url, key)
+    }
+}
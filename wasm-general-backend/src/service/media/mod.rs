@@ -0,0 +1,347 @@
+mod local;
+mod store;
+
+use actix_web::web;
+use image::{DynamicImage, ImageFormat, ImageReader, Limits};
+use sqlx::{types::Json, PgPool};
+use std::io::Cursor;
+use uuid::Uuid;
+use chrono::Utc;
+use config::MediaConfig;
+use models::media::{Image, ImageContentType, ImageOwner, Thumbnail, UpdateImage};
+use crate::error::ServiceError;
+
+pub use local::LocalMediaStore;
+pub use store::*;
+
+/// Longest edge, in pixels, of an image that is decoded at all; larger
+/// ones are refused before their pixels are allocated.
+const MAX_IMAGE_EDGE: u32 = 10_000;
+
+/// Most pixels an image that is decoded may have. Decoding allocates four
+/// bytes per pixel, so this bounds the memory an upload takes.
+const MAX_IMAGE_PIXELS: u64 = 40_000_000;
+
+/// An uploaded file as it arrived, before it is checked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageUpload {
+    pub bytes: Vec<u8>,
+    /// The `Content-Type` the client sent for the file.
+    pub content_type: Option<String>,
+    pub alt_text: Option<String>,
+}
+
+/// A decoded upload and its encoded thumbnails.
+struct ProcessedImage {
+    width: u32,
+    height: u32,
+    thumbnails: Vec<(u32, DynamicImage, Vec<u8>)>,
+}
+
+/// Checks an upload, stores it with its thumbnails and adds it after the
+/// owner's other images. The file has to be a JPEG, PNG or WebP image of
+/// the type it was sent as, within the size limit. Thumbnails are made
+/// for each configured size smaller than the image, in its format.
+pub async fn upload_image(pool: &PgPool, store: &dyn MediaStore, config: &MediaConfig, owner: ImageOwner, actor: Uuid, upload: ImageUpload) -> Result<Image, ServiceError> {
+    let (column, owner_id) = owner_column(owner);
+    let table = match owner {
+        ImageOwner::Product(_) => "products",
+        ImageOwner::Vendor(_) => "vendors",
+    };
+    let exists: bool = sqlx::query_scalar(&format!("SELECT EXISTS (SELECT 1 FROM {} WHERE id = $1)", table))
+        .bind(owner_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Err(ServiceError::NotFound(format!("{} not found", owner_name(owner))));
+    }
+
+    if upload.bytes.is_empty() {
+        return Err(ServiceError::BadRequest("The uploaded file is empty".to_string()));
+    }
+    if upload.bytes.len() > config.max_upload_bytes {
+        return Err(ServiceError::PayloadTooLarge(format!("Images can be at most {} KB", config.max_upload_bytes / 1024)));
+    }
+    let content_type: ImageContentType = upload
+        .content_type
+        .as_deref()
+        .ok_or_else(|| ServiceError::UnsupportedMediaType("The uploaded file has no content type".to_string()))?
+        .parse()
+        .map_err(ServiceError::UnsupportedMediaType)?;
+
+    let bytes = upload.bytes;
+    let sizes = config.thumbnail_sizes.clone();
+    let (bytes, processed) = web::block(move || {
+        let processed = process_image(&bytes, content_type, &sizes);
+        (bytes, processed)
+    })
+    .await
+    .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+    let processed = processed?;
+
+    let id = Uuid::new_v4();
+    let byte_size = bytes.len() as i64;
+    let storage_key = format!("images/{}/original.{}", id, content_type.extension());
+    let mut stored = Vec::with_capacity(processed.thumbnails.len() + 1);
+    let mut thumbnails = Vec::with_capacity(processed.thumbnails.len());
+    let result = async {
+        store.put(&storage_key, bytes, content_type.as_str()).await?;
+        stored.push(storage_key.clone());
+        for (size, thumbnail, encoded) in processed.thumbnails {
+            let key = format!("images/{}/{}.{}", id, size, content_type.extension());
+            store.put(&key, encoded, content_type.as_str()).await?;
+            stored.push(key.clone());
+            thumbnails.push(Thumbnail {
+                size,
+                width: thumbnail.width(),
+                height: thumbnail.height(),
+                content_type,
+                url: store.url(&key),
+                storage_key: key,
+            });
+        }
+        Ok::<_, String>(())
+    }
+    .await;
+    if let Err(e) = result {
+        log::error!("Failed to store image {}: {}", id, e);
+        remove_files(store, stored.iter().map(String::as_str)).await;
+        return Err(ServiceError::InternalServerError("The image could not be stored".to_string()));
+    }
+
+    let image = sqlx::query_as::<_, Image>(&format!(
+        "INSERT INTO images (id, {column}, storage_key, url, content_type, byte_size, width, height, thumbnails, alt_text, position, uploaded_by, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, (SELECT COALESCE(MAX(position) + 1, 0) FROM images WHERE {column} = $2), $11, $12)
+        RETURNING *"
+    ))
+    .bind(id)
+    .bind(owner_id)
+    .bind(&storage_key)
+    .bind(store.url(&storage_key))
+    .bind(content_type)
+    .bind(byte_size)
+    .bind(processed.width as i32)
+    .bind(processed.height as i32)
+    .bind(Json(thumbnails))
+    .bind(non_empty(upload.alt_text))
+    .bind(actor)
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await;
+    if image.is_err() {
+        remove_files(store, stored.iter().map(String::as_str)).await;
+    }
+
+    Ok(image?)
+}
+
+/// The owner's images in display order.
+pub async fn get_images(pool: &PgPool, owner: ImageOwner) -> Result<Vec<Image>, ServiceError> {
+    let (column, owner_id) = owner_column(owner);
+    let images = sqlx::query_as::<_, Image>(&format!(
+        "SELECT * FROM images WHERE {} = $1 ORDER BY position, created_at",
+        column
+    ))
+    .bind(owner_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(images)
+}
+
+pub async fn update_image(pool: &PgPool, owner: ImageOwner, image_id: Uuid, updated_image: UpdateImage) -> Result<Image, ServiceError> {
+    let (column, owner_id) = owner_column(owner);
+    let existing_image = sqlx::query_as::<_, Image>(&format!("SELECT * FROM images WHERE id = $1 AND {} = $2", column))
+        .bind(image_id)
+        .bind(owner_id)
+        .fetch_one(pool)
+        .await?;
+
+    let image = sqlx::query_as::<_, Image>(
+        "UPDATE images SET alt_text = $1, position = $2 WHERE id = $3 RETURNING *"
+    )
+    .bind(non_empty(updated_image.alt_text.unwrap_or(existing_image.alt_text)))
+    .bind(updated_image.position.unwrap_or(existing_image.position))
+    .bind(image_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(image)
+}
+
+/// Removes the record first and the files after; files that cannot be
+/// removed are only logged.
+pub async fn delete_image(pool: &PgPool, store: &dyn MediaStore, owner: ImageOwner, image_id: Uuid) -> Result<(), ServiceError> {
+    let (column, owner_id) = owner_column(owner);
+    let image = sqlx::query_as::<_, Image>(&format!("DELETE FROM images WHERE id = $1 AND {} = $2 RETURNING *", column))
+        .bind(image_id)
+        .bind(owner_id)
+        .fetch_one(pool)
+        .await?;
+    remove_files(store, image.storage_keys()).await;
+
+    Ok(())
+}
+
+/// Removes the files of images whose records are gone, e.g. with their
+/// product.
+pub async fn remove_image_files(store: &dyn MediaStore, images: &[Image]) {
+    remove_files(store, images.iter().flat_map(Image::storage_keys)).await;
+}
+
+/// A stored file and its content type, for serving it. Only images are
+/// served; other keys are not found.
+pub async fn get_media(store: &dyn MediaStore, key: &str) -> Result<(Vec<u8>, ImageContentType), ServiceError> {
+    let not_found = || ServiceError::NotFound("File not found".to_string());
+    let content_type = key
+        .rsplit_once('.')
+        .and_then(|(_, extension)| ImageContentType::from_extension(extension))
+        .filter(|_| is_valid_storage_key(key))
+        .ok_or_else(not_found)?;
+    let bytes = store
+        .get(key)
+        .await
+        .map_err(ServiceError::InternalServerError)?
+        .ok_or_else(not_found)?;
+
+    Ok((bytes, content_type))
+}
+
+/// Decodes the upload after checking that its content is of the declared
+/// type, and encodes a thumbnail for each size below its longest edge.
+fn process_image(bytes: &[u8], content_type: ImageContentType, sizes: &[u32]) -> Result<ProcessedImage, ServiceError> {
+    let format = image_format(content_type);
+    if image::guess_format(bytes).ok() != Some(format) {
+        return Err(ServiceError::UnsupportedMediaType(format!("The uploaded file is not of type {}", content_type)));
+    }
+
+    let (width, height) = ImageReader::with_format(Cursor::new(bytes), format)
+        .into_dimensions()
+        .map_err(|e| ServiceError::BadRequest(format!("The image cannot be read: {}", e)))?;
+    if width as u64 * height as u64 > MAX_IMAGE_PIXELS {
+        return Err(ServiceError::BadRequest(format!(
+            "Images can have at most {} megapixels",
+            MAX_IMAGE_PIXELS / 1_000_000
+        )));
+    }
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_EDGE);
+    limits.max_image_height = Some(MAX_IMAGE_EDGE);
+    limits.max_alloc = Some(MAX_IMAGE_PIXELS * 4);
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    reader.limits(limits);
+    let image = reader
+        .decode()
+        .map_err(|e| ServiceError::BadRequest(format!("The image cannot be read: {}", e)))?;
+
+    let longest_edge = image.width().max(image.height());
+    let mut sizes: Vec<u32> = sizes.iter().copied().filter(|size| *size > 0 && *size < longest_edge).collect();
+    sizes.sort_unstable();
+    sizes.dedup();
+
+    let mut thumbnails = Vec::with_capacity(sizes.len());
+    for size in sizes {
+        let thumbnail = image.thumbnail(size, size);
+        let encoded = encode(&thumbnail, format)
+            .map_err(|e| ServiceError::InternalServerError(format!("Failed to make a thumbnail: {}", e)))?;
+        thumbnails.push((size, thumbnail, encoded));
+    }
+
+    Ok(ProcessedImage {
+        width: image.width(),
+        height: image.height(),
+        thumbnails,
+    })
+}
+
+/// JPEG has no alpha channel and the WebP encoder only takes 8-bit RGBA.
+fn encode(image: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, image::ImageError> {
+    let image = match format {
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8()),
+        ImageFormat::WebP => DynamicImage::ImageRgba8(image.to_rgba8()),
+        _ => image.clone(),
+    };
+    let mut encoded = Cursor::new(Vec::new());
+    image.write_to(&mut encoded, format)?;
+    Ok(encoded.into_inner())
+}
+
+fn image_format(content_type: ImageContentType) -> ImageFormat {
+    match content_type {
+        ImageContentType::Jpeg => ImageFormat::Jpeg,
+        ImageContentType::Png => ImageFormat::Png,
+        ImageContentType::Webp => ImageFormat::WebP,
+    }
+}
+
+async fn remove_files<'a>(store: &dyn MediaStore, keys: impl Iterator<Item = &'a str>) {
+    for key in keys {
+        if let Err(e) = store.delete(key).await {
+            log::warn!("{}", e);
+        }
+    }
+}
+
+fn owner_column(owner: ImageOwner) -> (&'static str, Uuid) {
+    match owner {
+        ImageOwner::Product(id) => ("product_id", id),
+        ImageOwner::Vendor(id) => ("vendor_id", id),
+    }
+}
+
+fn owner_name(owner: ImageOwner) -> &'static str {
+    match owner {
+        ImageOwner::Product(_) => "Product",
+        ImageOwner::Vendor(_) => "Vendor",
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Rgba, RgbaImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, Rgba([200, 200, 200, 128])));
+        encode(&image, ImageFormat::Png).unwrap()
+    }
+
+    #[test]
+    fn test_uploads_are_checked_and_get_smaller_thumbnails() {
+        let processed = process_image(&png(800, 400), ImageContentType::Png, &[480, 160, 1024, 160]).unwrap();
+        assert_eq!((processed.width, processed.height), (800, 400));
+        let sizes: Vec<(u32, u32, u32)> = processed
+            .thumbnails
+            .iter()
+            .map(|(size, thumbnail, _)| (*size, thumbnail.width(), thumbnail.height()))
+            .collect();
+        assert_eq!(sizes, vec![(160, 160, 80), (480, 480, 240)]);
+        assert_eq!(image::guess_format(&processed.thumbnails[0].2).unwrap(), ImageFormat::Png);
+
+        assert!(matches!(process_image(&png(10, 10), ImageContentType::Jpeg, &[]), Err(ServiceError::UnsupportedMediaType(_))));
+        assert!(matches!(process_image(b"<svg></svg>", ImageContentType::Png, &[]), Err(ServiceError::UnsupportedMediaType(_))));
+        assert!(matches!(process_image(&png(10, 10)[..40], ImageContentType::Png, &[]), Err(ServiceError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_refuses_images_with_too_many_pixels() {
+        // Within both edges, but more pixels than may be decoded.
+        let image = DynamicImage::ImageLuma8(GrayImage::new(8_000, 5_001));
+        let bytes = encode(&image, ImageFormat::Png).unwrap();
+        assert!(matches!(process_image(&bytes, ImageContentType::Png, &[]), Err(ServiceError::BadRequest(message)) if message.contains("megapixels")));
+    }
+
+    #[test]
+    fn test_storage_keys_stay_inside_the_store() {
+        assert!(is_valid_storage_key("images/5f0c/original.jpg"));
+        assert!(!is_valid_storage_key("../secrets.jpg"));
+        assert!(!is_valid_storage_key("/etc/passwd"));
+        assert!(!is_valid_storage_key("images//a.jpg"));
+        assert!(!is_valid_storage_key("images/a b.jpg"));
+    }
+}
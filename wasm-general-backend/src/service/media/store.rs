@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use config::MediaConfig;
+use super::local::LocalMediaStore;
+
+/// Where uploaded files are kept. Keys are relative paths such as
+/// `images/<id>/original.jpg` (see [`is_valid_storage_key`]); errors are
+/// store messages.
+#[async_trait]
+pub trait MediaStore: Send + Sync {
+    /// Logged when the service starts.
+    fn name(&self) -> &'static str;
+
+    /// Stores `bytes` under `key`, replacing anything stored there.
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<(), String>;
+
+    /// The stored bytes, or `None` when nothing is stored under `key`.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String>;
+
+    /// Deleting a key that holds nothing succeeds.
+    async fn delete(&self, key: &str) -> Result<(), String>;
+
+    /// Public URL the file under `key` is served from.
+    fn url(&self, key: &str) -> String;
+}
+
+pub fn media_store_from_config(config: &MediaConfig) -> Result<Arc<dyn MediaStore>, String> {
+    match config.storage.as_str() {
+        "local" => Ok(Arc::new(LocalMediaStore::new(&config.local_root, &config.public_url)?)),
+        other => Err(format!("Unknown media storage `{}`", other)),
+    }
+}
+
+/// Keys are `/`-separated segments of letters, digits, dots, dashes and
+/// underscores, none of them `.` or `..`, so a key cannot point outside
+/// the store.
+pub fn is_valid_storage_key(key: &str) -> bool {
+    key.len() <= 255
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && segment != "."
+                && segment != ".."
+                && segment.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        })
+}
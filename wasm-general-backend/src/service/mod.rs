@@ -0,0 +1,33 @@
+mod category;
+mod exchange_rate;
+mod idempotency;
+mod inventory;
+mod low_stock;
+mod media;
+mod notification;
+mod order;
+mod payment;
+mod pricing;
+mod product;
+mod product_search;
+mod product_variant;
+mod reservation;
+mod vendor;
+mod warehouse;
+
+pub use category::*;
+pub use exchange_rate::*;
+pub use idempotency::*;
+pub use inventory::*;
+pub use low_stock::*;
+pub use media::*;
+pub use notification::*;
+pub use order::*;
+pub use payment::*;
+pub use pricing::*;
+pub use product::*;
+pub use product_search::*;
+pub use product_variant::*;
+pub use reservation::*;
+pub use vendor::*;
+pub use warehouse::*;
@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use sqlx::FromRow;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+use config::NotificationConfig;
+use models::notification::{DeliveryChannel, DeliveryStatus};
+use super::{email::EmailChannel, sms::SmsChannel};
+
+/// Contact details of the user a notification is addressed to.
+#[derive(Debug, Clone, FromRow)]
+pub struct Recipient {
+    pub user_id: Uuid,
+    pub email: String,
+    pub phone: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutgoingMessage {
+    pub subject: String,
+    pub body: String,
+}
+
+/// What a provider reported back after accepting a message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeliveryReceipt {
+    pub status: DeliveryStatus,
+    pub provider_message_id: Option<String>,
+}
+
+/// A way of getting a message to a user: an SMS gateway, a mail server, or
+/// the in-app inbox. Errors are provider messages recorded on the notification.
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    fn kind(&self) -> DeliveryChannel;
+
+    async fn deliver(&self, recipient: &Recipient, message: &OutgoingMessage) -> Result<DeliveryReceipt, String>;
+}
+
+/// The stored notification row is the in-app message, so there is nothing
+/// left to send.
+pub struct InAppChannel;
+
+#[async_trait]
+impl NotificationChannel for InAppChannel {
+    fn kind(&self) -> DeliveryChannel {
+        DeliveryChannel::InApp
+    }
+
+    async fn deliver(&self, _recipient: &Recipient, _message: &OutgoingMessage) -> Result<DeliveryReceipt, String> {
+        Ok(DeliveryReceipt { status: DeliveryStatus::Delivered, provider_message_id: None })
+    }
+}
+
+/// Result of one delivery attempt, as persisted on the notification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeliveryOutcome {
+    pub status: DeliveryStatus,
+    pub error: Option<String>,
+    pub provider_message_id: Option<String>,
+}
+
+/// Routes notifications to the channel they were created for.
+pub struct Notifier {
+    channels: HashMap<DeliveryChannel, Arc<dyn NotificationChannel>>,
+}
+
+impl Notifier {
+    /// A notifier with only the in-app channel.
+    pub fn new() -> Self {
+        Self { channels: HashMap::new() }.with_channel(Arc::new(InAppChannel))
+    }
+
+    /// Registers `channel`, replacing any channel of the same kind.
+    pub fn with_channel(mut self, channel: Arc<dyn NotificationChannel>) -> Self {
+        self.channels.insert(channel.kind(), channel);
+        self
+    }
+
+    /// Enables SMS and email for whichever providers are configured.
+    pub fn from_config(config: &NotificationConfig) -> Result<Self, String> {
+        let mut notifier = Self::new();
+
+        if let (Some(api_key), Some(sender)) = (&config.sms_api_key, &config.sms_sender) {
+            notifier = notifier.with_channel(Arc::new(SmsChannel::new(&config.sms_api_url, api_key, sender)));
+        }
+        if let Some(host) = &config.smtp_host {
+            let channel = EmailChannel::new(
+                host,
+                config.smtp_port,
+                config.smtp_username.as_deref().zip(config.smtp_password.as_deref()),
+                &config.email_from,
+            )?;
+            notifier = notifier.with_channel(Arc::new(channel));
+        }
+
+        Ok(notifier)
+    }
+
+    pub fn is_enabled(&self, channel: DeliveryChannel) -> bool {
+        self.channels.contains_key(&channel)
+    }
+
+    pub async fn dispatch(&self, channel: DeliveryChannel, recipient: &Recipient, message: &OutgoingMessage) -> DeliveryOutcome {
+        let Some(provider) = self.channels.get(&channel) else {
+            return DeliveryOutcome {
+                status: DeliveryStatus::Failed,
+                error: Some(format!("The {} channel is not configured", channel)),
+                provider_message_id: None,
+            };
+        };
+
+        match provider.deliver(recipient, message).await {
+            Ok(receipt) => DeliveryOutcome {
+                status: receipt.status,
+                error: None,
+                provider_message_id: receipt.provider_message_id,
+            },
+            Err(error) => {
+                log::warn!("Failed to deliver {} notification to user {}: {}", channel, recipient.user_id, error);
+                DeliveryOutcome { status: DeliveryStatus::Failed, error: Some(error), provider_message_id: None }
+            }
+        }
+    }
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,61 @@
+use async_trait::async_trait;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use models::notification::{DeliveryChannel, DeliveryStatus};
+use super::channel::{DeliveryReceipt, NotificationChannel, OutgoingMessage, Recipient};
+
+/// Sends plain-text UTF-8 mail through an SMTP relay using STARTTLS.
+pub struct EmailChannel {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl EmailChannel {
+    pub fn new(host: &str, port: u16, credentials: Option<(&str, &str)>, from: &str) -> Result<Self, String> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(|e| format!("Invalid SMTP host `{}`: {}", host, e))?
+            .port(port);
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username.to_string(), password.to_string()));
+        }
+        let from = from
+            .parse()
+            .map_err(|e| format!("Invalid sender address `{}`: {}", from, e))?;
+
+        Ok(Self { transport: builder.build(), from })
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for EmailChannel {
+    fn kind(&self) -> DeliveryChannel {
+        DeliveryChannel::Email
+    }
+
+    async fn deliver(&self, recipient: &Recipient, message: &OutgoingMessage) -> Result<DeliveryReceipt, String> {
+        let to: Mailbox = recipient
+            .email
+            .parse()
+            .map_err(|e| format!("Invalid recipient address: {}", e))?;
+
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(message.subject.as_str())
+            .header(ContentType::TEXT_PLAIN)
+            .body(message.body.clone())
+            .map_err(|e| format!("Failed to build email: {}", e))?;
+
+        let response = self
+            .transport
+            .send(email)
+            .await
+            .map_err(|e| format!("SMTP delivery failed: {}", e))?;
+
+        Ok(DeliveryReceipt {
+            status: DeliveryStatus::Sent,
+            provider_message_id: response.first_line().map(str::to_string),
+        })
+    }
+}
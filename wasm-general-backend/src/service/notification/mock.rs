@@ -0,0 +1,59 @@
+use async_trait::async_trait;
+use std::sync::Mutex;
+use uuid::Uuid;
+use models::notification::{DeliveryChannel, DeliveryStatus};
+use super::channel::{DeliveryReceipt, NotificationChannel, OutgoingMessage, Recipient};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedMessage {
+    pub user_id: Uuid,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Records every message instead of sending it, for tests and local runs.
+/// A failing mock rejects every message with the given error.
+pub struct MockChannel {
+    kind: DeliveryChannel,
+    error: Option<String>,
+    messages: Mutex<Vec<RecordedMessage>>,
+}
+
+impl MockChannel {
+    pub fn new(kind: DeliveryChannel) -> Self {
+        Self { kind, error: None, messages: Mutex::new(Vec::new()) }
+    }
+
+    pub fn failing(kind: DeliveryChannel, error: &str) -> Self {
+        Self { error: Some(error.to_string()), ..Self::new(kind) }
+    }
+
+    pub fn messages(&self) -> Vec<RecordedMessage> {
+        self.messages.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for MockChannel {
+    fn kind(&self) -> DeliveryChannel {
+        self.kind
+    }
+
+    async fn deliver(&self, recipient: &Recipient, message: &OutgoingMessage) -> Result<DeliveryReceipt, String> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+
+        let mut messages = self.messages.lock().unwrap_or_else(|e| e.into_inner());
+        messages.push(RecordedMessage {
+            user_id: recipient.user_id,
+            subject: message.subject.clone(),
+            body: message.body.clone(),
+        });
+
+        Ok(DeliveryReceipt {
+            status: DeliveryStatus::Sent,
+            provider_message_id: Some(format!("mock-{}", messages.len())),
+        })
+    }
+}
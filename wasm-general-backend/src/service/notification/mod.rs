@@ -0,0 +1,210 @@
+mod channel;
+mod email;
+mod mock;
+mod sms;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+use models::{notification::{Notification, CreateNotification, DeliveryStatus, UpdateNotification}, pagination::{Pagination, PaginatedResponse}};
+use crate::error::ServiceError;
+
+pub use channel::*;
+pub use email::EmailChannel;
+pub use mock::{MockChannel, RecordedMessage};
+pub use sms::SmsChannel;
+
+/// Subject used when a free-form message goes out by email.
+const DEFAULT_SUBJECT: &str = "اعلان پما";
+
+/// Stores the notification and immediately pushes it through its channel.
+/// A failed delivery is recorded on the notification rather than returned as an error.
+pub async fn create_notification(pool: &PgPool, notifier: &Notifier, new_notification: CreateNotification) -> Result<Notification, ServiceError> {
+    let (message, notification_type, subject) = match &new_notification.template {
+        Some(template) => (template.render(), template.notification_type().to_string(), template.subject()),
+        None => (new_notification.message, new_notification.notification_type, DEFAULT_SUBJECT.to_string()),
+    };
+    if message.trim().is_empty() {
+        return Err(ServiceError::BadRequest("Either a message or a template is required".to_string()));
+    }
+
+    let notification = sqlx::query_as::<_, Notification>(
+        "INSERT INTO notifications (user_id, message, notification_type, is_read, channel, delivery_status, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING *"
+    )
+    .bind(new_notification.user_id)
+    .bind(message)
+    .bind(notification_type)
+    .bind(false)
+    .bind(new_notification.channel)
+    .bind(DeliveryStatus::Pending)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    deliver_notification(pool, notifier, notification, subject).await
+}
+
+/// Sends a pending or failed notification again.
+pub async fn retry_notification(pool: &PgPool, notifier: &Notifier, notification_id: Uuid) -> Result<Notification, ServiceError> {
+    let notification = get_notification_by_id(pool, notification_id).await?;
+    if !matches!(notification.delivery_status, DeliveryStatus::Pending | DeliveryStatus::Failed) {
+        return Err(ServiceError::BadRequest(format!("Notification was already {}", notification.delivery_status)));
+    }
+
+    deliver_notification(pool, notifier, notification, DEFAULT_SUBJECT.to_string()).await
+}
+
+async fn deliver_notification(pool: &PgPool, notifier: &Notifier, notification: Notification, subject: String) -> Result<Notification, ServiceError> {
+    let recipient = sqlx::query_as::<_, Recipient>("SELECT id AS user_id, email, phone FROM users WHERE id = $1")
+        .bind(notification.user_id)
+        .fetch_one(pool)
+        .await?;
+    let message = OutgoingMessage { subject, body: notification.message.clone() };
+
+    let outcome = notifier.dispatch(notification.channel, &recipient, &message).await;
+    let sent_at = (outcome.status != DeliveryStatus::Failed).then(Utc::now);
+
+    let notification = sqlx::query_as::<_, Notification>(
+        "UPDATE notifications SET delivery_status = $1, delivery_error = $2, provider_message_id = $3, sent_at = $4, updated_at = $5 WHERE id = $6 RETURNING *"
+    )
+    .bind(outcome.status)
+    .bind(outcome.error)
+    .bind(outcome.provider_message_id)
+    .bind(sent_at)
+    .bind(Utc::now())
+    .bind(notification.id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(notification)
+}
+
+pub async fn get_notifications(pool: &PgPool, pagination: Pagination) -> Result<PaginatedResponse<Notification>, ServiceError> {
+    let offset = pagination.offset().map_err(ServiceError::BadRequest)?;
+
+    let total_items: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM notifications")
+        .fetch_one(pool)
+        .await?;
+
+    let notifications = sqlx::query_as::<_, Notification>(
+        "SELECT * FROM notifications ORDER BY created_at DESC LIMIT $1 OFFSET $2"
+    )
+    .bind(pagination.limit as i64)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let total_pages = (total_items as f64 / pagination.limit as f64).ceil() as u32;
+
+    Ok(PaginatedResponse {
+        items: notifications,
+        total_items: total_items as u32,
+        current_page: pagination.page,
+        total_pages,
+        limit: pagination.limit,
+    })
+}
+
+pub async fn get_notifications_for_user(pool: &PgPool, user_id: Uuid, pagination: Pagination) -> Result<PaginatedResponse<Notification>, ServiceError> {
+    let offset = pagination.offset().map_err(ServiceError::BadRequest)?;
+
+    let total_items: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    let notifications = sqlx::query_as::<_, Notification>(
+        "SELECT * FROM notifications WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2 OFFSET $3"
+    )
+    .bind(user_id)
+    .bind(pagination.limit as i64)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let total_pages = (total_items as f64 / pagination.limit as f64).ceil() as u32;
+
+    Ok(PaginatedResponse {
+        items: notifications,
+        total_items: total_items as u32,
+        current_page: pagination.page,
+        total_pages,
+        limit: pagination.limit,
+    })
+}
+
+pub async fn get_notification_by_id(pool: &PgPool, notification_id: Uuid) -> Result<Notification, ServiceError> {
+    let notification = sqlx::query_as::<_, Notification>(
+        "SELECT * FROM notifications WHERE id = $1"
+    )
+    .bind(notification_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(notification)
+}
+
+pub async fn update_notification(pool: &PgPool, notification_id: Uuid, updated_notification: UpdateNotification) -> Result<Notification, ServiceError> {
+    let existing_notification = get_notification_by_id(pool, notification_id).await?;
+
+    let notification = sqlx::query_as::<_, Notification>(
+        "UPDATE notifications SET message = $1, notification_type = $2, is_read = $3, updated_at = $4 WHERE id = $5 RETURNING *"
+    )
+    .bind(updated_notification.message.unwrap_or(existing_notification.message))
+    .bind(updated_notification.notification_type.unwrap_or(existing_notification.notification_type))
+    .bind(updated_notification.is_read.unwrap_or(existing_notification.is_read))
+    .bind(Utc::now())
+    .bind(notification_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(notification)
+}
+
+pub async fn delete_notification(pool: &PgPool, notification_id: Uuid) -> Result<(), ServiceError> {
+    sqlx::query("DELETE FROM notifications WHERE id = $1")
+        .bind(notification_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use models::notification::{DeliveryChannel, NotificationTemplate};
+
+    fn recipient() -> Recipient {
+        Recipient { user_id: Uuid::new_v4(), email: "user@example.com".to_string(), phone: Some("09121234567".to_string()) }
+    }
+
+    #[actix_web::test]
+    async fn test_dispatch_routes_to_the_notification_channel() {
+        let sms = Arc::new(MockChannel::new(DeliveryChannel::Sms));
+        let notifier = Notifier::new()
+            .with_channel(sms.clone())
+            .with_channel(Arc::new(MockChannel::failing(DeliveryChannel::Email, "mailbox full")));
+        let template = NotificationTemplate::VerificationCode { code: "1234".to_string() };
+        let message = OutgoingMessage { subject: template.subject(), body: template.render() };
+        let recipient = recipient();
+
+        let outcome = notifier.dispatch(DeliveryChannel::Sms, &recipient, &message).await;
+        assert_eq!(outcome.status, DeliveryStatus::Sent);
+        assert_eq!(outcome.provider_message_id.as_deref(), Some("mock-1"));
+        assert_eq!(sms.messages(), vec![RecordedMessage { user_id: recipient.user_id, subject: message.subject.clone(), body: message.body.clone() }]);
+
+        let outcome = notifier.dispatch(DeliveryChannel::Email, &recipient, &message).await;
+        assert_eq!(outcome.status, DeliveryStatus::Failed);
+        assert_eq!(outcome.error.as_deref(), Some("mailbox full"));
+
+        let outcome = notifier.dispatch(DeliveryChannel::InApp, &recipient, &message).await;
+        assert_eq!(outcome.status, DeliveryStatus::Delivered);
+        assert_eq!(sms.messages().len(), 1);
+
+        let outcome = Notifier::new().dispatch(DeliveryChannel::Sms, &recipient, &message).await;
+        assert_eq!(outcome.status, DeliveryStatus::Failed);
+    }
+}
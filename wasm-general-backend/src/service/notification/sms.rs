@@ -0,0 +1,76 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use models::{auth::EmailOrPhone, notification::{DeliveryChannel, DeliveryStatus}};
+use super::channel::{DeliveryReceipt, NotificationChannel, OutgoingMessage, Recipient};
+
+/// Sends text messages through a Kavenegar-compatible HTTP API:
+/// `POST {api_url}/{api_key}/sms/send.json` with `receptor`, `sender` and `message`.
+pub struct SmsChannel {
+    client: reqwest::Client,
+    endpoint: String,
+    sender: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SendResponse {
+    #[serde(rename = "return")]
+    result: SendResult,
+    #[serde(default)]
+    entries: Vec<SendEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SendResult {
+    status: u16,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SendEntry {
+    messageid: i64,
+}
+
+impl SmsChannel {
+    pub fn new(api_url: &str, api_key: &str, sender: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: format!("{}/{}/sms/send.json", api_url.trim_end_matches('/'), api_key),
+            sender: sender.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for SmsChannel {
+    fn kind(&self) -> DeliveryChannel {
+        DeliveryChannel::Sms
+    }
+
+    async fn deliver(&self, recipient: &Recipient, message: &OutgoingMessage) -> Result<DeliveryReceipt, String> {
+        let phone = recipient
+            .phone
+            .as_deref()
+            .ok_or_else(|| "The user has no phone number".to_string())?;
+        let receptor = EmailOrPhone::Phone(phone.to_string()).normalized();
+
+        let response: SendResponse = self
+            .client
+            .post(&self.endpoint)
+            .form(&[("receptor", receptor.as_str()), ("sender", self.sender.as_str()), ("message", message.body.as_str())])
+            .send()
+            .await
+            .map_err(|e| format!("SMS request failed: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid SMS provider response: {}", e))?;
+
+        if response.result.status != 200 {
+            return Err(format!("SMS provider rejected the message ({}): {}", response.result.status, response.result.message));
+        }
+
+        Ok(DeliveryReceipt {
+            status: DeliveryStatus::Sent,
+            provider_message_id: response.entries.first().map(|entry| entry.messageid.to_string()),
+        })
+    }
+}
@@ -0,0 +1,400 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use chrono::Utc;
+use std::collections::HashMap;
+use config::CheckoutConfig;
+use models::{money::{Currency, Money, MoneyError}, order::{Order, OrderItem, OrderStatus, OrderStatusChange, CreateOrder, UpdateOrder}, pagination::{Pagination, PaginatedResponse}, product::{Product, ProductVariant}};
+use crate::error::ServiceError;
+use super::exchange_rate::rials_per_unit;
+use super::pricing::{locked_prices, use_price_lock};
+use super::reservation::{commit_reservations, fulfill_reservations, release_reservations, reserve_stock};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderTotals {
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub shipping_amount: Money,
+    pub total_amount: Money,
+}
+
+/// Tax is rounded to whole rials; shipping is waived once the subtotal
+/// reaches the free-shipping threshold.
+pub fn compute_totals(checkout: &CheckoutConfig, subtotal: Money) -> Result<OrderTotals, MoneyError> {
+    let tax_amount = subtotal.checked_mul_rate(checkout.tax_rate)?;
+    let shipping_amount = match checkout.free_shipping_threshold {
+        Some(threshold) if subtotal.minor_units() >= threshold => Money::zero(subtotal.currency()),
+        _ => Money::new(checkout.shipping_fee, subtotal.currency()),
+    };
+
+    Ok(OrderTotals {
+        subtotal,
+        tax_amount,
+        shipping_amount,
+        total_amount: subtotal.checked_add(tax_amount)?.checked_add(shipping_amount)?,
+    })
+}
+
+/// Whether a figure the client showed is the server's, which is in the
+/// order's currency. Rials and tomans are interchangeable.
+fn same_amount(client: Money, server: Money) -> bool {
+    client.in_currency(server.currency()).is_ok_and(|client| client == server)
+}
+
+/// Prices every line from the variant's current price and stores the order
+/// and its items together, reserving their stock. `actor` is recorded as the
+/// author of the order's first status.
+pub async fn create_order(pool: &PgPool, checkout: &CheckoutConfig, actor: Uuid, new_order: CreateOrder) -> Result<Order, ServiceError> {
+    if new_order.items.is_empty() {
+        return Err(ServiceError::BadRequest("An order needs at least one item".to_string()));
+    }
+    let mut variant_ids = Vec::with_capacity(new_order.items.len());
+    for item in &new_order.items {
+        if item.quantity <= 0 {
+            return Err(ServiceError::BadRequest(format!("Quantity for variant {} must be positive", item.variant_id)));
+        }
+        if variant_ids.contains(&item.variant_id) {
+            return Err(ServiceError::BadRequest(format!("Variant {} appears more than once", item.variant_id)));
+        }
+        variant_ids.push(item.variant_id);
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // Locking products and then their variants, each in id order, keeps
+    // concurrent orders for the same products from deadlocking; the locks
+    // are held until the stock is reserved.
+    let products: HashMap<Uuid, Product> = sqlx::query_as::<_, Product>(
+        "SELECT * FROM products WHERE id IN (SELECT product_id FROM product_variants WHERE id = ANY($1)) ORDER BY id FOR UPDATE"
+    )
+    .bind(&variant_ids)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|product| (product.id, product))
+    .collect();
+    let variants: HashMap<Uuid, ProductVariant> = sqlx::query_as::<_, ProductVariant>(
+        "SELECT * FROM product_variants WHERE id = ANY($1) ORDER BY id FOR UPDATE"
+    )
+    .bind(&variant_ids)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|variant| (variant.id, variant))
+    .collect();
+
+    let locked = match new_order.price_lock_id {
+        Some(lock_id) => locked_prices(&mut tx, lock_id, new_order.user_id).await?,
+        None => HashMap::new(),
+    };
+    let currency = new_order.currency;
+    let exchange_rate = rials_per_unit(&mut tx, currency).await?;
+    let mut lines = Vec::with_capacity(new_order.items.len());
+    for item in &new_order.items {
+        let (variant, product) = variants
+            .get(&item.variant_id)
+            .and_then(|variant| Some((variant, products.get(&variant.product_id)?)))
+            .ok_or_else(|| ServiceError::BadRequest(format!("Product variant {} does not exist", item.variant_id)))?;
+        let unit_price = locked.get(&variant.id).copied().unwrap_or(variant.price);
+        let price = unit_price.checked_convert(currency, 1, exchange_rate)?;
+        if item.price.is_some_and(|client_price| !same_amount(client_price, price)) {
+            return Err(ServiceError::BadRequest(format!(
+                "The price of {} ({}) has changed to {}",
+                product.name, variant.sku, price
+            )));
+        }
+        lines.push((product, variant, item.quantity, unit_price, unit_price.checked_mul(item.quantity as i64)?));
+    }
+
+    let subtotal = Money::checked_sum(Currency::Irr, lines.iter().map(|(_, _, _, _, line_total)| *line_total))?;
+    let totals = compute_totals(checkout, subtotal)?;
+    let total = totals.total_amount.checked_convert(currency, 1, exchange_rate)?;
+    if new_order.total_amount.is_some_and(|client_total| !same_amount(client_total, total)) {
+        return Err(ServiceError::BadRequest(format!(
+            "Order total does not match the current prices; expected {}",
+            total
+        )));
+    }
+
+    let order = sqlx::query_as::<_, Order>(
+        "INSERT INTO orders (user_id, status, subtotal, tax_amount, shipping_amount, total_amount, currency, exchange_rate, shipping_address, notes, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) RETURNING *"
+    )
+    .bind(new_order.user_id)
+    .bind(OrderStatus::Pending)
+    .bind(totals.subtotal)
+    .bind(totals.tax_amount)
+    .bind(totals.shipping_amount)
+    .bind(totals.total_amount)
+    .bind(currency)
+    .bind(exchange_rate)
+    .bind(new_order.shipping_address)
+    .bind(new_order.notes)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for (product, variant, quantity, unit_price, line_total) in &lines {
+        sqlx::query(
+            "INSERT INTO order_items (order_id, product_id, variant_id, product_name, sku, quantity, unit_price, line_total, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+        )
+        .bind(order.id)
+        .bind(product.id)
+        .bind(variant.id)
+        .bind(&product.name)
+        .bind(&variant.sku)
+        .bind(quantity)
+        .bind(unit_price)
+        .bind(line_total)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let reserved: Vec<(&Product, &ProductVariant, i32)> = lines
+        .iter()
+        .map(|(product, variant, quantity, _, _)| (*product, *variant, *quantity))
+        .collect();
+    reserve_stock(&mut tx, order.id, &reserved, checkout.reservation_ttl_seconds).await?;
+    if let Some(lock_id) = new_order.price_lock_id {
+        use_price_lock(&mut tx, lock_id, order.id).await?;
+    }
+
+    record_status_change(&mut tx, order.id, None, OrderStatus::Pending, Some(actor), None).await?;
+
+    tx.commit().await?;
+
+    Ok(order)
+}
+
+pub async fn get_orders(pool: &PgPool, pagination: Pagination) -> Result<PaginatedResponse<Order>, ServiceError> {
+    let offset = pagination.offset().map_err(ServiceError::BadRequest)?;
+
+    let total_items: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM orders")
+        .fetch_one(pool)
+        .await?;
+
+    let orders = sqlx::query_as::<_, Order>(
+        "SELECT * FROM orders ORDER BY created_at DESC LIMIT $1 OFFSET $2"
+    )
+    .bind(pagination.limit as i64)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let total_pages = (total_items as f64 / pagination.limit as f64).ceil() as u32;
+
+    Ok(PaginatedResponse {
+        items: orders,
+        total_items: total_items as u32,
+        current_page: pagination.page,
+        total_pages,
+        limit: pagination.limit,
+    })
+}
+
+pub async fn get_orders_for_user(pool: &PgPool, user_id: Uuid, pagination: Pagination) -> Result<PaginatedResponse<Order>, ServiceError> {
+    let offset = pagination.offset().map_err(ServiceError::BadRequest)?;
+
+    let total_items: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM orders WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    let orders = sqlx::query_as::<_, Order>(
+        "SELECT * FROM orders WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2 OFFSET $3"
+    )
+    .bind(user_id)
+    .bind(pagination.limit as i64)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let total_pages = (total_items as f64 / pagination.limit as f64).ceil() as u32;
+
+    Ok(PaginatedResponse {
+        items: orders,
+        total_items: total_items as u32,
+        current_page: pagination.page,
+        total_pages,
+        limit: pagination.limit,
+    })
+}
+
+pub async fn get_order_by_id(pool: &PgPool, order_id: Uuid) -> Result<Order, ServiceError> {
+    let order = sqlx::query_as::<_, Order>(
+        "SELECT * FROM orders WHERE id = $1"
+    )
+    .bind(order_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(order)
+}
+
+pub async fn get_order_items(pool: &PgPool, order_id: Uuid) -> Result<Vec<OrderItem>, ServiceError> {
+    let items = sqlx::query_as::<_, OrderItem>(
+        "SELECT * FROM order_items WHERE order_id = $1 ORDER BY created_at, id"
+    )
+    .bind(order_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(items)
+}
+
+/// A status change in the request goes through the same checks as
+/// [`change_order_status`].
+pub async fn update_order(pool: &PgPool, order_id: Uuid, actor: Uuid, updated_order: UpdateOrder) -> Result<Order, ServiceError> {
+    let mut tx = pool.begin().await?;
+
+    let existing_order = match updated_order.status {
+        Some(status) => transition_order(&mut tx, order_id, status, Some(actor), None).await?,
+        None => lock_order(&mut tx, order_id).await?,
+    };
+
+    let order = sqlx::query_as::<_, Order>(
+        "UPDATE orders SET shipping_address = $1, notes = $2, updated_at = $3 WHERE id = $4 RETURNING *"
+    )
+    .bind(updated_order.shipping_address.or(existing_order.shipping_address))
+    .bind(updated_order.notes.or(existing_order.notes))
+    .bind(Utc::now())
+    .bind(order_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(order)
+}
+
+/// Moves an order to `status` if the state machine allows it and records the
+/// change. `actor` is `None` for changes made by the system.
+pub async fn change_order_status(pool: &PgPool, order_id: Uuid, status: OrderStatus, actor: Option<Uuid>, note: Option<String>) -> Result<Order, ServiceError> {
+    let mut tx = pool.begin().await?;
+    let order = transition_order(&mut tx, order_id, status, actor, note).await?;
+    tx.commit().await?;
+
+    Ok(order)
+}
+
+pub async fn get_order_status_history(pool: &PgPool, order_id: Uuid) -> Result<Vec<OrderStatusChange>, ServiceError> {
+    let history = sqlx::query_as::<_, OrderStatusChange>(
+        "SELECT * FROM order_status_history WHERE order_id = $1 ORDER BY created_at, id"
+    )
+    .bind(order_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(history)
+}
+
+/// Locks the order row for the rest of the transaction, so concurrent
+/// transitions are applied one after the other.
+pub(crate) async fn lock_order(conn: &mut PgConnection, order_id: Uuid) -> Result<Order, ServiceError> {
+    let order = sqlx::query_as::<_, Order>(
+        "SELECT * FROM orders WHERE id = $1 FOR UPDATE"
+    )
+    .bind(order_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(order)
+}
+
+/// Moving an order to the status it is already in is a no-op and is not
+/// recorded. Paying commits the order's reserved stock, shipping books it
+/// out of the warehouses, and cancelling or refunding an order that has not
+/// shipped returns it.
+pub(crate) async fn transition_order(conn: &mut PgConnection, order_id: Uuid, status: OrderStatus, actor: Option<Uuid>, note: Option<String>) -> Result<Order, ServiceError> {
+    let order = lock_order(conn, order_id).await?;
+    let from_status = order.status;
+    if from_status == status {
+        return Ok(order);
+    }
+    if !from_status.can_transition_to(status) {
+        return Err(ServiceError::BadRequest(format!(
+            "An order that is {} cannot be moved to {}",
+            from_status, status
+        )));
+    }
+
+    let order = sqlx::query_as::<_, Order>(
+        "UPDATE orders SET status = $1, updated_at = $2 WHERE id = $3 RETURNING *"
+    )
+    .bind(status)
+    .bind(Utc::now())
+    .bind(order_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    match status {
+        OrderStatus::Paid => commit_reservations(conn, order_id).await?,
+        OrderStatus::Shipped => fulfill_reservations(conn, order_id, actor).await?,
+        OrderStatus::Cancelled | OrderStatus::Refunded => release_reservations(conn, order_id).await?,
+        _ => {}
+    }
+
+    record_status_change(conn, order_id, Some(from_status), status, actor, note).await?;
+
+    Ok(order)
+}
+
+async fn record_status_change(conn: &mut PgConnection, order_id: Uuid, from_status: Option<OrderStatus>, to_status: OrderStatus, actor: Option<Uuid>, note: Option<String>) -> Result<(), ServiceError> {
+    sqlx::query(
+        "INSERT INTO order_status_history (order_id, from_status, to_status, changed_by, note, created_at) VALUES ($1, $2, $3, $4, $5, $6)"
+    )
+    .bind(order_id)
+    .bind(from_status)
+    .bind(to_status)
+    .bind(actor)
+    .bind(note)
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Deleting an order that has not shipped yet returns its stock, as
+/// cancelling it would.
+pub async fn delete_order(pool: &PgPool, order_id: Uuid) -> Result<(), ServiceError> {
+    let mut tx = pool.begin().await?;
+
+    let order = lock_order(&mut tx, order_id).await?;
+    if order.status.can_transition_to(OrderStatus::Cancelled) {
+        release_reservations(&mut tx, order_id).await?;
+    }
+
+    sqlx::query("DELETE FROM orders WHERE id = $1")
+        .bind(order_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_totals() {
+        let checkout = CheckoutConfig {
+            tax_rate: 0.09,
+            shipping_fee: 500_000,
+            free_shipping_threshold: Some(10_000_000),
+            ..CheckoutConfig::default()
+        };
+
+        let totals = compute_totals(&checkout, Money::rials(2_345_678)).unwrap();
+        assert_eq!(totals.tax_amount, Money::rials(211_111));
+        assert_eq!(totals.shipping_amount, Money::rials(500_000));
+        assert_eq!(totals.total_amount, Money::rials(2_345_678 + 211_111 + 500_000));
+
+        let totals = compute_totals(&checkout, Money::rials(10_000_000)).unwrap();
+        assert_eq!(totals.shipping_amount, Money::rials(0));
+        assert_eq!(totals.total_amount, Money::rials(10_900_000));
+    }
+}
@@ -0,0 +1,118 @@
+use async_trait::async_trait;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::sync::Arc;
+use uuid::Uuid;
+use config::PaymentConfig;
+use super::{mock::MockGateway, zarinpal::ZarinpalGateway};
+
+/// What a gateway needs to start a payment. Amounts are in rials.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentRequest {
+    pub order_id: Uuid,
+    pub amount: i64,
+    pub description: String,
+    /// Where the gateway sends the payer back to.
+    pub callback_url: String,
+    pub mobile: Option<String>,
+    pub email: Option<String>,
+}
+
+/// A payment the gateway is waiting for the payer to complete.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentSession {
+    pub authority: String,
+    /// Gateway page the payer has to be redirected to.
+    pub redirect_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedPayment {
+    /// Bank reference of the settled payment.
+    pub reference_id: String,
+    pub card_pan: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefundReceipt {
+    pub refund_id: String,
+}
+
+/// Where a payment stands at the gateway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GatewayPaymentStatus {
+    /// The payer has not finished paying.
+    Initiated,
+    /// Paid but not verified yet; unverified payments are returned to the
+    /// payer by the gateway.
+    Paid,
+    Verified,
+    Failed,
+    Refunded,
+}
+
+/// A failed gateway call.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GatewayError {
+    /// The gateway answered and refused the operation.
+    Rejected(String),
+    /// The gateway could not be reached or its answer could not be read, so
+    /// the operation may still succeed when retried.
+    Unavailable(String),
+}
+
+impl Display for GatewayError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            GatewayError::Rejected(message) | GatewayError::Unavailable(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<GatewayError> for String {
+    fn from(error: GatewayError) -> Self {
+        error.to_string()
+    }
+}
+
+/// An internet payment gateway (IPG): the payer is redirected to the
+/// gateway, comes back to the callback URL and the payment is then
+/// verified server to server. Errors are gateway messages.
+#[async_trait]
+pub trait PaymentGateway: Send + Sync {
+    /// Stored on payments made through this gateway.
+    fn name(&self) -> &'static str;
+
+    async fn initiate(&self, request: &PaymentRequest) -> Result<PaymentSession, String>;
+
+    /// Settles a payment the payer completed. Verifying an already verified
+    /// payment succeeds again with the same reference. Only a
+    /// [`GatewayError::Rejected`] payment is known not to settle.
+    async fn verify(&self, authority: &str, amount: i64) -> Result<VerifiedPayment, GatewayError>;
+
+    /// Returns `amount` of a verified payment to the payer. After a
+    /// [`GatewayError::Unavailable`] the money may or may not have been
+    /// returned, so the refund must not simply be repeated.
+    async fn refund(&self, authority: &str, amount: i64) -> Result<RefundReceipt, GatewayError>;
+
+    async fn query(&self, authority: &str) -> Result<GatewayPaymentStatus, String>;
+}
+
+/// Builds the configured gateway. The mock gateway's server is started
+/// here and runs until the service stops.
+pub async fn gateway_from_config(config: &PaymentConfig) -> Result<Arc<dyn PaymentGateway>, String> {
+    match config.gateway.as_str() {
+        "zarinpal" => {
+            let merchant_id = config
+                .zarinpal_merchant_id
+                .as_deref()
+                .ok_or_else(|| "ZARINPAL_MERCHANT_ID is required for the zarinpal gateway".to_string())?;
+            Ok(Arc::new(ZarinpalGateway::new(&config.zarinpal_api_url, merchant_id)))
+        }
+        "mock" => {
+            let gateway = MockGateway::start().await?;
+            log::warn!("Payments go through the mock gateway at {}; no money is collected", gateway.server().base_url());
+            Ok(Arc::new(gateway))
+        }
+        other => Err(format!("Unknown payment gateway `{}`", other)),
+    }
+}
@@ -0,0 +1,300 @@
+use actix_web::{dev::ServerHandle, http::header, web, App, HttpResponse, HttpServer};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+use super::gateway::{GatewayError, GatewayPaymentStatus, PaymentGateway, PaymentRequest, PaymentSession, RefundReceipt, VerifiedPayment};
+use super::zarinpal::ZarinpalGateway;
+
+/// The only merchant id the mock gateway accepts.
+pub const MOCK_MERCHANT_ID: &str = "00000000-0000-0000-0000-000000000000";
+/// Smallest payment the gateway takes, in rials.
+const MIN_AMOUNT: i64 = 10_000;
+
+/// A payment as the mock gateway sees it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockTransaction {
+    pub authority: String,
+    pub amount: i64,
+    pub callback_url: String,
+    pub status: GatewayPaymentStatus,
+    pub ref_id: Option<i64>,
+    pub refunded: i64,
+}
+
+impl MockTransaction {
+    /// Settles the StartPay page and returns the callback URL the payer is
+    /// sent back to.
+    fn finish(&mut self, paid: bool) -> String {
+        if self.status == GatewayPaymentStatus::Initiated {
+            self.status = if paid { GatewayPaymentStatus::Paid } else { GatewayPaymentStatus::Failed };
+        }
+        let separator = if self.callback_url.contains('?') { '&' } else { '?' };
+        let status = if self.status == GatewayPaymentStatus::Failed { "NOK" } else { "OK" };
+        format!("{}{}Authority={}&Status={}", self.callback_url, separator, self.authority, status)
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    issued: u64,
+    transactions: HashMap<String, MockTransaction>,
+}
+
+type SharedState = web::Data<Mutex<MockState>>;
+
+/// An in-process HTTP server speaking the Zarinpal v4 protocol, for tests
+/// and local runs. Visiting its StartPay page pays immediately (or cancels
+/// with `?status=NOK`) and redirects to the callback.
+pub struct MockGatewayServer {
+    base_url: String,
+    state: SharedState,
+    handle: ServerHandle,
+}
+
+impl MockGatewayServer {
+    /// Listens on a free local port. Must be called inside an actix runtime.
+    pub async fn start() -> Result<Self, String> {
+        let state: SharedState = web::Data::new(Mutex::new(MockState::default()));
+        let app_state = state.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(app_state.clone())
+                .route("/pg/v4/payment/request.json", web::post().to(request))
+                .route("/pg/v4/payment/verify.json", web::post().to(verify))
+                .route("/pg/v4/payment/refund.json", web::post().to(refund))
+                .route("/pg/v4/payment/inquiry.json", web::post().to(inquiry))
+                .route("/pg/StartPay/{authority}", web::get().to(start_pay))
+        })
+        .workers(1)
+        .disable_signals()
+        .bind(("127.0.0.1", 0))
+        .map_err(|e| format!("Failed to start the mock payment gateway: {}", e))?;
+
+        let address = server.addrs()[0];
+        let server = server.run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        Ok(Self { base_url: format!("http://{}", address), state, handle })
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Does what the payer would on the StartPay page and returns the
+    /// callback URL the payer is sent back to.
+    pub fn complete(&self, authority: &str, paid: bool) -> Option<String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.transactions.get_mut(authority).map(|transaction| transaction.finish(paid))
+    }
+
+    pub fn transaction(&self, authority: &str) -> Option<MockTransaction> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.transactions.get(authority).cloned()
+    }
+
+    pub async fn stop(&self) {
+        self.handle.stop(true).await;
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestIn {
+    merchant_id: String,
+    amount: i64,
+    callback_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthorityIn {
+    merchant_id: String,
+    authority: String,
+    #[serde(default)]
+    amount: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StartPayQuery {
+    #[serde(default)]
+    status: Option<String>,
+}
+
+fn ok(data: serde_json::Value) -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({ "data": data, "errors": [] }))
+}
+
+fn error(code: i64, message: &str) -> HttpResponse {
+    HttpResponse::UnprocessableEntity().json(serde_json::json!({
+        "data": [],
+        "errors": { "code": code, "message": message, "validations": [] },
+    }))
+}
+
+fn merchant_error(merchant_id: &str) -> Option<HttpResponse> {
+    (merchant_id != MOCK_MERCHANT_ID)
+        .then(|| error(-10, "Terminal is not valid, please check merchant_id or ip address."))
+}
+
+async fn request(state: SharedState, body: web::Json<RequestIn>) -> HttpResponse {
+    if let Some(response) = merchant_error(&body.merchant_id) {
+        return response;
+    }
+    if body.amount < MIN_AMOUNT {
+        return error(-9, "The input params invalid, validation error.");
+    }
+
+    // Random so that authorities stay unique across restarts of the service.
+    let authority = format!("A{:035}", Uuid::new_v4().as_u128() % 10u128.pow(35));
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    state.transactions.insert(authority.clone(), MockTransaction {
+        authority: authority.clone(),
+        amount: body.amount,
+        callback_url: body.callback_url.clone(),
+        status: GatewayPaymentStatus::Initiated,
+        ref_id: None,
+        refunded: 0,
+    });
+
+    ok(serde_json::json!({ "code": 100, "message": "Success", "authority": authority, "fee_type": "Merchant", "fee": 0 }))
+}
+
+async fn start_pay(state: SharedState, authority: web::Path<String>, query: web::Query<StartPayQuery>) -> HttpResponse {
+    let paid = !query.status.as_deref().is_some_and(|status| status.eq_ignore_ascii_case("NOK"));
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(transaction) = state.transactions.get_mut(authority.as_str()) else {
+        return HttpResponse::NotFound().body("Unknown payment");
+    };
+
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, transaction.finish(paid)))
+        .finish()
+}
+
+async fn verify(state: SharedState, body: web::Json<AuthorityIn>) -> HttpResponse {
+    if let Some(response) = merchant_error(&body.merchant_id) {
+        return response;
+    }
+
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    state.issued += 1;
+    let next_ref = 100_000_000 + state.issued as i64;
+    let Some(transaction) = state.transactions.get_mut(&body.authority) else {
+        return error(-54, "Invalid authority.");
+    };
+    if body.amount != Some(transaction.amount) {
+        return error(-50, "Session is not valid, amounts values is not the same.");
+    }
+
+    let code = match transaction.status {
+        GatewayPaymentStatus::Paid => {
+            transaction.status = GatewayPaymentStatus::Verified;
+            transaction.ref_id = Some(next_ref);
+            100
+        }
+        GatewayPaymentStatus::Verified | GatewayPaymentStatus::Refunded => 101,
+        GatewayPaymentStatus::Initiated | GatewayPaymentStatus::Failed => {
+            return error(-51, "Session is not valid, session is not active paid try.");
+        }
+    };
+
+    ok(serde_json::json!({
+        "code": code,
+        "message": if code == 100 { "Paid" } else { "Verified" },
+        "ref_id": transaction.ref_id,
+        "card_pan": "502229******5995",
+        "card_hash": "",
+        "fee_type": "Merchant",
+        "fee": 0,
+    }))
+}
+
+async fn refund(state: SharedState, body: web::Json<AuthorityIn>) -> HttpResponse {
+    if let Some(response) = merchant_error(&body.merchant_id) {
+        return response;
+    }
+
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    state.issued += 1;
+    let refund_id = format!("R{:09}", state.issued);
+    let Some(transaction) = state.transactions.get_mut(&body.authority) else {
+        return error(-54, "Invalid authority.");
+    };
+    if transaction.status != GatewayPaymentStatus::Verified {
+        return error(-51, "Session is not valid, session is not active paid try.");
+    }
+    let amount = body.amount.unwrap_or(transaction.amount - transaction.refunded);
+    if amount <= 0 || amount > transaction.amount - transaction.refunded {
+        return error(-9, "The input params invalid, validation error.");
+    }
+
+    transaction.refunded += amount;
+    if transaction.refunded == transaction.amount {
+        transaction.status = GatewayPaymentStatus::Refunded;
+    }
+
+    ok(serde_json::json!({ "code": 100, "message": "Success", "refund_id": refund_id }))
+}
+
+async fn inquiry(state: SharedState, body: web::Json<AuthorityIn>) -> HttpResponse {
+    if let Some(response) = merchant_error(&body.merchant_id) {
+        return response;
+    }
+
+    let state = state.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(transaction) = state.transactions.get(&body.authority) else {
+        return error(-54, "Invalid authority.");
+    };
+    let status = match transaction.status {
+        GatewayPaymentStatus::Initiated => "IN_BANK",
+        GatewayPaymentStatus::Paid => "PAID",
+        GatewayPaymentStatus::Verified => "VERIFIED",
+        GatewayPaymentStatus::Failed => "FAILED",
+        GatewayPaymentStatus::Refunded => "REVERSED",
+    };
+
+    ok(serde_json::json!({ "code": 100, "message": "Success", "status": status }))
+}
+
+/// The Zarinpal client pointed at its own [`MockGatewayServer`].
+pub struct MockGateway {
+    server: MockGatewayServer,
+    client: ZarinpalGateway,
+}
+
+impl MockGateway {
+    pub async fn start() -> Result<Self, String> {
+        let server = MockGatewayServer::start().await?;
+        let client = ZarinpalGateway::new(server.base_url(), MOCK_MERCHANT_ID);
+        Ok(Self { server, client })
+    }
+
+    pub fn server(&self) -> &MockGatewayServer {
+        &self.server
+    }
+}
+
+#[async_trait]
+impl PaymentGateway for MockGateway {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn initiate(&self, request: &PaymentRequest) -> Result<PaymentSession, String> {
+        self.client.initiate(request).await
+    }
+
+    async fn verify(&self, authority: &str, amount: i64) -> Result<VerifiedPayment, GatewayError> {
+        self.client.verify(authority, amount).await
+    }
+
+    async fn refund(&self, authority: &str, amount: i64) -> Result<RefundReceipt, GatewayError> {
+        self.client.refund(authority, amount).await
+    }
+
+    async fn query(&self, authority: &str) -> Result<GatewayPaymentStatus, String> {
+        self.client.query(authority).await
+    }
+}
@@ -0,0 +1,261 @@
+mod gateway;
+mod mock;
+mod refund;
+mod webhook;
+mod zarinpal;
+
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use chrono::Utc;
+use config::PaymentConfig;
+use models::{
+    money::{Currency, Money},
+    order::OrderStatus,
+    payment::{Payment, CreatePayment, PaymentCallback, PaymentStatus, PaymentStatusResponse},
+};
+use crate::error::ServiceError;
+use super::order::{get_order_by_id, lock_order, transition_order};
+
+pub use gateway::*;
+pub use mock::{MockGateway, MockGatewayServer, MockTransaction, MOCK_MERCHANT_ID};
+pub use refund::*;
+pub use webhook::*;
+pub use zarinpal::ZarinpalGateway;
+
+/// Starts paying for a pending order at the gateway. The returned payment's
+/// `redirect_url` is where the payer has to go next.
+pub async fn process_payment(pool: &PgPool, gateway: &dyn PaymentGateway, config: &PaymentConfig, new_payment: CreatePayment) -> Result<Payment, ServiceError> {
+    let order = get_order_by_id(pool, new_payment.order_id).await?;
+
+    if order.status != OrderStatus::Pending {
+        return Err(ServiceError::BadRequest(format!("An order that is {} cannot be paid", order.status)));
+    }
+    let amount = new_payment.amount.in_currency(Currency::Irr)?;
+    if !amount.is_positive() {
+        return Err(ServiceError::BadRequest("Payment amount must be positive".to_string()));
+    }
+    let outstanding = order.total_amount.checked_sub(paid_amount(&mut *pool.acquire().await?, order.id).await?)?;
+    if amount > outstanding {
+        return Err(ServiceError::BadRequest("Payment amount exceeds the order total".to_string()));
+    }
+
+    let (email, phone): (String, Option<String>) = sqlx::query_as("SELECT email, phone FROM users WHERE id = $1")
+        .bind(order.user_id)
+        .fetch_one(pool)
+        .await?;
+    let session = gateway
+        .initiate(&PaymentRequest {
+            order_id: order.id,
+            amount: amount.minor_units(),
+            description: format!("پرداخت سفارش {}", order.id),
+            callback_url: config.callback_url.clone(),
+            mobile: phone,
+            email: Some(email),
+        })
+        .await
+        .map_err(ServiceError::BadGateway)?;
+
+    let payment = sqlx::query_as::<_, Payment>(
+        "INSERT INTO payments (order_id, amount, status, gateway, transaction_id, redirect_url, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING *"
+    )
+    .bind(order.id)
+    .bind(amount)
+    .bind(PaymentStatus::Pending)
+    .bind(gateway.name())
+    .bind(session.authority)
+    .bind(session.redirect_url)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(payment)
+}
+
+/// Handles the payer coming back from the gateway.
+pub async fn handle_payment_callback(pool: &PgPool, gateway: &dyn PaymentGateway, callback: PaymentCallback) -> Result<Payment, ServiceError> {
+    let payment = sqlx::query_as::<_, Payment>(
+        "SELECT * FROM payments WHERE gateway = $1 AND transaction_id = $2"
+    )
+    .bind(gateway.name())
+    .bind(&callback.authority)
+    .fetch_one(pool)
+    .await?;
+
+    settle_payment(pool, gateway, payment, callback.is_ok()).await
+}
+
+/// Asks the gateway about a pending payment whose payer never came back
+/// and settles it accordingly.
+pub async fn refresh_payment(pool: &PgPool, gateway: &dyn PaymentGateway, payment_id: Uuid) -> Result<Payment, ServiceError> {
+    let payment = get_payment_by_id(pool, payment_id).await?;
+    if payment.status != PaymentStatus::Pending || payment.gateway != gateway.name() {
+        return Ok(payment);
+    }
+
+    match gateway.query(&payment.transaction_id).await.map_err(ServiceError::BadGateway)? {
+        GatewayPaymentStatus::Initiated => Ok(payment),
+        GatewayPaymentStatus::Failed => settle_payment(pool, gateway, payment, false).await,
+        GatewayPaymentStatus::Paid | GatewayPaymentStatus::Verified | GatewayPaymentStatus::Refunded => {
+            settle_payment(pool, gateway, payment, true).await
+        }
+    }
+}
+
+/// Verifies a completed payment with the gateway and marks the order paid
+/// once its payments cover the total. Payments that were already settled
+/// are returned unchanged, so repeated callbacks are harmless.
+async fn settle_payment(pool: &PgPool, gateway: &dyn PaymentGateway, payment: Payment, completed: bool) -> Result<Payment, ServiceError> {
+    let mut tx = pool.begin().await?;
+    let payment = settle_payment_in(&mut tx, gateway, payment.id, payment.order_id, completed).await?;
+    tx.commit().await?;
+
+    Ok(payment)
+}
+
+/// [`settle_payment`] inside the caller's transaction.
+///
+/// An order that stopped being payable (e.g. its reservation expired) is
+/// not verified; the gateway then returns the money to the payer. A payment
+/// the gateway could not be asked about stays pending so that it can be
+/// settled again later.
+async fn settle_payment_in(conn: &mut PgConnection, gateway: &dyn PaymentGateway, payment_id: Uuid, order_id: Uuid, completed: bool) -> Result<Payment, ServiceError> {
+    let order = lock_order(&mut *conn, order_id).await?;
+    let payment = sqlx::query_as::<_, Payment>("SELECT * FROM payments WHERE id = $1 FOR UPDATE")
+        .bind(payment_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if payment.status != PaymentStatus::Pending {
+        return Ok(payment);
+    }
+
+    let failure = if !completed {
+        Some("The payment was cancelled".to_string())
+    } else if order.status != OrderStatus::Pending {
+        Some(format!("The order was {} before the payment was verified", order.status))
+    } else {
+        match gateway.verify(&payment.transaction_id, payment.amount.minor_units()).await {
+            Ok(verified) => {
+                let payment = sqlx::query_as::<_, Payment>(
+                    "UPDATE payments SET status = $1, reference_id = $2, card_pan = $3, paid_at = $4, updated_at = $4 WHERE id = $5 RETURNING *"
+                )
+                .bind(PaymentStatus::Paid)
+                .bind(&verified.reference_id)
+                .bind(verified.card_pan)
+                .bind(Utc::now())
+                .bind(payment.id)
+                .fetch_one(&mut *conn)
+                .await?;
+
+                if paid_amount(&mut *conn, order.id).await? >= order.total_amount {
+                    let note = format!("Payment {} verified", verified.reference_id);
+                    transition_order(&mut *conn, order.id, OrderStatus::Paid, None, Some(note)).await?;
+                }
+
+                return Ok(payment);
+            }
+            Err(GatewayError::Rejected(error)) => {
+                log::warn!("Failed to verify payment {}: {}", payment.id, error);
+                Some(error)
+            }
+            Err(GatewayError::Unavailable(error)) => {
+                log::warn!("Could not verify payment {}, leaving it pending: {}", payment.id, error);
+                return Err(ServiceError::BadGateway(error));
+            }
+        }
+    };
+
+    let payment = sqlx::query_as::<_, Payment>(
+        "UPDATE payments SET status = $1, failure_reason = $2, updated_at = $3 WHERE id = $4 RETURNING *"
+    )
+    .bind(PaymentStatus::Failed)
+    .bind(failure)
+    .bind(Utc::now())
+    .bind(payment.id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(payment)
+}
+
+/// What the order's payments collected, less what was refunded.
+pub(crate) async fn paid_amount(conn: &mut PgConnection, order_id: Uuid) -> Result<Money, ServiceError> {
+    let paid: Money = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(amount - refunded_amount), 0) FROM payments WHERE order_id = $1 AND status IN ($2, $3, $4)"
+    )
+    .bind(order_id)
+    .bind(PaymentStatus::Paid)
+    .bind(PaymentStatus::PartiallyRefunded)
+    .bind(PaymentStatus::Refunded)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(paid)
+}
+
+pub async fn get_payment_by_id(pool: &PgPool, payment_id: Uuid) -> Result<Payment, ServiceError> {
+    let payment = sqlx::query_as::<_, Payment>(
+        "SELECT * FROM payments WHERE id = $1"
+    )
+    .bind(payment_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(payment)
+}
+
+pub async fn get_payment_status(pool: &PgPool, payment_id: Uuid) -> Result<PaymentStatusResponse, ServiceError> {
+    let payment = get_payment_by_id(pool, payment_id).await?;
+
+    Ok(PaymentStatusResponse { status: payment.status, reference_id: payment.reference_id })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(server: &MockGatewayServer, amount: i64) -> PaymentRequest {
+        PaymentRequest {
+            order_id: Uuid::new_v4(),
+            amount,
+            description: "پرداخت سفارش".to_string(),
+            callback_url: format!("{}/callback", server.base_url()),
+            mobile: Some("09121234567".to_string()),
+            email: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_mock_gateway_follows_the_zarinpal_flow() {
+        let gateway = MockGateway::start().await.unwrap();
+        let server = gateway.server();
+
+        let session = gateway.initiate(&request(server, 250_000)).await.unwrap();
+        assert_eq!(session.redirect_url, format!("{}/pg/StartPay/{}", server.base_url(), session.authority));
+        assert_eq!(gateway.query(&session.authority).await, Ok(GatewayPaymentStatus::Initiated));
+        assert!(matches!(gateway.verify(&session.authority, 250_000).await, Err(GatewayError::Rejected(error)) if error.contains("-51")));
+
+        let callback = server.complete(&session.authority, true).unwrap();
+        assert!(callback.ends_with(&format!("/callback?Authority={}&Status=OK", session.authority)));
+        assert!(matches!(gateway.verify(&session.authority, 100_000).await, Err(GatewayError::Rejected(error)) if error.contains("-50")));
+        let verified = gateway.verify(&session.authority, 250_000).await.unwrap();
+        assert_eq!(gateway.verify(&session.authority, 250_000).await, Ok(verified.clone()));
+        assert_eq!(gateway.query(&session.authority).await, Ok(GatewayPaymentStatus::Verified));
+
+        gateway.refund(&session.authority, 100_000).await.unwrap();
+        assert!(gateway.refund(&session.authority, 200_000).await.is_err());
+        gateway.refund(&session.authority, 150_000).await.unwrap();
+        assert_eq!(gateway.query(&session.authority).await, Ok(GatewayPaymentStatus::Refunded));
+
+        let cancelled = gateway.initiate(&request(server, 250_000)).await.unwrap();
+        assert!(server.complete(&cancelled.authority, false).unwrap().ends_with("Status=NOK"));
+        assert_eq!(gateway.query(&cancelled.authority).await, Ok(GatewayPaymentStatus::Failed));
+
+        assert!(gateway.initiate(&request(server, 1_000)).await.unwrap_err().contains("-9"));
+        let stranger = ZarinpalGateway::new(server.base_url(), "not-a-merchant");
+        assert!(stranger.initiate(&request(server, 250_000)).await.unwrap_err().contains("-10"));
+
+        server.stop().await;
+        assert!(matches!(gateway.verify(&session.authority, 250_000).await, Err(GatewayError::Unavailable(_))));
+    }
+}
@@ -0,0 +1,410 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use chrono::Utc;
+use std::collections::HashMap;
+use models::{
+    inventory::MovementType,
+    money::{Currency, Money, MoneyError},
+    order::{Order, OrderItem, OrderStatus},
+    payment::{CreateRefund, Payment, PaymentStatus, Refund, RefundItem, RefundStatus, RefundWithItems, ResolveRefund},
+};
+use crate::error::ServiceError;
+use crate::service::inventory::{record_movement, NewMovement};
+use crate::service::order::{lock_order, transition_order};
+use crate::service::reservation::release_reserved_units;
+use crate::service::warehouse::default_warehouse_id;
+use super::{gateway::{GatewayError, PaymentGateway}, get_payment_by_id, paid_amount};
+
+/// Units of an order line a refund pays back.
+struct RefundLine<'a> {
+    item: &'a OrderItem,
+    quantity: i32,
+    amount: Money,
+}
+
+/// Gives money of a paid payment back through its gateway and records the
+/// refund against the order lines it covers.
+///
+/// On success the refund is applied; see [`apply_refund`]. A refund the
+/// gateway refuses is recorded as failed and changes nothing else. One the
+/// gateway could not be asked about is recorded as pending, and the
+/// payment cannot be refunded again until it is resolved with
+/// [`resolve_refund`], so that the payer is not paid twice.
+pub async fn refund_payment(pool: &PgPool, gateway: &dyn PaymentGateway, payment_id: Uuid, actor: Uuid, new_refund: CreateRefund) -> Result<RefundWithItems, ServiceError> {
+    let payment = get_payment_by_id(pool, payment_id).await?;
+
+    let mut tx = pool.begin().await?;
+    let order = lock_order(&mut tx, payment.order_id).await?;
+    let payment = sqlx::query_as::<_, Payment>("SELECT * FROM payments WHERE id = $1 FOR UPDATE")
+        .bind(payment.id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if !matches!(payment.status, PaymentStatus::Paid | PaymentStatus::PartiallyRefunded) {
+        return Err(ServiceError::BadRequest(format!("A payment that is {} cannot be refunded", payment.status)));
+    }
+    if payment.gateway != gateway.name() {
+        return Err(ServiceError::BadRequest(format!(
+            "The payment was made through `{}`, which is not the configured gateway",
+            payment.gateway
+        )));
+    }
+    let pending: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM refunds WHERE payment_id = $1 AND status = $2)")
+        .bind(payment.id)
+        .bind(RefundStatus::Pending)
+        .fetch_one(&mut *tx)
+        .await?;
+    if pending {
+        return Err(ServiceError::BadRequest("A refund of this payment is still pending and has to be resolved first".to_string()));
+    }
+
+    let items = sqlx::query_as::<_, OrderItem>("SELECT * FROM order_items WHERE order_id = $1 ORDER BY created_at, id")
+        .bind(order.id)
+        .fetch_all(&mut *tx)
+        .await?;
+    let refunded = refunded_quantities(&mut tx, order.id).await?;
+    let remaining = |item: &OrderItem| item.quantity - refunded.get(&item.id).copied().unwrap_or(0);
+
+    let full_refund = new_refund.items.is_empty() && new_refund.amount.is_none();
+    let mut lines = Vec::new();
+    if full_refund {
+        // The lines could not be split between several payments.
+        let payments: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM payments WHERE order_id = $1 AND status IN ($2, $3, $4)")
+            .bind(order.id)
+            .bind(PaymentStatus::Paid)
+            .bind(PaymentStatus::PartiallyRefunded)
+            .bind(PaymentStatus::Refunded)
+            .fetch_one(&mut *tx)
+            .await?;
+        if payments > 1 {
+            return Err(ServiceError::BadRequest(
+                "The order was paid in several payments, so the items or amount to refund have to be given".to_string(),
+            ));
+        }
+        for item in &items {
+            if remaining(item) > 0 {
+                lines.push(RefundLine { item, quantity: remaining(item), amount: line_amount(&order, item, remaining(item))? });
+            }
+        }
+    }
+    for requested in &new_refund.items {
+        let item = items
+            .iter()
+            .find(|item| item.id == requested.order_item_id)
+            .ok_or_else(|| ServiceError::BadRequest(format!("Order line {} is not part of the order", requested.order_item_id)))?;
+        if lines.iter().any(|line: &RefundLine| line.item.id == item.id) {
+            return Err(ServiceError::BadRequest(format!("Order line {} is listed twice", item.id)));
+        }
+        if requested.quantity <= 0 || requested.quantity > remaining(item) {
+            return Err(ServiceError::BadRequest(format!(
+                "Between 1 and {} units of {} can be refunded",
+                remaining(item),
+                item.product_name
+            )));
+        }
+        lines.push(RefundLine { item, quantity: requested.quantity, amount: line_amount(&order, item, requested.quantity)? });
+    }
+
+    let refundable = payment.refundable_amount();
+    let amount = match new_refund.amount {
+        Some(amount) => amount.in_currency(Currency::Irr)?,
+        None if full_refund => refundable,
+        None => Money::checked_sum(Currency::Irr, lines.iter().map(|line| line.amount))?,
+    };
+    if !amount.is_positive() {
+        return Err(ServiceError::BadRequest("Refund amount must be positive".to_string()));
+    }
+    if amount > refundable {
+        return Err(ServiceError::BadRequest(format!("Only {} of the payment can still be refunded", refundable)));
+    }
+
+    let outcome = gateway.refund(&payment.transaction_id, amount.minor_units()).await;
+    let status = match &outcome {
+        Ok(_) => RefundStatus::Succeeded,
+        Err(GatewayError::Rejected(_)) => RefundStatus::Failed,
+        Err(GatewayError::Unavailable(_)) => RefundStatus::Pending,
+    };
+    let refund = sqlx::query_as::<_, Refund>(
+        "INSERT INTO refunds (payment_id, order_id, amount, status, reason, restock, gateway_refund_id, failure_reason, created_by, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING *"
+    )
+    .bind(payment.id)
+    .bind(order.id)
+    .bind(amount)
+    .bind(status)
+    .bind(new_refund.reason)
+    .bind(new_refund.restock && !lines.is_empty())
+    .bind(outcome.as_ref().ok().map(|receipt| receipt.refund_id.clone()))
+    .bind(outcome.as_ref().err().map(ToString::to_string))
+    .bind(actor)
+    .bind(Utc::now())
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let mut refund_items = Vec::with_capacity(lines.len());
+    for line in &lines {
+        let refund_item = sqlx::query_as::<_, RefundItem>(
+            "INSERT INTO refund_items (refund_id, order_item_id, quantity, amount) VALUES ($1, $2, $3, $4) RETURNING *"
+        )
+        .bind(refund.id)
+        .bind(line.item.id)
+        .bind(line.quantity)
+        .bind(line.amount)
+        .fetch_one(&mut *tx)
+        .await?;
+        refund_items.push(refund_item);
+    }
+
+    if let Err(error) = outcome {
+        // The attempt is kept for the record.
+        tx.commit().await?;
+        match &error {
+            GatewayError::Rejected(_) => log::warn!("The gateway refused refund {} of payment {}: {}", refund.id, payment.id, error),
+            GatewayError::Unavailable(_) => log::warn!("Refund {} of payment {} is pending: {}", refund.id, payment.id, error),
+        }
+        return Err(ServiceError::BadGateway(error.to_string()));
+    }
+
+    apply_refund(&mut tx, &order, &refund, &lines, actor).await?;
+    tx.commit().await?;
+
+    Ok(RefundWithItems { refund, items: refund_items })
+}
+
+/// Records whether a pending refund gave the money back, as looked up in
+/// the gateway's records, and applies it if it did.
+pub async fn resolve_refund(pool: &PgPool, payment_id: Uuid, refund_id: Uuid, actor: Uuid, resolution: ResolveRefund) -> Result<RefundWithItems, ServiceError> {
+    if resolution.status == RefundStatus::Pending {
+        return Err(ServiceError::BadRequest("A refund can only be resolved as succeeded or failed".to_string()));
+    }
+    let payment = get_payment_by_id(pool, payment_id).await?;
+
+    let mut tx = pool.begin().await?;
+    let order = lock_order(&mut tx, payment.order_id).await?;
+    sqlx::query("SELECT id FROM payments WHERE id = $1 FOR UPDATE")
+        .bind(payment.id)
+        .execute(&mut *tx)
+        .await?;
+    let refund = sqlx::query_as::<_, Refund>("SELECT * FROM refunds WHERE id = $1 AND payment_id = $2 FOR UPDATE")
+        .bind(refund_id)
+        .bind(payment.id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if refund.status != RefundStatus::Pending {
+        return Err(ServiceError::BadRequest(format!("The refund already {}", refund.status)));
+    }
+
+    let failure_reason = match resolution.status {
+        RefundStatus::Failed => resolution.failure_reason.or(refund.failure_reason),
+        _ => None,
+    };
+    let refund = sqlx::query_as::<_, Refund>(
+        "UPDATE refunds SET status = $1, gateway_refund_id = $2, failure_reason = $3 WHERE id = $4 RETURNING *"
+    )
+    .bind(resolution.status)
+    .bind(resolution.gateway_refund_id)
+    .bind(failure_reason)
+    .bind(refund.id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let refund_items = sqlx::query_as::<_, RefundItem>("SELECT * FROM refund_items WHERE refund_id = $1 ORDER BY id")
+        .bind(refund.id)
+        .fetch_all(&mut *tx)
+        .await?;
+    if refund.status == RefundStatus::Succeeded {
+        let items = sqlx::query_as::<_, OrderItem>("SELECT * FROM order_items WHERE order_id = $1")
+            .bind(order.id)
+            .fetch_all(&mut *tx)
+            .await?;
+        let lines = refund_items
+            .iter()
+            .filter_map(|refund_item| {
+                let item = items.iter().find(|item| item.id == refund_item.order_item_id)?;
+                Some(RefundLine { item, quantity: refund_item.quantity, amount: refund_item.amount })
+            })
+            .collect::<Vec<_>>();
+        apply_refund(&mut tx, &order, &refund, &lines, actor).await?;
+    }
+    tx.commit().await?;
+
+    Ok(RefundWithItems { refund, items: refund_items })
+}
+
+/// Applies a refund that gave the money back: the payment's refunded amount
+/// and status are reconciled, refunded items are put back into stock if
+/// asked to, and the order moves to refunded once everything it collected
+/// was given back.
+async fn apply_refund(conn: &mut PgConnection, order: &Order, refund: &Refund, lines: &[RefundLine<'_>], actor: Uuid) -> Result<(), ServiceError> {
+    reconcile_payment(&mut *conn, refund.payment_id).await?;
+    if refund.restock {
+        for line in lines {
+            restock(&mut *conn, order, line, refund.id, actor).await?;
+        }
+    }
+    if !paid_amount(&mut *conn, order.id).await?.is_positive() && order.status.can_transition_to(OrderStatus::Refunded) {
+        let note = format!("Refund {}", refund.id);
+        transition_order(&mut *conn, order.id, OrderStatus::Refunded, Some(actor), Some(note)).await?;
+    }
+
+    Ok(())
+}
+
+/// The line's price plus its share of the order's tax; shipping is only
+/// given back by a full refund.
+fn line_amount(order: &Order, item: &OrderItem, quantity: i32) -> Result<Money, MoneyError> {
+    let price = item.unit_price.checked_mul(quantity as i64)?;
+    if order.subtotal.is_zero() {
+        return Ok(price);
+    }
+    price.checked_add(order.tax_amount.checked_mul_ratio(price.minor_units(), order.subtotal.minor_units())?)
+}
+
+/// Units of each order line that successful refunds already paid back.
+async fn refunded_quantities(conn: &mut PgConnection, order_id: Uuid) -> Result<HashMap<Uuid, i32>, ServiceError> {
+    let rows: Vec<(Uuid, i64)> = sqlx::query_as(
+        "SELECT ri.order_item_id, SUM(ri.quantity)::BIGINT
+        FROM refund_items ri JOIN refunds r ON r.id = ri.refund_id
+        WHERE r.order_id = $1 AND r.status = $2
+        GROUP BY ri.order_item_id"
+    )
+    .bind(order_id)
+    .bind(RefundStatus::Succeeded)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows.into_iter().map(|(id, quantity)| (id, quantity as i32)).collect())
+}
+
+/// Derives the payment's refunded amount and status from its successful
+/// refunds.
+pub(crate) async fn reconcile_payment(conn: &mut PgConnection, payment_id: Uuid) -> Result<Payment, ServiceError> {
+    let payment = sqlx::query_as::<_, Payment>(
+        "UPDATE payments SET
+            refunded_amount = refunded.total,
+            status = CASE
+                WHEN refunded.total >= payments.amount THEN $2
+                WHEN refunded.total > 0 THEN $3
+                ELSE $4
+            END,
+            updated_at = $5
+        FROM (
+            SELECT COALESCE(SUM(amount), 0) AS total FROM refunds WHERE payment_id = $1 AND status = $6
+        ) refunded
+        WHERE payments.id = $1 AND payments.status IN ($2, $3, $4)
+        RETURNING payments.*"
+    )
+    .bind(payment_id)
+    .bind(PaymentStatus::Refunded)
+    .bind(PaymentStatus::PartiallyRefunded)
+    .bind(PaymentStatus::Paid)
+    .bind(Utc::now())
+    .bind(RefundStatus::Succeeded)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(payment)
+}
+
+/// Puts refunded units back into stock: an order that has not shipped
+/// gives up its reservation, and goods that were shipped come back as a
+/// return into the warehouse most of them left from. Cancelled orders
+/// already gave their stock back.
+async fn restock(conn: &mut PgConnection, order: &Order, line: &RefundLine<'_>, refund_id: Uuid, actor: Uuid) -> Result<(), ServiceError> {
+    match order.status {
+        OrderStatus::Paid | OrderStatus::Processing => {
+            release_reserved_units(conn, order.id, line.item.variant_id, line.quantity).await
+        }
+        OrderStatus::Shipped | OrderStatus::Delivered => {
+            let shipped_from: Option<Uuid> = sqlx::query_scalar(
+                "SELECT warehouse_id FROM stock_movements
+                WHERE reference_id = $1 AND variant_id = $2 AND movement_type = $3
+                ORDER BY quantity, created_at DESC LIMIT 1"
+            )
+            .bind(order.id)
+            .bind(line.item.variant_id)
+            .bind(MovementType::Sale)
+            .fetch_optional(&mut *conn)
+            .await?;
+            let warehouse_id = match shipped_from {
+                Some(warehouse_id) => warehouse_id,
+                None => default_warehouse_id(conn).await?,
+            };
+
+            record_movement(conn, NewMovement {
+                variant_id: line.item.variant_id,
+                warehouse_id,
+                movement_type: MovementType::Return,
+                quantity: line.quantity,
+                reference_id: Some(order.id),
+                note: Some(format!("Refund {}", refund_id)),
+                created_by: Some(actor),
+            })
+            .await?;
+            Ok(())
+        }
+        OrderStatus::Pending | OrderStatus::Cancelled | OrderStatus::Refunded => Ok(()),
+    }
+}
+
+/// A payment's refunds, newest first, failed attempts included.
+pub async fn get_payment_refunds(pool: &PgPool, payment_id: Uuid) -> Result<Vec<RefundWithItems>, ServiceError> {
+    let refunds = sqlx::query_as::<_, Refund>("SELECT * FROM refunds WHERE payment_id = $1 ORDER BY created_at DESC, id")
+        .bind(payment_id)
+        .fetch_all(pool)
+        .await?;
+    let ids: Vec<Uuid> = refunds.iter().map(|refund| refund.id).collect();
+    let items = sqlx::query_as::<_, RefundItem>("SELECT * FROM refund_items WHERE refund_id = ANY($1) ORDER BY id")
+        .bind(&ids)
+        .fetch_all(pool)
+        .await?;
+
+    let mut items_by_refund: HashMap<Uuid, Vec<RefundItem>> = HashMap::new();
+    for item in items {
+        items_by_refund.entry(item.refund_id).or_default().push(item);
+    }
+
+    Ok(refunds
+        .into_iter()
+        .map(|refund| RefundWithItems { items: items_by_refund.remove(&refund.id).unwrap_or_default(), refund })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_amount_includes_the_tax_share() {
+        let now = Utc::now();
+        let order = Order {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            status: OrderStatus::Paid,
+            subtotal: Money::rials(3_000_000),
+            tax_amount: Money::rials(270_000),
+            shipping_amount: Money::rials(500_000),
+            total_amount: Money::rials(3_770_000),
+            currency: Currency::Irr,
+            exchange_rate: 1,
+            shipping_address: None,
+            notes: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let item = OrderItem {
+            id: Uuid::new_v4(),
+            order_id: order.id,
+            product_id: Uuid::new_v4(),
+            variant_id: Uuid::new_v4(),
+            product_name: "انگشتر".to_string(),
+            sku: "RNG-925-54".to_string(),
+            quantity: 3,
+            unit_price: Money::rials(1_000_000),
+            line_total: Money::rials(3_000_000),
+            created_at: now,
+        };
+
+        assert_eq!(line_amount(&order, &item, 1), Ok(Money::rials(1_090_000)));
+        assert_eq!(line_amount(&order, &item, 3), Ok(Money::rials(3_270_000)));
+    }
+}
@@ -0,0 +1,100 @@
+use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use models::payment::{Payment, PaymentEvent, PaymentEventType};
+use crate::error::ServiceError;
+use super::{gateway::PaymentGateway, get_payment_by_id, settle_payment_in};
+
+/// Unix time the webhook was sent at, in seconds.
+pub const WEBHOOK_TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+/// Hex HMAC-SHA256 of `{timestamp}.{body}` under the webhook secret.
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+/// Older (or newer) deliveries are rejected, so a captured request cannot
+/// be replayed later.
+const WEBHOOK_TOLERANCE_SECONDS: i64 = 5 * 60;
+
+fn webhook_mac(secret: &str, timestamp: &str, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// The signature a webhook sent at `timestamp` carries.
+pub fn sign_webhook(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    hex::encode(webhook_mac(secret, &timestamp.to_string(), body).finalize().into_bytes())
+}
+
+pub fn verify_webhook_signature(secret: &str, timestamp: &str, signature: &str, body: &[u8], now: DateTime<Utc>) -> Result<(), ServiceError> {
+    let invalid = || ServiceError::Unauthorized("Invalid webhook signature".to_string());
+
+    let sent_at: i64 = timestamp.trim().parse().map_err(|_| invalid())?;
+    if (now.timestamp() - sent_at).abs() > WEBHOOK_TOLERANCE_SECONDS {
+        return Err(ServiceError::Unauthorized("The webhook timestamp is out of range".to_string()));
+    }
+    let signature = hex::decode(signature.trim()).map_err(|_| invalid())?;
+
+    webhook_mac(secret, timestamp.trim(), body)
+        .verify_slice(&signature)
+        .map_err(|_| invalid())
+}
+
+/// Applies a verified gateway event to its payment. Each event is applied
+/// once: redeliveries of an event that was handled return the payment as
+/// it is, and concurrent deliveries wait for the first one to finish.
+pub async fn handle_payment_event(pool: &PgPool, gateway: &dyn PaymentGateway, event: PaymentEvent) -> Result<Payment, ServiceError> {
+    let payment = sqlx::query_as::<_, Payment>(
+        "SELECT * FROM payments WHERE gateway = $1 AND transaction_id = $2"
+    )
+    .bind(gateway.name())
+    .bind(&event.authority)
+    .fetch_one(pool)
+    .await?;
+
+    let mut tx = pool.begin().await?;
+    let recorded = sqlx::query(
+        "INSERT INTO payment_events (gateway, event_id, event_type, payment_id, received_at) VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (gateway, event_id) DO NOTHING"
+    )
+    .bind(gateway.name())
+    .bind(&event.id)
+    .bind(event.event_type)
+    .bind(payment.id)
+    .bind(Utc::now())
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if recorded == 0 {
+        log::info!("Ignoring redelivered payment event `{}`", event.id);
+        return get_payment_by_id(pool, payment.id).await;
+    }
+
+    let completed = event.event_type == PaymentEventType::Paid;
+    let payment = settle_payment_in(&mut tx, gateway, payment.id, payment.order_id, completed).await?;
+    tx.commit().await?;
+
+    Ok(payment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verifies_webhook_signatures() {
+        let now = Utc::now();
+        let body = br#"{"id":"evt_1","type":"payment.paid","authority":"A1"}"#;
+        let timestamp = now.timestamp().to_string();
+        let signature = sign_webhook("secret", now.timestamp(), body);
+
+        assert!(verify_webhook_signature("secret", &timestamp, &signature, body, now).is_ok());
+        assert!(verify_webhook_signature("other", &timestamp, &signature, body, now).is_err());
+        assert!(verify_webhook_signature("secret", &timestamp, &signature, br#"{"id":"evt_2"}"#, now).is_err());
+        assert!(verify_webhook_signature("secret", &timestamp, "not-hex", body, now).is_err());
+
+        let later = now + chrono::Duration::seconds(WEBHOOK_TOLERANCE_SECONDS + 1);
+        assert!(verify_webhook_signature("secret", &timestamp, &signature, body, later).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::Duration;
+use super::gateway::{GatewayError, GatewayPaymentStatus, PaymentGateway, PaymentRequest, PaymentSession, RefundReceipt, VerifiedPayment};
+
+/// Zarinpal's success code; verify answers `101` for a payment that was
+/// already verified.
+const CODE_SUCCESS: i64 = 100;
+const CODE_ALREADY_VERIFIED: i64 = 101;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Talks to a Zarinpal v4 compatible IPG:
+/// `POST {api_url}/pg/v4/payment/{request,verify,refund,inquiry}.json`, with
+/// the payer sent to `{api_url}/pg/StartPay/{authority}`.
+pub struct ZarinpalGateway {
+    client: reqwest::Client,
+    api_url: String,
+    merchant_id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct RequestBody<'a> {
+    merchant_id: &'a str,
+    amount: i64,
+    currency: &'static str,
+    description: &'a str,
+    callback_url: &'a str,
+    metadata: RequestMetadata<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct RequestMetadata<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mobile: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<&'a str>,
+    order_id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AuthorityBody<'a> {
+    merchant_id: &'a str,
+    authority: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    amount: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestData {
+    code: i64,
+    authority: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyData {
+    code: i64,
+    ref_id: i64,
+    #[serde(default)]
+    card_pan: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RefundData {
+    code: i64,
+    refund_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct InquiryData {
+    code: i64,
+    status: String,
+}
+
+/// Failed calls answer `{"data": [], "errors": {"code": -9, "message": "..."}}`.
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    code: i64,
+    message: String,
+}
+
+impl ZarinpalGateway {
+    pub fn new(api_url: &str, merchant_id: &str) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            merchant_id: merchant_id.to_string(),
+        }
+    }
+
+    async fn call<B: Serialize, T: DeserializeOwned>(&self, operation: &str, body: &B) -> Result<T, GatewayError> {
+        let response: serde_json::Value = self
+            .client
+            .post(format!("{}/pg/v4/payment/{}.json", self.api_url, operation))
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| GatewayError::Unavailable(format!("Zarinpal {} request failed: {}", operation, e)))?
+            .json()
+            .await
+            .map_err(|e| GatewayError::Unavailable(format!("Invalid Zarinpal {} response: {}", operation, e)))?;
+
+        if let Ok(error) = serde_json::from_value::<ErrorBody>(response["errors"].clone()) {
+            return Err(GatewayError::Rejected(format!("Zarinpal rejected the {} ({}): {}", operation, error.code, error.message)));
+        }
+        serde_json::from_value(response["data"].clone())
+            .map_err(|e| GatewayError::Unavailable(format!("Invalid Zarinpal {} response: {}", operation, e)))
+    }
+}
+
+fn check_code(operation: &str, code: i64, accepted: &[i64]) -> Result<(), GatewayError> {
+    if accepted.contains(&code) {
+        Ok(())
+    } else {
+        Err(GatewayError::Rejected(format!("Zarinpal rejected the {} ({})", operation, code)))
+    }
+}
+
+#[async_trait]
+impl PaymentGateway for ZarinpalGateway {
+    fn name(&self) -> &'static str {
+        "zarinpal"
+    }
+
+    async fn initiate(&self, request: &PaymentRequest) -> Result<PaymentSession, String> {
+        let data: RequestData = self
+            .call("request", &RequestBody {
+                merchant_id: &self.merchant_id,
+                amount: request.amount,
+                currency: "IRR",
+                description: &request.description,
+                callback_url: &request.callback_url,
+                metadata: RequestMetadata {
+                    mobile: request.mobile.as_deref(),
+                    email: request.email.as_deref(),
+                    order_id: request.order_id.to_string(),
+                },
+            })
+            .await?;
+        check_code("request", data.code, &[CODE_SUCCESS])?;
+
+        Ok(PaymentSession {
+            redirect_url: format!("{}/pg/StartPay/{}", self.api_url, data.authority),
+            authority: data.authority,
+        })
+    }
+
+    async fn verify(&self, authority: &str, amount: i64) -> Result<VerifiedPayment, GatewayError> {
+        let data: VerifyData = self
+            .call("verify", &AuthorityBody { merchant_id: &self.merchant_id, authority, amount: Some(amount) })
+            .await?;
+        check_code("verify", data.code, &[CODE_SUCCESS, CODE_ALREADY_VERIFIED])?;
+
+        Ok(VerifiedPayment { reference_id: data.ref_id.to_string(), card_pan: data.card_pan })
+    }
+
+    async fn refund(&self, authority: &str, amount: i64) -> Result<RefundReceipt, GatewayError> {
+        let data: RefundData = self
+            .call("refund", &AuthorityBody { merchant_id: &self.merchant_id, authority, amount: Some(amount) })
+            .await?;
+        check_code("refund", data.code, &[CODE_SUCCESS])?;
+
+        Ok(RefundReceipt { refund_id: data.refund_id })
+    }
+
+    async fn query(&self, authority: &str) -> Result<GatewayPaymentStatus, String> {
+        let data: InquiryData = self
+            .call("inquiry", &AuthorityBody { merchant_id: &self.merchant_id, authority, amount: None })
+            .await?;
+        check_code("inquiry", data.code, &[CODE_SUCCESS])?;
+
+        match data.status.as_str() {
+            "IN_BANK" => Ok(GatewayPaymentStatus::Initiated),
+            "PAID" => Ok(GatewayPaymentStatus::Paid),
+            "VERIFIED" => Ok(GatewayPaymentStatus::Verified),
+            "FAILED" => Ok(GatewayPaymentStatus::Failed),
+            "REVERSED" => Ok(GatewayPaymentStatus::Refunded),
+            other => Err(format!("Unknown Zarinpal payment status `{}`", other)),
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use chrono::{Duration, Utc};
+use std::collections::HashMap;
+use models::{
+    money::Money,
+    pricing::{CreatePriceLock, Metal, MetalRate, PriceChange, PriceChangeReason, PriceLock, PriceLockItem, PriceLockWithItems, PricingRule, SetPricingRule, UpdateMetalRate},
+    product::{Product, ProductVariant},
+    weight::Weight,
+};
+use crate::error::ServiceError;
+use super::exchange_rate::to_rials;
+use super::product_variant::refresh_product;
+
+pub async fn get_metal_rates(pool: &PgPool) -> Result<Vec<MetalRate>, ServiceError> {
+    let rates = sqlx::query_as::<_, MetalRate>("SELECT * FROM metal_rates ORDER BY metal")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rates)
+}
+
+/// Sets the rate of a metal and reprices every product priced by it.
+/// Orders already placed, and checkouts holding a price lock, keep their
+/// prices.
+pub async fn set_metal_rate(pool: &PgPool, metal: Metal, actor: Uuid, update: UpdateMetalRate) -> Result<MetalRate, ServiceError> {
+    if !update.rials_per_gram.is_positive() {
+        return Err(ServiceError::BadRequest("A metal rate must be positive".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+    let rials_per_gram = to_rials(&mut tx, update.rials_per_gram).await?;
+    let rate = sqlx::query_as::<_, MetalRate>(
+        "INSERT INTO metal_rates (metal, rials_per_gram, updated_by, updated_at) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (metal) DO UPDATE
+        SET rials_per_gram = EXCLUDED.rials_per_gram, updated_by = EXCLUDED.updated_by, updated_at = EXCLUDED.updated_at
+        RETURNING *"
+    )
+    .bind(metal)
+    .bind(rials_per_gram)
+    .bind(actor)
+    .bind(Utc::now())
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // Products are locked before their variants, in id order, as orders
+    // lock them.
+    let products = sqlx::query_as::<_, Product>(
+        "SELECT * FROM products WHERE id IN (SELECT product_id FROM product_pricing_rules WHERE metal = $1) ORDER BY id FOR UPDATE"
+    )
+    .bind(metal)
+    .fetch_all(&mut *tx)
+    .await?;
+    for product in &products {
+        let rule = sqlx::query_as::<_, PricingRule>("SELECT * FROM product_pricing_rules WHERE product_id = $1")
+            .bind(product.id)
+            .fetch_one(&mut *tx)
+            .await?;
+        reprice_variants(&mut tx, &rule, rate.rials_per_gram, PriceChangeReason::MetalRate, actor).await?;
+        refresh_product(&mut tx, product.id).await?;
+    }
+    tx.commit().await?;
+
+    Ok(rate)
+}
+
+pub async fn get_pricing_rule(pool: &PgPool, product_id: Uuid) -> Result<PricingRule, ServiceError> {
+    let rule = sqlx::query_as::<_, PricingRule>("SELECT * FROM product_pricing_rules WHERE product_id = $1")
+        .bind(product_id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(rule)
+}
+
+/// Prices the product's variants by the metal from now on and reprices
+/// them at the current rate. Every variant needs a weight.
+pub async fn set_pricing_rule(pool: &PgPool, product_id: Uuid, actor: Uuid, new_rule: SetPricingRule) -> Result<PricingRule, ServiceError> {
+    if new_rule.making_charge.is_negative() {
+        return Err(ServiceError::BadRequest("The making charge cannot be negative".to_string()));
+    }
+    if !(0..=10_000).contains(&new_rule.margin_basis_points) {
+        return Err(ServiceError::BadRequest("The margin is given in basis points, from 0 to 10000".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT id FROM products WHERE id = $1 FOR UPDATE")
+        .bind(product_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let making_charge = to_rials(&mut tx, new_rule.making_charge).await?;
+    let rate = metal_rate(&mut tx, new_rule.metal).await?;
+
+    let rule = sqlx::query_as::<_, PricingRule>(
+        "INSERT INTO product_pricing_rules (product_id, metal, making_charge, margin_basis_points, updated_by, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (product_id) DO UPDATE
+        SET metal = EXCLUDED.metal, making_charge = EXCLUDED.making_charge, margin_basis_points = EXCLUDED.margin_basis_points,
+            updated_by = EXCLUDED.updated_by, updated_at = EXCLUDED.updated_at
+        RETURNING *"
+    )
+    .bind(product_id)
+    .bind(new_rule.metal)
+    .bind(making_charge)
+    .bind(new_rule.margin_basis_points)
+    .bind(actor)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .fetch_one(&mut *tx)
+    .await?;
+    reprice_variants(&mut tx, &rule, rate, PriceChangeReason::PricingRule, actor).await?;
+    refresh_product(&mut tx, product_id).await?;
+    tx.commit().await?;
+
+    Ok(rule)
+}
+
+/// The variants keep their current prices, which can then be set by hand.
+pub async fn delete_pricing_rule(pool: &PgPool, product_id: Uuid) -> Result<(), ServiceError> {
+    let result = sqlx::query("DELETE FROM product_pricing_rules WHERE product_id = $1")
+        .bind(product_id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ServiceError::NotFound("Pricing rule not found".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Price changes of the product's variants, latest first.
+pub async fn get_price_history(pool: &PgPool, product_id: Uuid) -> Result<Vec<PriceChange>, ServiceError> {
+    let changes = sqlx::query_as::<_, PriceChange>(
+        "SELECT * FROM price_history WHERE product_id = $1 ORDER BY created_at DESC"
+    )
+    .bind(product_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(changes)
+}
+
+/// Quotes the current prices of the variants and holds them for `user_id`
+/// for `ttl_seconds`.
+pub async fn create_price_lock(pool: &PgPool, user_id: Uuid, ttl_seconds: u64, new_lock: CreatePriceLock) -> Result<PriceLockWithItems, ServiceError> {
+    if new_lock.variant_ids.is_empty() {
+        return Err(ServiceError::BadRequest("A price lock needs at least one variant".to_string()));
+    }
+    let mut variant_ids = new_lock.variant_ids;
+    variant_ids.sort_unstable();
+    variant_ids.dedup();
+
+    let mut tx = pool.begin().await?;
+    let now = Utc::now();
+    let lock = sqlx::query_as::<_, PriceLock>(
+        "INSERT INTO price_locks (user_id, expires_at, created_at) VALUES ($1, $2, $3) RETURNING *"
+    )
+    .bind(user_id)
+    .bind(now + Duration::seconds(ttl_seconds as i64))
+    .bind(now)
+    .fetch_one(&mut *tx)
+    .await?;
+    let items = sqlx::query_as::<_, PriceLockItem>(
+        "INSERT INTO price_lock_items (price_lock_id, variant_id, unit_price)
+        SELECT $1, id, price FROM product_variants WHERE id = ANY($2)
+        RETURNING *"
+    )
+    .bind(lock.id)
+    .bind(&variant_ids)
+    .fetch_all(&mut *tx)
+    .await?;
+    if let Some(missing) = variant_ids.iter().find(|id| !items.iter().any(|item| item.variant_id == **id)) {
+        return Err(ServiceError::BadRequest(format!("Product variant {} does not exist", missing)));
+    }
+    tx.commit().await?;
+
+    Ok(PriceLockWithItems { lock, items })
+}
+
+pub async fn get_price_lock(pool: &PgPool, lock_id: Uuid) -> Result<PriceLockWithItems, ServiceError> {
+    let lock = sqlx::query_as::<_, PriceLock>("SELECT * FROM price_locks WHERE id = $1")
+        .bind(lock_id)
+        .fetch_one(pool)
+        .await?;
+    let items = sqlx::query_as::<_, PriceLockItem>(
+        "SELECT * FROM price_lock_items WHERE price_lock_id = $1 ORDER BY variant_id"
+    )
+    .bind(lock_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(PriceLockWithItems { lock, items })
+}
+
+/// The prices a lock holds for an order of `user_id`'s. The lock stays
+/// locked until the order is placed and it is marked as used with
+/// [`use_price_lock`].
+pub(crate) async fn locked_prices(conn: &mut PgConnection, lock_id: Uuid, user_id: Uuid) -> Result<HashMap<Uuid, Money>, ServiceError> {
+    let lock = sqlx::query_as::<_, PriceLock>("SELECT * FROM price_locks WHERE id = $1 FOR UPDATE")
+        .bind(lock_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .filter(|lock| lock.user_id == user_id)
+        .ok_or_else(|| ServiceError::BadRequest("Price lock not found".to_string()))?;
+    if lock.order_id.is_some() {
+        return Err(ServiceError::BadRequest("The price lock was already used for an order".to_string()));
+    }
+    if lock.expires_at <= Utc::now() {
+        return Err(ServiceError::BadRequest("The price lock has expired; prices may have changed".to_string()));
+    }
+
+    let prices = sqlx::query_as::<_, PriceLockItem>("SELECT * FROM price_lock_items WHERE price_lock_id = $1")
+        .bind(lock_id)
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|item| (item.variant_id, item.unit_price))
+        .collect();
+
+    Ok(prices)
+}
+
+pub(crate) async fn use_price_lock(conn: &mut PgConnection, lock_id: Uuid, order_id: Uuid) -> Result<(), ServiceError> {
+    sqlx::query("UPDATE price_locks SET order_id = $1 WHERE id = $2")
+        .bind(order_id)
+        .bind(lock_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn pricing_rule(conn: &mut PgConnection, product_id: Uuid) -> Result<Option<PricingRule>, ServiceError> {
+    let rule = sqlx::query_as::<_, PricingRule>("SELECT * FROM product_pricing_rules WHERE product_id = $1")
+        .bind(product_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    Ok(rule)
+}
+
+/// Rials a gram of the pure metal costs now.
+pub(crate) async fn metal_rate(conn: &mut PgConnection, metal: Metal) -> Result<Money, ServiceError> {
+    sqlx::query_scalar("SELECT rials_per_gram FROM metal_rates WHERE metal = $1")
+        .bind(metal)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| ServiceError::BadRequest(format!("There is no {} rate yet", metal)))
+}
+
+/// The price of a variant under `rule` at the current rate, with the rate
+/// it was computed at.
+pub(crate) async fn rule_price(conn: &mut PgConnection, rule: &PricingRule, sku: &str, weight_grams: Option<Weight>, purity: Option<i32>) -> Result<(Money, Money), ServiceError> {
+    let rate = metal_rate(conn, rule.metal).await?;
+    Ok((price_at(rule, sku, weight_grams, purity, rate)?, rate))
+}
+
+pub(crate) async fn record_price_change(conn: &mut PgConnection, variant: &ProductVariant, old_price: Money, reason: PriceChangeReason, metal_rate: Option<Money>, actor: Option<Uuid>) -> Result<(), ServiceError> {
+    sqlx::query(
+        "INSERT INTO price_history (product_id, variant_id, old_price, new_price, reason, metal_rate, changed_by, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+    )
+    .bind(variant.product_id)
+    .bind(variant.id)
+    .bind(old_price)
+    .bind(variant.price)
+    .bind(reason)
+    .bind(metal_rate)
+    .bind(actor)
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Reprices the variants of a product locked by the caller. The product's
+/// summary is left to [`refresh_product`].
+async fn reprice_variants(conn: &mut PgConnection, rule: &PricingRule, rate: Money, reason: PriceChangeReason, actor: Uuid) -> Result<(), ServiceError> {
+    let variants = sqlx::query_as::<_, ProductVariant>(
+        "SELECT * FROM product_variants WHERE product_id = $1 ORDER BY id FOR UPDATE"
+    )
+    .bind(rule.product_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for variant in variants {
+        let price = price_at(rule, &variant.sku, variant.weight_grams, variant.purity, rate)?;
+        if price == variant.price {
+            continue;
+        }
+        let repriced = sqlx::query_as::<_, ProductVariant>(
+            "UPDATE product_variants SET price = $1, updated_at = $2 WHERE id = $3 RETURNING *"
+        )
+        .bind(price)
+        .bind(Utc::now())
+        .bind(variant.id)
+        .fetch_one(&mut *conn)
+        .await?;
+        record_price_change(conn, &repriced, variant.price, reason, Some(rate), Some(actor)).await?;
+    }
+
+    Ok(())
+}
+
+fn price_at(rule: &PricingRule, sku: &str, weight_grams: Option<Weight>, purity: Option<i32>, rate: Money) -> Result<Money, ServiceError> {
+    let weight = weight_grams
+        .ok_or_else(|| ServiceError::BadRequest(format!("{} needs a weight to be priced by the {} rate", sku, rule.metal)))?;
+    Ok(rule.price(weight, purity, rate)?)
+}
@@ -0,0 +1,99 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+use chrono::Utc;
+use models::product::{Product, ProductVariant, CreateProduct, UpdateProduct, UpdateProductVariant};
+use crate::error::ServiceError;
+use super::category::category_exists;
+use super::product_variant::{insert_variant, refresh_product, update_variant};
+
+/// Creates the product with its first variant, whose initial stock is
+/// booked as a receipt into the default warehouse. A price in another
+/// currency is stored in rials at the current rate.
+pub async fn create_product(pool: &PgPool, actor: Uuid, new_product: CreateProduct) -> Result<Product, ServiceError> {
+    let mut tx = pool.begin().await?;
+    if !category_exists(&mut tx, new_product.category_id).await? {
+        return Err(ServiceError::BadRequest("The product's category does not exist".to_string()));
+    }
+    // Price and stock are summed up from the variant once it exists.
+    let product = sqlx::query_as::<_, Product>(
+        "INSERT INTO products (name, description, price, stock, category_id, vendor_id, created_at, updated_at) VALUES ($1, $2, 0, 0, $3, $4, $5, $6) RETURNING *"
+    )
+    .bind(new_product.name)
+    .bind(new_product.description)
+    .bind(new_product.category_id)
+    .bind(new_product.vendor_id)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .fetch_one(&mut *tx)
+    .await?;
+
+    insert_variant(&mut tx, &product, actor, new_product.variant).await?;
+    let product = refresh_product(&mut tx, product.id).await?;
+    tx.commit().await?;
+
+    Ok(product)
+}
+
+pub async fn get_product_by_id(pool: &PgPool, product_id: Uuid) -> Result<Product, ServiceError> {
+    let product = sqlx::query_as::<_, Product>(
+        "SELECT * FROM products WHERE id = $1"
+    )
+    .bind(product_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(product)
+}
+
+/// A price or stock figure is applied to the product's only variant;
+/// products with several variants have them changed one by one.
+pub async fn update_product(pool: &PgPool, product_id: Uuid, actor: Uuid, updated_product: UpdateProduct) -> Result<Product, ServiceError> {
+    let existing_product = get_product_by_id(pool, product_id).await?;
+
+    let mut tx = pool.begin().await?;
+    if let Some(category_id) = updated_product.category_id {
+        if !category_exists(&mut tx, category_id).await? {
+            return Err(ServiceError::BadRequest("The product's category does not exist".to_string()));
+        }
+    }
+
+    let product = sqlx::query_as::<_, Product>(
+        "UPDATE products SET name = $1, description = $2, category_id = $3, updated_at = $4 WHERE id = $5 RETURNING *"
+    )
+    .bind(updated_product.name.unwrap_or(existing_product.name))
+    .bind(updated_product.description.or(existing_product.description))
+    .bind(updated_product.category_id.unwrap_or(existing_product.category_id))
+    .bind(Utc::now())
+    .bind(product_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if updated_product.price.is_some() || updated_product.stock.is_some() {
+        let variants = sqlx::query_as::<_, ProductVariant>("SELECT * FROM product_variants WHERE product_id = $1 FOR UPDATE")
+            .bind(product_id)
+            .fetch_all(&mut *tx)
+            .await?;
+        let [variant] = <[ProductVariant; 1]>::try_from(variants).map_err(|_| {
+            ServiceError::BadRequest("The product has several variants; set the price and stock of each one".to_string())
+        })?;
+        update_variant(&mut tx, &product, variant, actor, UpdateProductVariant {
+            price: updated_product.price,
+            stock: updated_product.stock,
+            ..Default::default()
+        })
+        .await?;
+    }
+    let product = refresh_product(&mut tx, product_id).await?;
+    tx.commit().await?;
+
+    Ok(product)
+}
+
+pub async fn delete_product(pool: &PgPool, product_id: Uuid) -> Result<(), ServiceError> {
+    sqlx::query("DELETE FROM products WHERE id = $1")
+        .bind(product_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,295 @@
+use sqlx::{postgres::PgArguments, query::QueryAs, PgConnection, PgPool, Postgres};
+use uuid::Uuid;
+use models::{
+    category::Category,
+    money::Money,
+    pagination::{PaginatedResponse, Pagination},
+    persian::normalize_search_text,
+    product::{CategoryFacet, PriceRangeFacet, Product, ProductFacets, ProductSearch, ProductSearchResult, StockFacet},
+};
+use crate::error::ServiceError;
+use super::category::category_path;
+
+/// Where the price facet's ranges start and end.
+const PRICE_FACET_BOUNDS: [Money; 4] = [
+    Money::rials(10_000_000),
+    Money::rials(50_000_000),
+    Money::rials(200_000_000),
+    Money::rials(1_000_000_000),
+];
+
+/// Products matching a [`SearchFilter`], which binds `$1` to `$6`.
+const SEARCH_FILTER: &str = "($1::TSQUERY IS NULL OR search_vector @@ $1::TSQUERY)
+    AND ($2::TEXT IS NULL OR category_id IN (
+        WITH RECURSIVE subtree AS (
+            SELECT id FROM categories WHERE slug = $2
+            UNION ALL
+            SELECT categories.id FROM categories JOIN subtree ON categories.parent_id = subtree.id
+        )
+        SELECT id FROM subtree
+    ))
+    AND ($3::UUID IS NULL OR vendor_id = $3)
+    AND ($4::NUMERIC IS NULL OR price >= $4)
+    AND ($5::NUMERIC IS NULL OR price <= $5)
+    AND ($6::BOOL IS NULL OR (stock > 0) = $6)";
+
+#[derive(Debug, Clone, Default)]
+struct SearchFilter {
+    query: Option<String>,
+    category: Option<String>,
+    vendor_id: Option<Uuid>,
+    min_price: Option<Money>,
+    max_price: Option<Money>,
+    in_stock: Option<bool>,
+}
+
+impl SearchFilter {
+    fn bind<'q, O>(&self, query: QueryAs<'q, Postgres, O, PgArguments>) -> QueryAs<'q, Postgres, O, PgArguments> {
+        query
+            .bind(self.query.clone())
+            .bind(self.category.clone())
+            .bind(self.vendor_id)
+            .bind(self.min_price)
+            .bind(self.max_price)
+            .bind(self.in_stock)
+    }
+}
+
+/// The normalized words of `text`. They are made of letters and digits
+/// only, so they can be quoted into `tsvector` and `tsquery` literals as
+/// they are.
+fn search_words(text: &str) -> Vec<String> {
+    normalize_search_text(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// A prefix query for every word of `search`, e.g. `'گردن':* & 'بند':*`, so
+/// results show up while the shopper is still typing. `None` when nothing
+/// searchable is left.
+fn search_query(search: &str) -> Option<String> {
+    let words: Vec<String> = search_words(search)
+        .into_iter()
+        .map(|word| format!("'{}':*", word))
+        .collect();
+
+    (!words.is_empty()).then(|| words.join(" & "))
+}
+
+/// The `tsvector` a product is found by, given its variants' SKUs, its
+/// category and the categories above it. Names and SKUs weigh most in the
+/// ranking, then categories, then descriptions. Postgres' own parser is not used as
+/// it drops Persian words in databases that are not UTF-8. Words written
+/// with a half-space are also indexed joined up, as they are often typed
+/// that way: `گردن‌بند` is found by both `گردن بند` and `گردنبند`.
+fn search_document(product: &Product, skus: &[String], categories: &[Category]) -> String {
+    let category_names = categories.iter().flat_map(|category| [&category.slug, &category.name_fa, &category.name_en]);
+    let fields = std::iter::once((product.name.as_str(), 'A'))
+        .chain(skus.iter().map(|sku| (sku.as_str(), 'A')))
+        .chain(category_names.map(|name| (name.as_str(), 'B')))
+        .chain(std::iter::once((product.description.as_deref().unwrap_or_default(), 'C')));
+
+    fields
+        .flat_map(|(text, weight)| {
+            let joined = text
+                .split_whitespace()
+                .filter(|word| word.contains('\u{200c}'))
+                .map(|word| word.replace('\u{200c}', ""));
+            let words = search_words(text).into_iter().chain(joined.flat_map(|word| search_words(&word)));
+            words.map(move |word| (word, weight))
+        })
+        .enumerate()
+        .map(|(i, (word, weight))| format!("'{}':{}{}", word, (i + 1).min(16383), weight))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Rewrites the search vector of a product after it was created or changed.
+pub(crate) async fn index_product(conn: &mut PgConnection, product: &Product) -> Result<(), ServiceError> {
+    let categories = category_path(conn, product.category_id).await?;
+    let skus: Vec<String> = sqlx::query_scalar("SELECT sku FROM product_variants WHERE product_id = $1 ORDER BY created_at, sku")
+        .bind(product.id)
+        .fetch_all(&mut *conn)
+        .await?;
+    sqlx::query("UPDATE products SET search_vector = $1::TSVECTOR WHERE id = $2")
+        .bind(search_document(product, &skus, &categories))
+        .bind(product.id)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Indexes the products that have no search vector yet, such as those
+/// created before search was added. Returns how many were indexed.
+pub async fn index_unindexed_products(pool: &PgPool) -> Result<usize, ServiceError> {
+    let products = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE search_vector IS NULL")
+        .fetch_all(pool)
+        .await?;
+
+    let mut conn = pool.acquire().await?;
+    for product in &products {
+        index_product(&mut conn, product).await?;
+    }
+
+    Ok(products.len())
+}
+
+/// Lists products matching `search`, best matches first and newest first
+/// otherwise, with the facets of the matches.
+pub async fn get_products(pool: &PgPool, pagination: Pagination, search: ProductSearch) -> Result<ProductSearchResult, ServiceError> {
+    let offset = pagination.offset().map_err(ServiceError::BadRequest)?;
+    if let (Some(min_price), Some(max_price)) = (search.min_price, search.max_price) {
+        if min_price > max_price {
+            return Err(ServiceError::BadRequest("min_price cannot be above max_price".to_string()));
+        }
+    }
+
+    let filter = SearchFilter {
+        query: search.search.as_deref().and_then(search_query),
+        category: search.category.filter(|category| !category.trim().is_empty()),
+        vendor_id: search.vendor_id,
+        min_price: search.min_price,
+        max_price: search.max_price,
+        in_stock: search.in_stock,
+    };
+
+    let (total_items,): (i64,) = filter
+        .bind(sqlx::query_as(&format!("SELECT COUNT(*) FROM products WHERE {}", SEARCH_FILTER)))
+        .fetch_one(pool)
+        .await?;
+
+    let products = filter
+        .bind(sqlx::query_as::<_, Product>(&format!(
+            "SELECT * FROM products WHERE {}
+            ORDER BY ts_rank_cd(search_vector, $1::TSQUERY) DESC NULLS LAST, created_at DESC
+            LIMIT $7 OFFSET $8",
+            SEARCH_FILTER
+        )))
+        .bind(pagination.limit as i64)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+    let facets = product_facets(pool, &filter).await?;
+    let total_pages = (total_items as f64 / pagination.limit as f64).ceil() as u32;
+
+    Ok(ProductSearchResult {
+        page: PaginatedResponse {
+            items: products,
+            total_items: total_items as u32,
+            current_page: pagination.page,
+            total_pages,
+            limit: pagination.limit,
+        },
+        facets,
+    })
+}
+
+async fn product_facets(pool: &PgPool, filter: &SearchFilter) -> Result<ProductFacets, ServiceError> {
+    let categories: Vec<(Uuid, String, String, String, i64)> = SearchFilter { category: None, ..filter.clone() }
+        .bind(sqlx::query_as(&format!(
+            "SELECT category.id, category.slug, category.name_fa, category.name_en, COUNT(*)
+            FROM products JOIN categories AS category ON category.id = products.category_id
+            WHERE {} GROUP BY category.id ORDER BY COUNT(*) DESC, category.slug",
+            SEARCH_FILTER
+        )))
+        .fetch_all(pool)
+        .await?;
+
+    // width_bucket() numbers the range below the first bound 0.
+    let buckets: Vec<(i32, i64)> = SearchFilter { min_price: None, max_price: None, ..filter.clone() }
+        .bind(sqlx::query_as(&format!(
+            "SELECT width_bucket(price, $7), COUNT(*) FROM products WHERE {} GROUP BY 1",
+            SEARCH_FILTER
+        )))
+        .bind(PRICE_FACET_BOUNDS.to_vec())
+        .fetch_all(pool)
+        .await?;
+    let price_ranges = (0..=PRICE_FACET_BOUNDS.len())
+        .map(|bucket| PriceRangeFacet {
+            min: bucket.checked_sub(1).map(|i| PRICE_FACET_BOUNDS[i]),
+            max: PRICE_FACET_BOUNDS.get(bucket).copied(),
+            count: buckets.iter().find(|(b, _)| *b as usize == bucket).map_or(0, |(_, count)| *count),
+        })
+        .collect();
+
+    let (in_stock, out_of_stock): (i64, i64) = SearchFilter { in_stock: None, ..filter.clone() }
+        .bind(sqlx::query_as(&format!(
+            "SELECT COUNT(*) FILTER (WHERE stock > 0), COUNT(*) FILTER (WHERE stock <= 0) FROM products WHERE {}",
+            SEARCH_FILTER
+        )))
+        .fetch_one(pool)
+        .await?;
+
+    Ok(ProductFacets {
+        categories: categories
+            .into_iter()
+            .map(|(category_id, slug, name_fa, name_en, count)| CategoryFacet { category_id, slug, name_fa, name_en, count })
+            .collect(),
+        price_ranges,
+        stock: StockFacet { in_stock, out_of_stock },
+    })
+}
+
+/// The slugs of the categories products are listed in, alphabetically.
+/// `categories/tree` has all categories with their names.
+pub async fn get_product_categories(pool: &PgPool) -> Result<Vec<String>, ServiceError> {
+    let categories = sqlx::query_scalar(
+        "SELECT slug FROM categories WHERE EXISTS (SELECT 1 FROM products WHERE category_id = categories.id) ORDER BY slug"
+    )
+        .fetch_all(pool)
+        .await?;
+
+    Ok(categories)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_query_matches_word_prefixes() {
+        assert_eq!(search_query("گردن‌بند نقره"), Some("'گردن':* & 'بند':* & 'نقره':*".to_string()));
+        assert_eq!(search_query("انگشتر ۹۲۵!"), Some("'انگشتر':* & '925':*".to_string()));
+        assert_eq!(search_query("  'ring' & | "), Some("'ring':*".to_string()));
+        assert_eq!(search_query(" !?"), None);
+    }
+
+    #[test]
+    fn test_search_document_weighs_fields() {
+        let now = chrono::Utc::now();
+        let category = |slug: &str, name_fa: &str, name_en: &str| Category {
+            id: Uuid::new_v4(),
+            parent_id: None,
+            slug: slug.to_string(),
+            name_fa: name_fa.to_string(),
+            name_en: name_en.to_string(),
+            position: 0,
+            attributes: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        };
+        let rings = category("silver-rings", "انگشتر", "Rings");
+        let jewelry = category("jewelry", "زیورآلات", "Jewelry");
+        let product = Product {
+            id: Uuid::new_v4(),
+            name: "انگشتر علي".to_string(),
+            description: Some("نقره ۹۲۵ دست‌ساز".to_string()),
+            price: Money::rials(8_000_000),
+            stock: 1,
+            category_id: rings.id,
+            vendor_id: Uuid::new_v4(),
+            created_at: now,
+            updated_at: now,
+        };
+
+        assert_eq!(
+            search_document(&product, &["RNG-925-54".to_string()], &[rings, jewelry]),
+            "'انگشتر':1A 'علی':2A 'rng':3A '925':4A '54':5A 'silver':6B 'rings':7B 'انگشتر':8B 'rings':9B 'jewelry':10B \
+            'زیورالات':11B 'jewelry':12B 'نقره':13C '925':14C 'دست':15C 'ساز':16C 'دستساز':17C"
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use sqlx::{types::Json, PgConnection, PgPool};
+use uuid::Uuid;
+use chrono::Utc;
+use std::collections::BTreeMap;
+use models::{
+    category::{AttributeValue, CategoryAttribute},
+    inventory::MovementType,
+    pricing::PriceChangeReason,
+    product::{is_valid_sku, CreateProductVariant, Product, ProductVariant, UpdateProductVariant},
+    weight::Weight,
+};
+use crate::error::ServiceError;
+use super::category::category_attributes;
+use super::exchange_rate::to_rials;
+use super::inventory::{record_movement, NewMovement};
+use super::pricing::{pricing_rule, record_price_change, rule_price};
+use super::product_search::index_product;
+use super::warehouse::default_warehouse_id;
+
+pub async fn create_product_variant(pool: &PgPool, product_id: Uuid, actor: Uuid, new_variant: CreateProductVariant) -> Result<ProductVariant, ServiceError> {
+    let mut tx = pool.begin().await?;
+    let product = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1 FOR UPDATE")
+        .bind(product_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let variant = insert_variant(&mut tx, &product, actor, new_variant).await?;
+    refresh_product(&mut tx, product_id).await?;
+    tx.commit().await?;
+
+    Ok(variant)
+}
+
+pub async fn get_product_variants(pool: &PgPool, product_id: Uuid) -> Result<Vec<ProductVariant>, ServiceError> {
+    let variants = sqlx::query_as::<_, ProductVariant>(
+        "SELECT * FROM product_variants WHERE product_id = $1 ORDER BY created_at, sku"
+    )
+    .bind(product_id)
+    .fetch_all(pool)
+    .await?;
+    if variants.is_empty() {
+        return Err(ServiceError::NotFound("Product not found".to_string()));
+    }
+
+    Ok(variants)
+}
+
+pub async fn get_product_variant_by_id(pool: &PgPool, product_id: Uuid, variant_id: Uuid) -> Result<ProductVariant, ServiceError> {
+    let variant = sqlx::query_as::<_, ProductVariant>(
+        "SELECT * FROM product_variants WHERE id = $1 AND product_id = $2"
+    )
+    .bind(variant_id)
+    .bind(product_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(variant)
+}
+
+/// The product a variant belongs to.
+pub async fn get_variant_product(pool: &PgPool, variant_id: Uuid) -> Result<Product, ServiceError> {
+    let product = sqlx::query_as::<_, Product>(
+        "SELECT products.* FROM products JOIN product_variants ON product_variants.product_id = products.id WHERE product_variants.id = $1"
+    )
+    .bind(variant_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(product)
+}
+
+pub async fn update_product_variant(pool: &PgPool, product_id: Uuid, variant_id: Uuid, actor: Uuid, updated_variant: UpdateProductVariant) -> Result<ProductVariant, ServiceError> {
+    let mut tx = pool.begin().await?;
+    let (product, current) = lock_variant(&mut tx, variant_id).await?;
+    if product.id != product_id {
+        return Err(ServiceError::NotFound("Record not found".to_string()));
+    }
+    let variant = update_variant(&mut tx, &product, current, actor, updated_variant).await?;
+    refresh_product(&mut tx, product_id).await?;
+    tx.commit().await?;
+
+    Ok(variant)
+}
+
+/// Only variants that never held stock and were never ordered can be
+/// deleted, and a product keeps at least one.
+pub async fn delete_product_variant(pool: &PgPool, product_id: Uuid, variant_id: Uuid) -> Result<(), ServiceError> {
+    let mut tx = pool.begin().await?;
+    let (product, _) = lock_variant(&mut tx, variant_id).await?;
+    if product.id != product_id {
+        return Err(ServiceError::NotFound("Record not found".to_string()));
+    }
+
+    let (variants, used): (i64, bool) = sqlx::query_as(
+        "SELECT (SELECT COUNT(*) FROM product_variants WHERE product_id = $1),
+                EXISTS (SELECT 1 FROM stock_movements WHERE variant_id = $2)
+                    OR EXISTS (SELECT 1 FROM order_items WHERE variant_id = $2)"
+    )
+    .bind(product_id)
+    .bind(variant_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if variants == 1 {
+        return Err(ServiceError::BadRequest("A product needs at least one variant".to_string()));
+    }
+    if used {
+        return Err(ServiceError::BadRequest("Variants that were stocked or ordered cannot be deleted".to_string()));
+    }
+
+    sqlx::query("DELETE FROM product_variants WHERE id = $1")
+        .bind(variant_id)
+        .execute(&mut *tx)
+        .await?;
+    refresh_product(&mut tx, product_id).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Adds a variant to a product locked by the caller. Initial stock is
+/// booked as a receipt into the default warehouse; a price in another
+/// currency is stored in rials at the current rate, and a product with a
+/// pricing rule prices the variant by the rule instead. The product's
+/// summary is left to [`refresh_product`].
+pub(crate) async fn insert_variant(conn: &mut PgConnection, product: &Product, actor: Uuid, new_variant: CreateProductVariant) -> Result<ProductVariant, ServiceError> {
+    if new_variant.price.is_negative() {
+        return Err(ServiceError::BadRequest("Product price cannot be negative".to_string()));
+    }
+    if new_variant.stock < 0 {
+        return Err(ServiceError::BadRequest("Product stock cannot be negative".to_string()));
+    }
+
+    let id = Uuid::new_v4();
+    let sku = match new_variant.sku {
+        Some(sku) => sku.trim().to_string(),
+        None => format!("SKU-{}", id.simple()).to_uppercase(),
+    };
+    let schema = category_attributes(conn, product.category_id).await?;
+    validate_variant(&sku, new_variant.weight_grams, new_variant.purity, &new_variant.attributes, &schema)?;
+    let price = match pricing_rule(conn, product.id).await? {
+        Some(rule) => rule_price(conn, &rule, &sku, new_variant.weight_grams, new_variant.purity).await?.0,
+        None => to_rials(conn, new_variant.price).await?,
+    };
+
+    let mut variant = sqlx::query_as::<_, ProductVariant>(
+        "INSERT INTO product_variants (id, product_id, sku, weight_grams, purity, size, color, attributes, price, stock, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, 0, $10, $11) RETURNING *"
+    )
+    .bind(id)
+    .bind(product.id)
+    .bind(sku)
+    .bind(new_variant.weight_grams)
+    .bind(new_variant.purity)
+    .bind(non_empty(new_variant.size))
+    .bind(non_empty(new_variant.color))
+    .bind(Json(new_variant.attributes))
+    .bind(price)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if new_variant.stock > 0 {
+        let warehouse_id = default_warehouse_id(conn).await?;
+        record_movement(conn, NewMovement {
+            variant_id: variant.id,
+            warehouse_id,
+            movement_type: MovementType::Receipt,
+            quantity: new_variant.stock,
+            reference_id: None,
+            note: Some("Initial stock".to_string()),
+            created_by: Some(actor),
+        })
+        .await?;
+        variant.stock = new_variant.stock;
+    }
+
+    Ok(variant)
+}
+
+/// Applies changes to a variant locked with [`lock_variant`]. A changed
+/// stock figure is booked as an adjustment in the default warehouse, and a
+/// changed price is logged to the price history. Under a pricing rule the
+/// price follows the weight and purity and cannot be set. The product's
+/// summary is left to [`refresh_product`].
+pub(crate) async fn update_variant(conn: &mut PgConnection, product: &Product, current: ProductVariant, actor: Uuid, updated_variant: UpdateProductVariant) -> Result<ProductVariant, ServiceError> {
+    if updated_variant.price.is_some_and(|price| price.is_negative()) {
+        return Err(ServiceError::BadRequest("Product price cannot be negative".to_string()));
+    }
+    if updated_variant.stock.is_some_and(|stock| stock < 0) {
+        return Err(ServiceError::BadRequest("Product stock cannot be negative".to_string()));
+    }
+
+    let sku = updated_variant.sku.map_or(current.sku, |sku| sku.trim().to_string());
+    let weight_grams = updated_variant.weight_grams.unwrap_or(current.weight_grams);
+    let purity = updated_variant.purity.unwrap_or(current.purity);
+    let attributes = updated_variant.attributes.unwrap_or(current.attributes);
+    let schema = category_attributes(conn, product.category_id).await?;
+    validate_variant(&sku, weight_grams, purity, &attributes, &schema)?;
+
+    if let Some(stock) = updated_variant.stock.filter(|stock| *stock != current.stock) {
+        let warehouse_id = default_warehouse_id(conn).await?;
+        record_movement(conn, NewMovement {
+            variant_id: current.id,
+            warehouse_id,
+            movement_type: MovementType::Adjustment,
+            quantity: stock - current.stock,
+            reference_id: None,
+            note: None,
+            created_by: Some(actor),
+        })
+        .await?;
+    }
+
+    let (price, reason, metal_rate) = match (pricing_rule(conn, product.id).await?, updated_variant.price) {
+        (Some(_), Some(_)) => {
+            return Err(ServiceError::BadRequest(format!("The prices of {} follow its pricing rule", product.name)));
+        }
+        (Some(rule), None) => {
+            let (price, rate) = rule_price(conn, &rule, &sku, weight_grams, purity).await?;
+            (price, PriceChangeReason::PricingRule, Some(rate))
+        }
+        (None, Some(price)) => (to_rials(conn, price).await?, PriceChangeReason::Manual, None),
+        (None, None) => (current.price, PriceChangeReason::Manual, None),
+    };
+    let variant = sqlx::query_as::<_, ProductVariant>(
+        "UPDATE product_variants SET sku = $1, weight_grams = $2, purity = $3, size = $4, color = $5, attributes = $6, price = $7, updated_at = $8 WHERE id = $9 RETURNING *"
+    )
+    .bind(sku)
+    .bind(weight_grams)
+    .bind(purity)
+    .bind(non_empty(updated_variant.size.unwrap_or(current.size)))
+    .bind(non_empty(updated_variant.color.unwrap_or(current.color)))
+    .bind(Json(attributes))
+    .bind(price)
+    .bind(Utc::now())
+    .bind(current.id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if variant.price != current.price {
+        record_price_change(conn, &variant, current.price, reason, metal_rate, Some(actor)).await?;
+    }
+
+    Ok(variant)
+}
+
+/// Locks a variant for a stock or price change. Its product is locked
+/// first, as every change to a product's variants also changes the
+/// product's summary; taking the locks in this order keeps concurrent
+/// changes from deadlocking.
+pub(crate) async fn lock_variant(conn: &mut PgConnection, variant_id: Uuid) -> Result<(Product, ProductVariant), ServiceError> {
+    let product = sqlx::query_as::<_, Product>(
+        "SELECT * FROM products WHERE id = (SELECT product_id FROM product_variants WHERE id = $1) FOR UPDATE"
+    )
+    .bind(variant_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| ServiceError::NotFound("Product variant not found".to_string()))?;
+    let variant = sqlx::query_as::<_, ProductVariant>("SELECT * FROM product_variants WHERE id = $1 FOR UPDATE")
+        .bind(variant_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok((product, variant))
+}
+
+/// Recomputes a product's price and stock from its variants and reindexes
+/// it for search, whose document holds the variants' SKUs.
+pub(crate) async fn refresh_product(conn: &mut PgConnection, product_id: Uuid) -> Result<Product, ServiceError> {
+    let product = sqlx::query_as::<_, Product>(
+        "UPDATE products SET
+            price = (SELECT MIN(price) FROM product_variants WHERE product_id = $1),
+            stock = (SELECT COALESCE(SUM(stock), 0) FROM product_variants WHERE product_id = $1),
+            updated_at = $2
+        WHERE id = $1 RETURNING *"
+    )
+    .bind(product_id)
+    .bind(Utc::now())
+    .fetch_one(&mut *conn)
+    .await?;
+    index_product(conn, &product).await?;
+
+    Ok(product)
+}
+
+/// Attribute values are checked against the category's schema as it is
+/// when the variant is saved.
+fn validate_variant(sku: &str, weight_grams: Option<Weight>, purity: Option<i32>, attributes: &BTreeMap<String, AttributeValue>, schema: &[CategoryAttribute]) -> Result<(), ServiceError> {
+    if !is_valid_sku(sku) {
+        return Err(ServiceError::BadRequest("SKUs are up to 64 letters, digits, dots, dashes and underscores".to_string()));
+    }
+    if weight_grams.is_some_and(|weight| !weight.is_positive()) {
+        return Err(ServiceError::BadRequest("Weight must be positive".to_string()));
+    }
+    if purity.is_some_and(|purity| !(1..=1000).contains(&purity)) {
+        return Err(ServiceError::BadRequest("Purity is given in parts per thousand, e.g. 925".to_string()));
+    }
+
+    CategoryAttribute::validate_values(schema, attributes).map_err(ServiceError::BadRequest)
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
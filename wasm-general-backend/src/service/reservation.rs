@@ -0,0 +1,271 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use chrono::{Duration, Utc};
+use models::{inventory::{MovementType, ReservationStatus, StockReservation, StockShortage}, order::OrderStatus, product::{Product, ProductVariant}};
+use crate::error::ServiceError;
+use super::inventory::{record_movement, NewMovement};
+use super::order::{lock_order, transition_order};
+use super::product_variant::lock_variant;
+
+const TIMEOUT_NOTE: &str = "Payment was not received in time";
+
+/// Takes each line's quantity off the variant's and the product's stock and
+/// records the hold. The products and variants must have been read
+/// `FOR UPDATE` in the same transaction; any variant without enough stock
+/// fails the whole order.
+pub(crate) async fn reserve_stock(conn: &mut PgConnection, order_id: Uuid, lines: &[(&Product, &ProductVariant, i32)], ttl_seconds: u64) -> Result<(), ServiceError> {
+    let shortages: Vec<StockShortage> = lines
+        .iter()
+        .filter(|(_, variant, quantity)| variant.stock < *quantity)
+        .map(|(product, variant, quantity)| shortage(product, variant, *quantity, variant.stock))
+        .collect();
+    if !shortages.is_empty() {
+        return Err(ServiceError::InsufficientStock(shortages));
+    }
+
+    let expires_at = Utc::now() + Duration::seconds(ttl_seconds as i64);
+    for (product, variant, quantity) in lines {
+        sqlx::query("UPDATE product_variants SET stock = stock - $1, updated_at = $2 WHERE id = $3")
+            .bind(quantity)
+            .bind(Utc::now())
+            .bind(variant.id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("UPDATE products SET stock = stock - $1, updated_at = $2 WHERE id = $3")
+            .bind(quantity)
+            .bind(Utc::now())
+            .bind(product.id)
+            .execute(&mut *conn)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO stock_reservations (order_id, product_id, variant_id, quantity, status, expires_at, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+        )
+        .bind(order_id)
+        .bind(product.id)
+        .bind(variant.id)
+        .bind(quantity)
+        .bind(ReservationStatus::Active)
+        .bind(expires_at)
+        .bind(Utc::now())
+        .bind(Utc::now())
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Called when the order is paid: the held stock is now sold and no longer
+/// expires.
+pub(crate) async fn commit_reservations(conn: &mut PgConnection, order_id: Uuid) -> Result<(), ServiceError> {
+    sqlx::query("UPDATE stock_reservations SET status = $1, updated_at = $2 WHERE order_id = $3 AND status = $4")
+        .bind(ReservationStatus::Committed)
+        .bind(Utc::now())
+        .bind(order_id)
+        .bind(ReservationStatus::Active)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Puts everything the order still holds back into stock. Cancelled orders
+/// have not shipped, so committed stock is returned as well.
+pub(crate) async fn release_reservations(conn: &mut PgConnection, order_id: Uuid) -> Result<(), ServiceError> {
+    // The products are locked before their variants, like everywhere else.
+    sqlx::query(
+        "SELECT id FROM products WHERE id IN (
+            SELECT product_id FROM stock_reservations WHERE order_id = $1 AND status IN ($2, $3)
+        ) ORDER BY id FOR UPDATE"
+    )
+    .bind(order_id)
+    .bind(ReservationStatus::Active)
+    .bind(ReservationStatus::Committed)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "WITH released AS (
+            UPDATE stock_reservations SET status = $1, updated_at = $2
+            WHERE order_id = $3 AND status IN ($4, $5)
+            RETURNING product_id, variant_id, quantity
+        ), variants AS (
+            UPDATE product_variants SET stock = product_variants.stock + released.quantity, updated_at = $2
+            FROM released WHERE product_variants.id = released.variant_id
+        )
+        UPDATE products SET stock = products.stock + totals.quantity, updated_at = $2
+        FROM (SELECT product_id, SUM(quantity) AS quantity FROM released GROUP BY product_id) totals
+        WHERE products.id = totals.product_id"
+    )
+    .bind(ReservationStatus::Released)
+    .bind(Utc::now())
+    .bind(order_id)
+    .bind(ReservationStatus::Active)
+    .bind(ReservationStatus::Committed)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Gives up to `quantity` units of what an unshipped order holds of a
+/// variant back to stock, e.g. when they were refunded.
+pub(crate) async fn release_reserved_units(conn: &mut PgConnection, order_id: Uuid, variant_id: Uuid, quantity: i32) -> Result<(), ServiceError> {
+    let (product, variant) = lock_variant(conn, variant_id).await?;
+    let Some(reservation) = sqlx::query_as::<_, StockReservation>(
+        "SELECT * FROM stock_reservations WHERE order_id = $1 AND variant_id = $2 AND status IN ($3, $4) FOR UPDATE"
+    )
+    .bind(order_id)
+    .bind(variant.id)
+    .bind(ReservationStatus::Active)
+    .bind(ReservationStatus::Committed)
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(());
+    };
+
+    let released = quantity.min(reservation.quantity);
+    if released == reservation.quantity {
+        sqlx::query("UPDATE stock_reservations SET status = $1, updated_at = $2 WHERE id = $3")
+            .bind(ReservationStatus::Released)
+            .bind(Utc::now())
+            .bind(reservation.id)
+            .execute(&mut *conn)
+            .await?;
+    } else {
+        sqlx::query("UPDATE stock_reservations SET quantity = quantity - $1, updated_at = $2 WHERE id = $3")
+            .bind(released)
+            .bind(Utc::now())
+            .bind(reservation.id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    sqlx::query("UPDATE product_variants SET stock = stock + $1, updated_at = $2 WHERE id = $3")
+        .bind(released)
+        .bind(Utc::now())
+        .bind(variant.id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("UPDATE products SET stock = stock + $1, updated_at = $2 WHERE id = $3")
+        .bind(released)
+        .bind(Utc::now())
+        .bind(product.id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Called when the order ships: books the sold stock out of the warehouses
+/// that hold the most of it.
+pub(crate) async fn fulfill_reservations(conn: &mut PgConnection, order_id: Uuid, actor: Option<Uuid>) -> Result<(), ServiceError> {
+    let reservations = sqlx::query_as::<_, StockReservation>(
+        "SELECT * FROM stock_reservations WHERE order_id = $1 AND status IN ($2, $3) ORDER BY product_id, variant_id FOR UPDATE"
+    )
+    .bind(order_id)
+    .bind(ReservationStatus::Active)
+    .bind(ReservationStatus::Committed)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut shortages = Vec::new();
+    for reservation in &reservations {
+        let (product, variant) = lock_variant(conn, reservation.variant_id).await?;
+        let levels: Vec<(Uuid, i32)> = sqlx::query_as(
+            "SELECT warehouse_id, quantity FROM inventory_items WHERE variant_id = $1 AND quantity > 0 ORDER BY quantity DESC, warehouse_id FOR UPDATE"
+        )
+        .bind(variant.id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let on_hand: i32 = levels.iter().map(|(_, quantity)| quantity).sum();
+        if on_hand < reservation.quantity {
+            shortages.push(shortage(&product, &variant, reservation.quantity, on_hand));
+            continue;
+        }
+
+        let mut remaining = reservation.quantity;
+        for (warehouse_id, quantity) in levels {
+            if remaining == 0 {
+                break;
+            }
+            let taken = remaining.min(quantity);
+            record_movement(conn, NewMovement {
+                variant_id: reservation.variant_id,
+                warehouse_id,
+                movement_type: MovementType::Sale,
+                quantity: -taken,
+                reference_id: Some(order_id),
+                note: None,
+                created_by: actor,
+            })
+            .await?;
+            remaining -= taken;
+        }
+    }
+    if !shortages.is_empty() {
+        return Err(ServiceError::InsufficientStock(shortages));
+    }
+
+    sqlx::query("UPDATE stock_reservations SET status = $1, updated_at = $2 WHERE order_id = $3 AND status IN ($4, $5)")
+        .bind(ReservationStatus::Fulfilled)
+        .bind(Utc::now())
+        .bind(order_id)
+        .bind(ReservationStatus::Active)
+        .bind(ReservationStatus::Committed)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_order_reservations(pool: &PgPool, order_id: Uuid) -> Result<Vec<StockReservation>, ServiceError> {
+    let reservations = sqlx::query_as::<_, StockReservation>(
+        "SELECT * FROM stock_reservations WHERE order_id = $1 ORDER BY created_at, id"
+    )
+    .bind(order_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(reservations)
+}
+
+/// Cancels unpaid orders whose reservations have run out, returning their
+/// stock. Returns how many orders were cancelled.
+pub async fn expire_reservations(pool: &PgPool) -> Result<u64, ServiceError> {
+    let order_ids: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT DISTINCT order_id FROM stock_reservations WHERE status = $1 AND expires_at < $2"
+    )
+    .bind(ReservationStatus::Active)
+    .bind(Utc::now())
+    .fetch_all(pool)
+    .await?;
+
+    let mut expired = 0;
+    for order_id in order_ids {
+        let mut tx = pool.begin().await?;
+        // The order may have been paid since it was selected.
+        if lock_order(&mut tx, order_id).await?.status != OrderStatus::Pending {
+            continue;
+        }
+        transition_order(&mut tx, order_id, OrderStatus::Cancelled, None, Some(TIMEOUT_NOTE.to_string())).await?;
+        tx.commit().await?;
+        expired += 1;
+    }
+
+    Ok(expired)
+}
+
+fn shortage(product: &Product, variant: &ProductVariant, requested: i32, available: i32) -> StockShortage {
+    StockShortage {
+        product_id: product.id,
+        variant_id: variant.id,
+        product_name: product.name.clone(),
+        sku: variant.sku.clone(),
+        requested,
+        available,
+    }
+}
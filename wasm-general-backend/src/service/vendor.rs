@@ -6,13 +6,14 @@ use crate::error::ServiceError;
 
 pub async fn create_vendor(pool: &PgPool, new_vendor: CreateVendor) -> Result<Vendor, ServiceError> {
     let vendor = sqlx::query_as::<_, Vendor>(
-        "INSERT INTO vendors (name, contact_person, email, phone, address, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING *"
+        "INSERT INTO vendors (name, contact_person, email, phone, address, user_id, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING *"
     )
     .bind(new_vendor.name)
     .bind(new_vendor.contact_person)
     .bind(new_vendor.email)
     .bind(new_vendor.phone)
     .bind(new_vendor.address)
+    .bind(new_vendor.user_id)
     .bind(Utc::now())
     .bind(Utc::now())
     .fetch_one(pool)
@@ -22,7 +23,7 @@ pub async fn create_vendor(pool: &PgPool, new_vendor: CreateVendor) -> Result<Ve
 }
 
 pub async fn get_vendors(pool: &PgPool, pagination: Pagination) -> Result<PaginatedResponse<Vendor>, ServiceError> {
-    let offset = (pagination.page - 1) * pagination.limit;
+    let offset = pagination.offset().map_err(ServiceError::BadRequest)?;
 
     let total_items: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM vendors")
         .fetch_one(pool)
@@ -32,7 +33,7 @@ pub async fn get_vendors(pool: &PgPool, pagination: Pagination) -> Result<Pagina
         "SELECT * FROM vendors ORDER BY created_at DESC LIMIT $1 OFFSET $2"
     )
     .bind(pagination.limit as i64)
-    .bind(offset as i64)
+    .bind(offset)
     .fetch_all(pool)
     .await?;
 
@@ -58,17 +59,28 @@ pub async fn get_vendor_by_id(pool: &PgPool, vendor_id: Uuid) -> Result<Vendor,
     Ok(vendor)
 }
 
+/// Vendors the user's account manages.
+pub async fn get_user_vendor_ids(pool: &PgPool, user_id: Uuid) -> Result<Vec<Uuid>, ServiceError> {
+    let vendor_ids = sqlx::query_scalar("SELECT id FROM vendors WHERE user_id = $1 ORDER BY created_at")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(vendor_ids)
+}
+
 pub async fn update_vendor(pool: &PgPool, vendor_id: Uuid, updated_vendor: UpdateVendor) -> Result<Vendor, ServiceError> {
     let existing_vendor = get_vendor_by_id(pool, vendor_id).await?;
 
     let vendor = sqlx::query_as::<_, Vendor>(
-        "UPDATE vendors SET name = $1, contact_person = $2, email = $3, phone = $4, address = $5, updated_at = $6 WHERE id = $7 RETURNING *"
+        "UPDATE vendors SET name = $1, contact_person = $2, email = $3, phone = $4, address = $5, user_id = $6, updated_at = $7 WHERE id = $8 RETURNING *"
     )
     .bind(updated_vendor.name.unwrap_or(existing_vendor.name))
     .bind(updated_vendor.contact_person.unwrap_or(existing_vendor.contact_person))
     .bind(updated_vendor.email.unwrap_or(existing_vendor.email))
     .bind(updated_vendor.phone.unwrap_or(existing_vendor.phone))
     .bind(updated_vendor.address.unwrap_or(existing_vendor.address))
+    .bind(updated_vendor.user_id.or(existing_vendor.user_id))
     .bind(Utc::now())
     .bind(vendor_id)
     .fetch_one(pool)
@@ -0,0 +1,140 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use chrono::Utc;
+use models::{warehouse::{Warehouse, CreateWarehouse, UpdateWarehouse}, pagination::{Pagination, PaginatedResponse}};
+use crate::error::ServiceError;
+
+pub async fn create_warehouse(pool: &PgPool, new_warehouse: CreateWarehouse) -> Result<Warehouse, ServiceError> {
+    let code = new_warehouse.code.trim().to_lowercase();
+    if code.is_empty() {
+        return Err(ServiceError::BadRequest("Warehouse code cannot be empty".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+    if new_warehouse.is_default {
+        clear_default_warehouse(&mut tx).await?;
+    }
+
+    let warehouse = sqlx::query_as::<_, Warehouse>(
+        "INSERT INTO warehouses (code, name, address, is_default, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *"
+    )
+    .bind(code)
+    .bind(new_warehouse.name)
+    .bind(new_warehouse.address)
+    .bind(new_warehouse.is_default)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(warehouse)
+}
+
+pub async fn get_warehouses(pool: &PgPool, pagination: Pagination) -> Result<PaginatedResponse<Warehouse>, ServiceError> {
+    let offset = pagination.offset().map_err(ServiceError::BadRequest)?;
+
+    let total_items: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM warehouses")
+        .fetch_one(pool)
+        .await?;
+
+    let warehouses = sqlx::query_as::<_, Warehouse>(
+        "SELECT * FROM warehouses ORDER BY is_default DESC, code LIMIT $1 OFFSET $2"
+    )
+    .bind(pagination.limit as i64)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let total_pages = (total_items as f64 / pagination.limit as f64).ceil() as u32;
+
+    Ok(PaginatedResponse {
+        items: warehouses,
+        total_items: total_items as u32,
+        current_page: pagination.page,
+        total_pages,
+        limit: pagination.limit,
+    })
+}
+
+pub async fn get_warehouse_by_id(pool: &PgPool, warehouse_id: Uuid) -> Result<Warehouse, ServiceError> {
+    let warehouse = sqlx::query_as::<_, Warehouse>(
+        "SELECT * FROM warehouses WHERE id = $1"
+    )
+    .bind(warehouse_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(warehouse)
+}
+
+pub async fn update_warehouse(pool: &PgPool, warehouse_id: Uuid, updated_warehouse: UpdateWarehouse) -> Result<Warehouse, ServiceError> {
+    let existing_warehouse = get_warehouse_by_id(pool, warehouse_id).await?;
+
+    let is_default = match updated_warehouse.is_default {
+        Some(false) if existing_warehouse.is_default => {
+            return Err(ServiceError::BadRequest("Make another warehouse the default instead".to_string()));
+        }
+        Some(is_default) => is_default,
+        None => existing_warehouse.is_default,
+    };
+
+    let mut tx = pool.begin().await?;
+    if is_default && !existing_warehouse.is_default {
+        clear_default_warehouse(&mut tx).await?;
+    }
+
+    let warehouse = sqlx::query_as::<_, Warehouse>(
+        "UPDATE warehouses SET name = $1, address = $2, is_default = $3, updated_at = $4 WHERE id = $5 RETURNING *"
+    )
+    .bind(updated_warehouse.name.unwrap_or(existing_warehouse.name))
+    .bind(updated_warehouse.address.or(existing_warehouse.address))
+    .bind(is_default)
+    .bind(Utc::now())
+    .bind(warehouse_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(warehouse)
+}
+
+/// Only warehouses that never held stock can be deleted; the ledger keeps
+/// referring to all others.
+pub async fn delete_warehouse(pool: &PgPool, warehouse_id: Uuid) -> Result<(), ServiceError> {
+    let warehouse = get_warehouse_by_id(pool, warehouse_id).await?;
+    if warehouse.is_default {
+        return Err(ServiceError::BadRequest("The default warehouse cannot be deleted".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM inventory_items WHERE warehouse_id = $1 AND quantity = 0")
+        .bind(warehouse_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM warehouses WHERE id = $1")
+        .bind(warehouse_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub(crate) async fn default_warehouse_id(conn: &mut PgConnection) -> Result<Uuid, ServiceError> {
+    sqlx::query_scalar("SELECT id FROM warehouses WHERE is_default")
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| ServiceError::BadRequest("No default warehouse is configured".to_string()))
+}
+
+async fn clear_default_warehouse(conn: &mut PgConnection) -> Result<(), ServiceError> {
+    sqlx::query("UPDATE warehouses SET is_default = FALSE, updated_at = $1 WHERE is_default")
+        .bind(Utc::now())
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}